@@ -0,0 +1,170 @@
+use crate::{style::LineStyle, wav::WavData, LyricLine};
+
+const FRAME_SECONDS: f64 = 0.02;
+// Pauses shorter than this are breaths inside a phrase, not gaps between lines
+const MIN_GAP: f64 = 0.35;
+// Voiced blips shorter than this are noise
+const MIN_REGION: f64 = 0.15;
+// A line boundary moves onto a pause when one is at most this far away
+const SNAP_DISTANCE: f64 = 1.5;
+
+// Stretches of the recording that contain singing, in seconds
+fn voiced_regions(levels: &[f32]) -> Vec<(f64, f64)> {
+    let peak = levels.iter().cloned().fold(0.0_f32, f32::max);
+    let threshold = (peak * 0.1).max(0.01);
+
+    let mut regions: Vec<(f64, f64)> = Vec::new();
+    let mut start = None;
+    for (i, &level) in levels.iter().chain(std::iter::once(&0.0)).enumerate() {
+        let time = i as f64 * FRAME_SECONDS;
+        match (level >= threshold, start) {
+            (true, None) => start = Some(time),
+            (false, Some(begin)) => {
+                start = None;
+                match regions.last_mut() {
+                    Some(last) if begin - last.1 < MIN_GAP => last.1 = time,
+                    _ => regions.push((begin, time)),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    regions.retain(|(start, end)| end - start >= MIN_REGION);
+    regions
+}
+
+// Maps a position in singing time (silences skipped) back to the recording. At the exact
+// edge of a region `prefer_next` picks the start of the following one instead of the end.
+fn real_time(regions: &[(f64, f64)], mut voiced: f64, prefer_next: bool) -> f64 {
+    for &(start, end) in regions {
+        let length = end - start;
+        if voiced < length || (!prefer_next && voiced <= length) {
+            return start + voiced;
+        }
+        voiced -= length;
+    }
+    regions.last().map_or(0.0, |region| region.1)
+}
+
+// How much singing a line probably takes; letters are a decent stand-in for syllables
+fn weight(text: &str) -> f64 {
+    text.chars().filter(|c| c.is_alphanumeric()).count().max(1) as f64
+}
+
+// Estimates line timing by spreading the lines over the voiced parts of the recording in
+// proportion to their length, then moving each boundary onto a nearby pause
+pub fn align_levels(lines: &[String], levels: &[f32]) -> Result<Vec<LyricLine>, String> {
+    let regions = voiced_regions(levels);
+    if regions.is_empty() {
+        return Err("no singing found in the recording".to_string());
+    }
+
+    let total_voiced: f64 = regions.iter().map(|(start, end)| end - start).sum();
+    let total_weight: f64 = lines.iter().map(|line| weight(line)).sum();
+
+    let mut cumulative = 0.0;
+    let mut lyrics: Vec<LyricLine> = lines
+        .iter()
+        .map(|text| {
+            let start = cumulative / total_weight * total_voiced;
+            cumulative += weight(text);
+            let end = cumulative / total_weight * total_voiced;
+            LyricLine {
+                text: text.clone(),
+                start_time: real_time(&regions, start, true),
+                end_time: real_time(&regions, end, false),
+                segments: Vec::new(),
+                singer: 0,
+                style: LineStyle::default(),
+                chords: Vec::new(),
+            }
+        })
+        .collect();
+
+    let gaps: Vec<(f64, f64)> = regions.windows(2).map(|pair| (pair[0].1, pair[1].0)).collect();
+    for i in 1..lyrics.len() {
+        let boundary = lyrics[i].start_time;
+        if boundary > lyrics[i - 1].end_time {
+            continue;
+        }
+        let nearest = gaps
+            .iter()
+            .filter(|gap| gap.0 > lyrics[i - 1].start_time && gap.1 < lyrics[i].end_time)
+            .min_by(|a, b| (a.0 - boundary).abs().total_cmp(&(b.0 - boundary).abs()));
+        if let Some(&(gap_start, gap_end)) = nearest {
+            if (gap_start - boundary).abs().min((gap_end - boundary).abs()) <= SNAP_DISTANCE {
+                lyrics[i - 1].end_time = gap_start;
+                lyrics[i].start_time = gap_end;
+            }
+        }
+    }
+
+    Ok(lyrics)
+}
+
+pub fn align(text: &str, recording: &WavData) -> Result<Vec<LyricLine>, String> {
+    let lines: Vec<String> = text
+        .lines()
+        .map(|line| line.trim().to_string())
+        .filter(|line| !line.is_empty())
+        .collect();
+    if lines.is_empty() {
+        return Err("the lyrics file has no text".to_string());
+    }
+    align_levels(&lines, &recording.rms_levels(FRAME_SECONDS))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // One level per 20ms frame: `true` stretches are singing
+    fn levels(pattern: &[(bool, f64)]) -> Vec<f32> {
+        pattern
+            .iter()
+            .flat_map(|&(voiced, seconds)| {
+                let frames = (seconds / FRAME_SECONDS).round() as usize;
+                std::iter::repeat_n(if voiced { 0.5 } else { 0.0 }, frames)
+            })
+            .collect()
+    }
+
+    fn lines(texts: &[&str]) -> Vec<String> {
+        texts.iter().map(|text| text.to_string()).collect()
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 0.05, "{} is not close to {}", actual, expected);
+    }
+
+    #[test]
+    fn lines_land_on_separate_phrases() {
+        let recording = levels(&[(false, 1.0), (true, 2.0), (false, 1.0), (true, 2.0)]);
+        let lyrics = align_levels(&lines(&["first line", "other line"]), &recording).unwrap();
+        assert_close(lyrics[0].start_time, 1.0);
+        assert_close(lyrics[0].end_time, 3.0);
+        assert_close(lyrics[1].start_time, 4.0);
+        assert_close(lyrics[1].end_time, 6.0);
+    }
+
+    #[test]
+    fn boundaries_snap_to_a_nearby_pause() {
+        // The short line would end at 1.5s by length alone; the pause at 2s wins
+        let recording = levels(&[(true, 2.0), (false, 0.5), (true, 4.0)]);
+        let lyrics = align_levels(&lines(&["ab", "abcdefghij"]), &recording).unwrap();
+        assert_close(lyrics[0].end_time, 2.0);
+        assert_close(lyrics[1].start_time, 2.5);
+    }
+
+    #[test]
+    fn breaths_do_not_split_a_phrase() {
+        let recording = levels(&[(true, 1.0), (false, 0.2), (true, 1.0)]);
+        assert_eq!(voiced_regions(&recording).len(), 1);
+    }
+
+    #[test]
+    fn silence_is_an_error() {
+        assert!(align_levels(&lines(&["hello"]), &[0.0; 100]).is_err());
+    }
+}
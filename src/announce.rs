@@ -0,0 +1,230 @@
+use std::{
+    io::{self, Write},
+    process::{Child, Command, Stdio},
+    sync::mpsc::{self, SyncSender, TrySendError},
+    thread,
+};
+
+use crate::{layout, plugin::Renderer, KaraokeApp};
+
+// Reads each line of its stdin aloud as it arrives
+pub const DEFAULT_COMMAND: &str = "espeak-ng";
+
+// Lines seconds ahead of when they're sung, as a guide for songs the singer doesn't know yet
+pub const DEFAULT_GUIDE_LEAD: f64 = 1.5;
+
+// Lines waiting for a speech command that reads slower than they come are dropped past this
+const QUEUED_LINES: usize = 16;
+
+// A text-to-speech program fed one line of text at a time; it's killed when this is dropped
+pub struct Speech {
+    child: Child,
+    lines: Option<SyncSender<String>>,
+}
+
+impl Speech {
+    pub fn start(command: &str) -> io::Result<Self> {
+        let mut parts = command.split_whitespace();
+        let program = parts.next().ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "empty speech command"))?;
+        let mut child = Command::new(program)
+            .args(parts)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| io::Error::new(e.kind(), format!("couldn't start the speech command `{}`: {}", command, e)))?;
+        let (lines, queue) = mpsc::sync_channel::<String>(QUEUED_LINES);
+        if let Some(mut stdin) = child.stdin.take() {
+            thread::spawn(move || {
+                for text in queue {
+                    if let Err(e) = writeln!(stdin, "{}", text).and_then(|_| stdin.flush()) {
+                        log::warn!("the speech command stopped reading: {}", e);
+                        break;
+                    }
+                }
+            });
+        }
+        Ok(Self { child, lines: Some(lines) })
+    }
+
+    // Speech that stops reading is left out, and speech that falls behind skips lines
+    fn say(&mut self, text: &str) {
+        let Some(lines) = &self.lines else {
+            return;
+        };
+        match lines.try_send(text.to_string()) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => log::warn!("the speech command is behind; dropped a line"),
+            Err(TrySendError::Disconnected(_)) => self.lines = None,
+        }
+    }
+}
+
+impl Drop for Speech {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+enum Voice {
+    Speech(Speech),
+    Stdout,
+}
+
+// Speaks or prints each line as it becomes active, along with the song's title, instrumental
+// breaks and the end, for singers who follow along with a screen reader or by ear
+pub struct Announcer {
+    voice: Voice,
+    song_sent: bool,
+    line: Option<usize>,
+    in_break: bool,
+    ended: bool,
+}
+
+impl Announcer {
+    // Without a command, announcements go to stdout in place of the plain output
+    pub fn start(command: Option<&str>) -> io::Result<Self> {
+        let voice = match command {
+            Some(command) => Voice::Speech(Speech::start(command)?),
+            None => Voice::Stdout,
+        };
+        Ok(Self {
+            voice,
+            song_sent: false,
+            line: None,
+            in_break: false,
+            ended: false,
+        })
+    }
+
+    fn changes(&mut self, app: &KaraokeApp) -> Vec<String> {
+        let mut announcements = Vec::new();
+        if !self.song_sent {
+            announcements.push(format!("Now playing: {}", app.song_title));
+            self.song_sent = true;
+        }
+
+        let time = app.lyric_time();
+        let gap = layout::instrumental_break(app, time);
+        if let Some(gap) = gap.as_ref().filter(|_| !self.in_break) {
+            announcements.push(format!("Instrumental break, next line in {:.0} seconds", gap.remaining));
+        }
+        self.in_break = gap.is_some();
+
+        let index = app.active_line(time);
+        if let Some(i) = index.filter(|&i| self.line != Some(i)) {
+            announcements.push(app.lyrics[i].text.clone());
+        }
+        if index.is_some() {
+            self.line = index;
+        }
+
+        let ended = app.is_song_ended();
+        if ended && !self.ended {
+            announcements.push("End of song".to_string());
+        }
+        self.ended = ended;
+        announcements
+    }
+}
+
+impl Renderer for Announcer {
+    fn render(&mut self, app: &KaraokeApp) -> io::Result<()> {
+        for announcement in self.changes(app) {
+            match &mut self.voice {
+                Voice::Stdout => {
+                    let mut stdout = io::stdout();
+                    writeln!(stdout, "{}", announcement).and_then(|_| stdout.flush())?;
+                }
+                Voice::Speech(speech) => speech.say(&announcement),
+            }
+        }
+        Ok(())
+    }
+
+    fn song_changed(&mut self) {
+        self.song_sent = false;
+        self.line = None;
+        self.in_break = false;
+        self.ended = false;
+    }
+}
+
+// Reads each line `lead` seconds before it starts. Only lines still ahead are read, so a seek
+// into the middle of one waits for the next.
+pub struct GuideVocal {
+    speech: Speech,
+    lead: f64,
+    spoken: Option<usize>,
+}
+
+impl GuideVocal {
+    pub fn new(speech: Speech, lead: f64) -> Self {
+        Self { speech, lead, spoken: None }
+    }
+
+    fn due(&mut self, app: &KaraokeApp) -> Option<usize> {
+        let time = app.lyric_time();
+        let opened = app.lyrics.partition_point(|line| line.start_time - self.lead <= time);
+        let index = opened.checked_sub(1)?;
+        if app.paused || self.spoken == Some(index) || time >= app.lyrics[index].start_time {
+            return None;
+        }
+        self.spoken = Some(index);
+        Some(index)
+    }
+}
+
+impl Renderer for GuideVocal {
+    fn render(&mut self, app: &KaraokeApp) -> io::Result<()> {
+        if let Some(index) = self.due(app) {
+            self.speech.say(&app.lyrics[index].text);
+        }
+        Ok(())
+    }
+
+    fn song_changed(&mut self) {
+        self.spoken = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn title_lines_and_end_are_announced_once() {
+        let mut app = KaraokeApp::from_config(crate::song_config::SongConfig::load(), None);
+        app.paused = true;
+        app.current_position = app.lyrics[1].start_time + 0.1;
+        let mut announcer = Announcer::start(None).unwrap();
+
+        let announcements = announcer.changes(&app);
+        assert_eq!(announcements, [format!("Now playing: {}", app.song_title), app.lyrics[1].text.clone()]);
+        assert!(announcer.changes(&app).is_empty());
+
+        app.current_position = app.song_duration;
+        assert_eq!(announcer.changes(&app), ["End of song"]);
+    }
+
+    #[test]
+    fn guide_reads_each_line_once_just_before_it() {
+        let mut app = KaraokeApp::from_config(crate::song_config::SongConfig::load(), None);
+        app.paused = true;
+        let mut guide = GuideVocal::new(Speech::start("true").unwrap(), 1.0);
+        let start = app.lyrics[1].start_time;
+
+        app.current_position = start - 0.5;
+        assert_eq!(guide.due(&app), None);
+        app.paused = false;
+        app.start_time = app.clock.now();
+        assert_eq!(guide.due(&app), Some(1));
+        assert_eq!(guide.due(&app), None);
+
+        app.paused = true;
+        app.current_position = start + 0.1;
+        guide.song_changed();
+        assert_eq!(guide.due(&app), None);
+    }
+}
@@ -0,0 +1,143 @@
+use ratatui::{
+    layout::{Alignment, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::Paragraph,
+    Frame,
+};
+use std::time::{Duration, Instant};
+
+use crate::{
+    background::Theme,
+    bigtext,
+    highlight::{Highlight, HighlightStyle},
+    history::{self, SortBy},
+    party::PartySession,
+};
+
+// How long each page stays up before the next one
+const PAGE_TIME: Duration = Duration::from_secs(8);
+const POPULAR_SHOWN: usize = 5;
+// How fast the rainbow slides along the headings, in columns a second
+const SWEEP_SPEED: f64 = 12.0;
+const TITLE: &str = "KARAOKE";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Page {
+    Title,
+    Popular,
+    Requests,
+}
+
+// What a party shows once nothing has been sung for a while, until someone presses a key
+#[derive(Debug, Clone)]
+pub struct Attract {
+    // Titles and play counts, most played first
+    popular: Vec<(String, u32)>,
+    started: Instant,
+}
+
+impl Attract {
+    pub fn new(now: Instant) -> Self {
+        let popular = match history::PlayHistory::load(&history::history_path()) {
+            Ok(history) => history
+                .sorted(SortBy::Plays)
+                .into_iter()
+                .take(POPULAR_SHOWN)
+                .map(|(_, stats)| (stats.title.clone(), stats.plays))
+                .collect(),
+            Err(e) => {
+                log::warn!("couldn't read the play history: {}", e);
+                Vec::new()
+            }
+        };
+        Self { popular, started: now }
+    }
+
+    // Pages with nothing to show are skipped
+    pub fn page(&self, has_request_page: bool, now: Instant) -> Page {
+        let pages: Vec<Page> = [(Page::Title, true), (Page::Popular, !self.popular.is_empty()), (Page::Requests, has_request_page)]
+            .into_iter()
+            .filter_map(|(page, shown)| shown.then_some(page))
+            .collect();
+        let turn = now.duration_since(self.started).as_secs() / PAGE_TIME.as_secs();
+        pages[turn as usize % pages.len()]
+    }
+
+    // A heading in big letters when it fits, with a rainbow sliding along it
+    fn heading(&self, text: &str, width: usize, height: usize, now: Instant) -> Vec<Line<'static>> {
+        let rows = bigtext::fit(None, text, width, height).unwrap_or_else(|| vec![text.to_string()]);
+        let rainbow = Highlight::new(HighlightStyle::Rainbow, &[], Theme::Flat);
+        let shift = (now.duration_since(self.started).as_secs_f64() * SWEEP_SPEED) as usize;
+        rows.into_iter()
+            .map(|row| {
+                let spans: Vec<Span> = row
+                    .chars()
+                    .enumerate()
+                    .map(|(column, c)| {
+                        let color = rainbow.color(Color::White, (column + shift) / 4, 0);
+                        Span::styled(c.to_string(), Style::default().fg(color).add_modifier(Modifier::BOLD))
+                    })
+                    .collect();
+                Line::from(spans)
+            })
+            .collect()
+    }
+}
+
+pub fn render(f: &mut Frame, area: Rect, attract: &Attract, party: Option<&PartySession>, now: Instant) {
+    let request_page = party.and_then(|session| session.request_page.as_deref());
+    // The heading gets up to a third of the screen
+    let (width, height) = (area.width as usize, area.height as usize / 3);
+    let plain = Style::default().fg(Color::White);
+    let accent = Style::default().fg(Color::Rgb(0, 200, 255));
+    let (mut lines, body) = match attract.page(request_page.is_some(), now) {
+        Page::Title => {
+            let next_up = party.and_then(PartySession::next_up).map(|(song, singers)| format!("Next up: {} - {}", song, singers));
+            (attract.heading(TITLE, width, height, now), next_up.map(|line| Line::styled(line, accent)).into_iter().collect())
+        }
+        Page::Popular => {
+            let songs = attract.popular.iter().enumerate().map(|(rank, (title, plays))| {
+                let times = if *plays == 1 { "time" } else { "times" };
+                Line::styled(format!("{}. {}  ({} {})", rank + 1, title, plays, times), plain)
+            });
+            (attract.heading("Most sung", width, height, now), songs.collect())
+        }
+        Page::Requests => {
+            let url = request_page.unwrap_or_default();
+            // The code when it fits, leaving a row for the heading
+            let body = crate::qr::lines(url)
+                .filter(|code| code.len() + 4 <= area.height as usize)
+                .unwrap_or_else(|| vec![Line::styled(url.to_string(), accent)]);
+            let height = height.min((area.height as usize).saturating_sub(body.len() + 3));
+            (attract.heading("Request a song", width, height, now), body)
+        }
+    };
+    lines.push(Line::default());
+    lines.extend(body);
+    lines.push(Line::default());
+    lines.push(Line::styled("Press any key", Style::default().fg(Color::DarkGray)));
+
+    let top = (area.height as usize).saturating_sub(lines.len()) / 2;
+    let lines: Vec<Line> = std::iter::repeat_n(Line::default(), top).chain(lines).collect();
+    f.render_widget(Paragraph::new(lines).alignment(Alignment::Center), area);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pages_take_turns_skipping_empty_ones() {
+        let start = Instant::now();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+        let mut attract = Attract { popular: Vec::new(), started: start };
+        assert_eq!(attract.page(false, at(0)), Page::Title);
+        assert_eq!(attract.page(false, at(9)), Page::Title);
+        assert_eq!(attract.page(true, at(9)), Page::Requests);
+
+        attract.popular.push(("Song".to_string(), 3));
+        let pages: Vec<Page> = [0, 8, 16, 24].into_iter().map(|secs| attract.page(true, at(secs))).collect();
+        assert_eq!(pages, [Page::Title, Page::Popular, Page::Requests, Page::Title]);
+    }
+}
@@ -0,0 +1,180 @@
+use ratatui::{buffer::Buffer, layout::Rect, style::Color};
+use std::{f64::consts::TAU, time::Instant};
+
+// About one star for every this many cells
+const STAR_SPACING: usize = 40;
+const STARS: [&str; 3] = [".", "+", "*"];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Theme {
+    #[default]
+    Flat,
+    Gradient,
+    Starfield,
+    Wave,
+}
+
+impl std::str::FromStr for Theme {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "flat" => Ok(Theme::Flat),
+            "gradient" => Ok(Theme::Gradient),
+            "starfield" => Ok(Theme::Starfield),
+            "wave" => Ok(Theme::Wave),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Theme {
+    // The two colors each theme works between: the gradient's ends, the sky and its stars, or
+    // the wave's troughs and crests
+    fn colors(self) -> (Color, Color) {
+        match self {
+            Theme::Flat => (Color::Rgb(20, 24, 40), Color::Rgb(20, 24, 40)),
+            Theme::Gradient => (Color::Rgb(20, 24, 40), Color::Rgb(60, 20, 70)),
+            Theme::Starfield => (Color::Rgb(8, 8, 20), Color::Rgb(200, 200, 255)),
+            Theme::Wave => (Color::Rgb(20, 24, 40), Color::Rgb(20, 55, 85)),
+        }
+    }
+
+    // Where a gradient highlight ends up on this background
+    pub fn accent(self) -> Color {
+        match self {
+            Theme::Flat => Color::Rgb(255, 0, 255),
+            Theme::Gradient => Color::Rgb(255, 150, 0),
+            Theme::Starfield => Color::Rgb(200, 200, 255),
+            Theme::Wave => Color::Rgb(0, 255, 200),
+        }
+    }
+}
+
+// What's drawn behind the lyrics. Everything is worked out from the time, so nothing is kept
+// between frames, and a paused screen only redraws it at the idle rate.
+#[derive(Debug, Clone)]
+pub struct Background {
+    pub theme: Theme,
+    colors: (Color, Color),
+    // 1.0 is the theme's own pace; 0.0 holds it still
+    speed: f64,
+    started: Instant,
+}
+
+impl Default for Background {
+    fn default() -> Self {
+        Self::new(Theme::Flat, &[], 1.0)
+    }
+}
+
+impl Background {
+    // Colors not given keep the theme's own
+    pub fn new(theme: Theme, colors: &[Color], speed: f64) -> Self {
+        let (first, second) = theme.colors();
+        Self {
+            theme,
+            colors: (colors.first().copied().unwrap_or(first), colors.get(1).copied().unwrap_or(second)),
+            speed,
+            started: Instant::now(),
+        }
+    }
+
+    // Hook scripts switch themes mid-song; the new one keeps the pace but brings its own colors
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
+        self.colors = theme.colors();
+    }
+
+    // The color behind one cell at the given time
+    fn cell(&self, x: u16, y: u16, area: Rect, time: f64) -> Color {
+        let (width, height) = (f64::from(area.width.max(1)), f64::from(area.height.max(1)));
+        let (x, y) = (f64::from(x) / width, f64::from(y) / height);
+        match self.theme {
+            Theme::Flat | Theme::Starfield => self.colors.0,
+            // The blend drifts up and down the screen over about twenty seconds
+            Theme::Gradient => blend(self.colors.0, self.colors.1, 0.5 + 0.5 * (TAU * (y / 2.0 + time / 20.0)).sin()),
+            Theme::Wave => {
+                let crest = (TAU * (x * 1.5 - time / 8.0) + y * 3.0).sin() * 0.5 + 0.5;
+                blend(self.colors.0, self.colors.1, crest * crest)
+            }
+        }
+    }
+
+    // Stars drift slowly left, the nearer ones faster, and twinkle
+    fn stars(&self, area: Rect, time: f64) -> Vec<(u16, u16, &'static str, Color)> {
+        let (width, height) = (f64::from(area.width), f64::from(area.height));
+        let count = usize::from(area.width) * usize::from(area.height) / STAR_SPACING;
+        (0..count)
+            .map(|star| {
+                let depth = (star % STARS.len()) as f64 + 1.0;
+                let x = (random(star, 1) * width - time * depth / 2.0).rem_euclid(width);
+                let y = random(star, 2) * height;
+                let twinkle = 0.6 + 0.4 * (time * (1.0 + random(star, 3)) + random(star, 4) * TAU).sin();
+                let color = blend(self.colors.0, self.colors.1, twinkle * depth / STARS.len() as f64);
+                ((x as u16).min(area.width - 1), y as u16, STARS[star % STARS.len()], color)
+            })
+            .collect()
+    }
+}
+
+pub fn blend(from: Color, to: Color, t: f64) -> Color {
+    match (from, to) {
+        (Color::Rgb(r1, g1, b1), Color::Rgb(r2, g2, b2)) => {
+            let mix = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t.clamp(0.0, 1.0)).round() as u8;
+            Color::Rgb(mix(r1, r2), mix(g1, g2), mix(b1, b2))
+        }
+        _ => from,
+    }
+}
+
+// Fixed for a given star, so it keeps its place from frame to frame
+fn random(star: usize, salt: u64) -> f64 {
+    let mut x = (star as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15) ^ salt.wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x ^= x >> 31;
+    x = x.wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^= x >> 29;
+    (x >> 11) as f64 / (1u64 << 53) as f64
+}
+
+// Fills the area before anything else is drawn on it; the lyrics only set the cells they write
+pub fn render(buf: &mut Buffer, area: Rect, background: &Background, now: Instant) {
+    let time = now.duration_since(background.started).as_secs_f64() * background.speed;
+    for y in 0..area.height {
+        for x in 0..area.width {
+            buf.get_mut(area.x + x, area.y + y).set_symbol(" ").set_bg(background.cell(x, y, area, time));
+        }
+    }
+    if background.theme == Theme::Starfield {
+        for (x, y, symbol, color) in background.stars(area, time) {
+            buf.get_mut(area.x + x, area.y + y).set_symbol(symbol).set_fg(color);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn themes_fill_the_area_and_move_with_time() {
+        let area = Rect::new(0, 0, 40, 10);
+        let flat = Background::default();
+        assert_eq!(flat.cell(3, 4, area, 0.0), Color::Rgb(20, 24, 40));
+        assert_eq!(flat.cell(3, 4, area, 9.0), Color::Rgb(20, 24, 40));
+
+        let gradient = Background::new(Theme::Gradient, &[Color::Rgb(0, 0, 0)], 1.0);
+        assert_ne!(gradient.cell(0, 0, area, 0.0), gradient.cell(0, 5, area, 0.0));
+        assert_ne!(gradient.cell(0, 0, area, 0.0), gradient.cell(0, 0, area, 5.0));
+
+        let stars = Background::new(Theme::Starfield, &[], 1.0);
+        let (now, later) = (stars.stars(area, 0.0), stars.stars(area, 3.0));
+        assert_eq!(now.len(), 10);
+        assert!(now.iter().chain(&later).all(|&(x, y, _, _)| x < 40 && y < 10));
+        assert_ne!(now, later);
+
+        let mut buf = Buffer::empty(area);
+        render(&mut buf, area, &Background::new(Theme::Wave, &[], 0.0), Instant::now());
+        assert!(buf.content.iter().all(|cell| cell.bg != Color::Reset));
+    }
+}
@@ -0,0 +1,177 @@
+use crate::{song_config::Tempo, wav::WavData};
+
+const FRAME_SECONDS: f64 = 0.01;
+const MIN_BPM: f64 = 70.0;
+const MAX_BPM: f64 = 180.0;
+// How long the accent stays lit after each beat
+const PULSE_LENGTH: f64 = 0.15;
+
+pub struct BeatGrid {
+    pub period: f64,
+    pub offset: f64,
+    pub beats_per_bar: u32,
+}
+
+// Rises in loudness from one frame to the next; drum hits and note attacks stand out
+fn onset_strength(recording: &WavData) -> Vec<f64> {
+    let levels = recording.rms_levels(FRAME_SECONDS);
+    levels
+        .windows(2)
+        .map(|pair| (pair[1] - pair[0]).max(0.0) as f64)
+        .collect()
+}
+
+// Beat phase that lines up with the most onset energy for a given period
+fn best_offset(onsets: &[f64], period: f64) -> f64 {
+    let frames_per_beat = (period / FRAME_SECONDS).round().max(1.0) as usize;
+    (0..frames_per_beat)
+        .map(|phase| {
+            let mut strength = 0.0;
+            let mut beat = 0.0;
+            loop {
+                // Onsets are measured between frames, so frame i + 1 is where index i ends
+                let idx = ((phase as f64 + beat * period / FRAME_SECONDS).round() as usize).saturating_sub(1);
+                let Some(onset) = onsets.get(idx) else {
+                    break;
+                };
+                strength += onset;
+                beat += 1.0;
+            }
+            (phase, strength)
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map_or(0.0, |(phase, _)| phase as f64 * FRAME_SECONDS)
+}
+
+impl BeatGrid {
+    pub fn from_bpm(bpm: f64) -> Self {
+        Self {
+            period: 60.0 / bpm,
+            offset: 0.0,
+            beats_per_bar: 4,
+        }
+    }
+
+    pub fn from_tempo(tempo: &Tempo) -> Option<Self> {
+        (tempo.bpm.is_finite() && tempo.bpm > 0.0).then(|| Self {
+            period: 60.0 / tempo.bpm,
+            offset: tempo.offset,
+            beats_per_bar: tempo.beats_per_bar(),
+        })
+    }
+
+    // Finds the tempo (unless given) and beat phase of a recording from its onsets
+    pub fn detect(recording: &WavData, bpm: Option<f64>) -> Result<Self, String> {
+        let onsets = onset_strength(recording);
+        if onsets.iter().all(|&onset| onset == 0.0) {
+            return Err("no beats found in the recording".to_string());
+        }
+
+        let period = match bpm {
+            Some(bpm) => 60.0 / bpm,
+            None => {
+                let min_lag = (60.0 / MAX_BPM / FRAME_SECONDS).round() as usize;
+                let max_lag = (60.0 / MIN_BPM / FRAME_SECONDS).round() as usize;
+                let lag = (min_lag..=max_lag)
+                    .map(|lag| {
+                        let correlation: f64 =
+                            onsets.iter().zip(onsets.iter().skip(lag)).map(|(a, b)| a * b).sum();
+                        (lag, correlation)
+                    })
+                    .max_by(|a, b| a.1.total_cmp(&b.1))
+                    .map_or(min_lag, |(lag, _)| lag);
+                lag as f64 * FRAME_SECONDS
+            }
+        };
+
+        Ok(Self {
+            period,
+            offset: best_offset(&onsets, period),
+            beats_per_bar: 4,
+        })
+    }
+
+    // Beats counted from the first one, negative before it
+    pub fn beat(&self, time: f64) -> i64 {
+        ((time - self.offset) / self.period).floor() as i64
+    }
+
+    // Where a beat falls in its bar, 0 being the downbeat
+    pub fn beat_in_bar(&self, beat: i64) -> u32 {
+        beat.rem_euclid(self.beats_per_bar as i64) as u32
+    }
+
+    // 1.0 right on a beat, fading to 0.0 shortly after
+    pub fn pulse(&self, time: f64) -> f64 {
+        let since_beat = (time - self.offset).rem_euclid(self.period);
+        (1.0 - since_beat / PULSE_LENGTH).max(0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A 1kHz mono recording with a short click on every beat
+    fn clicks(bpm: f64, offset: f64, seconds: f64) -> WavData {
+        let sample_rate = 1000;
+        let period = 60.0 / bpm;
+        let samples = (0..(seconds * sample_rate as f64) as usize)
+            .map(|i| {
+                let time = i as f64 / sample_rate as f64;
+                let since_beat = (time - offset).rem_euclid(period);
+                if time >= offset && since_beat < 0.03 { 0.8 } else { 0.0 }
+            })
+            .collect();
+        WavData {
+            sample_rate,
+            channels: 1,
+            samples,
+        }
+    }
+
+    #[test]
+    fn detects_tempo_and_phase() {
+        let grid = BeatGrid::detect(&clicks(120.0, 0.25, 10.0), None).unwrap();
+        assert!((grid.period - 0.5).abs() < 0.011, "period {}", grid.period);
+        assert!((grid.offset - 0.25).abs() < 0.021, "offset {}", grid.offset);
+    }
+
+    #[test]
+    fn given_bpm_only_needs_the_phase() {
+        let grid = BeatGrid::detect(&clicks(100.0, 0.1, 10.0), Some(100.0)).unwrap();
+        assert_eq!(grid.period, 0.6);
+        assert!((grid.offset - 0.1).abs() < 0.021, "offset {}", grid.offset);
+    }
+
+    #[test]
+    fn pulse_fades_after_each_beat() {
+        let grid = BeatGrid::from_bpm(120.0);
+        assert_eq!(grid.pulse(1.0), 1.0);
+        assert!(grid.pulse(1.075) > 0.4 && grid.pulse(1.075) < 0.6);
+        assert_eq!(grid.pulse(1.3), 0.0);
+    }
+
+    #[test]
+    fn song_tempo_sets_the_bar() {
+        let tempo = Tempo {
+            bpm: 90.0,
+            time_signature: "3/4".to_string(),
+            offset: 0.5,
+        };
+        let grid = BeatGrid::from_tempo(&tempo).unwrap();
+        let bars: Vec<(i64, u32)> = [0.4, 0.5, 1.9, 2.6].iter().map(|&time| (grid.beat(time), grid.beat_in_bar(grid.beat(time)))).collect();
+        assert_eq!(bars, [(-1, 2), (0, 0), (2, 2), (3, 0)]);
+        assert!(BeatGrid::from_tempo(&Tempo { bpm: 0.0, ..tempo }).is_none());
+    }
+
+    #[test]
+    fn silence_has_no_beats() {
+        let silent = WavData {
+            sample_rate: 1000,
+            channels: 1,
+            samples: vec![0.0; 5000],
+        };
+        assert!(BeatGrid::detect(&silent, None).is_err());
+    }
+}
@@ -0,0 +1,137 @@
+use std::borrow::Cow;
+
+// Terminals lay text out left to right, so Hebrew and Arabic have to be put in display order
+// before they're drawn. This covers what lyrics need: a line's direction comes from its first
+// strong letter, runs of the other direction keep their own order inside it, and brackets are
+// mirrored where they're reversed. Arabic letters are not joined into their contextual forms.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Class {
+    Left,
+    Right,
+    Neutral,
+}
+
+fn is_rtl_char(c: char) -> bool {
+    matches!(c as u32, 0x0590..=0x08FF | 0xFB1D..=0xFDFF | 0xFE70..=0xFEFF | 0x10800..=0x10FFF | 0x1E800..=0x1EFFF)
+}
+
+fn class(c: char) -> Class {
+    if is_rtl_char(c) {
+        Class::Right
+    } else if c.is_alphanumeric() {
+        Class::Left
+    } else {
+        Class::Neutral
+    }
+}
+
+pub fn has_rtl(text: &str) -> bool {
+    text.chars().any(is_rtl_char)
+}
+
+// Lines whose first letter is Hebrew or Arabic read, and are sung, from right to left
+pub fn is_rtl(text: &str) -> bool {
+    text.chars().map(class).find(|class| *class != Class::Neutral) == Some(Class::Right)
+}
+
+fn mirror(c: char) -> char {
+    match c {
+        '(' => ')',
+        ')' => '(',
+        '[' => ']',
+        ']' => '[',
+        '{' => '}',
+        '}' => '{',
+        '<' => '>',
+        '>' => '<',
+        '«' => '»',
+        '»' => '«',
+        _ => c,
+    }
+}
+
+// Embedding levels per character: even ones read left to right, odd ones right to left
+fn levels(text: &str) -> Vec<u8> {
+    let classes: Vec<Class> = text.chars().map(class).collect();
+    let base = if is_rtl(text) { Class::Right } else { Class::Left };
+
+    // Neutrals between two letters of the same direction go with them, the rest with the line
+    (0..classes.len())
+        .map(|i| {
+            let resolved = match classes[i] {
+                Class::Neutral => {
+                    let before = classes[..i].iter().rev().find(|class| **class != Class::Neutral);
+                    let after = classes[i + 1..].iter().find(|class| **class != Class::Neutral);
+                    match (before, after) {
+                        (Some(before), Some(after)) if before == after => *before,
+                        _ => base,
+                    }
+                }
+                class => class,
+            };
+            match (base, resolved) {
+                (Class::Right, Class::Left) => 2,
+                (Class::Right, _) | (_, Class::Right) => 1,
+                _ => 0,
+            }
+        })
+        .collect()
+}
+
+// The character indices of `text` from left to right on screen
+pub fn visual_order(text: &str) -> Vec<usize> {
+    let levels = levels(text);
+    let mut order: Vec<usize> = (0..levels.len()).collect();
+    // From the highest level down to the lowest odd one, reverse every run at or above it
+    let highest = levels.iter().copied().max().unwrap_or(0);
+    for level in (1..=highest).rev() {
+        let mut i = 0;
+        while i < order.len() {
+            if levels[order[i]] < level {
+                i += 1;
+                continue;
+            }
+            let end = (i..order.len()).find(|&j| levels[order[j]] < level).unwrap_or(order.len());
+            order[i..end].reverse();
+            i = end;
+        }
+    }
+    order
+}
+
+// The line as it should appear on screen; plain left-to-right text is passed through
+pub fn visual(text: &str) -> Cow<'_, str> {
+    if !has_rtl(text) {
+        return Cow::Borrowed(text);
+    }
+    let chars: Vec<char> = text.chars().collect();
+    let levels = levels(text);
+    // Reversed brackets point the wrong way unless they're mirrored
+    visual_order(text)
+        .into_iter()
+        .map(|i| if levels[i] % 2 == 1 { mirror(chars[i]) } else { chars[i] })
+        .collect::<String>()
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hebrew_reads_right_to_left_around_latin_runs() {
+        assert!(is_rtl("שלום world") && !is_rtl("hello שלום") && !is_rtl("123"));
+        assert_eq!(visual("שלום"), "םולש");
+        assert_eq!(visual("שלום world!"), "!world םולש");
+        assert_eq!(visual("say שלום עולם now"), "say םלוע םולש now");
+        assert_eq!(visual("(שלום)"), "(םולש)");
+        assert!(matches!(visual("plain"), Cow::Borrowed("plain")));
+    }
+
+    #[test]
+    fn order_maps_screen_columns_back_to_characters() {
+        assert_eq!(visual_order("אב cd"), [3, 4, 2, 1, 0]);
+        assert_eq!(visual_order("ab"), [0, 1]);
+    }
+}
@@ -0,0 +1,284 @@
+use std::{collections::HashMap, fs, io, path::Path};
+
+// The built-in 5x7 pixel font; letters are drawn uppercase
+const GLYPH_HEIGHT: usize = 7;
+const MAX_SCALE: usize = 8;
+// Columns each built-in character takes at the smallest scale, with the gap after it
+pub const CHAR_COLUMNS: usize = 6;
+
+#[rustfmt::skip]
+const FONT: [(char, [&str; GLYPH_HEIGHT]); 51] = [
+    ('A', [".###.", "#...#", "#...#", "#####", "#...#", "#...#", "#...#"]),
+    ('B', ["####.", "#...#", "#...#", "####.", "#...#", "#...#", "####."]),
+    ('C', [".###.", "#...#", "#....", "#....", "#....", "#...#", ".###."]),
+    ('D', ["####.", "#...#", "#...#", "#...#", "#...#", "#...#", "####."]),
+    ('E', ["#####", "#....", "#....", "####.", "#....", "#....", "#####"]),
+    ('F', ["#####", "#....", "#....", "####.", "#....", "#....", "#...."]),
+    ('G', [".###.", "#...#", "#....", "#.###", "#...#", "#...#", ".####"]),
+    ('H', ["#...#", "#...#", "#...#", "#####", "#...#", "#...#", "#...#"]),
+    ('I', [".###.", "..#..", "..#..", "..#..", "..#..", "..#..", ".###."]),
+    ('J', ["..###", "...#.", "...#.", "...#.", "...#.", "#..#.", ".##.."]),
+    ('K', ["#...#", "#..#.", "#.#..", "##...", "#.#..", "#..#.", "#...#"]),
+    ('L', ["#....", "#....", "#....", "#....", "#....", "#....", "#####"]),
+    ('M', ["#...#", "##.##", "#.#.#", "#.#.#", "#...#", "#...#", "#...#"]),
+    ('N', ["#...#", "#...#", "##..#", "#.#.#", "#..##", "#...#", "#...#"]),
+    ('O', [".###.", "#...#", "#...#", "#...#", "#...#", "#...#", ".###."]),
+    ('P', ["####.", "#...#", "#...#", "####.", "#....", "#....", "#...."]),
+    ('Q', [".###.", "#...#", "#...#", "#...#", "#.#.#", "#..#.", ".##.#"]),
+    ('R', ["####.", "#...#", "#...#", "####.", "#.#..", "#..#.", "#...#"]),
+    ('S', [".####", "#....", "#....", ".###.", "....#", "....#", "####."]),
+    ('T', ["#####", "..#..", "..#..", "..#..", "..#..", "..#..", "..#.."]),
+    ('U', ["#...#", "#...#", "#...#", "#...#", "#...#", "#...#", ".###."]),
+    ('V', ["#...#", "#...#", "#...#", "#...#", "#...#", ".#.#.", "..#.."]),
+    ('W', ["#...#", "#...#", "#...#", "#.#.#", "#.#.#", "#.#.#", ".#.#."]),
+    ('X', ["#...#", "#...#", ".#.#.", "..#..", ".#.#.", "#...#", "#...#"]),
+    ('Y', ["#...#", "#...#", ".#.#.", "..#..", "..#..", "..#..", "..#.."]),
+    ('Z', ["#####", "....#", "...#.", "..#..", ".#...", "#....", "#####"]),
+    ('0', [".###.", "#...#", "#..##", "#.#.#", "##..#", "#...#", ".###."]),
+    ('1', ["..#..", ".##..", "..#..", "..#..", "..#..", "..#..", ".###."]),
+    ('2', [".###.", "#...#", "....#", "...#.", "..#..", ".#...", "#####"]),
+    ('3', ["####.", "....#", "....#", ".###.", "....#", "....#", "####."]),
+    ('4', ["...#.", "..##.", ".#.#.", "#..#.", "#####", "...#.", "...#."]),
+    ('5', ["#####", "#....", "####.", "....#", "....#", "#...#", ".###."]),
+    ('6', ["..##.", ".#...", "#....", "####.", "#...#", "#...#", ".###."]),
+    ('7', ["#####", "....#", "...#.", "..#..", ".#...", ".#...", ".#..."]),
+    ('8', [".###.", "#...#", "#...#", ".###.", "#...#", "#...#", ".###."]),
+    ('9', [".###.", "#...#", "#...#", ".####", "....#", "...#.", ".##.."]),
+    (' ', [".....", ".....", ".....", ".....", ".....", ".....", "....."]),
+    ('!', ["..#..", "..#..", "..#..", "..#..", "..#..", ".....", "..#.."]),
+    ('?', [".###.", "#...#", "....#", "...#.", "..#..", ".....", "..#.."]),
+    ('.', [".....", ".....", ".....", ".....", ".....", ".....", "..#.."]),
+    (',', [".....", ".....", ".....", ".....", ".....", "..#..", ".#..."]),
+    ('\'', ["..#..", "..#..", ".#...", ".....", ".....", ".....", "....."]),
+    ('"', [".#.#.", ".#.#.", ".....", ".....", ".....", ".....", "....."]),
+    ('-', [".....", ".....", ".....", ".###.", ".....", ".....", "....."]),
+    (':', [".....", "..#..", ".....", ".....", "..#..", ".....", "....."]),
+    (';', [".....", "..#..", ".....", ".....", "..#..", "..#..", ".#..."]),
+    ('(', ["...#.", "..#..", ".#...", ".#...", ".#...", "..#..", "...#."]),
+    (')', [".#...", "..#..", "...#.", "...#.", "...#.", "..#..", ".#..."]),
+    ('&', [".##..", "#..#.", "#.#..", ".#...", "#.#.#", "#..#.", ".##.#"]),
+    ('/', ["....#", "....#", "...#.", "..#..", ".#...", "#....", "#...."]),
+    ('*', [".....", "#.#.#", ".###.", "#####", ".###.", "#.#.#", "....."]),
+];
+
+fn glyph(c: char) -> Option<&'static [&'static str; GLYPH_HEIGHT]> {
+    let c = match c {
+        '’' | '‘' => '\'',
+        '“' | '”' => '"',
+        _ => c.to_ascii_uppercase(),
+    };
+    FONT.iter().find(|(ch, _)| *ch == c).map(|(_, rows)| rows)
+}
+
+// A FIGlet font (.flf); only the printable ASCII characters are read
+pub struct FigFont {
+    height: usize,
+    glyphs: HashMap<char, Vec<String>>,
+}
+
+impl FigFont {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        Self::parse(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path.display(), e)))
+    }
+
+    fn parse(text: &str) -> Result<Self, String> {
+        let mut lines = text.lines();
+        let header = lines.next().unwrap_or_default();
+        let signature = header.split_whitespace().next().unwrap_or_default();
+        let Some(hardblank) = signature.strip_prefix("flf2a").and_then(|rest| rest.chars().next()) else {
+            return Err("not a FIGlet font".to_string());
+        };
+        let numbers: Vec<usize> = header.split_whitespace().skip(1).map_while(|n| n.parse().ok()).collect();
+        let [height, _baseline, _max_length, _layout, comments, ..] = numbers[..] else {
+            return Err("incomplete FIGlet header".to_string());
+        };
+
+        let mut lines = lines.skip(comments);
+        let mut glyphs = HashMap::new();
+        for code in 32u8..=126 {
+            let rows: Vec<String> = lines
+                .by_ref()
+                .take(height)
+                .map(|row| {
+                    // Each row ends in one endmark, the last row of a character in two
+                    let endmark = row.chars().last().unwrap_or('@');
+                    row.trim_end_matches(endmark).replace(hardblank, " ")
+                })
+                .collect();
+            if rows.len() < height {
+                return Err(format!("font ends before character {:?}", code as char));
+            }
+            glyphs.insert(code as char, rows);
+        }
+        Ok(Self { height, glyphs })
+    }
+}
+
+// How a line is drawn: the built-in font at some scale, or a FIGlet font as is
+#[derive(Clone, Copy)]
+enum Face<'a> {
+    Pixels(usize),
+    Figlet(&'a FigFont),
+}
+
+impl Face<'_> {
+    fn render(&self, text: &str) -> Option<Vec<String>> {
+        match *self {
+            Face::Pixels(scale) => render_pixels(text, scale),
+            Face::Figlet(font) => {
+                let mut rows = vec![String::new(); font.height];
+                for c in text.chars() {
+                    let glyph = font.glyphs.get(&c)?;
+                    for (row, part) in rows.iter_mut().zip(glyph) {
+                        row.push_str(part);
+                    }
+                }
+                Some(rows)
+            }
+        }
+    }
+}
+
+// Two pixel rows per terminal row using half blocks, so scaled pixels come out about square
+fn render_pixels(text: &str, scale: usize) -> Option<Vec<String>> {
+    let glyphs: Vec<_> = text.chars().map(glyph).collect::<Option<_>>()?;
+    let pixel_rows: Vec<Vec<bool>> = (0..GLYPH_HEIGHT * scale)
+        .map(|y| {
+            let row = y / scale;
+            glyphs
+                .iter()
+                .enumerate()
+                .flat_map(|(i, glyph)| {
+                    let gap = if i == 0 { 0 } else { scale };
+                    std::iter::repeat_n(false, gap).chain(
+                        glyph[row]
+                            .chars()
+                            .flat_map(move |pixel| std::iter::repeat_n(pixel == '#', scale)),
+                    )
+                })
+                .collect()
+        })
+        .collect();
+
+    Some(
+        pixel_rows
+            .chunks(2)
+            .map(|pair| {
+                let bottom = pair.get(1);
+                (0..pair[0].len())
+                    .map(|x| match (pair[0][x], bottom.is_some_and(|row| row[x])) {
+                        (true, true) => '█',
+                        (true, false) => '▀',
+                        (false, true) => '▄',
+                        (false, false) => ' ',
+                    })
+                    .collect()
+            })
+            .collect(),
+    )
+}
+
+// The built-in font at its smallest scale, four rows tall, for a line shown bigger than the rest
+pub fn small(text: &str) -> Option<Vec<String>> {
+    render_pixels(text, 1)
+}
+
+fn width(rows: &[String]) -> usize {
+    rows.iter().map(|row| row.chars().count()).max().unwrap_or(0)
+}
+
+// Greedy word wrap by rendered width; None when a single word is already too wide
+fn wrap(face: Face, text: &str, max_width: usize) -> Option<Vec<Vec<String>>> {
+    let mut lines = Vec::new();
+    let mut current = String::new();
+    for word in text.split_whitespace() {
+        let candidate = if current.is_empty() { word.to_string() } else { format!("{} {}", current, word) };
+        if width(&face.render(&candidate)?) <= max_width {
+            current = candidate;
+        } else if current.is_empty() {
+            return None;
+        } else {
+            lines.push(face.render(&current)?);
+            current = word.to_string();
+            if width(&face.render(&current)?) > max_width {
+                return None;
+            }
+        }
+    }
+    if !current.is_empty() {
+        lines.push(face.render(&current)?);
+    }
+    Some(lines)
+}
+
+fn flatten(lines: Vec<Vec<String>>) -> Vec<String> {
+    lines.join(&[String::new()][..])
+}
+
+pub struct BigText {
+    pub current: Vec<String>,
+    pub next: Vec<String>,
+}
+
+// From the biggest face down
+fn faces(font: Option<&FigFont>) -> impl Iterator<Item = Face<'_>> {
+    font.map(Face::Figlet).into_iter().chain((1..=MAX_SCALE).rev().map(Face::Pixels))
+}
+
+// The biggest face both lines fit in; None when even the smallest can't show them, e.g. for
+// characters the font doesn't have
+pub fn layout(font: Option<&FigFont>, current: &str, next: &str, width: usize, height: usize) -> Option<BigText> {
+    faces(font).find_map(|face| {
+        let current = flatten(wrap(face, current, width)?);
+        let next = flatten(wrap(face, next, width)?);
+        let gap = usize::from(!current.is_empty() && !next.is_empty()) * 2;
+        (current.len() + gap + next.len() <= height).then_some(BigText { current, next })
+    })
+}
+
+// A single line in the biggest face it fits in
+pub fn fit(font: Option<&FigFont>, text: &str, width: usize, height: usize) -> Option<Vec<String>> {
+    faces(font).find_map(|face| Some(flatten(wrap(face, text, width)?)).filter(|rows| rows.len() <= height))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pixels_pair_up_into_half_blocks() {
+        let rows = render_pixels("i", 1).unwrap();
+        assert_eq!(rows, [" ▀█▀ ", "  █  ", "  █  ", " ▀▀▀ "]);
+        assert_eq!(render_pixels("ab", 2).unwrap()[0].chars().count(), 22);
+        assert!(render_pixels("歌", 1).is_none());
+    }
+
+    #[test]
+    fn picks_the_biggest_scale_that_fits() {
+        let big = layout(None, "hi", "", 40, 40).unwrap();
+        // Two letters at scale 3 are 33 columns; scale 4 would need 44
+        assert_eq!(width(&big.current), 33);
+        assert!(big.next.is_empty());
+    }
+
+    #[test]
+    fn long_lines_wrap_between_words() {
+        let big = layout(None, "one two", "three", 30, 100).unwrap();
+        // Each word on its own row at scale 1, with a blank row between them
+        assert_eq!(big.current.len(), 4 * 2 + 1);
+        assert!(layout(None, "unfittable", "", 5, 100).is_none());
+    }
+
+    #[test]
+    fn reads_figlet_fonts() {
+        let mut text = "flf2a$ 2 1 4 0 1\ncomment\n".to_string();
+        for code in 32u8..=126 {
+            let c = if code == b' ' { '$' } else { code as char };
+            text.push_str(&format!("{}{}@\n{}{}@@\n", c, c, c, c));
+        }
+        let font = FigFont::parse(&text).unwrap();
+        assert_eq!(Face::Figlet(&font).render("a b").unwrap(), ["aa  bb", "aa  bb"]);
+        assert!(FigFont::parse("hello").is_err());
+    }
+}
@@ -0,0 +1,54 @@
+use std::{
+    io,
+    process::{Command, Stdio},
+    thread,
+};
+
+// Casting is left to a command given the TV page's address. catt opens it on a Chromecast
+// through its web page receiver; anything else that shows a page on a TV works as well. The
+// page follows playback itself, so the terminal stays the controller.
+pub const DEFAULT_COMMAND: &str = "catt cast_site";
+pub const PAGE_PATH: &str = "/tv";
+
+// The program and its arguments: the device, the way catt takes it, before the rest
+fn arguments(command: &str, device: Option<&str>, url: &str) -> Option<(String, Vec<String>)> {
+    let mut parts = command.split_whitespace();
+    let program = parts.next()?.to_string();
+    let mut args: Vec<String> = device.map(|device| vec!["-d".to_string(), device.to_string()]).unwrap_or_default();
+    args.extend(parts.map(str::to_string));
+    args.push(url.to_string());
+    Some((program, args))
+}
+
+// Runs in the background; a cast that fails is logged, and the song plays on regardless
+pub fn start(command: &str, device: Option<&str>, url: &str) -> io::Result<()> {
+    let (program, args) = arguments(command, device, url).ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "the cast command is empty"))?;
+    let mut child = Command::new(&program)
+        .args(&args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| io::Error::new(e.kind(), format!("couldn't start the cast command `{}`: {}", command, e)))?;
+    let command = command.to_string();
+    thread::spawn(move || match child.wait() {
+        Ok(status) if !status.success() => log::warn!("the cast command `{}` failed: {}", command, status),
+        Err(e) => log::warn!("the cast command `{}` failed: {}", command, e),
+        Ok(_) => {}
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_device_goes_before_the_command_and_the_page_after() {
+        let (program, args) = arguments(DEFAULT_COMMAND, Some("Living Room TV"), "http://10.0.0.2:8080/tv").unwrap();
+        assert_eq!(program, "catt");
+        assert_eq!(args, ["-d", "Living Room TV", "cast_site", "http://10.0.0.2:8080/tv"]);
+        assert_eq!(arguments("open-on-tv", None, "http://x/tv").unwrap().1, ["http://x/tv"]);
+        assert!(arguments("  ", None, "http://x/tv").is_none());
+    }
+}
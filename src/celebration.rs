@@ -0,0 +1,168 @@
+use ratatui::{buffer::Buffer, layout::Rect, style::Color};
+use std::{
+    f64::consts::TAU,
+    time::{Duration, Instant},
+};
+
+const CONFETTI: [&str; 6] = ["▪", "●", "◆", "▲", "✦", "❖"];
+const SPARKS: [&str; 3] = ["✸", "*", "·"];
+const COLORS: [Color; 6] = [
+    Color::Rgb(255, 80, 80),
+    Color::Rgb(255, 200, 0),
+    Color::Rgb(0, 255, 0),
+    Color::Rgb(0, 200, 255),
+    Color::Rgb(200, 100, 255),
+    Color::Rgb(255, 0, 255),
+];
+// Rows a second; the pieces are spread over the whole range
+const FALL_SPEED: (f64, f64) = (6.0, 14.0);
+const FIREWORKS: usize = 5;
+const SPARKS_PER_BURST: usize = 16;
+// How long a burst's sparks fly after it goes off, and how fast they leave it in rows a second
+const BURST_TIME: f64 = 1.4;
+const SPARK_SPEED: f64 = 7.0;
+// Cells are about twice as tall as they're wide, so bursts are stretched sideways to look round
+const ASPECT: f64 = 2.0;
+// Lines sung well in a row before a burst of confetti, again at every multiple
+pub const STREAK_MILESTONE: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Effect {
+    Confetti,
+    Fireworks,
+}
+
+// Falling confetti or fireworks drawn over whatever is on screen, for a while
+#[derive(Debug, Clone)]
+pub struct Celebration {
+    pub effect: Effect,
+    pieces: usize,
+    length: Duration,
+    started: Instant,
+    seed: u64,
+}
+
+impl Celebration {
+    fn new(effect: Effect, pieces: usize, length: Duration, now: Instant) -> Self {
+        Self {
+            effect,
+            pieces,
+            length,
+            started: now,
+            seed: crate::quiz::Quiz::seed(),
+        }
+    }
+
+    // Four stars gets confetti and five gets fireworks; anything less isn't celebrated
+    pub fn for_stars(stars: usize, now: Instant) -> Option<Self> {
+        match stars {
+            5 => Some(Self::new(Effect::Fireworks, FIREWORKS, Duration::from_secs(5), now)),
+            4 => Some(Self::new(Effect::Confetti, 80, Duration::from_secs(5), now)),
+            _ => None,
+        }
+    }
+
+    // A short shower for a long streak, kept light so the lyrics stay readable
+    pub fn milestone(now: Instant) -> Self {
+        Self::new(Effect::Confetti, 25, Duration::from_secs(2), now)
+    }
+
+    pub fn is_over(&self, now: Instant) -> bool {
+        now.duration_since(self.started) >= self.length
+    }
+
+    // The same piece always gets the same numbers, so it moves smoothly from frame to frame
+    fn random(&self, piece: usize, salt: u64) -> f64 {
+        let mut x = self.seed ^ (piece as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15) ^ salt.wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        x ^= x >> 30;
+        x = x.wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        x ^= x >> 27;
+        x = x.wrapping_mul(0x94D0_49BB_1331_11EB);
+        x ^= x >> 31;
+        (x >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    // Each piece's cell, relative to the area, with what's drawn there
+    pub fn particles(&self, now: Instant, width: u16, height: u16) -> Vec<(u16, u16, &'static str, Color)> {
+        let elapsed = now.duration_since(self.started).as_secs_f64();
+        let (width, height) = (f64::from(width), f64::from(height));
+        let mut particles = Vec::new();
+        let mut place = |x: f64, y: f64, symbol, color| {
+            if (0.0..width).contains(&x) && (0.0..height).contains(&y) {
+                particles.push((x as u16, y as u16, symbol, color));
+            }
+        };
+        match self.effect {
+            // Dropped in over the first half, so the last pieces are still falling at the end
+            Effect::Confetti => {
+                let drop_time = self.length.as_secs_f64() / 2.0;
+                for piece in 0..self.pieces {
+                    let t = elapsed - self.random(piece, 1) * drop_time;
+                    if t < 0.0 {
+                        continue;
+                    }
+                    let speed = FALL_SPEED.0 + self.random(piece, 2) * (FALL_SPEED.1 - FALL_SPEED.0);
+                    let sway = (t * 3.0 + self.random(piece, 3) * TAU).sin() * 2.0;
+                    let x = self.random(piece, 4) * width + sway;
+                    let y = t * speed - 1.0;
+                    let symbol = CONFETTI[piece % CONFETTI.len()];
+                    place(x, y, symbol, COLORS[(self.random(piece, 5) * COLORS.len() as f64) as usize]);
+                }
+            }
+            Effect::Fireworks => {
+                let spacing = (self.length.as_secs_f64() - BURST_TIME) / self.pieces as f64;
+                for burst in 0..self.pieces {
+                    let t = elapsed - burst as f64 * spacing;
+                    if !(0.0..BURST_TIME).contains(&t) {
+                        continue;
+                    }
+                    let center_x = (0.15 + self.random(burst, 1) * 0.7) * width;
+                    let center_y = (0.15 + self.random(burst, 2) * 0.45) * height;
+                    let color = COLORS[(self.random(burst, 3) * COLORS.len() as f64) as usize];
+                    // Sparks slow down and droop as the burst fades
+                    let age = t / BURST_TIME;
+                    let symbol = SPARKS[((age * SPARKS.len() as f64) as usize).min(SPARKS.len() - 1)];
+                    let distance = SPARK_SPEED * t * (1.0 - age / 2.0);
+                    for spark in 0..SPARKS_PER_BURST {
+                        let angle = spark as f64 / SPARKS_PER_BURST as f64 * TAU;
+                        let x = center_x + angle.cos() * distance * ASPECT;
+                        let y = center_y + angle.sin() * distance + 2.0 * t * t;
+                        place(x, y, symbol, color);
+                    }
+                }
+            }
+        }
+        particles
+    }
+}
+
+// Only the cells with a piece in them change, so the screen underneath shows through
+pub fn render(buf: &mut Buffer, area: Rect, celebration: &Celebration, now: Instant) {
+    for (x, y, symbol, color) in celebration.particles(now, area.width, area.height) {
+        buf.get_mut(area.x + x, area.y + y).set_symbol(symbol).set_fg(color);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn high_scores_celebrate_inside_the_screen_for_a_while() {
+        let start = Instant::now();
+        assert!(Celebration::for_stars(3, start).is_none());
+        assert_eq!(Celebration::for_stars(4, start).map(|c| c.effect), Some(Effect::Confetti));
+        let fireworks = Celebration::for_stars(5, start).unwrap();
+        assert_eq!(fireworks.effect, Effect::Fireworks);
+
+        let at = |secs: f64| start + Duration::from_secs_f64(secs);
+        for celebration in [fireworks, Celebration::milestone(start)] {
+            let frames: Vec<_> = (0..20).map(|i| celebration.particles(at(i as f64 * 0.1), 40, 12)).collect();
+            assert!(frames.iter().any(|frame| !frame.is_empty()));
+            assert!(frames.iter().flatten().all(|&(x, y, _, _)| x < 40 && y < 12));
+            // The same moment draws the same picture
+            assert_eq!(celebration.particles(at(0.5), 40, 12), celebration.particles(at(0.5), 40, 12));
+            assert!(!celebration.is_over(at(1.0)) && celebration.is_over(at(5.0)));
+        }
+    }
+}
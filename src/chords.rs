@@ -0,0 +1,211 @@
+use serde::{Deserialize, Serialize};
+
+// A chord change at a character of the line's text
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Chord {
+    pub name: String,
+    pub position: usize,
+}
+
+// Note names with an optional accidental, e.g. F#
+fn note_len(name: &str) -> Option<usize> {
+    let mut chars = name.chars();
+    if !matches!(chars.next()?, 'A'..='G') {
+        return None;
+    }
+    Some(match chars.next() {
+        Some('#') | Some('b') => 2,
+        _ => 1,
+    })
+}
+
+// G, Am, C#m7, Bbmaj7, Dsus4, G/B, N.C. and the like
+pub fn is_chord(name: &str) -> bool {
+    if name == "N.C." {
+        return true;
+    }
+    let Some(root) = note_len(name) else {
+        return false;
+    };
+    let (quality, bass) = match name[root..].split_once('/') {
+        Some((quality, bass)) => (quality, Some(bass)),
+        None => (&name[root..], None),
+    };
+    let quality_ok = quality
+        .chars()
+        .all(|c| c.is_ascii_digit() || "majminsudgadd+-()#b°ø".contains(c));
+    let bass_ok = bass.is_none_or(|bass| note_len(bass) == Some(bass.len()));
+    quality_ok && bass_ok
+}
+
+// Takes ChordPro-style `[G]` chords out of an LRC line body. Positions count the characters of the
+// text as it will be shown: without word timestamps and leading spaces.
+pub fn extract(body: &str) -> (Vec<Chord>, String) {
+    let mut chords = Vec::new();
+    let mut text = String::with_capacity(body.len());
+    let mut visible = 0;
+    let mut rest = body;
+
+    while let Some(c) = rest.chars().next() {
+        let tag = match c {
+            '[' => rest.find(']'),
+            '<' => rest.find('>'),
+            _ => None,
+        }
+        .map(|close| (&rest[1..close], close));
+        match tag {
+            Some((name, close)) if c == '[' && is_chord(name) => {
+                chords.push(Chord {
+                    name: name.to_string(),
+                    position: visible,
+                });
+                rest = &rest[close + 1..];
+            }
+            // Word timestamps are kept for the line parser but take up no room on screen
+            Some((stamp, close)) if c == '<' && stamp.chars().all(|c| c.is_ascii_digit() || c == ':' || c == '.') => {
+                text.push_str(&rest[..=close]);
+                rest = &rest[close + 1..];
+            }
+            _ => {
+                if !(visible == 0 && c.is_whitespace()) {
+                    visible += 1;
+                }
+                text.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+    (chords, text)
+}
+
+// Puts chords back in front of their characters; `text` starts at character `offset` of the line
+pub fn insert(text: &str, chords: &[Chord], offset: usize) -> String {
+    let mut out = String::new();
+    for (i, c) in text.chars().enumerate() {
+        for chord in chords.iter().filter(|chord| chord.position == offset + i) {
+            out.push_str(&format!("[{}]", chord.name));
+        }
+        out.push(c);
+    }
+    out
+}
+
+// Chords after the last character of a line, e.g. on a closing bar
+pub fn trailing(chords: &[Chord], len: usize) -> String {
+    chords
+        .iter()
+        .filter(|chord| chord.position >= len)
+        .map(|chord| format!("[{}]", chord.name))
+        .collect()
+}
+
+const SHARP_NAMES: [&str; 12] = ["C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B"];
+const FLAT_NAMES: [&str; 12] = ["C", "Db", "D", "Eb", "E", "F", "Gb", "G", "Ab", "A", "Bb", "B"];
+// How each key is usually written, by the semitone of its root
+const MAJOR_KEYS: [&str; 12] = ["C", "Db", "D", "Eb", "E", "F", "F#", "G", "Ab", "A", "Bb", "B"];
+const MINOR_KEYS: [&str; 12] = ["C", "C#", "D", "Eb", "E", "F", "F#", "G", "G#", "A", "Bb", "B"];
+
+fn pitch(note: &str) -> Option<i32> {
+    let base: i32 = match note.chars().next()? {
+        'C' => 0,
+        'D' => 2,
+        'E' => 4,
+        'F' => 5,
+        'G' => 7,
+        'A' => 9,
+        'B' => 11,
+        _ => return None,
+    };
+    let accidental = match note[1..].chars().next() {
+        Some('#') => 1,
+        Some('b') => -1,
+        _ => 0,
+    };
+    Some((base + accidental).rem_euclid(12))
+}
+
+fn is_minor(quality: &str) -> bool {
+    quality.starts_with('m') && !quality.starts_with("maj")
+}
+
+// Keys whose signatures have flats are spelled with flats: F, Bb, Eb... and Dm, Gm, Cm...
+pub fn uses_flats(key: &str) -> bool {
+    let Some(root) = note_len(key) else {
+        return false;
+    };
+    let flat_root = match is_minor(&key[root..]) {
+        true => ["D", "G", "C", "F"].contains(&&key[..root]),
+        false => &key[..root] == "F",
+    };
+    key[..root].ends_with('b') || flat_root
+}
+
+// A song's key and the name it's usually given, e.g. A# major is written Bb
+pub fn transpose_key(key: &str, semitones: i32) -> String {
+    let Some(root) = note_len(key).filter(|_| semitones.rem_euclid(12) != 0) else {
+        return key.to_string();
+    };
+    let names = if is_minor(&key[root..]) { MINOR_KEYS } else { MAJOR_KEYS };
+    let pitch = pitch(key).unwrap_or(0) + semitones;
+    format!("{}{}", names[pitch.rem_euclid(12) as usize], &key[root..])
+}
+
+// The key of a song that doesn't say, guessed from its first chord
+pub fn guess_key<'a>(mut chords: impl Iterator<Item = &'a Chord>) -> Option<String> {
+    let name = &chords.find(|chord| note_len(&chord.name).is_some())?.name;
+    let root = note_len(name)?;
+    Some(format!("{}{}", &name[..root], if is_minor(&name[root..]) { "m" } else { "" }))
+}
+
+// Moves the root and bass note of a chord, spelling them with sharps or flats
+pub fn transpose(name: &str, semitones: i32, flats: bool) -> String {
+    let spell = |note: &str| {
+        let names = if flats { FLAT_NAMES } else { SHARP_NAMES };
+        names[(pitch(note).unwrap_or(0) + semitones).rem_euclid(12) as usize]
+    };
+    let Some(root) = note_len(name).filter(|_| semitones.rem_euclid(12) != 0) else {
+        return name.to_string();
+    };
+    match name[root..].split_once('/') {
+        Some((quality, bass)) if note_len(bass).is_some() => {
+            format!("{}{}/{}", spell(&name[..root]), quality, spell(bass))
+        }
+        _ => format!("{}{}", spell(&name[..root]), &name[root..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recognises_chords_but_not_words() {
+        for chord in ["G", "Am", "C#m7", "Bbmaj7", "Dsus4", "G/B", "Cadd9", "N.C."] {
+            assert!(is_chord(chord), "{}", chord);
+        }
+        for word in ["Chorus", "ar:Artist", "x", "Go", "G/H", ""] {
+            assert!(!is_chord(word), "{}", word);
+        }
+    }
+
+    #[test]
+    fn positions_skip_timestamps_and_leading_spaces() {
+        let (chords, text) = extract(" [G]Hel<00:01.50>lo [C]world");
+        assert_eq!(text, " Hel<00:01.50>lo world");
+        let positions: Vec<_> = chords.iter().map(|chord| (chord.name.as_str(), chord.position)).collect();
+        assert_eq!(positions, [("G", 0), ("C", 6)]);
+        assert_eq!(insert("Hello world", &chords, 0), "[G]Hello [C]world");
+        assert_eq!(insert("world", &chords, 6), "[C]world");
+    }
+
+    #[test]
+    fn transposing_spells_notes_for_the_new_key() {
+        assert_eq!(transpose("C#m7/G#", 2, false), "D#m7/A#");
+        assert_eq!(transpose("C#m7/G#", 2, true), "Ebm7/Bb");
+        assert_eq!(transpose("G", -12, true), "G");
+        assert_eq!((transpose_key("G", 3), transpose_key("Cm", -1), transpose_key("E", 2)), ("Bb".to_string(), "Bm".to_string(), "F#".to_string()));
+        assert!(uses_flats("Bb") && uses_flats("Dm") && uses_flats("F") && !uses_flats("Am") && !uses_flats("D"));
+        let chords = [Chord { name: "N.C.".to_string(), position: 0 }, Chord { name: "Ebm7".to_string(), position: 3 }];
+        assert_eq!(guess_key(chords.iter()).as_deref(), Some("Ebm"));
+    }
+}
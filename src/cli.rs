@@ -0,0 +1,1167 @@
+use std::path::PathBuf;
+
+use ratatui::style::Color;
+
+use crate::{announce, background::Theme, cast, compat::ColorDepth, daemon, formats::{self, bundle::Extras, encoding::Encoding}, highlight::HighlightStyle, history::{self, SortBy}, layout::WindowOptions, leaderboard, metronome, mic, mixdown::MixOptions, monitor, notifications, now_line::BarFormat, playback, providers, screen::CountIn, style, time_format::TimeFormat};
+
+pub const USAGE: &str = "\
+Usage:
+  karaoke-lyric [<song>] [--score <vocal.wav> | --tap] [--bpm <x>] [--beats <audio.wav>] [--audio <song.wav>]
+                [--play-audio] [--audio-device <name> | --audio-command <command>] [--volume <0-100>] [--audio-latency <seconds>] [--remove-vocals]
+                [--mic [--mic-command <command>] [--mic-gain <dB>] [--monitor [--monitor-device <name> | --monitor-command <command>] [--echo <0-100>]]] [--mouse] [--time-format auto|hours|tenths]
+                [--hook-command <command>] [--status-fd <fd|file>] [--notify [--notify-command <command>]] [--scrobble] [--osc <host:port> [--osc-prefix <address>]]
+                [--midi-in <device> | --midi-out <device> --bpm <x>]
+                [--sync-host <addr> | --sync-join <host:port>]
+                [--big-text | --vertical | --teleprompter [--lead <seconds>]] [--font <font.flf>] [--ball | --big-line]
+                [--lines <n>] [--center <row>] [--no-past-lines] [--fill left|right|center|fade]
+                [--no-splash | --countdown <seconds>] [--preview] [--outro <seconds>] [--fix-timing] [--watch] [--transpose <semitones>] [--capo <fret>]
+                [--metronome] [--click [--click-command <command>]] [--count-in <seconds>[s] | <beats>b | --resume-countdown]
+                [--output tui|plain] [--progress] [--output-file <file>] [--log <file>] [--encoding <name>]
+                [--title <text>] [--artist <text>] [--lrc-offset <ms>] [--library <dir>] [--singer <name>]
+                [--max-fps <fps>] [--colors truecolor|256|16] [--ascii]
+                [--announce [--announce-command <command>]] [--high-contrast] [--no-blink] [--no-confetti]
+                [--background flat|gradient|starfield|wave [--background-colors <color>[,<color>]] [--background-speed <x>]]
+                [--highlight solid|gradient|rainbow [--highlight-colors <color>,...]]
+                [--guide-vocal [--guide-command <command>] [--guide-lead <seconds>]] [--quiz | --typing]
+  karaoke-lyric serve [--bind <addr>] [--remote --token <token>] [--cast <device> [--cast-command <command>] | --cast-command <command>] [<song>] [play options]
+  karaoke-lyric --daemon [--daemon-socket <path>] [<song>] [play options]
+  karaoke-lyric now-line [--daemon-socket <path>] [--format plain|waybar|polybar] [--max-width <cells> [--scroll]]
+  karaoke-lyric party --player <name>... [--duet] [--session <file>] [--preview] [--play-audio [--crossfade <seconds>]] [--requests <addr> [--library <dir>]] [--no-confetti] [--attract <minutes>] [--panes] <song>...
+  karaoke-lyric party --setlist <file> [--session <file>] [--preview] [--play-audio [--crossfade <seconds>]] [--requests <addr> [--library <dir>]] [--no-confetti] [--attract <minutes>] [--panes]
+  karaoke-lyric party --resume <file> [--preview] [--play-audio [--crossfade <seconds>]] [--requests <addr> [--library <dir>]] [--no-confetti] [--attract <minutes>] [--panes]
+  karaoke-lyric search [--providers <name,...>] [--library <dir>] <query>... | --audio <song.wav>
+  karaoke-lyric fetch [--library <dir>] [--lang <code>] [--play] <url>
+  karaoke-lyric library import <dir> [--library <dir>] [--link] [--dry-run]
+  karaoke-lyric library export <dir> [--library <dir>] [--dry-run]
+  karaoke-lyric singer [<name> [--transpose <semitones>] [--mic-gain <dB>] [--favorite <song>]... [--unfavorite <song>]...]
+  karaoke-lyric setlist edit <setlist.json> [<song>...]
+  karaoke-lyric setlist export <setlist.json> <output.md|html>
+  karaoke-lyric leaderboard [--from <YYYY-MM-DD>] [--to <YYYY-MM-DD>] [--sort total|best|average|songs|name] [--export <file.csv|json>]
+  karaoke-lyric stats [--sort plays|completions|best|recent|title]
+  karaoke-lyric edit <song> [--output <file.lrc>] [--encoding <name>]
+  karaoke-lyric check <song> [--fix [--output <file.lrc>]] [--encoding <name>]
+  karaoke-lyric convert <song> <output> [--encoding <name>]
+  karaoke-lyric bundle <song> <output.karaoke> [--audio <song.wav>] [--translation <file>] [--cover <image>] [--encoding <name>]
+  karaoke-lyric retime <song> <output.lrc> [--shift <seconds>] [--scale <factor> | <from>/<to>] [--encoding <name>]
+  karaoke-lyric align <lyrics.txt> <vocal.wav> <output.lrc>
+  karaoke-lyric mixdown <vocal.wav> <backing.wav> <output.wav> [--vocal-gain <x>] [--backing-gain <x>] [--offset <seconds>]";
+
+#[derive(Default, Clone)]
+pub struct PlayOptions {
+    pub song: Option<PathBuf>,
+    pub score_recording: Option<PathBuf>,
+    pub tap_score: bool,
+    pub output: OutputMode,
+    pub show_progress: bool,
+    pub output_file: Option<PathBuf>,
+    pub serve: Option<String>,
+    pub remote: bool,
+    pub token: Option<String>,
+    pub bpm: Option<f64>,
+    pub beats_recording: Option<PathBuf>,
+    pub audio: Option<PathBuf>,
+    pub mic_command: Option<String>,
+    pub monitor: bool,
+    pub monitor_command: Option<String>,
+    pub monitor_device: Option<String>,
+    pub echo: Option<f64>,
+    pub mic_gain: Option<f64>,
+    // Whose profile the song is set up from and whose scores it adds to
+    pub singer: Option<String>,
+    pub mouse: bool,
+    pub time_format: TimeFormat,
+    pub hook_command: Option<String>,
+    pub status_fd: Option<String>,
+    pub notify_command: Option<String>,
+    // Shows the TV page on a Chromecast or smart TV, through the catt command by default
+    pub cast: Option<String>,
+    pub cast_command: Option<String>,
+    pub scrobble: bool,
+    pub osc: Option<String>,
+    pub osc_prefix: Option<String>,
+    pub midi_in: Option<PathBuf>,
+    pub midi_out: Option<PathBuf>,
+    pub sync_host: Option<String>,
+    pub sync_join: Option<String>,
+    pub big_text: bool,
+    pub ball: bool,
+    pub big_line: bool,
+    pub window: WindowOptions,
+    pub vertical: bool,
+    pub teleprompter: bool,
+    pub lead: Option<f64>,
+    pub font: Option<PathBuf>,
+    pub no_splash: bool,
+    pub preview: bool,
+    pub outro: Option<f64>,
+    pub countdown: Option<u64>,
+    pub fix_timing: bool,
+    pub watch: bool,
+    pub daemon: Option<PathBuf>,
+    // Given on the command line, over the singer's own
+    pub transpose: Option<i32>,
+    pub capo: u8,
+    pub metronome: bool,
+    pub click_command: Option<String>,
+    pub count_in: Option<CountIn>,
+    pub resume_countdown: bool,
+    pub log_file: Option<PathBuf>,
+    pub max_fps: Option<f64>,
+    pub colors: Option<ColorDepth>,
+    pub ascii: bool,
+    pub announce: bool,
+    pub announce_command: Option<String>,
+    pub high_contrast: bool,
+    pub no_blink: bool,
+    pub no_confetti: bool,
+    // Behind the lyrics, with the theme's colors and pace unless given
+    pub background: Theme,
+    pub background_colors: Vec<Color>,
+    pub background_speed: Option<f64>,
+    pub highlight: HighlightStyle,
+    pub highlight_colors: Vec<Color>,
+    pub guide_command: Option<String>,
+    pub guide_lead: Option<f64>,
+    pub quiz: bool,
+    pub typing: bool,
+    pub encoding: Option<Encoding>,
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub lrc_offset: Option<f64>,
+    // Searched for lyrics when only a recording is given
+    pub library: Option<PathBuf>,
+    pub play_audio: bool,
+    pub audio_device: Option<String>,
+    pub audio_command: Option<String>,
+    pub volume: Option<f64>,
+    pub audio_latency: Option<f64>,
+    pub remove_vocals: bool,
+}
+
+pub const DEFAULT_BIND: &str = "127.0.0.1:8080";
+
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
+pub enum OutputMode {
+    #[default]
+    Tui,
+    Plain,
+}
+
+impl std::str::FromStr for OutputMode {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "tui" => Ok(OutputMode::Tui),
+            "plain" => Ok(OutputMode::Plain),
+            _ => Err(()),
+        }
+    }
+}
+
+pub struct PartyOptions {
+    pub players: Vec<String>,
+    pub songs: Vec<PathBuf>,
+    pub duet: bool,
+    pub session: PathBuf,
+    pub resume: bool,
+    pub play_audio: bool,
+    pub crossfade: f64,
+    pub preview: bool,
+    // Players and songs come from a setlist instead, in its order and with its singers
+    pub setlist: Option<PathBuf>,
+    // Where guests' song request page is served, and the songs they pick from
+    pub requests: Option<String>,
+    pub library: PathBuf,
+    pub no_confetti: bool,
+    // Minutes without a song playing before the attract screen comes up
+    pub attract: Option<f64>,
+    // The scoreboard and the queue on either side of the lyrics, instead of the leaderboard alone
+    pub panes: bool,
+}
+
+#[derive(Clone)]
+pub struct SearchOptions {
+    pub query: String,
+    // A recording to search for instead, by its tags or else by how it sounds
+    pub audio: Option<PathBuf>,
+    pub providers: Vec<String>,
+    pub library: PathBuf,
+}
+
+pub struct FetchOptions {
+    pub url: String,
+    pub library: PathBuf,
+    pub lang: String,
+    pub play: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum LibraryAction {
+    Import,
+    Export,
+}
+
+pub enum SetlistAction {
+    // Songs given are added to the end of the setlist
+    Edit(Vec<PathBuf>),
+    Export(PathBuf),
+}
+
+pub struct LeaderboardOptions {
+    // Days as timestamps, both counting in full
+    pub from: Option<u64>,
+    pub until: Option<u64>,
+    pub sort: leaderboard::SortBy,
+    pub export: Option<PathBuf>,
+}
+
+pub struct SingerOptions {
+    // Without a name, every singer is listed
+    pub name: Option<String>,
+    pub transpose: Option<i32>,
+    pub mic_gain: Option<f64>,
+    pub favorite: Vec<PathBuf>,
+    pub unfavorite: Vec<PathBuf>,
+}
+
+pub struct LibraryOptions {
+    pub action: LibraryAction,
+    // Where songs are imported from or exported to
+    pub dir: PathBuf,
+    pub library: PathBuf,
+    pub link: bool,
+    pub dry_run: bool,
+}
+
+#[derive(Default)]
+pub struct NowLineOptions {
+    pub socket: PathBuf,
+    pub format: BarFormat,
+    pub max_width: Option<usize>,
+    pub scroll: bool,
+}
+
+pub enum Command {
+    Play(Box<PlayOptions>),
+    Party(PartyOptions),
+    Search(SearchOptions),
+    Fetch(FetchOptions),
+    Library(LibraryOptions),
+    Singer(SingerOptions),
+    Leaderboard(LeaderboardOptions),
+    Setlist {
+        setlist: PathBuf,
+        action: SetlistAction,
+    },
+    NowLine(NowLineOptions),
+    Mixdown {
+        vocal: PathBuf,
+        backing: PathBuf,
+        output: PathBuf,
+        options: MixOptions,
+    },
+    Align {
+        lyrics: PathBuf,
+        audio: PathBuf,
+        output: PathBuf,
+    },
+    Stats {
+        sort: SortBy,
+    },
+    Edit {
+        song: PathBuf,
+        output: Option<PathBuf>,
+        encoding: Option<Encoding>,
+    },
+    Check {
+        song: PathBuf,
+        fix: bool,
+        output: Option<PathBuf>,
+        encoding: Option<Encoding>,
+    },
+    Convert {
+        song: PathBuf,
+        output: PathBuf,
+        encoding: Option<Encoding>,
+    },
+    Bundle {
+        song: PathBuf,
+        output: PathBuf,
+        extras: Extras,
+        encoding: Option<Encoding>,
+    },
+    Retime {
+        song: PathBuf,
+        output: PathBuf,
+        shift: f64,
+        scale: f64,
+        encoding: Option<Encoding>,
+    },
+}
+
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Command, String> {
+    let mut args = args.into_iter().peekable();
+
+    match args.peek().map(String::as_str) {
+        Some("mixdown") => {
+            args.next();
+            parse_mixdown(args)
+        }
+        Some("serve") => {
+            args.next();
+            let mut command = parse_play(args)?;
+            if let Command::Play(options) = &mut command {
+                options.serve.get_or_insert_with(|| DEFAULT_BIND.to_string());
+            }
+            Ok(command)
+        }
+        Some("party") => {
+            args.next();
+            parse_party(args)
+        }
+        Some("align") => {
+            args.next();
+            let positional: Vec<PathBuf> = args.map(PathBuf::from).collect();
+            let [lyrics, audio, output]: [PathBuf; 3] = positional
+                .try_into()
+                .map_err(|_| "align expects <lyrics.txt> <vocal.wav> <output.lrc>".to_string())?;
+            Ok(Command::Align { lyrics, audio, output })
+        }
+        Some("leaderboard") => {
+            args.next();
+            parse_leaderboard(args)
+        }
+        Some("stats") => {
+            args.next();
+            let mut sort = SortBy::Plays;
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--sort" => sort = parse_value(&arg, args.next())?,
+                    _ => return Err(format!("unexpected argument: {}", arg)),
+                }
+            }
+            Ok(Command::Stats { sort })
+        }
+        Some("edit") => {
+            args.next();
+            let mut song = None;
+            let mut output = None;
+            let mut encoding = None;
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--output" => output = Some(parse_value(&arg, args.next())?),
+                    "--encoding" => encoding = Some(parse_value(&arg, args.next())?),
+                    _ if arg.starts_with("--") => return Err(format!("unknown option: {}", arg)),
+                    _ if song.is_none() => song = Some(PathBuf::from(arg)),
+                    _ => return Err(format!("unexpected argument: {}", arg)),
+                }
+            }
+            let song = song.ok_or_else(|| "edit expects a lyrics file".to_string())?;
+            Ok(Command::Edit { song, output, encoding })
+        }
+        Some("check") => {
+            args.next();
+            let mut song = None;
+            let mut fix = false;
+            let mut output = None;
+            let mut encoding = None;
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--fix" => fix = true,
+                    "--output" => output = Some(parse_value(&arg, args.next())?),
+                    "--encoding" => encoding = Some(parse_value(&arg, args.next())?),
+                    _ if arg.starts_with("--") => return Err(format!("unknown option: {}", arg)),
+                    _ if song.is_none() => song = Some(PathBuf::from(arg)),
+                    _ => return Err(format!("unexpected argument: {}", arg)),
+                }
+            }
+            let song = song.ok_or_else(|| "check expects a lyrics file".to_string())?;
+            if output.is_some() && !fix {
+                return Err("--output requires --fix".to_string());
+            }
+            Ok(Command::Check { song, fix, output, encoding })
+        }
+        Some("convert") => {
+            args.next();
+            let mut positional = Vec::new();
+            let mut encoding = None;
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--encoding" => encoding = Some(parse_value(&arg, args.next())?),
+                    _ if arg.starts_with("--") => return Err(format!("unknown option: {}", arg)),
+                    _ => positional.push(PathBuf::from(arg)),
+                }
+            }
+            let [song, output]: [PathBuf; 2] = positional
+                .try_into()
+                .map_err(|_| "convert expects <song> <output>".to_string())?;
+            Ok(Command::Convert { song, output, encoding })
+        }
+        Some("bundle") => {
+            args.next();
+            let mut positional = Vec::new();
+            let mut extras = Extras {
+                audio: None,
+                translation: None,
+                cover: None,
+            };
+            let mut encoding = None;
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--audio" => extras.audio = Some(parse_value(&arg, args.next())?),
+                    "--translation" => extras.translation = Some(parse_value(&arg, args.next())?),
+                    "--cover" => extras.cover = Some(parse_value(&arg, args.next())?),
+                    "--encoding" => encoding = Some(parse_value(&arg, args.next())?),
+                    _ if arg.starts_with("--") => return Err(format!("unknown option: {}", arg)),
+                    _ => positional.push(PathBuf::from(arg)),
+                }
+            }
+            let [song, output]: [PathBuf; 2] = positional
+                .try_into()
+                .map_err(|_| "bundle expects <song> <output.karaoke>".to_string())?;
+            if !formats::bundle::is_bundle(&output) {
+                return Err("bundles are written to .karaoke files".to_string());
+            }
+            Ok(Command::Bundle { song, output, extras, encoding })
+        }
+        Some("retime") => {
+            args.next();
+            parse_retime(args)
+        }
+        Some("search") => {
+            args.next();
+            parse_search(args)
+        }
+        Some("fetch") => {
+            args.next();
+            parse_fetch(args)
+        }
+        Some("library") => {
+            args.next();
+            parse_library(args)
+        }
+        Some("singer") => {
+            args.next();
+            parse_singer(args)
+        }
+        Some("setlist") => {
+            args.next();
+            parse_setlist(args)
+        }
+        Some("now-line") => {
+            args.next();
+            parse_now_line(args)
+        }
+        _ => parse_play(args),
+    }
+}
+
+fn parse_play(mut args: impl Iterator<Item = String>) -> Result<Command, String> {
+    let mut options = PlayOptions::default();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--score" => options.score_recording = Some(parse_value(&arg, args.next())?),
+            "--tap" => options.tap_score = true,
+            "--output" => options.output = parse_value(&arg, args.next())?,
+            "--progress" => options.show_progress = true,
+            "--output-file" => options.output_file = Some(parse_value(&arg, args.next())?),
+            "--bind" => options.serve = Some(parse_value(&arg, args.next())?),
+            "--remote" => options.remote = true,
+            "--token" => options.token = Some(parse_value(&arg, args.next())?),
+            "--bpm" => options.bpm = Some(parse_value(&arg, args.next())?),
+            "--beats" => options.beats_recording = Some(parse_value(&arg, args.next())?),
+            "--audio" => options.audio = Some(parse_value(&arg, args.next())?),
+            "--play-audio" => options.play_audio = true,
+            "--audio-device" => options.audio_device = Some(parse_value(&arg, args.next())?),
+            "--audio-command" => options.audio_command = Some(parse_value(&arg, args.next())?),
+            "--volume" => options.volume = Some(parse_value(&arg, args.next())?),
+            "--audio-latency" => options.audio_latency = Some(parse_value(&arg, args.next())?),
+            "--remove-vocals" => options.remove_vocals = true,
+            "--mic" => {
+                options.mic_command.get_or_insert_with(|| mic::DEFAULT_COMMAND.to_string());
+            }
+            "--mouse" => options.mouse = true,
+            "--midi-in" => options.midi_in = Some(parse_value(&arg, args.next())?),
+            "--midi-out" => options.midi_out = Some(parse_value(&arg, args.next())?),
+            "--sync-host" => options.sync_host = Some(parse_value(&arg, args.next())?),
+            "--sync-join" => options.sync_join = Some(parse_value(&arg, args.next())?),
+            "--big-text" => options.big_text = true,
+            "--ball" => options.ball = true,
+            "--big-line" => options.big_line = true,
+            "--lines" => options.window.lines = Some(parse_value(&arg, args.next())?),
+            "--center" => options.window.center = Some(parse_value(&arg, args.next())?),
+            "--no-past-lines" => options.window.hide_past_lines = true,
+            "--fill" => options.window.fill = Some(parse_value(&arg, args.next())?),
+            "--vertical" => options.vertical = true,
+            "--teleprompter" => options.teleprompter = true,
+            "--lead" => options.lead = Some(parse_value(&arg, args.next())?),
+            "--font" => options.font = Some(parse_value(&arg, args.next())?),
+            "--no-splash" => options.no_splash = true,
+            "--preview" => options.preview = true,
+            "--outro" => options.outro = Some(parse_value(&arg, args.next())?),
+            "--countdown" => options.countdown = Some(parse_value(&arg, args.next())?),
+            "--fix-timing" => options.fix_timing = true,
+            "--watch" => options.watch = true,
+            "--daemon" => {
+                options.daemon.get_or_insert_with(daemon::default_socket);
+            }
+            "--daemon-socket" => options.daemon = Some(parse_value(&arg, args.next())?),
+            "--transpose" => options.transpose = Some(parse_value(&arg, args.next())?),
+            "--capo" => options.capo = parse_value(&arg, args.next())?,
+            "--metronome" => options.metronome = true,
+            "--click" => {
+                options.click_command.get_or_insert_with(|| metronome::DEFAULT_COMMAND.to_string());
+            }
+            "--click-command" => options.click_command = Some(parse_value(&arg, args.next())?),
+            "--count-in" => options.count_in = Some(parse_value(&arg, args.next())?),
+            "--resume-countdown" => options.resume_countdown = true,
+            "--encoding" => options.encoding = Some(parse_value(&arg, args.next())?),
+            "--title" => options.title = Some(parse_value(&arg, args.next())?),
+            "--artist" => options.artist = Some(parse_value(&arg, args.next())?),
+            "--lrc-offset" => options.lrc_offset = Some(parse_value(&arg, args.next())?),
+            "--log" => options.log_file = Some(parse_value(&arg, args.next())?),
+            "--max-fps" => options.max_fps = Some(parse_value(&arg, args.next())?),
+            "--colors" => options.colors = Some(parse_value(&arg, args.next())?),
+            "--ascii" => options.ascii = true,
+            "--announce" => options.announce = true,
+            "--announce-command" => {
+                options.announce = true;
+                options.announce_command = Some(parse_value(&arg, args.next())?);
+            }
+            "--high-contrast" => options.high_contrast = true,
+            "--no-blink" => options.no_blink = true,
+            "--no-confetti" => options.no_confetti = true,
+            "--background" => options.background = parse_value(&arg, args.next())?,
+            "--background-colors" => options.background_colors = parse_colors(&arg, args.next())?,
+            "--background-speed" => options.background_speed = Some(parse_value(&arg, args.next())?),
+            "--highlight" => options.highlight = parse_value(&arg, args.next())?,
+            "--highlight-colors" => options.highlight_colors = parse_colors(&arg, args.next())?,
+            "--guide-vocal" => {
+                options.guide_command.get_or_insert_with(|| announce::DEFAULT_COMMAND.to_string());
+            }
+            "--guide-command" => options.guide_command = Some(parse_value(&arg, args.next())?),
+            "--guide-lead" => options.guide_lead = Some(parse_value(&arg, args.next())?),
+            "--quiz" => options.quiz = true,
+            "--typing" => options.typing = true,
+            "--osc" => options.osc = Some(parse_value(&arg, args.next())?),
+            "--osc-prefix" => options.osc_prefix = Some(parse_value(&arg, args.next())?),
+            "--hook-command" => options.hook_command = Some(parse_value(&arg, args.next())?),
+            "--status-fd" => options.status_fd = Some(parse_value(&arg, args.next())?),
+            "--notify" => {
+                options.notify_command.get_or_insert_with(|| notifications::DEFAULT_COMMAND.to_string());
+            }
+            "--notify-command" => options.notify_command = Some(parse_value(&arg, args.next())?),
+            "--cast" => {
+                options.cast = Some(parse_value(&arg, args.next())?);
+                options.cast_command.get_or_insert_with(|| cast::DEFAULT_COMMAND.to_string());
+            }
+            "--cast-command" => options.cast_command = Some(parse_value(&arg, args.next())?),
+            "--scrobble" => options.scrobble = true,
+            "--library" => options.library = Some(parse_value(&arg, args.next())?),
+            "--time-format" => options.time_format = parse_value(&arg, args.next())?,
+            "--mic-command" => options.mic_command = Some(parse_value(&arg, args.next())?),
+            "--monitor" => options.monitor = true,
+            "--monitor-device" => options.monitor_device = Some(parse_value(&arg, args.next())?),
+            "--monitor-command" => options.monitor_command = Some(parse_value(&arg, args.next())?),
+            "--echo" => options.echo = Some(parse_value(&arg, args.next())?),
+            "--mic-gain" => options.mic_gain = Some(parse_value(&arg, args.next())?),
+            "--singer" => options.singer = Some(parse_value(&arg, args.next())?),
+            _ if arg.starts_with("--") => return Err(format!("unknown option: {}", arg)),
+            _ if options.song.is_none() => options.song = Some(PathBuf::from(arg)),
+            _ => return Err(format!("unexpected argument: {}", arg)),
+        }
+    }
+
+    if options.tap_score && options.score_recording.is_some() {
+        return Err("--score and --tap cannot be combined".to_string());
+    }
+
+    if options.quiz && options.typing {
+        return Err("--quiz and --typing cannot be combined".to_string());
+    }
+
+    if options.typing && (options.tap_score || options.output == OutputMode::Plain) {
+        return Err("--typing is typed into the TUI and can't be combined with --tap or --output plain".to_string());
+    }
+
+    if options.quiz && (options.tap_score || options.output == OutputMode::Plain) {
+        return Err("--quiz is typed into the TUI and can't be combined with --tap or --output plain".to_string());
+    }
+
+    if options.output == OutputMode::Tui && (options.show_progress || options.output_file.is_some()) {
+        return Err("--progress and --output-file require --output plain".to_string());
+    }
+
+    // Announcements are spoken over the TUI; in plain output they can be printed instead
+    if options.announce && options.output == OutputMode::Tui {
+        options.announce_command.get_or_insert_with(|| announce::DEFAULT_COMMAND.to_string());
+    }
+
+    if options.announce && options.announce_command.is_none() && (options.show_progress || options.output_file.is_some()) {
+        return Err("printed announcements replace --progress and --output-file".to_string());
+    }
+
+    // A recording given as the song is played with whichever lyrics match it
+    if options.audio.is_none() && options.song.as_ref().is_some_and(|song| song.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("wav"))) {
+        options.audio = options.song.take();
+    }
+
+    if options.library.is_some() && (options.song.is_some() || options.audio.is_none()) {
+        return Err("--library is searched for lyrics to a recording given without them".to_string());
+    }
+
+    // Picking a player is asking for playback
+    options.play_audio |= options.audio_device.is_some() || options.audio_command.is_some();
+    if options.play_audio && options.audio.is_none() {
+        return Err("--play-audio, --audio-device and --audio-command require --audio".to_string());
+    }
+
+    if options.audio_device.is_some() && options.audio_command.is_some() {
+        return Err("--audio-device picks the device for the default player; put it in --audio-command instead".to_string());
+    }
+
+    if (options.volume.is_some() || options.audio_latency.is_some() || options.remove_vocals) && !options.play_audio {
+        return Err("--volume, --audio-latency and --remove-vocals require --play-audio".to_string());
+    }
+
+    if options.volume.is_some_and(|volume| !(0.0..=100.0).contains(&volume)) {
+        return Err("--volume must be between 0 and 100".to_string());
+    }
+
+    if options.audio_latency.is_some_and(|latency| !(-2.0..=2.0).contains(&latency)) {
+        return Err("--audio-latency must be between -2 and 2 seconds".to_string());
+    }
+
+    if options.outro.is_some_and(|outro| !(0.0..=60.0).contains(&outro)) {
+        return Err("--outro must be between 0 and 60 seconds".to_string());
+    }
+
+    options.monitor |= options.monitor_device.is_some() || options.monitor_command.is_some();
+    if options.monitor && options.mic_command.is_none() {
+        return Err("--monitor, --monitor-device and --monitor-command require --mic".to_string());
+    }
+
+    if options.monitor_device.is_some() && options.monitor_command.is_some() {
+        return Err("--monitor-device picks the device for the default player; put it in --monitor-command instead".to_string());
+    }
+
+    if options.echo.is_some() && !options.monitor {
+        return Err("--echo requires --monitor".to_string());
+    }
+
+    if options.echo.is_some_and(|echo| !(0.0..=100.0).contains(&echo)) {
+        return Err("--echo must be between 0 and 100".to_string());
+    }
+
+    // The player to run, if monitoring
+    if options.monitor {
+        options.monitor_command = Some(monitor::command(options.monitor_command.as_deref(), options.monitor_device.as_deref()));
+    }
+
+    if options.guide_lead.is_some() && options.guide_command.is_none() {
+        return Err("--guide-lead requires --guide-vocal".to_string());
+    }
+
+    if options.guide_lead.is_some_and(|lead| !(0.0..=10.0).contains(&lead)) {
+        return Err("--guide-lead must be between 0 and 10 seconds".to_string());
+    }
+
+    if options.bpm.is_some_and(|bpm| !bpm.is_finite() || bpm <= 0.0) {
+        return Err("--bpm must be positive".to_string());
+    }
+
+    if options.max_fps.is_some_and(|fps| !(1.0..=240.0).contains(&fps)) {
+        return Err("--max-fps must be between 1 and 240".to_string());
+    }
+
+    if options.sync_join.is_some() && (options.sync_host.is_some() || options.song.is_some()) {
+        return Err("--sync-join plays the host's song and can't host or take a song of its own".to_string());
+    }
+
+    if options.sync_join.is_some() && options.output == OutputMode::Plain {
+        return Err("--sync-join needs the TUI and can't be combined with --output plain".to_string());
+    }
+
+    if options.watch && (options.song.is_none() || options.output == OutputMode::Plain) {
+        return Err("--watch needs a song and can't be combined with --output plain".to_string());
+    }
+
+    if options.daemon.is_some()
+        && (options.output == OutputMode::Plain
+            || options.sync_join.is_some()
+            || options.mic_command.is_some()
+            || options.mouse
+            || options.quiz
+            || options.typing
+            || options.preview)
+    {
+        return Err("--daemon runs without a terminal and can't be combined with --output plain, --sync-join, --mic, --mouse, --quiz, --typing or --preview".to_string());
+    }
+
+    if options.no_splash && options.countdown.is_some() {
+        return Err("--no-splash and --countdown cannot be combined".to_string());
+    }
+
+    if options.mic_gain.is_some() && options.mic_command.is_none() {
+        return Err("--mic-gain requires --mic".to_string());
+    }
+
+    if options.mic_gain.is_some_and(|gain| !(-24.0..=24.0).contains(&gain)) {
+        return Err("--mic-gain must be between -24 and 24 dB".to_string());
+    }
+
+    if options.transpose.is_some_and(|transpose| !(-11..=11).contains(&transpose)) || options.capo > 11 {
+        return Err("--transpose must be between -11 and 11 and --capo at most 11".to_string());
+    }
+
+    if options.background_colors.len() > 2 {
+        return Err("--background-colors takes one or two colors".to_string());
+    }
+
+    if options.background_speed.is_some_and(|speed| !(0.0..=10.0).contains(&speed)) {
+        return Err("--background-speed must be between 0 and 10".to_string());
+    }
+
+    if !options.highlight_colors.is_empty() && options.highlight != HighlightStyle::Gradient {
+        return Err("--highlight-colors requires --highlight gradient".to_string());
+    }
+
+    if options.font.is_some() && !options.big_text && !options.teleprompter {
+        return Err("--font requires --big-text or --teleprompter".to_string());
+    }
+
+    if options.vertical && options.big_text {
+        return Err("--vertical and --big-text cannot be combined".to_string());
+    }
+
+    if options.teleprompter && (options.big_text || options.vertical) {
+        return Err("--teleprompter can't be combined with --big-text or --vertical".to_string());
+    }
+
+    if options.ball && (options.big_text || options.vertical || options.teleprompter) {
+        return Err("--ball can't be combined with --big-text, --vertical or --teleprompter".to_string());
+    }
+
+    if options.big_line && (options.ball || options.big_text || options.vertical || options.teleprompter) {
+        return Err("--big-line can't be combined with --ball, --big-text, --vertical or --teleprompter".to_string());
+    }
+
+    if options.window.lines.is_some_and(|lines| !(1..=21).contains(&lines)) {
+        return Err("--lines must be between 1 and 21".to_string());
+    }
+
+    if options.window.center.is_some_and(|center| center < 1 || center > options.window.lines.unwrap_or(21)) {
+        return Err("--center must be a row of the window, from 1 at the top".to_string());
+    }
+
+    if options.lead.is_some() && !options.teleprompter {
+        return Err("--lead requires --teleprompter".to_string());
+    }
+
+    if options.lead.is_some_and(|lead| !(0.0..=30.0).contains(&lead)) {
+        return Err("--lead must be between 0 and 30 seconds".to_string());
+    }
+
+    if options.midi_in.is_some() && options.midi_out.is_some() {
+        return Err("--midi-in and --midi-out cannot be combined".to_string());
+    }
+
+    if options.midi_out.is_some() && options.bpm.is_none() {
+        return Err("--midi-out needs the song's tempo from --bpm".to_string());
+    }
+
+    if options.osc_prefix.as_ref().is_some_and(|prefix| !prefix.starts_with('/')) {
+        return Err("--osc-prefix must start with /".to_string());
+    }
+
+    if options.osc_prefix.is_some() && options.osc.is_none() {
+        return Err("--osc-prefix requires --osc".to_string());
+    }
+
+    if options.token.is_some() && !options.remote {
+        return Err("--token requires --remote".to_string());
+    }
+
+    // Anything that can reach the port could drive the player otherwise
+    if options.remote && options.token.is_none() {
+        return Err("--remote requires --token".to_string());
+    }
+
+    if options.remote && options.output == OutputMode::Plain {
+        return Err("--remote controls the TUI and can't be combined with --output plain".to_string());
+    }
+
+    if options.remote && options.serve.is_none() {
+        options.serve = Some(DEFAULT_BIND.to_string());
+    }
+
+    // The TV has to be able to reach the page
+    if options.cast_command.is_some() {
+        let bind = options.serve.as_deref().unwrap_or(DEFAULT_BIND);
+        let local = bind.parse::<std::net::SocketAddr>().map_or(bind.starts_with("localhost"), |addr| addr.ip().is_loopback());
+        if options.serve.is_none() || local {
+            return Err("--cast needs `serve --bind <addr>` on an address the TV can reach, e.g. 0.0.0.0:8080".to_string());
+        }
+    }
+
+    Ok(Command::Play(Box::new(options)))
+}
+
+fn parse_party(mut args: impl Iterator<Item = String>) -> Result<Command, String> {
+    let mut options = PartyOptions {
+        players: Vec::new(),
+        songs: Vec::new(),
+        duet: false,
+        session: PathBuf::from("party-session.json"),
+        resume: false,
+        play_audio: false,
+        crossfade: playback::DEFAULT_CROSSFADE,
+        preview: false,
+        setlist: None,
+        requests: None,
+        library: PathBuf::from("library"),
+        no_confetti: false,
+        attract: None,
+        panes: false,
+    };
+    let mut crossfade = None;
+    let mut library_given = false;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--player" => options.players.push(parse_value(&arg, args.next())?),
+            "--duet" => options.duet = true,
+            "--play-audio" => options.play_audio = true,
+            "--preview" => options.preview = true,
+            "--no-confetti" => options.no_confetti = true,
+            "--panes" => options.panes = true,
+            "--attract" => options.attract = Some(parse_value(&arg, args.next())?),
+            "--crossfade" => crossfade = Some(parse_value(&arg, args.next())?),
+            "--session" => options.session = parse_value(&arg, args.next())?,
+            "--setlist" => options.setlist = Some(parse_value(&arg, args.next())?),
+            "--requests" => options.requests = Some(parse_value(&arg, args.next())?),
+            "--library" => {
+                options.library = parse_value(&arg, args.next())?;
+                library_given = true;
+            }
+            "--resume" => {
+                options.session = parse_value(&arg, args.next())?;
+                options.resume = true;
+            }
+            _ if arg.starts_with("--") => return Err(format!("unknown option: {}", arg)),
+            _ => options.songs.push(PathBuf::from(arg)),
+        }
+    }
+
+    if options.resume {
+        if !options.players.is_empty() || !options.songs.is_empty() || options.setlist.is_some() {
+            return Err("--resume takes players and songs from the saved session".to_string());
+        }
+    } else if options.setlist.is_some() {
+        if !options.players.is_empty() || !options.songs.is_empty() || options.duet {
+            return Err("--setlist takes players, songs and who sings them from the setlist".to_string());
+        }
+    } else if options.players.is_empty() || options.songs.is_empty() {
+        return Err("party needs at least one --player and one song".to_string());
+    }
+
+    if options.attract.is_some_and(|minutes| !minutes.is_finite() || minutes <= 0.0 || minutes > 24.0 * 60.0) {
+        return Err("--attract must be a positive number of minutes, up to a day".to_string());
+    }
+
+    if library_given && options.requests.is_none() {
+        return Err("--library requires --requests".to_string());
+    }
+
+    match crossfade {
+        Some(_) if !options.play_audio => return Err("--crossfade requires --play-audio".to_string()),
+        Some(seconds) if !(0.0..=30.0).contains(&seconds) => return Err("--crossfade must be between 0 and 30 seconds".to_string()),
+        Some(seconds) => options.crossfade = seconds,
+        None => {}
+    }
+
+    Ok(Command::Party(options))
+}
+
+fn parse_search(mut args: impl Iterator<Item = String>) -> Result<Command, String> {
+    let mut words = Vec::new();
+    let mut provider_list = providers::DEFAULT_PRIORITY.to_string();
+    let mut library = PathBuf::from("library");
+    let mut audio = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--providers" => provider_list = parse_value(&arg, args.next())?,
+            "--library" => library = parse_value(&arg, args.next())?,
+            "--audio" => audio = Some(parse_value(&arg, args.next())?),
+            _ if arg.starts_with("--") => return Err(format!("unknown option: {}", arg)),
+            _ => words.push(arg),
+        }
+    }
+
+    let names: Vec<String> = provider_list.split(',').map(|name| name.trim().to_ascii_lowercase()).collect();
+    if let Some(unknown) = names.iter().find(|name| !providers::PROVIDER_NAMES.contains(&name.as_str())) {
+        return Err(format!(
+            "unknown provider: {} (expected {})",
+            unknown,
+            providers::PROVIDER_NAMES.join(", ")
+        ));
+    }
+
+    match (words.is_empty(), audio.is_some()) {
+        (true, false) => return Err("search needs a query or --audio".to_string()),
+        (false, true) => return Err("search takes either a query or --audio".to_string()),
+        _ => {}
+    }
+
+    Ok(Command::Search(SearchOptions {
+        query: words.join(" "),
+        audio,
+        providers: names,
+        library,
+    }))
+}
+
+fn parse_fetch(mut args: impl Iterator<Item = String>) -> Result<Command, String> {
+    let mut urls = Vec::new();
+    let mut options = FetchOptions {
+        url: String::new(),
+        library: PathBuf::from("library"),
+        lang: "en".to_string(),
+        play: false,
+    };
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--library" => options.library = parse_value(&arg, args.next())?,
+            "--lang" => options.lang = parse_value(&arg, args.next())?,
+            "--play" => options.play = true,
+            _ if arg.starts_with("--") => return Err(format!("unknown option: {}", arg)),
+            _ => urls.push(arg),
+        }
+    }
+
+    let [url]: [String; 1] = urls.try_into().map_err(|_| "fetch expects one <url>".to_string())?;
+    options.url = url;
+    Ok(Command::Fetch(options))
+}
+
+fn parse_library(mut args: impl Iterator<Item = String>) -> Result<Command, String> {
+    let action = match args.next().as_deref() {
+        Some("import") => LibraryAction::Import,
+        Some("export") => LibraryAction::Export,
+        _ => return Err("library expects import or export".to_string()),
+    };
+    let mut dirs = Vec::new();
+    let (mut library, mut link, mut dry_run) = (PathBuf::from("library"), false, false);
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--library" => library = parse_value(&arg, args.next())?,
+            "--link" if action == LibraryAction::Import => link = true,
+            "--dry-run" => dry_run = true,
+            _ if arg.starts_with("--") => return Err(format!("unknown option: {}", arg)),
+            _ => dirs.push(PathBuf::from(arg)),
+        }
+    }
+
+    let [dir]: [PathBuf; 1] = dirs.try_into().map_err(|_| "library import and export expect one <dir>".to_string())?;
+    Ok(Command::Library(LibraryOptions {
+        action,
+        dir,
+        library,
+        link,
+        dry_run,
+    }))
+}
+
+fn parse_leaderboard(mut args: impl Iterator<Item = String>) -> Result<Command, String> {
+    let mut options = LeaderboardOptions {
+        from: None,
+        until: None,
+        sort: leaderboard::SortBy::Total,
+        export: None,
+    };
+    let date = |flag: &str, value: Option<String>| -> Result<u64, String> {
+        let value: String = parse_value(flag, value)?;
+        history::parse_date(&value).ok_or_else(|| format!("{} expects a date like 2026-10-14, not {}", flag, value))
+    };
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--from" => options.from = Some(date(&arg, args.next())?),
+            "--to" => options.until = Some(date(&arg, args.next())?),
+            "--sort" => options.sort = parse_value(&arg, args.next())?,
+            "--export" => options.export = Some(parse_value(&arg, args.next())?),
+            _ => return Err(format!("unexpected argument: {}", arg)),
+        }
+    }
+
+    if let (Some(from), Some(until)) = (options.from, options.until) {
+        if from > until {
+            return Err("--from has to come before --to".to_string());
+        }
+    }
+    Ok(Command::Leaderboard(options))
+}
+
+fn parse_singer(mut args: impl Iterator<Item = String>) -> Result<Command, String> {
+    let mut options = SingerOptions {
+        name: None,
+        transpose: None,
+        mic_gain: None,
+        favorite: Vec::new(),
+        unfavorite: Vec::new(),
+    };
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--transpose" => options.transpose = Some(parse_value(&arg, args.next())?),
+            "--mic-gain" => options.mic_gain = Some(parse_value(&arg, args.next())?),
+            "--favorite" => options.favorite.push(parse_value(&arg, args.next())?),
+            "--unfavorite" => options.unfavorite.push(parse_value(&arg, args.next())?),
+            _ if arg.starts_with("--") => return Err(format!("unknown option: {}", arg)),
+            _ if options.name.is_none() => options.name = Some(arg),
+            _ => return Err(format!("unexpected argument: {}", arg)),
+        }
+    }
+
+    let changing = options.transpose.is_some() || options.mic_gain.is_some() || !options.favorite.is_empty() || !options.unfavorite.is_empty();
+    if changing && options.name.is_none() {
+        return Err("say which singer to change".to_string());
+    }
+    if options.transpose.is_some_and(|transpose| !(-11..=11).contains(&transpose)) {
+        return Err("--transpose must be between -11 and 11".to_string());
+    }
+    if options.mic_gain.is_some_and(|gain| !(-24.0..=24.0).contains(&gain)) {
+        return Err("--mic-gain must be between -24 and 24 dB".to_string());
+    }
+    Ok(Command::Singer(options))
+}
+
+fn parse_setlist(mut args: impl Iterator<Item = String>) -> Result<Command, String> {
+    let action = args.next();
+    let setlist = args.next().map(PathBuf::from);
+    let rest: Vec<String> = args.collect();
+    if let Some(option) = rest.iter().find(|arg| arg.starts_with("--")) {
+        return Err(format!("unknown option: {}", option));
+    }
+    let action = match (action.as_deref(), &setlist) {
+        (Some("edit"), Some(_)) => SetlistAction::Edit(rest.into_iter().map(PathBuf::from).collect()),
+        (Some("export"), Some(_)) => {
+            let [output]: [String; 1] = rest.try_into().map_err(|_| "setlist export expects <setlist.json> <output.md|html>".to_string())?;
+            SetlistAction::Export(PathBuf::from(output))
+        }
+        _ => return Err("setlist expects edit or export and a setlist file".to_string()),
+    };
+    Ok(Command::Setlist {
+        setlist: setlist.unwrap_or_default(),
+        action,
+    })
+}
+
+fn parse_mixdown(mut args: impl Iterator<Item = String>) -> Result<Command, String> {
+    let mut positional = Vec::new();
+    let mut options = MixOptions::default();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--vocal-gain" => options.vocal_gain = parse_value(&arg, args.next())?,
+            "--backing-gain" => options.backing_gain = parse_value(&arg, args.next())?,
+            "--offset" => options.latency_offset = parse_value(&arg, args.next())?,
+            _ if arg.starts_with("--") => return Err(format!("unknown option: {}", arg)),
+            _ => positional.push(PathBuf::from(arg)),
+        }
+    }
+
+    let [vocal, backing, output]: [PathBuf; 3] = positional
+        .try_into()
+        .map_err(|_| "mixdown expects <vocal.wav> <backing.wav> <output.wav>".to_string())?;
+
+    Ok(Command::Mixdown {
+        vocal,
+        backing,
+        output,
+        options,
+    })
+}
+
+fn parse_retime(mut args: impl Iterator<Item = String>) -> Result<Command, String> {
+    let mut positional = Vec::new();
+    let (mut shift, mut scale, mut encoding) = (0.0, 1.0, None);
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--shift" => shift = parse_value(&arg, args.next())?,
+            "--scale" => scale = parse_factor(&arg, args.next())?,
+            "--encoding" => encoding = Some(parse_value(&arg, args.next())?),
+            _ if arg.starts_with("--") => return Err(format!("unknown option: {}", arg)),
+            _ => positional.push(PathBuf::from(arg)),
+        }
+    }
+
+    let [song, output]: [PathBuf; 2] = positional
+        .try_into()
+        .map_err(|_| "retime expects <song> <output.lrc>".to_string())?;
+    if shift == 0.0 && scale == 1.0 {
+        return Err("retime needs --shift or --scale".to_string());
+    }
+    Ok(Command::Retime { song, output, shift, scale, encoding })
+}
+
+// A positive number, or a ratio such as 120/126 for the tempos of two versions of a song
+fn parse_factor(flag: &str, value: Option<String>) -> Result<f64, String> {
+    let value: String = parse_value(flag, value)?;
+    let factor = match value.split_once('/') {
+        Some((from, to)) => from.trim().parse::<f64>().ok().zip(to.trim().parse::<f64>().ok()).map(|(from, to)| from / to),
+        None => value.parse().ok(),
+    };
+    factor
+        .filter(|factor| factor.is_finite() && *factor > 0.0)
+        .ok_or_else(|| format!("invalid value for {}: {}", flag, value))
+}
+
+fn parse_now_line(mut args: impl Iterator<Item = String>) -> Result<Command, String> {
+    let mut options = NowLineOptions {
+        socket: daemon::default_socket(),
+        ..NowLineOptions::default()
+    };
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--daemon-socket" => options.socket = parse_value(&arg, args.next())?,
+            "--format" => options.format = parse_value(&arg, args.next())?,
+            "--max-width" => options.max_width = Some(parse_value(&arg, args.next())?),
+            "--scroll" => options.scroll = true,
+            _ => return Err(format!("unexpected argument: {}", arg)),
+        }
+    }
+
+    if options.max_width == Some(0) {
+        return Err("--max-width must be at least 1".to_string());
+    }
+
+    if options.scroll && options.max_width.is_none() {
+        return Err("--scroll requires --max-width".to_string());
+    }
+
+    Ok(Command::NowLine(options))
+}
+
+// A comma-separated list of color names or #rrggbb
+fn parse_colors(flag: &str, value: Option<String>) -> Result<Vec<Color>, String> {
+    let value = value.ok_or_else(|| format!("{} expects a value", flag))?;
+    value
+        .split(',')
+        .map(|name| style::parse_color(name).ok_or_else(|| format!("invalid value for {}: {}", flag, name)))
+        .collect()
+}
+
+fn parse_value<T: std::str::FromStr>(flag: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("{} expects a value", flag))?;
+    value
+        .parse()
+        .map_err(|_| format!("invalid value for {}: {}", flag, value))
+}
@@ -0,0 +1,291 @@
+use ratatui::{buffer::Buffer, style::Color};
+use std::{env, sync::OnceLock};
+
+// How many colors the terminal can show
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorDepth {
+    TrueColor,
+    Palette256,
+    Ansi16,
+}
+
+impl std::str::FromStr for ColorDepth {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "truecolor" | "24bit" => Ok(ColorDepth::TrueColor),
+            "256" => Ok(ColorDepth::Palette256),
+            "16" => Ok(ColorDepth::Ansi16),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Capabilities {
+    pub colors: ColorDepth,
+    // Box drawing, blocks, stars and notes are swapped for plain ASCII
+    pub ascii: bool,
+    // Asked for rather than detected: black behind everything and only the brightest colors
+    pub high_contrast: bool,
+}
+
+static CAPABILITIES: OnceLock<Capabilities> = OnceLock::new();
+
+// The screens are drawn for a modern terminal, then toned down to what this one can show
+fn detect() -> Capabilities {
+    let var = |name: &str| env::var(name).unwrap_or_default().to_ascii_lowercase();
+    let (term, colorterm) = (var("TERM"), var("COLORTERM"));
+    // Windows Terminal does everything; the old console host only gets 16 colors right
+    let windows_terminal = env::var_os("WT_SESSION").is_some();
+    let legacy_console = cfg!(windows) && !windows_terminal && term.is_empty();
+
+    let colors = if colorterm.contains("truecolor") || colorterm.contains("24bit") || windows_terminal {
+        ColorDepth::TrueColor
+    } else if term.contains("256color") {
+        ColorDepth::Palette256
+    } else if legacy_console || matches!(term.as_str(), "linux" | "dumb" | "ansi" | "xterm" | "screen" | "vt100" | "vt220") {
+        ColorDepth::Ansi16
+    } else {
+        ColorDepth::TrueColor
+    };
+
+    let locale = ["LC_ALL", "LC_CTYPE", "LANG"].iter().map(|name| var(name)).find(|value| !value.is_empty());
+    let utf8 = match locale {
+        Some(locale) => locale.contains("utf-8") || locale.contains("utf8"),
+        None => windows_terminal || !cfg!(windows),
+    };
+    Capabilities {
+        colors,
+        ascii: !utf8 || legacy_console || term == "dumb",
+        high_contrast: false,
+    }
+}
+
+// Command-line choices win over what was detected; only the first call counts
+pub fn init(colors: Option<ColorDepth>, ascii: bool, high_contrast: bool) {
+    let detected = detect();
+    let _ = CAPABILITIES.set(Capabilities {
+        colors: colors.unwrap_or(detected.colors),
+        ascii: ascii || detected.ascii,
+        high_contrast,
+    });
+}
+
+fn capabilities() -> Capabilities {
+    *CAPABILITIES.get_or_init(detect)
+}
+
+// For drawings that can't be turned into ASCII and still work, like QR codes
+pub fn is_ascii() -> bool {
+    capabilities().ascii
+}
+
+// Rewrites a drawn frame for the terminal; does nothing on a capable one
+pub fn adapt(buffer: &mut Buffer) {
+    let capabilities = capabilities();
+    if capabilities == (Capabilities { colors: ColorDepth::TrueColor, ascii: false, high_contrast: false }) {
+        return;
+    }
+    for cell in &mut buffer.content {
+        if capabilities.high_contrast {
+            (cell.fg, cell.bg) = contrast(cell.fg, cell.bg);
+        }
+        cell.fg = downgrade(cell.fg, capabilities.colors);
+        cell.bg = downgrade(cell.bg, capabilities.colors);
+        if capabilities.ascii {
+            let mut chars = cell.symbol().chars();
+            if let (Some(c), None) = (chars.next(), chars.next()) {
+                if let Some(plain) = ascii_glyph(c) {
+                    cell.set_char(plain);
+                }
+            }
+        }
+    }
+}
+
+// The 16 ANSI colors as xterm shows them
+const ANSI: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::Gray, (229, 229, 229)),
+    (Color::DarkGray, (127, 127, 127)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+// What a 256-color palette entry looks like, so indexed colors can be brought down to 16 too
+fn palette_rgb(index: u8) -> (u8, u8, u8) {
+    let level = |v: u8| if v == 0 { 0 } else { 55 + v * 40 };
+    match index {
+        0..=15 => ANSI[index as usize].1,
+        16..=231 => {
+            let i = index - 16;
+            (level(i / 36), level(i / 6 % 6), level(i % 6))
+        }
+        _ => {
+            let gray = 8 + (index - 232) * 10;
+            (gray, gray, gray)
+        }
+    }
+}
+
+fn distance((r, g, b): (u8, u8, u8), (ar, ag, ab): (u8, u8, u8)) -> i32 {
+    let d = |x: u8, y: u8| (x as i32 - y as i32).pow(2);
+    d(r, ar) + d(g, ag) + d(b, ab)
+}
+
+// The closer of the nearest color cube entry and the nearest step of the gray ramp
+fn to_palette(r: u8, g: u8, b: u8) -> u8 {
+    let cube = |v: u8| match v {
+        0..=47 => 0,
+        48..=114 => 1,
+        _ => (v - 35) / 40,
+    };
+    let cube_index = 16 + 36 * cube(r) + 6 * cube(g) + cube(b);
+    let average = (r as u16 + g as u16 + b as u16) / 3;
+    let gray_index = 232 + (average.saturating_sub(3) / 10).min(23) as u8;
+    [cube_index, gray_index]
+        .into_iter()
+        .min_by_key(|&index| distance((r, g, b), palette_rgb(index)))
+        .unwrap_or(cube_index)
+}
+
+fn to_ansi(r: u8, g: u8, b: u8) -> Color {
+    ANSI.iter().min_by_key(|(_, rgb)| distance((r, g, b), *rgb)).map_or(Color::White, |(color, _)| *color)
+}
+
+pub fn downgrade(color: Color, depth: ColorDepth) -> Color {
+    match (color, depth) {
+        (_, ColorDepth::TrueColor) => color,
+        (Color::Rgb(r, g, b), ColorDepth::Palette256) => Color::Indexed(to_palette(r, g, b)),
+        (Color::Rgb(r, g, b), ColorDepth::Ansi16) => to_ansi(r, g, b),
+        (Color::Indexed(index), ColorDepth::Ansi16) => {
+            let (r, g, b) = palette_rgb(index);
+            to_ansi(r, g, b)
+        }
+        _ => color,
+    }
+}
+
+fn rgb(color: Color) -> Option<(u8, u8, u8)> {
+    match color {
+        Color::Rgb(r, g, b) => Some((r, g, b)),
+        Color::Indexed(index) => Some(palette_rgb(index)),
+        Color::Reset => None,
+        named => ANSI.iter().find(|(ansi, _)| *ansi == named).map(|(_, rgb)| *rgb),
+    }
+}
+
+// The colors high contrast keeps; blue is left out since it's hard to read on black
+const BRIGHT: [Color; 6] = [
+    Color::LightRed,
+    Color::LightGreen,
+    Color::LightYellow,
+    Color::LightMagenta,
+    Color::LightCyan,
+    Color::White,
+];
+
+// Backgrounds go black, or white under black text where they were light, like a selection.
+// Grays become white or, if they were dim, light gray; other colors their brightest ANSI peer.
+pub fn contrast(fg: Color, bg: Color) -> (Color, Color) {
+    let luma = |(r, g, b): (u8, u8, u8)| (299 * r as u32 + 587 * g as u32 + 114 * b as u32) / 1000;
+    if rgb(bg).is_some_and(|bg| luma(bg) >= 128) {
+        return (Color::Black, Color::White);
+    }
+    let bg = if bg == Color::Reset { bg } else { Color::Black };
+    let Some((r, g, b)) = rgb(fg) else {
+        return (fg, bg);
+    };
+    let (high, low) = (r.max(g).max(b), r.min(g).min(b));
+    let fg = match high - low {
+        _ if high == 0 => Color::Gray,
+        saturation if saturation < 60 => match luma((r, g, b)) {
+            0..=99 => Color::Gray,
+            _ => Color::White,
+        },
+        _ => {
+            let scale = |v: u8| (v as u32 * 255 / high as u32) as u8;
+            let brightest = (scale(r), scale(g), scale(b));
+            let ansi = |color: &Color| rgb(*color).map_or(i32::MAX, |rgb| distance(brightest, rgb));
+            BRIGHT.iter().copied().min_by_key(ansi).unwrap_or(Color::White)
+        }
+    };
+    (fg, bg)
+}
+
+// Stand-ins for the symbols the screens use, including ratatui's borders
+fn ascii_glyph(c: char) -> Option<char> {
+    Some(match c {
+        '─' | '━' | '═' => '-',
+        '│' | '┃' | '║' | '‖' => '|',
+        '┌' | '┐' | '└' | '┘' | '╭' | '╮' | '╰' | '╯' | '┼' | '├' | '┤' | '┬' | '┴' => '+',
+        '█' | '▉' | '▊' | '▋' | '▇' | '▆' | '▀' => '#',
+        '▌' | '▍' | '▎' | '▏' | '▅' | '▄' => '=',
+        '▃' | '▂' | '▁' => '_',
+        '░' | '·' => '.',
+        '♪' | '♫' => '~',
+        '●' | '•' | '★' | '✦' | '✸' | '❖' => '*',
+        '˙' => '\'',
+        '▪' | '◆' | '▲' => 'o',
+        '○' | '☆' => 'o',
+        '▶' | '→' => '>',
+        '◀' | '←' => '<',
+        '↑' => '^',
+        '↓' => 'v',
+        '⇄' => '=',
+        '⟲' => '@',
+        '‘' | '’' => '\'',
+        '“' | '”' => '"',
+        '⠀' => ' ',
+        '\u{2801}'..='\u{28ff}' => ':',
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colors_map_to_the_nearest_palette_entry() {
+        let rgb = Color::Rgb(0, 200, 255);
+        assert_eq!(downgrade(rgb, ColorDepth::TrueColor), rgb);
+        assert_eq!(downgrade(rgb, ColorDepth::Palette256), Color::Indexed(45));
+        assert_eq!(downgrade(Color::Rgb(20, 24, 40), ColorDepth::Palette256), Color::Indexed(234));
+        assert_eq!(downgrade(rgb, ColorDepth::Ansi16), Color::Cyan);
+        assert_eq!(downgrade(Color::Rgb(20, 24, 40), ColorDepth::Ansi16), Color::Black);
+        assert_eq!(downgrade(Color::Indexed(196), ColorDepth::Ansi16), Color::LightRed);
+        assert_eq!(downgrade(Color::Magenta, ColorDepth::Ansi16), Color::Magenta);
+    }
+
+    #[test]
+    fn high_contrast_keeps_only_bright_colors_on_black() {
+        let on_panel = |fg| contrast(fg, Color::Rgb(20, 24, 40));
+        assert_eq!(on_panel(Color::Rgb(0, 255, 0)), (Color::LightGreen, Color::Black));
+        assert_eq!(on_panel(Color::Rgb(0, 200, 255)), (Color::LightCyan, Color::Black));
+        assert_eq!(on_panel(Color::Rgb(255, 235, 160)), (Color::White, Color::Black));
+        assert_eq!(on_panel(Color::Rgb(80, 80, 80)), (Color::Gray, Color::Black));
+        assert_eq!(contrast(Color::Magenta, Color::Reset), (Color::LightMagenta, Color::Reset));
+        assert_eq!(contrast(Color::White, Color::Rgb(0, 200, 255)), (Color::Black, Color::White));
+    }
+
+    #[test]
+    fn symbols_have_ascii_stand_ins_but_lyrics_are_left_alone() {
+        let plain: String = "━━█▌ ♪ ● ○ ╭─╮".chars().map(|c| ascii_glyph(c).unwrap_or(c)).collect();
+        assert_eq!(plain, "--#= ~ * o +-+");
+        assert_eq!("hát 歌".chars().filter_map(ascii_glyph).count(), 0);
+    }
+}
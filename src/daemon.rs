@@ -0,0 +1,245 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    env, fs,
+    io::{self, BufRead, BufReader, Write},
+    os::unix::net::{UnixListener, UnixStream},
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Sender},
+    thread,
+};
+
+use crate::{
+    events::{AppEvent, EventBus},
+    plugin::Renderer,
+    remote::RemoteCommand,
+    KaraokeApp,
+};
+
+pub fn default_socket() -> PathBuf {
+    env::temp_dir().join("karaoke-lyric.sock")
+}
+
+// One JSON object per line each way, e.g. {"command":"seek","to":42.5}
+#[derive(Debug, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+enum Request {
+    Load { path: PathBuf },
+    Play,
+    Pause,
+    Toggle,
+    Seek { to: Option<f64>, by: Option<f64> },
+    Status,
+    Quit,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DaemonCommand {
+    Remote(RemoteCommand),
+    Status,
+    Quit,
+}
+
+// A client's command and where its answer goes
+#[derive(Debug, Clone)]
+pub struct Query {
+    pub command: DaemonCommand,
+    pub reply: Sender<String>,
+}
+
+#[derive(Serialize)]
+struct Status<'a> {
+    ok: bool,
+    song: Option<&'a Path>,
+    title: &'a str,
+    artist: Option<&'a str>,
+    position: f64,
+    duration: f64,
+    paused: bool,
+    ended: bool,
+    line: Option<usize>,
+    text: Option<&'a str>,
+    line_start: Option<f64>,
+}
+
+fn parse(line: &str) -> Result<DaemonCommand, String> {
+    let request: Request = serde_json::from_str(line).map_err(|e| format!("invalid command: {}", e))?;
+    let command = match request {
+        Request::Load { path } => RemoteCommand::Load(path),
+        Request::Play => RemoteCommand::Play,
+        Request::Pause => RemoteCommand::Pause,
+        Request::Toggle => RemoteCommand::TogglePause,
+        Request::Seek { to: Some(to), by: None } => RemoteCommand::SeekTo(to),
+        Request::Seek { to: None, by: Some(by) } => RemoteCommand::SeekBy(by),
+        Request::Seek { .. } => return Err("seek expects either \"to\" or \"by\"".to_string()),
+        Request::Status => return Ok(DaemonCommand::Status),
+        Request::Quit => return Ok(DaemonCommand::Quit),
+    };
+    Ok(DaemonCommand::Remote(command))
+}
+
+fn ok_body() -> String {
+    "{\"ok\":true}".to_string()
+}
+
+fn error_body(message: &str) -> String {
+    serde_json::json!({ "ok": false, "error": message }).to_string()
+}
+
+fn status_body(app: &KaraokeApp) -> String {
+    let line = app.active_line(app.lyric_time());
+    let status = Status {
+        ok: true,
+        song: app.song_path.as_deref(),
+        title: &app.song_title,
+        artist: app.song_artist.as_deref(),
+        position: app.get_current_time(),
+        duration: app.song_duration,
+        paused: app.is_paused(),
+        ended: app.is_song_ended(),
+        line,
+        text: line.map(|i| app.lyrics[i].text.as_str()),
+        line_start: line.map(|i| app.lyrics[i].start_time),
+    };
+    serde_json::to_string(&status).unwrap_or_else(|e| error_body(&e.to_string()))
+}
+
+// A socket left behind by a daemon that didn't shut down cleanly is replaced, but not one that
+// another daemon is still answering on
+fn bind(path: &Path) -> io::Result<UnixListener> {
+    if UnixStream::connect(path).is_ok() {
+        return Err(io::Error::new(
+            io::ErrorKind::AddrInUse,
+            format!("another daemon is already listening on {}", path.display()),
+        ));
+    }
+    let _ = fs::remove_file(path);
+    UnixListener::bind(path).map_err(|e| io::Error::new(e.kind(), format!("couldn't listen on {}: {}", path.display(), e)))
+}
+
+fn answer(stream: UnixStream, events: Sender<AppEvent>) -> io::Result<()> {
+    let mut writer = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let body = match parse(&line) {
+            // Answered first, as the daemon exits as soon as it gets this
+            Ok(DaemonCommand::Quit) => {
+                writer.write_all(format!("{}\n", ok_body()).as_bytes())?;
+                let (reply, _) = mpsc::channel();
+                let _ = events.send(AppEvent::Daemon(Query { command: DaemonCommand::Quit, reply }));
+                return Ok(());
+            }
+            Ok(command) => {
+                let (reply, replies) = mpsc::channel();
+                // The main loop drops the reply without answering only when it's on its way out
+                match events.send(AppEvent::Daemon(Query { command, reply })) {
+                    Ok(()) => replies.recv().unwrap_or_else(|_| error_body("the daemon is shutting down")),
+                    Err(_) => error_body("the daemon is shutting down"),
+                }
+            }
+            Err(message) => error_body(&message),
+        };
+        writer.write_all(format!("{}\n", body).as_bytes())?;
+    }
+    Ok(())
+}
+
+fn serve(listener: UnixListener, events: Sender<AppEvent>) {
+    for stream in listener.incoming().flatten() {
+        let events = events.clone();
+        thread::spawn(move || {
+            if let Err(e) = answer(stream, events) {
+                log::debug!("daemon client went away: {}", e);
+            }
+        });
+    }
+}
+
+// Loads and `--remote` API calls land here too; a song is loaded paused, waiting for `play`
+fn apply(app: &mut KaraokeApp, command: RemoteCommand, outputs: &mut [Box<dyn Renderer>]) -> Result<(), String> {
+    match command {
+        RemoteCommand::Load(path) => {
+            app.load_song(&path).map_err(|e| format!("couldn't load {}: {}", path.display(), e))?;
+            app.pause();
+            for output in outputs.iter_mut() {
+                output.song_changed();
+            }
+        }
+        RemoteCommand::Next => return Err("the daemon has no next song".to_string()),
+        command => {
+            crate::handle_remote(app, command);
+        }
+    }
+    Ok(())
+}
+
+// Plays without a terminal, driven by commands on a Unix socket
+pub fn run(app: &mut KaraokeApp, socket: &Path, events: &EventBus, mut outputs: Vec<Box<dyn Renderer>>) -> io::Result<()> {
+    let listener = bind(socket)?;
+    log::info!("daemon listening on {}", socket.display());
+    let sender = events.sender();
+    thread::spawn(move || serve(listener, sender));
+    events.spawn_background_sources(crate::TICK_RATE);
+    app.pause();
+
+    let result = (|| loop {
+        for output in outputs.iter_mut() {
+            output.render(app)?;
+        }
+        let Ok(event) = events.receiver().recv() else {
+            return Ok(());
+        };
+        match event {
+            AppEvent::Tick => {
+                if app.reload_if_saved() {
+                    for output in outputs.iter_mut() {
+                        output.song_changed();
+                    }
+                }
+                app.tick();
+            }
+            AppEvent::Terminate => return Ok(()),
+            AppEvent::Remote(command) => {
+                if let Err(e) = apply(app, command, &mut outputs) {
+                    log::warn!("remote command failed: {}", e);
+                }
+            }
+            AppEvent::Daemon(Query { command, reply }) => {
+                let body = match command {
+                    DaemonCommand::Remote(command) => match apply(app, command, &mut outputs) {
+                        Ok(()) => ok_body(),
+                        Err(e) => error_body(&e),
+                    },
+                    DaemonCommand::Status => status_body(app),
+                    DaemonCommand::Quit => return Ok(()),
+                };
+                let _ = reply.send(body);
+            }
+            _ => {}
+        }
+    })();
+    let _ = fs::remove_file(socket);
+    log::info!("daemon stopped");
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_are_read_from_json_lines() {
+        assert_eq!(parse(r#"{"command":"seek","to":42.5}"#), Ok(DaemonCommand::Remote(RemoteCommand::SeekTo(42.5))));
+        assert_eq!(parse(r#"{"command":"seek","by":-5}"#), Ok(DaemonCommand::Remote(RemoteCommand::SeekBy(-5.0))));
+        assert_eq!(
+            parse(r#"{"command":"load","path":"songs/a.lrc"}"#),
+            Ok(DaemonCommand::Remote(RemoteCommand::Load(PathBuf::from("songs/a.lrc"))))
+        );
+        assert_eq!(parse(r#"{"command":"status"}"#), Ok(DaemonCommand::Status));
+        assert!(parse(r#"{"command":"seek","to":1,"by":2}"#).is_err());
+        assert!(parse(r#"{"command":"dance"}"#).is_err());
+        assert!(parse("play").is_err());
+    }
+}
@@ -0,0 +1,99 @@
+use ratatui::{
+    layout::Rect,
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+use std::time::{Duration, Instant};
+
+// Timing numbers for the debug overlay, kept while it's shown
+#[derive(Debug, Clone)]
+pub struct DebugStats {
+    // How long the last frame took to draw, and how far apart the last two were
+    pub frame_time: Duration,
+    pub frame_interval: Duration,
+    last_frame: Option<Instant>,
+    // Where the song clock and the system clock were when the overlay opened
+    clock_origin: (Instant, f64),
+}
+
+impl DebugStats {
+    pub fn new(clock_now: f64, now: Instant) -> Self {
+        Self {
+            frame_time: Duration::ZERO,
+            frame_interval: Duration::ZERO,
+            last_frame: None,
+            clock_origin: (now, clock_now),
+        }
+    }
+
+    pub fn frame_drawn(&mut self, started: Instant, finished: Instant) {
+        self.frame_time = finished.duration_since(started);
+        if let Some(last) = self.last_frame {
+            self.frame_interval = started.duration_since(last);
+        }
+        self.last_frame = Some(started);
+    }
+
+    pub fn fps(&self) -> f64 {
+        match self.frame_interval.as_secs_f64() {
+            interval if interval > 0.0 => 1.0 / interval,
+            _ => 0.0,
+        }
+    }
+
+    // How far the song clock has run ahead of the system clock; a MIDI clock wanders, the
+    // system one stays at zero
+    pub fn clock_drift(&self, clock_now: f64, now: Instant) -> f64 {
+        let (started, clock_start) = self.clock_origin;
+        (clock_now - clock_start) - now.duration_since(started).as_secs_f64()
+    }
+}
+
+// A box of label/value rows in the bottom-left corner of `area`
+pub fn render(f: &mut Frame, area: Rect, rows: &[(&str, String)]) {
+    let label_width = rows.iter().map(|(label, _)| label.chars().count()).max().unwrap_or(0);
+    let value_width = rows.iter().map(|(_, value)| value.chars().count()).max().unwrap_or(0);
+    let width = ((label_width + value_width + 5) as u16).min(area.width);
+    let height = (rows.len() as u16 + 2).min(area.height);
+    if width < 5 || height < 3 {
+        return;
+    }
+    let corner = Rect::new(area.x, area.y + area.height - height, width, height);
+
+    let lines: Vec<Line> = rows
+        .iter()
+        .map(|(label, value)| {
+            Line::from(vec![
+                Span::styled(format!("{:<width$} ", label, width = label_width), Style::default().fg(Color::Gray)),
+                Span::styled(value.clone(), Style::default().fg(Color::White)),
+            ])
+        })
+        .collect();
+    let widget = Paragraph::new(lines).style(Style::default().bg(Color::Rgb(40, 44, 64))).block(
+        Block::default()
+            .title(" Debug ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Rgb(255, 150, 0))),
+    );
+    f.render_widget(widget, corner);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_and_drift_are_measured_against_the_system_clock() {
+        let start = Instant::now();
+        let mut stats = DebugStats::new(10.0, start);
+        stats.frame_drawn(start, start + Duration::from_millis(3));
+        assert_eq!((stats.frame_time, stats.fps()), (Duration::from_millis(3), 0.0));
+
+        stats.frame_drawn(start + Duration::from_millis(20), start + Duration::from_millis(22));
+        assert!((stats.fps() - 50.0).abs() < 1e-9);
+        let drift = stats.clock_drift(12.05, start + Duration::from_secs(2));
+        assert!((drift - 0.05).abs() < 1e-9);
+    }
+}
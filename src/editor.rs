@@ -0,0 +1,694 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use crate::{
+    events::{AppEvent, EventBus},
+    formats, song_config, timeline, Chord, LyricLine, Segment,
+};
+
+// Older snapshots are dropped past this many edits
+const HISTORY_LIMIT: usize = 200;
+const NUDGE: f64 = 0.1;
+// How much `[` and `]` stretch the whole song by
+const STRETCH: f64 = 0.01;
+const SEEK_STEP: f64 = 2.0;
+
+#[derive(Debug, Clone)]
+struct Snapshot {
+    label: String,
+    lines: Vec<LyricLine>,
+    selected: usize,
+}
+
+// Whole copies of the lines from before each edit. Lyric files are small, so this is simpler
+// than recording how to reverse every kind of edit.
+#[derive(Debug, Default)]
+pub struct EditHistory {
+    undo: Vec<Snapshot>,
+    redo: Vec<Snapshot>,
+}
+
+impl EditHistory {
+    pub fn record(&mut self, label: &str, lines: &[LyricLine], selected: usize) {
+        self.undo.push(Snapshot {
+            label: label.to_string(),
+            lines: lines.to_vec(),
+            selected,
+        });
+        if self.undo.len() > HISTORY_LIMIT {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+    }
+
+    // Restores the lines from before the last edit and returns what was undone
+    pub fn undo(&mut self, lines: &mut Vec<LyricLine>, selected: &mut usize) -> Option<String> {
+        let snapshot = self.undo.pop()?;
+        Some(Self::swap(snapshot, &mut self.redo, lines, selected))
+    }
+
+    pub fn redo(&mut self, lines: &mut Vec<LyricLine>, selected: &mut usize) -> Option<String> {
+        let snapshot = self.redo.pop()?;
+        Some(Self::swap(snapshot, &mut self.undo, lines, selected))
+    }
+
+    fn swap(snapshot: Snapshot, other: &mut Vec<Snapshot>, lines: &mut Vec<LyricLine>, selected: &mut usize) -> String {
+        let Snapshot { label, lines: restored, selected: restored_selected } = snapshot;
+        other.push(Snapshot {
+            label: label.clone(),
+            lines: std::mem::replace(lines, restored),
+            selected: std::mem::replace(selected, restored_selected),
+        });
+        label
+    }
+}
+
+// Moves a line and its word timings together
+fn shift_line(line: &mut LyricLine, delta: f64) {
+    let delta = delta.max(-line.start_time);
+    line.start_time += delta;
+    line.end_time += delta;
+    for segment in &mut line.segments {
+        segment.start_time += delta;
+        segment.end_time += delta;
+    }
+}
+
+// Splits at a character index, giving each half the share of the time its characters take up.
+// Lines with word timing are cut between words, or proportionally inside the word at the cut.
+fn split_line(line: &LyricLine, at: usize) -> Option<(LyricLine, LyricLine)> {
+    let (before, after) = line.text.split_at(line.text.char_indices().nth(at)?.0);
+    if before.trim().is_empty() || after.trim().is_empty() {
+        return None;
+    }
+
+    let segments = if line.segments.is_empty() {
+        vec![Segment {
+            text: line.text.clone(),
+            start_time: line.start_time,
+            end_time: line.end_time,
+        }]
+    } else {
+        line.segments.clone()
+    };
+
+    let mut first = Vec::new();
+    let mut second = Vec::new();
+    let mut seen = 0;
+    for segment in segments {
+        let len = segment.text.chars().count();
+        if seen + len <= at {
+            first.push(segment);
+        } else if seen >= at {
+            second.push(segment);
+        } else {
+            let cut = at - seen;
+            let split_time = segment.start_time + (segment.end_time - segment.start_time) * cut as f64 / len as f64;
+            let byte = segment.text.char_indices().nth(cut).map_or(segment.text.len(), |(i, _)| i);
+            first.push(Segment {
+                text: segment.text[..byte].to_string(),
+                start_time: segment.start_time,
+                end_time: split_time,
+            });
+            second.push(Segment {
+                text: segment.text[byte..].to_string(),
+                start_time: split_time,
+                end_time: segment.end_time,
+            });
+        }
+        seen += len;
+    }
+
+    // Chords move with their characters, and the second half loses its leading spaces
+    let skipped = at + (after.chars().count() - after.trim_start().chars().count());
+    let (first_chords, second_chords): (Vec<Chord>, Vec<Chord>) =
+        line.chords.iter().cloned().partition(|chord| chord.position < at);
+    let second_chords = second_chords
+        .into_iter()
+        .map(|chord| Chord {
+            position: chord.position.saturating_sub(skipped),
+            ..chord
+        })
+        .collect();
+
+    let keep_segments = !line.segments.is_empty();
+    let finish = |segments: Vec<Segment>, chords: Vec<Chord>| {
+        let mut part = formats::line_from_segments(segments)?;
+        part.singer = line.singer;
+        part.style = line.style.clone();
+        part.chords = chords;
+        if !keep_segments {
+            part.segments.clear();
+        }
+        Some(part)
+    };
+    Some((finish(first, first_chords)?, finish(second, second_chords)?))
+}
+
+// Joins a line with the one after it, keeping word timing if either had some
+fn merge_lines(first: &LyricLine, second: &LyricLine) -> LyricLine {
+    let as_segments = |line: &LyricLine| match line.segments.is_empty() {
+        true => vec![Segment {
+            text: line.text.clone(),
+            start_time: line.start_time,
+            end_time: line.end_time,
+        }],
+        false => line.segments.clone(),
+    };
+
+    let mut segments = as_segments(first);
+    if let Some(last) = segments.last_mut() {
+        last.text.push(' ');
+    }
+    segments.extend(as_segments(second));
+
+    let mut merged = LyricLine {
+        text: format!("{} {}", first.text, second.text),
+        start_time: first.start_time,
+        end_time: first.end_time.max(second.end_time),
+        segments: Vec::new(),
+        singer: first.singer,
+        style: first.style.clone(),
+        chords: first
+            .chords
+            .iter()
+            .cloned()
+            .chain(second.chords.iter().map(|chord| Chord {
+                position: chord.position + first.text.chars().count() + 1,
+                ..chord.clone()
+            }))
+            .collect(),
+    };
+    if !first.segments.is_empty() || !second.segments.is_empty() {
+        merged.segments = segments;
+    }
+    merged
+}
+
+// The selected line's text while it is being typed into
+pub struct TextEdit {
+    pub text: String,
+    pub cursor: usize,
+}
+
+impl TextEdit {
+    pub fn new(text: &str) -> Self {
+        Self {
+            cursor: text.chars().count(),
+            text: text.to_string(),
+        }
+    }
+
+    fn byte_at(&self, cursor: usize) -> usize {
+        self.text.char_indices().nth(cursor).map_or(self.text.len(), |(i, _)| i)
+    }
+
+    fn len(&self) -> usize {
+        self.text.chars().count()
+    }
+
+    // Moving the cursor and typing; returns false for keys that mean something else
+    pub fn type_key(&mut self, key: KeyEvent) -> bool {
+        match key.code {
+            KeyCode::Left => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Right => self.cursor = (self.cursor + 1).min(self.len()),
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::End => self.cursor = self.len(),
+            KeyCode::Backspace if self.cursor > 0 => {
+                self.cursor -= 1;
+                let at = self.byte_at(self.cursor);
+                self.text.remove(at);
+            }
+            KeyCode::Delete if self.cursor < self.len() => {
+                let at = self.byte_at(self.cursor);
+                self.text.remove(at);
+            }
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                let at = self.byte_at(self.cursor);
+                self.text.insert(at, c);
+                self.cursor += 1;
+            }
+            KeyCode::Backspace | KeyCode::Delete => {}
+            _ => return false,
+        }
+        true
+    }
+}
+
+struct Editor {
+    lyrics: Vec<LyricLine>,
+    duration: f64,
+    selected: usize,
+    history: EditHistory,
+    position: f64,
+    playing_since: Option<Instant>,
+    status: String,
+    dirty: bool,
+    quit_warned: bool,
+    text_edit: Option<TextEdit>,
+}
+
+impl Editor {
+    fn time(&self) -> f64 {
+        let running = self.playing_since.map_or(0.0, |since| since.elapsed().as_secs_f64());
+        (self.position + running).min(self.duration)
+    }
+
+    fn toggle_play(&mut self) {
+        self.position = self.time();
+        self.playing_since = match self.playing_since {
+            Some(_) => None,
+            None => Some(Instant::now()),
+        };
+    }
+
+    fn seek(&mut self, delta: f64) {
+        self.position = (self.time() + delta).clamp(0.0, self.duration);
+        if self.playing_since.is_some() {
+            self.playing_since = Some(Instant::now());
+        }
+    }
+
+    fn select(&mut self, delta: i64) {
+        let last = self.lyrics.len() as i64 - 1;
+        self.selected = (self.selected as i64 + delta).clamp(0, last.max(0)) as usize;
+    }
+
+    fn edit(&mut self, label: &str, change: impl FnOnce(&mut Vec<LyricLine>, usize)) {
+        if self.lyrics.is_empty() {
+            return;
+        }
+        self.history.record(label, &self.lyrics, self.selected);
+        change(&mut self.lyrics, self.selected);
+        self.dirty = true;
+        self.status = label.to_string();
+    }
+
+    // Tap sync: the selected line starts now, the one before it ends here, and the selection
+    // moves on so the next tap times the next line
+    fn tap(&mut self) {
+        let time = self.time();
+        self.edit("Set line start", |lines, i| {
+            let delta = time - lines[i].start_time;
+            shift_line(&mut lines[i], delta);
+            if let Some(previous) = i.checked_sub(1).map(|p| &mut lines[p]) {
+                if previous.end_time > time && previous.start_time < time {
+                    previous.end_time = time;
+                }
+            }
+        });
+        self.select(1);
+    }
+
+    fn nudge(&mut self, delta: f64) {
+        self.edit("Nudge line", |lines, i| shift_line(&mut lines[i], delta));
+    }
+
+    // Retiming the whole song, for a version that starts later or runs at another tempo
+    fn shift_all(&mut self, delta: f64) {
+        self.edit(&format!("Shift all lines {:+.1}s", delta), |lines, _| formats::shift(lines, delta));
+    }
+
+    fn stretch_all(&mut self, scale: f64) {
+        self.edit(&format!("Stretch all lines to {:.0}%", scale * 100.0), |lines, _| formats::retime(lines, scale, 0.0));
+        let end = self.lyrics.iter().map(|line| line.end_time).fold(0.0, f64::max);
+        self.duration = self.duration.max(end);
+    }
+
+    fn start_text_edit(&mut self) {
+        if let Some(line) = self.lyrics.get(self.selected) {
+            self.text_edit = Some(TextEdit::new(&line.text));
+        }
+    }
+
+    // Word timings only survive an edit that leaves the text as it was
+    fn finish_text_edit(&mut self) {
+        let Some(edit) = self.text_edit.take() else {
+            return;
+        };
+        let text = edit.text.trim().to_string();
+        if text.is_empty() || text == self.lyrics[self.selected].text {
+            return;
+        }
+        self.edit("Edit text", |lines, i| {
+            lines[i].text = text;
+            lines[i].segments.clear();
+        });
+    }
+
+    fn split_at_cursor(&mut self) {
+        let Some(edit) = self.text_edit.take() else {
+            return;
+        };
+        let mut line = self.lyrics[self.selected].clone();
+        if edit.text != line.text {
+            line.text = edit.text.clone();
+            line.segments.clear();
+        }
+        match split_line(&line, edit.cursor) {
+            Some((first, second)) => self.edit("Split line", |lines, i| {
+                lines[i] = first;
+                lines.insert(i + 1, second);
+            }),
+            None => {
+                self.status = "Move the cursor between two words to split".to_string();
+                self.text_edit = Some(edit);
+            }
+        }
+    }
+
+    fn merge_with_next(&mut self) {
+        if self.selected + 1 >= self.lyrics.len() {
+            self.status = "No line after this one to merge with".to_string();
+            return;
+        }
+        self.edit("Merge lines", |lines, i| {
+            let second = lines.remove(i + 1);
+            lines[i] = merge_lines(&lines[i], &second);
+        });
+    }
+
+    // Returns true when the key was used by the text being edited
+    fn handle_text_key(&mut self, key: KeyEvent) -> bool {
+        let Some(edit) = &mut self.text_edit else {
+            return false;
+        };
+        match key.code {
+            KeyCode::Enter => self.finish_text_edit(),
+            KeyCode::Esc => self.text_edit = None,
+            KeyCode::Tab => self.split_at_cursor(),
+            _ => {
+                edit.type_key(key);
+            }
+        }
+        true
+    }
+
+    fn undo(&mut self) {
+        self.status = match self.history.undo(&mut self.lyrics, &mut self.selected) {
+            Some(label) => {
+                self.dirty = true;
+                format!("Undid: {}", label)
+            }
+            None => "Nothing to undo".to_string(),
+        };
+    }
+
+    fn redo(&mut self) {
+        self.status = match self.history.redo(&mut self.lyrics, &mut self.selected) {
+            Some(label) => {
+                self.dirty = true;
+                format!("Redid: {}", label)
+            }
+            None => "Nothing to redo".to_string(),
+        };
+    }
+
+    // LRC lines have to be in time order, whatever order they were edited into
+    fn save(&mut self, output: &Path) {
+        let mut lines = self.lyrics.clone();
+        lines.sort_by(|a, b| a.start_time.total_cmp(&b.start_time));
+        self.status = match fs::write(output, formats::to_lrc(&lines)) {
+            Ok(()) => {
+                self.dirty = false;
+                format!("Saved {}", output.display())
+            }
+            Err(e) => format!("Save failed: {}", e),
+        };
+    }
+
+    // Returns true when the editor should close
+    fn handle_key(&mut self, key: KeyEvent, output: &Path) -> bool {
+        if self.handle_text_key(key) {
+            return false;
+        }
+
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Char('z') if ctrl => self.undo(),
+            KeyCode::Char('y') if ctrl => self.redo(),
+            KeyCode::Char('s') if ctrl => self.save(output),
+            KeyCode::Char('q') | KeyCode::Esc if self.dirty && !self.quit_warned => {
+                self.status = "Unsaved changes - press Q again to quit without saving".to_string();
+                self.quit_warned = true;
+                return false;
+            }
+            KeyCode::Char('q') | KeyCode::Esc => return true,
+            KeyCode::Char(' ') => self.toggle_play(),
+            KeyCode::Enter => self.tap(),
+            KeyCode::Up => self.select(-1),
+            KeyCode::Down => self.select(1),
+            KeyCode::Left => self.seek(-SEEK_STEP),
+            KeyCode::Right => self.seek(SEEK_STEP),
+            KeyCode::Char('+') | KeyCode::Char('=') => self.nudge(NUDGE),
+            KeyCode::Char('-') => self.nudge(-NUDGE),
+            KeyCode::Char('<') => self.shift_all(-NUDGE),
+            KeyCode::Char('>') => self.shift_all(NUDGE),
+            KeyCode::Char('[') => self.stretch_all(1.0 - STRETCH),
+            KeyCode::Char(']') => self.stretch_all(1.0 + STRETCH),
+            KeyCode::Char('e') | KeyCode::Char('E') => self.start_text_edit(),
+            KeyCode::Char('j') | KeyCode::Char('J') => self.merge_with_next(),
+            _ => {}
+        }
+        self.quit_warned = false;
+        false
+    }
+}
+
+fn timestamp(seconds: f64) -> String {
+    let centis = (seconds.max(0.0) * 100.0).round() as u64;
+    format!("{}:{:02}.{:02}", centis / 6000, centis / 100 % 60, centis % 100)
+}
+
+pub fn text_edit_spans(edit: &TextEdit) -> Vec<Span<'static>> {
+    let at = edit.byte_at(edit.cursor);
+    let under = edit.text[at..].chars().next();
+    let after = at + under.map_or(0, char::len_utf8);
+    let text_style = Style::default().fg(Color::White).add_modifier(Modifier::UNDERLINED);
+    vec![
+        Span::styled(edit.text[..at].to_string(), text_style),
+        Span::styled(
+            under.unwrap_or(' ').to_string(),
+            Style::default().fg(Color::Black).bg(Color::White),
+        ),
+        Span::styled(edit.text[after..].to_string(), text_style),
+    ]
+}
+
+fn ui(f: &mut Frame, editor: &Editor, output: &Path) {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(5), Constraint::Length(1), Constraint::Length(1), Constraint::Length(1)])
+        .split(f.size());
+
+    let time = editor.time();
+    let playing = timeline::active_index(&editor.lyrics, time);
+    let lines: Vec<Line> = editor
+        .lyrics
+        .iter()
+        .enumerate()
+        .map(|(i, line)| {
+            let marker = if playing == Some(i) { "▶ " } else { "  " };
+            let style = if i == editor.selected {
+                Style::default().fg(Color::Black).bg(Color::Rgb(0, 255, 0)).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::White)
+            };
+            let mut spans = vec![
+                Span::styled(marker, Style::default().fg(Color::Yellow)),
+                Span::styled(
+                    format!("{} - {}  ", timestamp(line.start_time), timestamp(line.end_time)),
+                    Style::default().fg(Color::Magenta),
+                ),
+            ];
+            match editor.text_edit.as_ref().filter(|_| i == editor.selected) {
+                Some(edit) => spans.extend(text_edit_spans(edit)),
+                None => spans.push(Span::styled(line.text.clone(), style)),
+            }
+            Line::from(spans)
+        })
+        .collect();
+    let scroll = editor.selected.saturating_sub(rows[0].height.saturating_sub(3) as usize / 2) as u16;
+    let title = format!(" Editing {}{} ", output.display(), if editor.dirty { " *" } else { "" });
+    f.render_widget(
+        Paragraph::new(lines)
+            .scroll((scroll, 0))
+            .block(Block::default().borders(Borders::ALL).title(title)),
+        rows[0],
+    );
+
+    let state = if editor.playing_since.is_some() { "▶" } else { "‖" };
+    f.render_widget(
+        Paragraph::new(format!("{} {} / {}", state, timestamp(time), timestamp(editor.duration)))
+            .style(Style::default().fg(Color::Rgb(0, 200, 255))),
+        rows[1],
+    );
+    f.render_widget(
+        Paragraph::new(editor.status.clone()).style(Style::default().fg(Color::Yellow)),
+        rows[2],
+    );
+    let help = match editor.text_edit {
+        Some(_) => "Type to edit  Enter Done  Tab Split at the cursor  Esc Cancel",
+        None => "Space Play  Enter Tap  ↑/↓ Select  ←/→ Seek  +/- Nudge  </> Shift all  [/] Stretch all  E Edit text  J Merge with next  Ctrl+Z/Y Undo/Redo  Ctrl+S Save  Q Quit",
+    };
+    f.render_widget(Paragraph::new(help).style(Style::default().fg(Color::White)), rows[3]);
+}
+
+pub fn run(song: &Path, output: Option<&Path>) -> Result<(), io::Error> {
+    let config = formats::load(song)?;
+    let duration = config.given_duration().unwrap_or_else(|| config.lyrics_end() + song_config::DEFAULT_OUTRO);
+    let output: PathBuf = output.map_or_else(|| song.with_extension("lrc"), Path::to_path_buf);
+    let mut editor = Editor {
+        status: format!("{} lines", config.lyrics.len()),
+        lyrics: config.lyrics,
+        duration,
+        selected: 0,
+        history: EditHistory::default(),
+        position: 0.0,
+        playing_since: None,
+        dirty: false,
+        quit_warned: false,
+        text_edit: None,
+    };
+
+    let events = EventBus::new();
+    let mut terminal = crate::setup_terminal()?;
+    events.spawn_terminal_sources(crate::TICK_RATE);
+
+    let result = (|| -> Result<(), io::Error> {
+        loop {
+            terminal.draw(|f| {
+                ui(f, &editor, &output);
+                crate::compat::adapt(f.buffer_mut());
+            })?;
+            let Ok(event) = events.receiver().recv() else {
+                return Ok(());
+            };
+            if let AppEvent::Terminate = event {
+                return Ok(());
+            }
+            if let AppEvent::Input(key) = event {
+                if editor.handle_key(key, &output) {
+                    return Ok(());
+                }
+            }
+        }
+    })();
+
+    crate::restore_terminal()?;
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::style::LineStyle;
+
+    fn line(text: &str, start_time: f64, end_time: f64) -> LyricLine {
+        LyricLine {
+            text: text.to_string(),
+            start_time,
+            end_time,
+            segments: Vec::new(),
+            singer: 0,
+            style: LineStyle::default(),
+            chords: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn undo_and_redo_restore_lines_and_selection() {
+        let mut history = EditHistory::default();
+        let mut lines = vec![line("one", 1.0, 2.0)];
+        let mut selected = 0;
+
+        history.record("Nudge line", &lines, selected);
+        lines[0].start_time = 1.5;
+        selected = 1;
+
+        assert_eq!(history.undo(&mut lines, &mut selected).as_deref(), Some("Nudge line"));
+        assert_eq!((lines[0].start_time, selected), (1.0, 0));
+        assert_eq!(history.undo(&mut lines, &mut selected), None);
+
+        assert_eq!(history.redo(&mut lines, &mut selected).as_deref(), Some("Nudge line"));
+        assert_eq!((lines[0].start_time, selected), (1.5, 1));
+
+        // A new edit after an undo throws away what could have been redone
+        history.undo(&mut lines, &mut selected);
+        history.record("Set line start", &lines, selected);
+        assert_eq!(history.redo(&mut lines, &mut selected), None);
+    }
+
+    #[test]
+    fn splitting_shares_the_time_by_characters() {
+        let (first, second) = split_line(&line("hold on tight", 10.0, 23.0), 8).unwrap();
+        assert_eq!((first.text.as_str(), first.start_time, first.end_time), ("hold on", 10.0, 18.0));
+        assert_eq!((second.text.as_str(), second.start_time, second.end_time), ("tight", 18.0, 23.0));
+        assert!(first.segments.is_empty());
+
+        assert!(split_line(&line("hold on", 0.0, 1.0), 0).is_none());
+    }
+
+    #[test]
+    fn split_then_merge_keeps_word_timing() {
+        let mut timed = line("hey you", 1.0, 3.0);
+        timed.segments = vec![
+            Segment { text: "hey ".to_string(), start_time: 1.0, end_time: 2.0 },
+            Segment { text: "you".to_string(), start_time: 2.0, end_time: 3.0 },
+        ];
+        timed.chords = vec![
+            Chord { name: "G".to_string(), position: 0 },
+            Chord { name: "C".to_string(), position: 4 },
+        ];
+        let (first, second) = split_line(&timed, 4).unwrap();
+        assert_eq!((first.chords.len(), second.chords[0].position), (1, 0));
+        assert_eq!((first.text.as_str(), first.end_time, first.segments.len()), ("hey", 2.0, 1));
+        assert_eq!((second.text.as_str(), second.start_time), ("you", 2.0));
+
+        let merged = merge_lines(&first, &second);
+        assert_eq!(merged.text, "hey you");
+        assert_eq!((merged.start_time, merged.end_time), (1.0, 3.0));
+        let texts: String = merged.segments.iter().map(|segment| segment.text.as_str()).collect();
+        assert_eq!(texts, merged.text);
+        assert_eq!(merged.chords, timed.chords);
+    }
+
+    #[test]
+    fn shifting_moves_word_timings_and_stops_at_zero() {
+        let mut moved = line("hey you", 1.0, 3.0);
+        moved.segments = vec![Segment {
+            text: "hey ".to_string(),
+            start_time: 1.0,
+            end_time: 2.0,
+        }];
+        shift_line(&mut moved, 0.5);
+        assert_eq!((moved.start_time, moved.end_time, moved.segments[0].start_time), (1.5, 3.5, 1.5));
+
+        shift_line(&mut moved, -5.0);
+        assert_eq!((moved.start_time, moved.end_time, moved.segments[0].end_time), (0.0, 2.0, 1.0));
+    }
+
+    #[test]
+    fn retiming_scales_from_the_start_then_shifts() {
+        let mut lines = vec![line("one", 2.0, 4.0), line("two", 6.0, 7.0)];
+        lines[0].segments = vec![Segment { text: "one".to_string(), start_time: 3.0, end_time: 4.0 }];
+        formats::retime(&mut lines, 0.5, 1.0);
+        let times: Vec<_> = lines.iter().map(|line| (line.start_time, line.end_time)).collect();
+        assert_eq!(times, [(2.0, 3.0), (4.0, 4.5)]);
+        assert_eq!(lines[0].segments[0].start_time, 2.5);
+
+        formats::shift(&mut lines, -3.0);
+        assert_eq!((lines[0].start_time, lines[1].start_time), (0.0, 1.0));
+    }
+}
@@ -0,0 +1,106 @@
+use crossterm::event::{self, Event, KeyEvent, KeyEventKind, MouseEvent};
+use signal_hook::{
+    consts::{SIGHUP, SIGINT, SIGTERM},
+    iterator::Signals,
+};
+use std::{
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+    time::Duration,
+};
+
+use crate::{daemon::Query, mic::MicLevel, remote::RemoteCommand, requests::SongRequest, sync::SyncUpdate};
+
+// Everything the main loop reacts to arrives as one of these, whichever thread produced it
+#[derive(Debug, Clone)]
+pub enum AppEvent {
+    Tick,
+    Input(KeyEvent),
+    Mouse(MouseEvent),
+    Resize,
+    Remote(RemoteCommand),
+    MicLevel(MicLevel),
+    Sync(SyncUpdate),
+    Daemon(Query),
+    SongRequest(SongRequest),
+    // SIGINT, SIGTERM or SIGHUP: wrap up and exit as if the user had quit
+    Terminate,
+}
+
+pub struct EventBus {
+    sender: Sender<AppEvent>,
+    receiver: Receiver<AppEvent>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self { sender, receiver }
+    }
+
+    // Other producers (network, audio, ...) clone this to inject their own events
+    pub fn sender(&self) -> Sender<AppEvent> {
+        self.sender.clone()
+    }
+
+    pub fn receiver(&self) -> &Receiver<AppEvent> {
+        &self.receiver
+    }
+
+    pub fn spawn_terminal_sources(&self, tick_rate: Duration) {
+        spawn_ticker(self.sender(), tick_rate);
+        spawn_input(self.sender());
+        spawn_signals(self.sender());
+    }
+
+    // Without a terminal there's no input to read
+    pub fn spawn_background_sources(&self, tick_rate: Duration) {
+        spawn_ticker(self.sender(), tick_rate);
+        spawn_signals(self.sender());
+    }
+}
+
+fn spawn_ticker(sender: Sender<AppEvent>, tick_rate: Duration) {
+    thread::spawn(move || loop {
+        thread::sleep(tick_rate);
+        if sender.send(AppEvent::Tick).is_err() {
+            break;
+        }
+    });
+}
+
+fn spawn_signals(sender: Sender<AppEvent>) {
+    let Ok(mut signals) = Signals::new([SIGINT, SIGTERM, SIGHUP]) else {
+        return;
+    };
+    thread::spawn(move || {
+        for _ in signals.forever() {
+            if sender.send(AppEvent::Terminate).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+fn spawn_input(sender: Sender<AppEvent>) {
+    thread::spawn(move || loop {
+        match event::poll(Duration::from_millis(250)) {
+            Ok(true) => {
+                let app_event = match event::read() {
+                    // Windows reports releases too, which would do everything twice
+                    Ok(Event::Key(key)) if key.kind == KeyEventKind::Release => continue,
+                    Ok(Event::Key(key)) => AppEvent::Input(key),
+                    Ok(Event::Mouse(mouse)) => AppEvent::Mouse(mouse),
+                    Ok(Event::Resize(_, _)) => AppEvent::Resize,
+                    Ok(_) => continue,
+                    Err(_) => break,
+                };
+                if sender.send(app_event).is_err() {
+                    break;
+                }
+            }
+            Ok(false) => {}
+            Err(_) => break,
+        }
+    });
+}
@@ -0,0 +1,111 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use crate::{cli::FetchOptions, formats};
+
+// What yt-dlp says it saved, one tab-separated line printed once the audio is in place
+struct Download {
+    audio: PathBuf,
+    title: String,
+    artist: Option<String>,
+}
+
+fn parse_printed(output: &str) -> Option<Download> {
+    let line = output.lines().rev().find(|line| !line.trim().is_empty())?;
+    let mut fields = line.split('\t');
+    let audio = PathBuf::from(fields.next()?);
+    let title = fields.next().unwrap_or_default().to_string();
+    let artist = fields.next().map(str::to_string).filter(|artist| !artist.is_empty() && artist != "NA");
+    Some(Download { audio, title, artist })
+}
+
+// yt-dlp names captions `<audio stem>.<language>.srv3`; the language asked for wins over any
+// others that matched
+fn captions_for(audio: &Path, lang: &str, names: &[String]) -> Option<PathBuf> {
+    let stem = audio.file_stem()?.to_str()?;
+    let exact = format!("{}.{}.srv3", stem, lang);
+    let mut candidates: Vec<&String> = names
+        .iter()
+        .filter(|name| name.starts_with(&format!("{}.", stem)) && name.ends_with(".srv3"))
+        .collect();
+    candidates.sort_by_key(|name| (**name != exact, name.len(), name.as_str()));
+    Some(audio.with_file_name(candidates.first()?))
+}
+
+fn captions(audio: &Path, lang: &str) -> io::Result<Option<PathBuf>> {
+    let dir = audio.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let names: Vec<String> = fs::read_dir(dir)?
+        .flatten()
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .collect();
+    Ok(captions_for(audio, lang, &names))
+}
+
+// Downloads a video's audio as WAV and its captions with yt-dlp, and turns them into a library
+// entry: the recording and an LRC of the same name. Returns both paths.
+pub fn run(options: &FetchOptions) -> io::Result<(PathBuf, PathBuf)> {
+    fs::create_dir_all(&options.library)?;
+    let template = options.library.join("%(title)s.%(ext)s");
+    eprintln!("Downloading {}...", options.url);
+    let output = Command::new("yt-dlp")
+        .args(["--no-playlist", "--extract-audio", "--audio-format", "wav"])
+        .args(["--write-subs", "--write-auto-subs", "--sub-format", "srv3", "--sub-langs", &options.lang])
+        .arg("--print")
+        .arg("after_move:%(filepath)s\t%(title)s\t%(artist,uploader)s")
+        .arg("--output")
+        .arg(&template)
+        .arg("--")
+        .arg(&options.url)
+        .stdin(Stdio::null())
+        .stderr(Stdio::inherit())
+        .output()
+        .map_err(|e| io::Error::new(e.kind(), format!("couldn't run yt-dlp (is it installed?): {}", e)))?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!("yt-dlp failed ({})", output.status)));
+    }
+    let download = parse_printed(&String::from_utf8_lossy(&output.stdout))
+        .ok_or_else(|| io::Error::other("yt-dlp didn't say where it saved the audio"))?;
+
+    let Some(subtitles) = captions(&download.audio, &options.lang)? else {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!(
+                "the video has no \"{}\" captions; the audio was saved to {}",
+                options.lang,
+                download.audio.display()
+            ),
+        ));
+    };
+    let mut config = formats::load(&subtitles)?;
+    if !download.title.is_empty() {
+        config.title = download.title;
+    }
+    config.artist = download.artist;
+    let lyrics = download.audio.with_extension("lrc");
+    formats::save(&config, &lyrics)?;
+    fs::remove_file(&subtitles)?;
+
+    println!("Saved {} and {} ({} lines)", download.audio.display(), lyrics.display(), config.lyrics.len());
+    Ok((lyrics, download.audio))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_what_yt_dlp_saved() {
+        let download = parse_printed("library/Song (Karaoke).wav\tSong (Karaoke)\tNA\n").unwrap();
+        assert_eq!(download.audio, PathBuf::from("library/Song (Karaoke).wav"));
+        assert_eq!((download.title.as_str(), download.artist), ("Song (Karaoke)", None));
+
+        let names = ["Song.wav", "Song.en-orig.srv3", "Song.en.srv3", "Other.en.srv3"].map(str::to_string);
+        let audio = Path::new("library/Song.wav");
+        assert_eq!(captions_for(audio, "en", &names), Some(PathBuf::from("library/Song.en.srv3")));
+        assert_eq!(captions_for(audio, "en.*", &names), Some(PathBuf::from("library/Song.en.srv3")));
+        assert_eq!(captions_for(audio, "de", &names[..1]), None);
+    }
+}
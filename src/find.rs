@@ -0,0 +1,100 @@
+use crate::LyricLine;
+
+// Looking for a line in the current song, opened with `/`. Matches ignore case, and the first
+// one picked is the next from where the song is, so repeating a search moves forward.
+#[derive(Debug, Clone, Default)]
+pub struct LyricFind {
+    pub query: String,
+    matches: Vec<usize>,
+    selected: usize,
+    // The line playback was at when the search opened
+    from: usize,
+}
+
+impl LyricFind {
+    pub fn new(from: usize) -> Self {
+        Self {
+            from,
+            ..Self::default()
+        }
+    }
+
+    fn update(&mut self, lyrics: &[LyricLine]) {
+        let query = self.query.to_lowercase();
+        self.matches = match query.trim().is_empty() {
+            true => Vec::new(),
+            false => (0..lyrics.len()).filter(|&i| lyrics[i].text.to_lowercase().contains(&query)).collect(),
+        };
+        self.selected = self.matches.iter().position(|&i| i >= self.from).unwrap_or(0);
+    }
+
+    pub fn push(&mut self, c: char, lyrics: &[LyricLine]) {
+        self.query.push(c);
+        self.update(lyrics);
+    }
+
+    pub fn pop(&mut self, lyrics: &[LyricLine]) {
+        self.query.pop();
+        self.update(lyrics);
+    }
+
+    // Moves through the matches, wrapping around at either end
+    pub fn step(&mut self, by: isize) {
+        if !self.matches.is_empty() {
+            self.selected = (self.selected as isize + by).rem_euclid(self.matches.len() as isize) as usize;
+        }
+    }
+
+    pub fn selected(&self) -> Option<usize> {
+        self.matches.get(self.selected).copied()
+    }
+
+    pub fn is_match(&self, line: usize) -> bool {
+        self.matches.binary_search(&line).is_ok()
+    }
+
+    pub fn describe(&self) -> String {
+        match self.matches.len() {
+            _ if self.query.is_empty() => "/▏  type to search the lyrics, Esc to cancel".to_string(),
+            0 => format!("/{}▏  no matches", self.query),
+            count => format!("/{}▏  {} of {} - ↑↓ to pick, Enter to jump", self.query, self.selected + 1, count),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_the_next_match_from_the_current_line() {
+        let lyrics: Vec<LyricLine> = ["Hold on", "let go", "hold ON tight", "go home"]
+            .iter()
+            .enumerate()
+            .map(|(i, text)| LyricLine {
+                text: text.to_string(),
+                start_time: i as f64,
+                end_time: i as f64 + 1.0,
+                segments: Vec::new(),
+                singer: 0,
+                style: Default::default(),
+                chords: Vec::new(),
+            })
+            .collect();
+        let mut find = LyricFind::new(1);
+        "on".chars().for_each(|c| find.push(c, &lyrics));
+        assert_eq!(find.selected(), Some(2));
+        assert!(find.is_match(0) && !find.is_match(1));
+        find.step(1);
+        assert_eq!(find.selected(), Some(0));
+        find.step(-1);
+        assert_eq!(find.describe(), "/on▏  2 of 2 - ↑↓ to pick, Enter to jump");
+
+        "x".chars().for_each(|c| find.push(c, &lyrics));
+        assert_eq!(find.selected(), None);
+        find.pop(&lyrics);
+        find.pop(&lyrics);
+        find.pop(&lyrics);
+        assert!(!find.is_match(0));
+    }
+}
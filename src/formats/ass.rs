@@ -0,0 +1,179 @@
+use super::{line_from_segments, words, ParsedLyrics};
+use crate::{song_config::SongConfig, style::LineStyle, LyricLine, Segment};
+
+// h:mm:ss.cc
+fn parse_time(value: &str) -> Option<f64> {
+    let mut parts = value.trim().split(':');
+    let hours: f64 = parts.next()?.parse().ok()?;
+    let minutes: f64 = parts.next()?.parse().ok()?;
+    let seconds: f64 = parts.next()?.parse().ok()?;
+    Some(hours * 3600.0 + minutes * 60.0 + seconds)
+}
+
+fn time(seconds: f64) -> String {
+    let centis = (seconds.max(0.0) * 100.0).round() as u64;
+    format!("{}:{:02}:{:02}.{:02}", centis / 360_000, centis / 6000 % 60, centis / 100 % 60, centis % 100)
+}
+
+// Reads the duration of a \k, \K, \kf or \ko tag inside an override block, in centiseconds
+fn karaoke_duration(block: &str) -> Option<f64> {
+    block.split('\\').find_map(|tag| {
+        let digits = tag
+            .strip_prefix("kf")
+            .or_else(|| tag.strip_prefix("ko"))
+            .or_else(|| tag.strip_prefix('k'))
+            .or_else(|| tag.strip_prefix('K'))?;
+        digits.trim().parse::<f64>().ok()
+    })
+}
+
+fn clean_text(text: &str) -> String {
+    text.replace("\\N", " ").replace("\\n", " ").replace("\\h", " ")
+}
+
+fn parse_dialogue(start_time: f64, end_time: f64, text: &str) -> Option<LyricLine> {
+    let mut segments: Vec<Segment> = Vec::new();
+    let mut plain = String::new();
+    let mut cursor = start_time;
+    let mut timed = false;
+    let mut rest = text;
+
+    while !rest.is_empty() {
+        if let Some(stripped) = rest.strip_prefix('{') {
+            let close = stripped.find('}').unwrap_or(stripped.len());
+            if let Some(centis) = karaoke_duration(&stripped[..close]) {
+                timed = true;
+                let duration = centis / 100.0;
+                segments.push(Segment {
+                    text: String::new(),
+                    start_time: cursor,
+                    end_time: (cursor + duration).min(end_time),
+                });
+                cursor += duration;
+            }
+            rest = stripped.get(close + 1..).unwrap_or("");
+            continue;
+        }
+
+        let next = rest.find('{').unwrap_or(rest.len());
+        let piece = clean_text(&rest[..next]);
+        match segments.last_mut() {
+            Some(segment) => segment.text.push_str(&piece),
+            None => plain.push_str(&piece),
+        }
+        rest = &rest[next..];
+    }
+
+    if !timed {
+        let text = plain.trim().to_string();
+        return (!text.is_empty()).then(|| LyricLine {
+            text,
+            start_time,
+            end_time,
+            segments: Vec::new(),
+            singer: 0,
+            style: LineStyle::default(),
+            chords: Vec::new(),
+        });
+    }
+
+    // Text before the first \k tag is sung together with the first syllable
+    if let Some(first) = segments.first_mut() {
+        first.text.insert_str(0, &plain);
+    }
+    let mut line = line_from_segments(segments)?;
+    line.start_time = start_time;
+    line.end_time = end_time.max(line.end_time);
+    Some(line)
+}
+
+pub fn parse(text: &str) -> Result<ParsedLyrics, String> {
+    let mut in_events = false;
+    let mut fields: Vec<String> = Vec::new();
+    let mut title = None;
+    let mut lyrics = Vec::new();
+
+    for raw in text.lines() {
+        let line = raw.trim();
+        if line.starts_with('[') {
+            in_events = line.eq_ignore_ascii_case("[events]");
+            continue;
+        }
+        if let Some(value) = line.strip_prefix("Title:") {
+            title = Some(value.trim().to_string()).filter(|t| !t.is_empty());
+        }
+        if !in_events {
+            continue;
+        }
+
+        if let Some(format) = line.strip_prefix("Format:") {
+            fields = format.split(',').map(|field| field.trim().to_ascii_lowercase()).collect();
+        } else if let Some(dialogue) = line.strip_prefix("Dialogue:") {
+            if fields.is_empty() {
+                return Err("Dialogue line before the Format line".to_string());
+            }
+            // Text is always the last field and may itself contain commas
+            let values: Vec<&str> = dialogue.splitn(fields.len(), ',').collect();
+            let field = |name: &str| fields.iter().position(|f| f == name).and_then(|i| values.get(i).copied());
+
+            let (Some(start), Some(end), Some(text)) = (field("start"), field("end"), field("text")) else {
+                continue;
+            };
+            let (Some(start), Some(end)) = (parse_time(start), parse_time(end)) else {
+                return Err(format!("invalid time in dialogue: {}", line));
+            };
+            lyrics.extend(parse_dialogue(start, end, text));
+        }
+    }
+
+    if lyrics.is_empty() {
+        return Err("no dialogue lines found".to_string());
+    }
+    lyrics.sort_by(|a: &LyricLine, b: &LyricLine| a.start_time.total_cmp(&b.start_time));
+
+    Ok(ParsedLyrics {
+        title,
+        artist: None,
+        album: None,
+        key: None,
+        duration: None,
+        lyrics,
+    })
+}
+
+fn centis(seconds: f64) -> i64 {
+    (seconds * 100.0).round() as i64
+}
+
+// One \k tag per word, with an empty one for any pause before it
+fn karaoke_text(line: &LyricLine) -> String {
+    let mut out = String::new();
+    let mut cursor = centis(line.start_time);
+    for word in words(line) {
+        let (start, end) = (centis(word.start_time).max(cursor), centis(word.end_time));
+        if start > cursor {
+            out.push_str(&format!("{{\\k{}}}", start - cursor));
+        }
+        out.push_str(&format!("{{\\k{}}}{}", (end - start).max(0), word.text));
+        cursor = end.max(start);
+    }
+    out
+}
+
+pub fn write(config: &SongConfig) -> String {
+    let mut out = format!(
+        "[Script Info]\nTitle: {}\nScriptType: v4.00+\n\n\
+         [V4+ Styles]\nFormat: Name, Fontname, Fontsize, PrimaryColour, SecondaryColour, Alignment\n\
+         Style: Default,Arial,48,&H00FFFFFF,&H0000FFFF,2\n\n\
+         [Events]\nFormat: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\n",
+        config.title
+    );
+    for line in &config.lyrics {
+        let text = match line.segments.is_empty() {
+            true => line.text.clone(),
+            false => karaoke_text(line),
+        };
+        out.push_str(&format!("Dialogue: 0,{},{},Default,,0,0,0,,{}\n", time(line.start_time), time(line.end_time), text));
+    }
+    out
+}
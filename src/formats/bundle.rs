@@ -0,0 +1,208 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use super::{extension_of, inflate::MAX_LYRICS, parse_bytes, song_from, zip};
+use crate::{song_config::SongConfig, timeline};
+
+pub const EXTENSION: &str = "karaoke";
+const MANIFEST: &str = "bundle.json";
+const FORMAT: u32 = 1;
+// An hour of CD-quality WAV is about 635 MB; nothing else in a bundle is big
+const MAX_AUDIO: usize = 1 << 30;
+const MAX_BUNDLE: usize = MAX_AUDIO + 4 * MAX_LYRICS;
+
+// A .karaoke file is a zip with a `bundle.json` naming the other files in it. Lyrics can be in
+// any supported format; bundles made here carry the song as JSON so nothing is lost.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Manifest {
+    format: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    artist: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    album: Option<String>,
+    lyrics: String,
+    // A WAV recording, played along with the lyrics
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    audio: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    translation: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cover: Option<String>,
+}
+
+// What goes into a bundle besides the song itself
+pub struct Extras {
+    pub audio: Option<PathBuf>,
+    pub translation: Option<PathBuf>,
+    pub cover: Option<PathBuf>,
+}
+
+pub fn is_bundle(path: &Path) -> bool {
+    extension_of(path) == EXTENSION
+}
+
+fn invalid(path: &Path, message: impl std::fmt::Display) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path.display(), message))
+}
+
+// Files are only read out of the archive when they're wanted, so loading the lyrics doesn't
+// unpack the audio
+struct Bundle {
+    path: PathBuf,
+    data: Vec<u8>,
+    manifest: Manifest,
+    entries: Vec<zip::Entry>,
+}
+
+impl Bundle {
+    fn open(path: &Path) -> io::Result<Self> {
+        let data = fs::read(path)?;
+        let entries = zip::entries(&data, MAX_BUNDLE).map_err(|e| invalid(path, e))?;
+        let manifest = entries
+            .iter()
+            .find(|entry| entry.name == MANIFEST)
+            .ok_or_else(|| invalid(path, format!("no {} in the bundle", MANIFEST)))?;
+        let manifest = zip::extract(&data, manifest, MAX_LYRICS).map_err(|e| invalid(path, e))?;
+        let manifest: Manifest = serde_json::from_slice(&manifest).map_err(|e| invalid(path, e))?;
+        if manifest.format > FORMAT {
+            return Err(invalid(path, "made by a newer version of karaoke-lyric"));
+        }
+        Ok(Self {
+            path: path.to_path_buf(),
+            data,
+            manifest,
+            entries,
+        })
+    }
+
+    fn file(&self, name: &str, limit: usize) -> io::Result<Vec<u8>> {
+        let entry = self
+            .entries
+            .iter()
+            .find(|entry| entry.name == name)
+            .ok_or_else(|| invalid(&self.path, format!("{} is missing", name)))?;
+        zip::extract(&self.data, entry, limit).map_err(|e| invalid(&self.path, e))
+    }
+}
+
+pub fn load(path: &Path) -> io::Result<SongConfig> {
+    let bundle = Bundle::open(path)?;
+    let name = &bundle.manifest.lyrics;
+    let lyrics = bundle.file(name, MAX_LYRICS)?;
+    let extension = extension_of(Path::new(name));
+    let mut config = match extension.as_str() {
+        "json" => {
+            let mut config: SongConfig = serde_json::from_slice(&lyrics).map_err(|e| invalid(path, e))?;
+            timeline::sort_lines(&mut config.lyrics);
+            config
+        }
+        _ => {
+            let mut parsed = parse_bytes(&extension, &lyrics).map_err(|e| invalid(path, e))?;
+            timeline::sort_lines(&mut parsed.lyrics);
+            song_from(parsed, path)
+        }
+    };
+    let manifest = bundle.manifest;
+    config.title = manifest.title.unwrap_or(config.title);
+    config.artist = manifest.artist.or(config.artist);
+    config.album = manifest.album.or(config.album);
+    Ok(config)
+}
+
+// The bundle's recording as WAV bytes, if it has one
+pub fn audio(path: &Path) -> io::Result<Option<Vec<u8>>> {
+    let bundle = Bundle::open(path)?;
+    match &bundle.manifest.audio {
+        Some(name) => bundle.file(name, MAX_AUDIO).map(Some),
+        None => Ok(None),
+    }
+}
+
+// Files are stored as `<kind>.<their extension>`
+fn entry(kind: &str, path: &Path) -> io::Result<(String, Vec<u8>)> {
+    let name = match extension_of(path) {
+        extension if extension.is_empty() => kind.to_string(),
+        extension => format!("{}.{}", kind, extension),
+    };
+    Ok((name, fs::read(path)?))
+}
+
+pub fn write(config: &SongConfig, extras: &Extras, output: &Path) -> io::Result<()> {
+    let lyrics = serde_json::to_vec_pretty(config).map_err(io::Error::other)?;
+    let mut manifest = Manifest {
+        format: FORMAT,
+        title: Some(config.title.clone()),
+        artist: config.artist.clone(),
+        album: config.album.clone(),
+        lyrics: "lyrics.json".to_string(),
+        ..Manifest::default()
+    };
+    let mut files = vec![(manifest.lyrics.clone(), lyrics)];
+
+    if let Some(audio) = &extras.audio {
+        if extension_of(audio) != "wav" {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "bundles carry their audio as WAV"));
+        }
+        files.push(entry("audio", audio)?);
+        manifest.audio = files.last().map(|(name, _)| name.clone());
+    }
+    if let Some(translation) = &extras.translation {
+        // Checked now so a bundle never carries lyrics that can't be read back
+        super::load(translation)?;
+        files.push(entry("translation", translation)?);
+        manifest.translation = files.last().map(|(name, _)| name.clone());
+    }
+    if let Some(cover) = &extras.cover {
+        files.push(entry("cover", cover)?);
+        manifest.cover = files.last().map(|(name, _)| name.clone());
+    }
+
+    let manifest = serde_json::to_vec_pretty(&manifest).map_err(io::Error::other)?;
+    files.insert(0, (MANIFEST.to_string(), manifest));
+    let zipped = zip::write(&files).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    fs::write(output, zipped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundles_round_trip() {
+        let dir = std::env::temp_dir().join(format!("karaoke-bundle-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let (recording, cover, output) = (dir.join("song.wav"), dir.join("art.png"), dir.join("song.karaoke"));
+        fs::write(&recording, b"RIFF....WAVE").unwrap();
+        fs::write(&cover, [0x89, b'P', b'N', b'G']).unwrap();
+
+        let mut config = SongConfig::load();
+        config.artist = Some("Somebody".to_string());
+        let extras = Extras {
+            audio: Some(recording),
+            translation: None,
+            cover: Some(cover),
+        };
+        write(&config, &extras, &output).unwrap();
+
+        let loaded = super::super::load(&output).unwrap();
+        assert_eq!((loaded.title, loaded.artist), (config.title.clone(), config.artist.clone()));
+        assert_eq!(loaded.lyrics.len(), config.lyrics.len());
+        assert_eq!(loaded.lyrics[1].text, config.lyrics[1].text);
+        assert_eq!(audio(&output).unwrap().as_deref(), Some(&b"RIFF....WAVE"[..]));
+
+        let data = fs::read(&output).unwrap();
+        let entries = zip::entries(&data, MAX_BUNDLE).unwrap();
+        let names: Vec<&str> = entries.iter().map(|entry| entry.name.as_str()).collect();
+        assert_eq!(names, ["bundle.json", "lyrics.json", "audio.wav", "cover.png"]);
+
+        // Sizes past the caps are refused before anything is unpacked
+        assert!(zip::entries(&data, 100).is_err());
+        assert!(zip::extract(&data, &entries[2], 4).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
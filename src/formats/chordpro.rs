@@ -0,0 +1,198 @@
+use super::{
+    lrc::{parse_timestamp, timestamp},
+    ParsedLyrics,
+};
+use crate::{chords, song_config::SongConfig, style, LyricLine};
+
+// How long each line lasts when the file says nothing about timing
+const DEFAULT_LINE_LENGTH: f64 = 4.0;
+
+// ChordPro songs: `{title: ...}` style directives and lyric lines with `[G]` chords. Lines may
+// start with an LRC time tag; lines without one are spread evenly between the ones that have one.
+pub fn parse(text: &str) -> Result<ParsedLyrics, String> {
+    let mut title = None;
+    let mut artist = None;
+    let mut key = None;
+    let mut duration = None;
+    let mut in_chorus = false;
+    let mut lines: Vec<(Option<f64>, LyricLine)> = Vec::new();
+
+    for raw in text.lines() {
+        let line = raw.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(directive) = line.strip_prefix('{').and_then(|rest| rest.strip_suffix('}')) {
+            let (name, value) = directive.split_once(':').unwrap_or((directive, ""));
+            let value = value.trim().to_string();
+            match name.trim().to_ascii_lowercase().as_str() {
+                "title" | "t" => title = Some(value),
+                "artist" | "subtitle" | "st" => artist = Some(value),
+                "key" => key = Some(value),
+                "duration" => duration = parse_timestamp(&value).or_else(|| value.parse().ok()),
+                "start_of_chorus" | "soc" => in_chorus = true,
+                "end_of_chorus" | "eoc" => in_chorus = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        let (start, body) = match line.strip_prefix('[').and_then(|rest| rest.split_once(']')) {
+            Some((tag, rest)) if parse_timestamp(tag).is_some() => (parse_timestamp(tag), rest),
+            _ => (None, line),
+        };
+        let (mut line_style, body) = style::split_hints(body);
+        let (chords, body) = chords::extract(body);
+        // Lines of nothing but chords are instrumental bars
+        if body.trim().is_empty() {
+            continue;
+        }
+        if in_chorus {
+            line_style.emphasis = Some(true);
+        }
+        lines.push((
+            start,
+            LyricLine {
+                text: body.trim().to_string(),
+                start_time: 0.0,
+                end_time: 0.0,
+                segments: Vec::new(),
+                singer: 0,
+                style: line_style,
+                chords,
+            },
+        ));
+    }
+
+    if lines.is_empty() {
+        return Err("no lyric lines found".to_string());
+    }
+
+    let starts = fill_times(&lines.iter().map(|line| line.0).collect::<Vec<_>>(), duration);
+    let mut lyrics: Vec<LyricLine> = lines.into_iter().map(|line| line.1).collect();
+    for (i, line) in lyrics.iter_mut().enumerate() {
+        line.start_time = starts[i];
+        line.end_time = starts.get(i + 1).copied().unwrap_or_else(|| match duration {
+            Some(duration) if duration > starts[i] => duration,
+            _ => starts[i] + DEFAULT_LINE_LENGTH,
+        });
+    }
+
+    let title = match (artist, title) {
+        (Some(artist), Some(title)) if !artist.is_empty() => Some(format!("{} - {}", artist, title)),
+        (_, title) => title,
+    };
+
+    Ok(ParsedLyrics {
+        title,
+        artist: None,
+        album: None,
+        key: key.filter(|key| !key.is_empty()),
+        duration,
+        lyrics,
+    })
+}
+
+// Start times for every line: known ones are kept and the rest share the time between them,
+// or the song's length when nothing is known
+fn fill_times(known: &[Option<f64>], duration: Option<f64>) -> Vec<f64> {
+    let step = match (known.iter().all(Option::is_none), duration) {
+        (true, Some(duration)) if duration > 0.0 => duration / known.len() as f64,
+        _ => DEFAULT_LINE_LENGTH,
+    };
+
+    let mut starts: Vec<f64> = Vec::with_capacity(known.len());
+    for i in 0..known.len() {
+        if let Some(time) = known[i] {
+            starts.push(time);
+            continue;
+        }
+        let start = match (starts.last(), known[i..].iter().position(Option::is_some)) {
+            // Between two timed lines
+            (Some(&last), Some(ahead)) => {
+                let until = known[i + ahead].unwrap_or(last);
+                last + (until - last) / (ahead + 1) as f64
+            }
+            // Before the first timed line, counting up from the start of the song
+            (None, Some(ahead)) => known[i + ahead].unwrap_or(0.0) * i as f64 / (i + ahead) as f64,
+            (Some(&last), None) => last + step,
+            (None, None) => 0.0,
+        };
+        starts.push(start);
+    }
+    starts
+}
+
+// Takes line times from a timing file, such as an LRC file synced to the same lyrics
+pub fn apply_timing(lyrics: &mut [LyricLine], timing: &[LyricLine]) {
+    for (line, timed) in lyrics.iter_mut().zip(timing) {
+        line.start_time = timed.start_time;
+        line.end_time = timed.end_time;
+        if timed.text.trim() == line.text {
+            line.segments = timed.segments.clone();
+        }
+    }
+    // Lines beyond the timing file keep their spacing after the last timed one
+    if let (Some(last), Some(timed)) = (timing.len().checked_sub(1), timing.last()) {
+        let shift = timed.end_time - lyrics.get(last + 1).map_or(0.0, |line| line.start_time);
+        for line in lyrics.iter_mut().skip(last + 1) {
+            line.start_time += shift;
+            line.end_time += shift;
+        }
+    }
+}
+
+// Each line starts with an LRC time tag; ChordPro has nowhere to keep end times or word timing
+pub fn write(config: &SongConfig) -> String {
+    let mut out = format!("{{title: {}}}\n", config.title);
+    let directives = [("artist", config.artist.clone()), ("key", config.key.clone()), ("duration", config.given_duration().map(timestamp))];
+    for (name, value) in directives {
+        if let Some(value) = value {
+            out.push_str(&format!("{{{}: {}}}\n", name, value));
+        }
+    }
+    out.push('\n');
+    for line in &config.lyrics {
+        out.push_str(&format!(
+            "[{}]{}{}{}\n",
+            timestamp(line.start_time),
+            style::hints(&line.style),
+            chords::insert(&line.text, &line.chords, 0),
+            chords::trailing(&line.chords, line.text.chars().count())
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SONG: &str = "{title: Example}\n{artist: Band}\n{key: G}\n{duration: 0:12}\n\n[G]Example [C]line\n{soc}\n[D]Sing it\n{eoc}\n[G] [C]\nLast line\n";
+
+    #[test]
+    fn reads_directives_and_spreads_untimed_lines() {
+        let parsed = parse(SONG).unwrap();
+        assert_eq!((parsed.title.as_deref(), parsed.key.as_deref()), (Some("Band - Example"), Some("G")));
+        let lines: Vec<(&str, f64, f64)> =
+            parsed.lyrics.iter().map(|line| (line.text.as_str(), line.start_time, line.end_time)).collect();
+        assert_eq!(lines, [("Example line", 0.0, 4.0), ("Sing it", 4.0, 8.0), ("Last line", 8.0, 12.0)]);
+        assert_eq!(parsed.lyrics[0].chords[1].position, 8);
+        assert!(parsed.lyrics[1].style.is_emphasized() && !parsed.lyrics[2].style.is_emphasized());
+    }
+
+    #[test]
+    fn untimed_lines_fill_the_gaps_between_time_tags() {
+        assert_eq!(fill_times(&[None, Some(6.0), None, None, Some(12.0), None], None), [0.0, 6.0, 8.0, 10.0, 12.0, 16.0]);
+    }
+
+    #[test]
+    fn timing_files_replace_the_even_spacing() {
+        let mut lyrics = parse(SONG).unwrap().lyrics;
+        let timing = super::super::lrc::parse("[00:10.00]Example line\n[00:13.50]Sing it\n[00:15.00]\n").unwrap().lyrics;
+        apply_timing(&mut lyrics, &timing);
+        let times: Vec<(f64, f64)> = lyrics.iter().map(|line| (line.start_time, line.end_time)).collect();
+        assert_eq!(times, [(10.0, 13.5), (13.5, 15.0), (15.0, 19.0)]);
+    }
+}
@@ -0,0 +1,168 @@
+// QQ Music's triple DES, which encrypted QRC lyrics go through. It's DES with two S-box entries
+// changed, and with each half of a block or key read with its bytes the other way round, so a
+// standard DES can't read them.
+
+const IP: [u8; 64] = [
+    58, 50, 42, 34, 26, 18, 10, 2, 60, 52, 44, 36, 28, 20, 12, 4, 62, 54, 46, 38, 30, 22, 14, 6, 64, 56, 48, 40, 32, 24, 16, 8,
+    57, 49, 41, 33, 25, 17, 9, 1, 59, 51, 43, 35, 27, 19, 11, 3, 61, 53, 45, 37, 29, 21, 13, 5, 63, 55, 47, 39, 31, 23, 15, 7,
+];
+const FP: [u8; 64] = [
+    40, 8, 48, 16, 56, 24, 64, 32, 39, 7, 47, 15, 55, 23, 63, 31, 38, 6, 46, 14, 54, 22, 62, 30, 37, 5, 45, 13, 53, 21, 61, 29,
+    36, 4, 44, 12, 52, 20, 60, 28, 35, 3, 43, 11, 51, 19, 59, 27, 34, 2, 42, 10, 50, 18, 58, 26, 33, 1, 41, 9, 49, 17, 57, 25,
+];
+const E: [u8; 48] = [
+    32, 1, 2, 3, 4, 5, 4, 5, 6, 7, 8, 9, 8, 9, 10, 11, 12, 13, 12, 13, 14, 15, 16, 17, 16, 17, 18, 19, 20, 21, 20, 21, 22, 23,
+    24, 25, 24, 25, 26, 27, 28, 29, 28, 29, 30, 31, 32, 1,
+];
+const P: [u8; 32] = [16, 7, 20, 21, 29, 12, 28, 17, 1, 15, 23, 26, 5, 18, 31, 10, 2, 8, 24, 14, 32, 27, 3, 9, 19, 13, 30, 6, 22, 11, 4, 25];
+const PC1: [u8; 56] = [
+    57, 49, 41, 33, 25, 17, 9, 1, 58, 50, 42, 34, 26, 18, 10, 2, 59, 51, 43, 35, 27, 19, 11, 3, 60, 52, 44, 36, 63, 55, 47, 39,
+    31, 23, 15, 7, 62, 54, 46, 38, 30, 22, 14, 6, 61, 53, 45, 37, 29, 21, 13, 5, 28, 20, 12, 4,
+];
+const PC2: [u8; 48] = [
+    14, 17, 11, 24, 1, 5, 3, 28, 15, 6, 21, 10, 23, 19, 12, 4, 26, 8, 16, 7, 27, 20, 13, 2, 41, 52, 31, 37, 47, 55, 30, 40, 51,
+    45, 33, 48, 44, 49, 39, 56, 34, 53, 46, 42, 50, 36, 29, 32,
+];
+const SHIFTS: [u32; 16] = [1, 1, 2, 2, 2, 2, 2, 2, 1, 2, 2, 2, 2, 2, 2, 1];
+
+// Four rows of sixteen each
+const SBOXES: [[u8; 64]; 8] = [
+    [
+        14, 4, 13, 1, 2, 15, 11, 8, 3, 10, 6, 12, 5, 9, 0, 7, 0, 15, 7, 4, 14, 2, 13, 1, 10, 6, 12, 11, 9, 5, 3, 8, 4, 1, 14, 8,
+        13, 6, 2, 11, 15, 12, 9, 7, 3, 10, 5, 0, 15, 12, 8, 2, 4, 9, 1, 7, 5, 11, 3, 14, 10, 0, 6, 13,
+    ],
+    [
+        15, 1, 8, 14, 6, 11, 3, 4, 9, 7, 2, 13, 12, 0, 5, 10, 3, 13, 4, 7, 15, 2, 8, 14, 12, 0, 1, 10, 6, 9, 11, 5, 0, 14, 7, 11,
+        10, 4, 13, 1, 5, 8, 12, 6, 9, 3, 2, 15, 13, 8, 10, 1, 3, 15, 4, 2, 11, 6, 7, 12, 0, 5, 14, 9,
+    ],
+    [
+        10, 0, 9, 14, 6, 3, 15, 5, 1, 13, 12, 7, 11, 4, 2, 8, 13, 7, 0, 9, 3, 4, 6, 10, 2, 8, 5, 14, 12, 11, 15, 1, 13, 6, 4, 9,
+        8, 15, 3, 0, 11, 1, 2, 12, 5, 10, 14, 7, 1, 10, 13, 0, 6, 9, 8, 7, 4, 15, 14, 3, 11, 5, 2, 12,
+    ],
+    [
+        7, 13, 14, 3, 0, 6, 9, 10, 1, 2, 8, 5, 11, 12, 4, 15, 13, 8, 11, 5, 6, 15, 0, 3, 4, 7, 2, 12, 1, 10, 14, 9, 10, 6, 9, 0,
+        12, 11, 7, 13, 15, 1, 3, 14, 5, 2, 8, 4, 3, 15, 0, 6, 10, 1, 13, 8, 9, 4, 5, 11, 12, 7, 2, 14,
+    ],
+    [
+        2, 12, 4, 1, 7, 10, 11, 6, 8, 5, 3, 15, 13, 0, 14, 9, 14, 11, 2, 12, 4, 7, 13, 1, 5, 0, 15, 10, 3, 9, 8, 6, 4, 2, 1, 11,
+        10, 13, 7, 8, 15, 9, 12, 5, 6, 3, 0, 14, 11, 8, 12, 7, 1, 14, 2, 13, 6, 15, 0, 9, 10, 4, 5, 3,
+    ],
+    [
+        12, 1, 10, 15, 9, 2, 6, 8, 0, 13, 3, 4, 14, 7, 5, 11, 10, 15, 4, 2, 7, 12, 9, 5, 6, 1, 13, 14, 0, 11, 3, 8, 9, 14, 15, 5,
+        2, 8, 12, 3, 7, 0, 4, 10, 1, 13, 11, 6, 4, 3, 2, 12, 9, 5, 15, 10, 11, 14, 1, 7, 6, 0, 8, 13,
+    ],
+    [
+        4, 11, 2, 14, 15, 0, 8, 13, 3, 12, 9, 7, 5, 10, 6, 1, 13, 0, 11, 7, 4, 9, 1, 10, 14, 3, 5, 12, 2, 15, 8, 6, 1, 4, 11, 13,
+        12, 3, 7, 14, 10, 15, 6, 8, 0, 5, 9, 2, 6, 11, 13, 8, 1, 4, 10, 7, 9, 5, 0, 15, 14, 2, 3, 12,
+    ],
+    [
+        13, 2, 8, 4, 6, 15, 11, 1, 10, 9, 3, 14, 5, 0, 12, 7, 1, 15, 13, 8, 10, 3, 7, 4, 12, 5, 6, 11, 0, 14, 9, 2, 7, 11, 4, 1,
+        9, 12, 14, 2, 0, 6, 10, 13, 15, 3, 5, 8, 2, 1, 14, 7, 4, 10, 8, 13, 15, 12, 9, 0, 3, 5, 6, 11,
+    ],
+];
+
+const QQ_SBOXES: [[u8; 64]; 8] = {
+    let mut sboxes = SBOXES;
+    sboxes[1][16 + 7] = 15;
+    sboxes[3][48 + 5] = 10;
+    sboxes
+};
+
+// Tables count bits from 1 at the top of the `width` bits given
+fn permute(input: u64, table: &[u8], width: u32) -> u64 {
+    table.iter().fold(0, |out, &bit| (out << 1) | ((input >> (width - u32::from(bit))) & 1))
+}
+
+fn subkeys(key: u64) -> [u64; 16] {
+    let key = permute(key, &PC1, 64);
+    let (mut c, mut d) = (key >> 28, key & 0x0fff_ffff);
+    let mut subkeys = [0; 16];
+    for (subkey, &shift) in subkeys.iter_mut().zip(&SHIFTS) {
+        c = ((c << shift) | (c >> (28 - shift))) & 0x0fff_ffff;
+        d = ((d << shift) | (d >> (28 - shift))) & 0x0fff_ffff;
+        *subkey = permute((c << 28) | d, &PC2, 56);
+    }
+    subkeys
+}
+
+fn feistel(half: u64, subkey: u64, sboxes: &[[u8; 64]; 8]) -> u64 {
+    let mixed = permute(half, &E, 32) ^ subkey;
+    let substituted = sboxes.iter().enumerate().fold(0, |out, (i, sbox)| {
+        let six = ((mixed >> (42 - 6 * i)) & 0x3f) as usize;
+        let (row, column) = (((six & 0x20) >> 4) | (six & 1), (six >> 1) & 0x0f);
+        (out << 4) | u64::from(sbox[row * 16 + column])
+    });
+    permute(substituted, &P, 32)
+}
+
+fn crypt(block: u64, subkeys: &[u64; 16], decrypt: bool, sboxes: &[[u8; 64]; 8]) -> u64 {
+    let block = permute(block, &IP, 64);
+    let (mut left, mut right) = (block >> 32, block & 0xffff_ffff);
+    for round in 0..16 {
+        let subkey = if decrypt { subkeys[15 - round] } else { subkeys[round] };
+        (left, right) = (right, left ^ feistel(right, subkey, sboxes));
+    }
+    permute((right << 32) | left, &FP, 64)
+}
+
+// Each half's bytes the other way round from standard DES
+fn read_block(bytes: &[u8]) -> u64 {
+    let half = |from: usize| u64::from(u32::from_le_bytes([bytes[from], bytes[from + 1], bytes[from + 2], bytes[from + 3]]));
+    (half(0) << 32) | half(4)
+}
+
+fn write_block(block: u64) -> [u8; 8] {
+    let mut bytes = [0; 8];
+    bytes[..4].copy_from_slice(&((block >> 32) as u32).to_le_bytes());
+    bytes[4..].copy_from_slice(&(block as u32).to_le_bytes());
+    bytes
+}
+
+// Decrypting undoes encrypting with the first key, decrypting with the second and encrypting
+// with the third
+fn triple(key: &[u8; 24], data: &[u8], decrypt: bool) -> Result<Vec<u8>, String> {
+    if !data.len().is_multiple_of(8) {
+        return Err("encrypted data isn't a whole number of blocks".to_string());
+    }
+    let keys = [0, 8, 16].map(|from| subkeys(read_block(&key[from..from + 8])));
+    let steps = match decrypt {
+        true => [(&keys[2], true), (&keys[1], false), (&keys[0], true)],
+        false => [(&keys[0], false), (&keys[1], true), (&keys[2], false)],
+    };
+    Ok(data
+        .chunks(8)
+        .flat_map(|chunk| {
+            let block = steps.iter().fold(read_block(chunk), |block, &(subkeys, decrypt)| crypt(block, subkeys, decrypt, &QQ_SBOXES));
+            write_block(block)
+        })
+        .collect())
+}
+
+pub fn decrypt(key: &[u8; 24], data: &[u8]) -> Result<Vec<u8>, String> {
+    triple(key, data, true)
+}
+
+#[cfg(test)]
+pub fn encrypt(key: &[u8; 24], data: &[u8]) -> Vec<u8> {
+    triple(key, data, false).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn standard_des_matches_the_worked_example_and_qq_music_differs_from_it() {
+        let subkeys = subkeys(0x1334_5779_9BBC_DFF1);
+        let encrypted = crypt(0x0123_4567_89AB_CDEF, &subkeys, false, &SBOXES);
+        assert_eq!(encrypted, 0x85E8_1354_0F0A_B405);
+        assert_eq!(crypt(encrypted, &subkeys, true, &SBOXES), 0x0123_4567_89AB_CDEF);
+        assert_ne!(crypt(0x0123_4567_89AB_CDEF, &subkeys, false, &QQ_SBOXES), encrypted);
+
+        let key = b"0123456789abcdefFEDCBA98";
+        let data = b"sixteen bytes!!!";
+        let encrypted = encrypt(key, data);
+        assert_eq!(decrypt(key, &encrypted).unwrap(), data);
+        assert!(decrypt(key, &encrypted[..7]).is_err());
+    }
+}
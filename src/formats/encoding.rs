@@ -0,0 +1,238 @@
+use std::{
+    borrow::Cow,
+    io::{self, Write},
+    process::{Command, Stdio},
+    sync::OnceLock,
+    thread,
+};
+
+// Text encodings of lyric files. UTF-8, UTF-16 and the Windows code pages are decoded here;
+// anything else, like the Chinese and Japanese double-byte encodings, goes through `iconv`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Windows1252,
+    Windows1258,
+    // An `iconv` name, e.g. GBK or SHIFT_JIS
+    Other(String),
+}
+
+impl std::str::FromStr for Encoding {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name: String = s.chars().filter(|c| c.is_ascii_alphanumeric()).collect::<String>().to_ascii_lowercase();
+        Ok(match name.as_str() {
+            "" => return Err(()),
+            "utf8" => Encoding::Utf8,
+            "utf16le" | "utf16" => Encoding::Utf16Le,
+            "utf16be" => Encoding::Utf16Be,
+            "windows1252" | "cp1252" | "latin1" | "iso88591" => Encoding::Windows1252,
+            "windows1258" | "cp1258" => Encoding::Windows1258,
+            _ => Encoding::Other(s.to_string()),
+        })
+    }
+}
+
+impl std::fmt::Display for Encoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Encoding::Utf8 => write!(f, "UTF-8"),
+            Encoding::Utf16Le => write!(f, "UTF-16LE"),
+            Encoding::Utf16Be => write!(f, "UTF-16BE"),
+            Encoding::Windows1252 => write!(f, "windows-1252"),
+            Encoding::Windows1258 => write!(f, "windows-1258"),
+            Encoding::Other(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+static FORCED: OnceLock<Encoding> = OnceLock::new();
+
+// `--encoding` wins over detection for every file loaded afterwards. It's set once from the
+// command line before the first song loads, like `--lrc-offset`; a global saves handing it to
+// every caller of `formats::load`, which includes playlists, parties and the library scan.
+pub fn force(encoding: Encoding) {
+    let _ = FORCED.set(encoding);
+}
+
+// What windows-1252 puts where Latin-1 has control characters; None is unused
+const CONTROL_RANGE: [Option<char>; 32] = [
+    Some('€'), None, Some('‚'), Some('ƒ'), Some('„'), Some('…'), Some('†'), Some('‡'),
+    Some('ˆ'), Some('‰'), Some('Š'), Some('‹'), Some('Œ'), None, Some('Ž'), None,
+    None, Some('‘'), Some('’'), Some('“'), Some('”'), Some('•'), Some('–'), Some('—'),
+    Some('˜'), Some('™'), Some('š'), Some('›'), Some('œ'), None, Some('ž'), Some('Ÿ'),
+];
+
+// Vietnamese letters and tone marks take over some Latin-1 positions. The tone marks combine
+// with the letter before them, which terminals draw as one character.
+fn windows_1258(byte: u8) -> Option<char> {
+    Some(match byte {
+        0x8A | 0x8E | 0x9A | 0x9E => return None,
+        0xC3 => 'Ă',
+        0xCC => '\u{300}',
+        0xD0 => 'Đ',
+        0xD2 => '\u{309}',
+        0xD5 => 'Ơ',
+        0xDD => 'Ư',
+        0xDE => '\u{303}',
+        0xE3 => 'ă',
+        0xEC => '\u{301}',
+        0xF0 => 'đ',
+        0xF2 => '\u{323}',
+        0xF5 => 'ơ',
+        0xFD => 'ư',
+        0xFE => '₫',
+        _ => return windows_1252(byte),
+    })
+}
+
+fn windows_1252(byte: u8) -> Option<char> {
+    match byte {
+        0x80..=0x9F => CONTROL_RANGE[byte as usize - 0x80],
+        _ => Some(byte as char),
+    }
+}
+
+// Double-byte text from the East Asian encodings: how many characters look like Japanese kana
+// in Shift-JIS, and like common Chinese in GBK less those that look rare. Rare pairs end in an
+// ASCII byte, which is what Latin text with accents looks like read as GBK. Invalid sequences
+// rule an encoding out.
+fn double_byte_scores(bytes: &[u8]) -> (Option<isize>, Option<isize>) {
+    let score = |lead: fn(u8) -> bool, trail: fn(u8) -> bool, common: fn(u8, u8) -> isize, single: fn(u8) -> bool| {
+        let (mut i, mut hits) = (0, 0);
+        while i < bytes.len() {
+            let byte = bytes[i];
+            if byte < 0x80 || single(byte) {
+                i += 1;
+                continue;
+            }
+            let next = *bytes.get(i + 1)?;
+            if !lead(byte) || !trail(next) {
+                return None;
+            }
+            hits += common(byte, next);
+            i += 2;
+        }
+        Some(hits)
+    };
+    let shift_jis = score(
+        |b| matches!(b, 0x81..=0x9F | 0xE0..=0xFC),
+        |b| matches!(b, 0x40..=0x7E | 0x80..=0xFC),
+        |lead, _| isize::from(matches!(lead, 0x82 | 0x83)),
+        |b| matches!(b, 0xA1..=0xDF),
+    );
+    let gbk = score(
+        |b| matches!(b, 0x81..=0xFE),
+        |b| matches!(b, 0x40..=0x7E | 0x80..=0xFE),
+        |lead, trail| match (lead, trail) {
+            (0xB0..=0xF7, 0xA1..=0xFE) => 1,
+            (_, 0x40..=0x7E) => -1,
+            _ => 0,
+        },
+        |_| false,
+    );
+    (shift_jis, gbk)
+}
+
+pub fn detect(bytes: &[u8]) -> Encoding {
+    match bytes {
+        [0xEF, 0xBB, 0xBF, ..] => return Encoding::Utf8,
+        [0xFF, 0xFE, ..] => return Encoding::Utf16Le,
+        [0xFE, 0xFF, ..] => return Encoding::Utf16Be,
+        _ if std::str::from_utf8(bytes).is_ok() => return Encoding::Utf8,
+        _ => {}
+    }
+    match double_byte_scores(bytes) {
+        (Some(kana), gbk) if kana > 0 && gbk.is_none_or(|gbk| kana >= gbk) => return Encoding::Other("SHIFT_JIS".to_string()),
+        (_, Some(hanzi)) if hanzi > 0 => return Encoding::Other("GBK".to_string()),
+        _ => {}
+    }
+    // Đ and Ư next to tone marks are Vietnamese; there's nothing else to tell the code pages apart
+    let has = |set: &[u8]| bytes.iter().any(|b| set.contains(b));
+    match has(&[0xD0, 0xF0, 0xDD, 0xFD, 0xD5, 0xF5]) && has(&[0xCC, 0xD2, 0xDE, 0xEC, 0xF2]) {
+        true => Encoding::Windows1258,
+        false => Encoding::Windows1252,
+    }
+}
+
+fn utf16(bytes: &[u8], unit: fn([u8; 2]) -> u16) -> String {
+    let units: Vec<u16> = bytes.chunks_exact(2).map(|pair| unit([pair[0], pair[1]])).collect();
+    let text = String::from_utf16_lossy(&units);
+    text.strip_prefix('\u{FEFF}').map(str::to_string).unwrap_or(text)
+}
+
+fn iconv(bytes: &[u8], from: &str) -> io::Result<String> {
+    let mut child = Command::new("iconv")
+        .args(["-f", from, "-t", "UTF-8"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| io::Error::new(e.kind(), format!("couldn't run iconv to read {}: {}", from, e)))?;
+    // Written from another thread so a full output pipe can't stall the input
+    let mut stdin = child.stdin.take().ok_or_else(|| io::Error::other("iconv has no stdin"))?;
+    let input = bytes.to_vec();
+    let writer = thread::spawn(move || stdin.write_all(&input));
+    let output = child.wait_with_output()?;
+    let _ = writer.join();
+    if !output.status.success() {
+        let message = String::from_utf8_lossy(&output.stderr);
+        return Err(io::Error::new(io::ErrorKind::InvalidData, format!("iconv couldn't read {}: {}", from, message.trim())));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+pub fn decode_as<'a>(bytes: &'a [u8], encoding: &Encoding) -> io::Result<Cow<'a, str>> {
+    let single = |table: fn(u8) -> Option<char>| bytes.iter().map(|&b| table(b).unwrap_or('\u{FFFD}')).collect::<String>();
+    Ok(match encoding {
+        Encoding::Utf8 => {
+            let bytes = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]).unwrap_or(bytes);
+            String::from_utf8_lossy(bytes)
+        }
+        Encoding::Utf16Le => utf16(bytes, u16::from_le_bytes).into(),
+        Encoding::Utf16Be => utf16(bytes, u16::from_be_bytes).into(),
+        Encoding::Windows1252 => single(windows_1252).into(),
+        Encoding::Windows1258 => single(windows_1258).into(),
+        Encoding::Other(name) => iconv(bytes, name)?.into(),
+    })
+}
+
+// A lyric file's text in the forced encoding or the one it looks like. If `iconv` can't help,
+// the text is read as UTF-8 with the invalid parts replaced, as before.
+pub fn decode(bytes: &[u8]) -> Cow<'_, str> {
+    let encoding = FORCED.get().cloned().unwrap_or_else(|| detect(bytes));
+    if encoding != Encoding::Utf8 {
+        log::info!("reading lyrics as {}", encoding);
+    }
+    decode_as(bytes, &encoding).unwrap_or_else(|e| {
+        log::warn!("{}", e);
+        String::from_utf8_lossy(bytes)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_the_usual_lyric_file_encodings() {
+        assert_eq!(detect("[00:01.00]hát ca".as_bytes()), Encoding::Utf8);
+        assert_eq!(detect(&[0xFF, 0xFE, b'h', 0]), Encoding::Utf16Le);
+        // あいう in Shift-JIS, 你好 in GBK
+        assert_eq!(detect(&[0x82, 0xA0, 0x82, 0xA2, 0x82, 0xA4]), Encoding::Other("SHIFT_JIS".to_string()));
+        assert_eq!(detect(&[0xC4, 0xE3, 0xBA, 0xC3]), Encoding::Other("GBK".to_string()));
+        // "Đời" and "café" in their Windows code pages
+        let vietnamese = [0xD0, 0xF5, 0xCC, b'i'];
+        assert_eq!(detect(&vietnamese), Encoding::Windows1258);
+        assert_eq!(decode_as(&vietnamese, &Encoding::Windows1258).unwrap(), "Đơ\u{300}i");
+        assert_eq!(detect(b"caf\xE9"), Encoding::Windows1252);
+        assert_eq!(decode_as(b"caf\xE9 \x93hi\x94", &Encoding::Windows1252).unwrap(), "café “hi”");
+
+        assert_eq!("Shift_JIS".parse(), Ok(Encoding::Other("Shift_JIS".to_string())));
+        assert_eq!("UTF-16BE".parse(), Ok(Encoding::Utf16Be));
+        assert_eq!("cp1258".parse(), Ok(Encoding::Windows1258));
+    }
+}
@@ -0,0 +1,247 @@
+// Minimal DEFLATE/zlib decoder for the compressed lyric formats
+
+// A few bytes of DEFLATE can stand for gigabytes, so callers say how much they'll take; lyrics
+// are never anywhere near this
+pub const MAX_LYRICS: usize = 16 << 20;
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    bit: u32,
+    bit_count: u32,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            pos: 0,
+            bit: 0,
+            bit_count: 0,
+        }
+    }
+
+    fn bits(&mut self, count: u32) -> Result<u32, String> {
+        while self.bit_count < count {
+            let byte = *self.data.get(self.pos).ok_or("unexpected end of compressed data")?;
+            self.pos += 1;
+            self.bit |= (byte as u32) << self.bit_count;
+            self.bit_count += 8;
+        }
+        let value = self.bit & ((1u64 << count) - 1) as u32;
+        self.bit >>= count;
+        self.bit_count -= count;
+        Ok(value)
+    }
+
+    fn align_to_byte(&mut self) {
+        self.bit = 0;
+        self.bit_count = 0;
+    }
+}
+
+// Canonical Huffman table: symbol counts per code length and symbols ordered by code
+struct Huffman {
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Self {
+        let mut counts = [0u16; 16];
+        for &len in lengths {
+            counts[len as usize] += 1;
+        }
+        counts[0] = 0;
+
+        let mut offsets = [0u16; 16];
+        for len in 1..16 {
+            offsets[len] = offsets[len - 1] + counts[len - 1];
+        }
+
+        let mut symbols = vec![0u16; lengths.len()];
+        for (symbol, &len) in lengths.iter().enumerate() {
+            if len != 0 {
+                symbols[offsets[len as usize] as usize] = symbol as u16;
+                offsets[len as usize] += 1;
+            }
+        }
+
+        Self { counts, symbols }
+    }
+
+    fn decode(&self, reader: &mut BitReader) -> Result<u16, String> {
+        let mut code: i32 = 0;
+        let mut first: i32 = 0;
+        let mut index: i32 = 0;
+        for len in 1..16 {
+            code |= reader.bits(1)? as i32;
+            let count = self.counts[len] as i32;
+            if code - count < first {
+                return Ok(self.symbols[(index + (code - first)) as usize]);
+            }
+            index += count;
+            first += count;
+            first <<= 1;
+            code <<= 1;
+        }
+        Err("invalid Huffman code".to_string())
+    }
+}
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0];
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145,
+    8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13];
+
+fn too_large(limit: usize) -> String {
+    format!("compressed data expands past {} bytes", limit)
+}
+
+fn inflate_block(reader: &mut BitReader, out: &mut Vec<u8>, lit: &Huffman, dist: &Huffman, limit: usize) -> Result<(), String> {
+    loop {
+        let symbol = lit.decode(reader)?;
+        match symbol {
+            0..=255 if out.len() >= limit => return Err(too_large(limit)),
+            0..=255 => out.push(symbol as u8),
+            256 => return Ok(()),
+            _ => {
+                let idx = (symbol - 257) as usize;
+                if idx >= LENGTH_BASE.len() {
+                    return Err("invalid length symbol".to_string());
+                }
+                let length = LENGTH_BASE[idx] as usize + reader.bits(LENGTH_EXTRA[idx] as u32)? as usize;
+
+                let dist_idx = dist.decode(reader)? as usize;
+                if dist_idx >= DIST_BASE.len() {
+                    return Err("invalid distance symbol".to_string());
+                }
+                let distance = DIST_BASE[dist_idx] as usize + reader.bits(DIST_EXTRA[dist_idx] as u32)? as usize;
+                if distance > out.len() {
+                    return Err("distance points before the start of the data".to_string());
+                }
+                if out.len() + length > limit {
+                    return Err(too_large(limit));
+                }
+
+                let start = out.len() - distance;
+                for i in 0..length {
+                    out.push(out[start + i]);
+                }
+            }
+        }
+    }
+}
+
+fn fixed_tables() -> (Huffman, Huffman) {
+    let mut lengths = [0u8; 288];
+    lengths[..144].fill(8);
+    lengths[144..256].fill(9);
+    lengths[256..280].fill(7);
+    lengths[280..].fill(8);
+    (Huffman::new(&lengths), Huffman::new(&[5u8; 30]))
+}
+
+fn dynamic_tables(reader: &mut BitReader) -> Result<(Huffman, Huffman), String> {
+    const ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+
+    let lit_count = reader.bits(5)? as usize + 257;
+    let dist_count = reader.bits(5)? as usize + 1;
+    let code_count = reader.bits(4)? as usize + 4;
+
+    let mut code_lengths = [0u8; 19];
+    for &idx in ORDER.iter().take(code_count) {
+        code_lengths[idx] = reader.bits(3)? as u8;
+    }
+    let code_table = Huffman::new(&code_lengths);
+
+    let mut lengths = Vec::with_capacity(lit_count + dist_count);
+    while lengths.len() < lit_count + dist_count {
+        let symbol = code_table.decode(reader)?;
+        let (value, repeat) = match symbol {
+            0..=15 => (symbol as u8, 1),
+            16 => {
+                let previous = *lengths.last().ok_or("repeat with no previous length")?;
+                (previous, 3 + reader.bits(2)? as usize)
+            }
+            17 => (0, 3 + reader.bits(3)? as usize),
+            _ => (0, 11 + reader.bits(7)? as usize),
+        };
+        lengths.extend(std::iter::repeat_n(value, repeat));
+    }
+    if lengths.len() > lit_count + dist_count {
+        return Err("code lengths overflow".to_string());
+    }
+
+    Ok((Huffman::new(&lengths[..lit_count]), Huffman::new(&lengths[lit_count..])))
+}
+
+pub fn inflate(data: &[u8], limit: usize) -> Result<Vec<u8>, String> {
+    let mut reader = BitReader::new(data);
+    let mut out = Vec::new();
+
+    loop {
+        let last = reader.bits(1)? == 1;
+        match reader.bits(2)? {
+            0 => {
+                reader.align_to_byte();
+                let pos = reader.pos;
+                let header = data.get(pos..pos + 4).ok_or("truncated stored block")?;
+                let len = u16::from_le_bytes([header[0], header[1]]);
+                if u16::from_le_bytes([header[2], header[3]]) != !len {
+                    return Err("damaged stored block".to_string());
+                }
+                let len = len as usize;
+                let block = data.get(pos + 4..pos + 4 + len).ok_or("truncated stored block")?;
+                if out.len() + len > limit {
+                    return Err(too_large(limit));
+                }
+                out.extend_from_slice(block);
+                reader.pos = pos + 4 + len;
+            }
+            1 => {
+                let (lit, dist) = fixed_tables();
+                inflate_block(&mut reader, &mut out, &lit, &dist, limit)?;
+            }
+            2 => {
+                let (lit, dist) = dynamic_tables(&mut reader)?;
+                inflate_block(&mut reader, &mut out, &lit, &dist, limit)?;
+            }
+            _ => return Err("invalid block type".to_string()),
+        }
+        if last {
+            return Ok(out);
+        }
+    }
+}
+
+// zlib wraps a DEFLATE stream in a two byte header and an Adler-32 trailer
+pub fn zlib_decompress(data: &[u8], limit: usize) -> Result<Vec<u8>, String> {
+    if data.len() < 2 || data[0] & 0x0F != 8 || (u16::from(data[0]) << 8 | u16::from(data[1])) % 31 != 0 {
+        return Err("not zlib compressed data".to_string());
+    }
+    inflate(&data[2..], limit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn output_stops_at_the_limit_and_damaged_blocks_are_refused() {
+        // A thousand a's
+        let zlib = [0x78, 0xda, 0x4b, 0x4c, 0x1c, 0x05, 0xa3, 0x60, 0x14, 0x0c, 0x77, 0x00, 0x00, 0xf9, 0xd8, 0x7a, 0xf8];
+        assert_eq!(zlib_decompress(&zlib, 1000).unwrap(), vec![b'a'; 1000]);
+        assert!(zlib_decompress(&zlib, 999).is_err());
+
+        let stored = [0x01, 0x03, 0x00, 0xfc, 0xff, b'a', b'b', b'c'];
+        assert_eq!(inflate(&stored, 3).unwrap(), b"abc");
+        assert!(inflate(&stored, 2).is_err());
+        assert!(inflate(&[0x01, 0x03, 0x00, 0x00, 0x00, b'a', b'b', b'c'], 3).is_err());
+    }
+}
@@ -0,0 +1,131 @@
+use super::{inflate, line_from_segments, words, ParsedLyrics};
+use crate::{song_config::SongConfig, Segment};
+
+// Kugou's fixed XOR key applied to the zlib stream after the "krc1" magic
+const KEY: [u8; 16] = [64, 71, 97, 119, 94, 50, 116, 71, 81, 54, 49, 45, 206, 210, 110, 105];
+
+pub fn is_encrypted(bytes: &[u8]) -> bool {
+    bytes.starts_with(b"krc1")
+}
+
+pub fn decrypt(bytes: &[u8]) -> Result<String, String> {
+    let xored: Vec<u8> = bytes[4..].iter().enumerate().map(|(i, b)| b ^ KEY[i % KEY.len()]).collect();
+    let plain = inflate::zlib_decompress(&xored, inflate::MAX_LYRICS)?;
+    String::from_utf8(plain).map_err(|_| "decrypted KRC is not valid UTF-8".to_string())
+}
+
+fn ms(value: &str) -> Option<f64> {
+    value.trim().parse::<f64>().ok().map(|v| v / 1000.0)
+}
+
+// Lines look like [start,duration]<offset,duration,0>Word<offset,duration,0>word with word
+// offsets relative to the line start, all in milliseconds
+pub fn parse(text: &str) -> Result<ParsedLyrics, String> {
+    let mut title = None;
+    let mut artist = None;
+    let mut lyrics = Vec::new();
+
+    for raw in text.lines() {
+        let line = raw.trim().trim_start_matches('\u{feff}');
+        let Some(rest) = line.strip_prefix('[') else {
+            continue;
+        };
+        let Some((tag, body)) = rest.split_once(']') else {
+            continue;
+        };
+
+        if let Some(value) = tag.strip_prefix("ti:") {
+            title = Some(value.trim().to_string());
+            continue;
+        }
+        if let Some(value) = tag.strip_prefix("ar:") {
+            artist = Some(value.trim().to_string());
+            continue;
+        }
+        let Some((Some(line_start), Some(_))) = tag.split_once(',').map(|(s, d)| (ms(s), ms(d))) else {
+            continue;
+        };
+
+        let mut segments = Vec::new();
+        let mut rest = body;
+        while let Some(open) = rest.find('<') {
+            let Some(close) = rest[open..].find('>').map(|i| i + open) else {
+                break;
+            };
+            let mut fields = rest[open + 1..close].split(',');
+            let (Some(offset), Some(duration)) = (fields.next().and_then(ms), fields.next().and_then(ms)) else {
+                return Err(format!("invalid word timing: {}", line));
+            };
+            let text_end = rest[close + 1..].find('<').map_or(rest.len(), |i| i + close + 1);
+            segments.push(Segment {
+                text: rest[close + 1..text_end].to_string(),
+                start_time: line_start + offset,
+                end_time: line_start + offset + duration,
+            });
+            rest = &rest[text_end..];
+        }
+
+        lyrics.extend(line_from_segments(segments));
+    }
+
+    if lyrics.is_empty() {
+        return Err("no timed lyric lines found".to_string());
+    }
+
+    let title = match (artist, title) {
+        (Some(artist), Some(title)) if !artist.is_empty() => Some(format!("{} - {}", artist, title)),
+        (_, title) => title,
+    };
+
+    Ok(ParsedLyrics {
+        title,
+        artist: None,
+        album: None,
+        key: None,
+        duration: None,
+        lyrics,
+    })
+}
+
+fn to_ms(seconds: f64) -> i64 {
+    (seconds.max(0.0) * 1000.0).round() as i64
+}
+
+// Plain, unencrypted KRC, which Kugou's own tools read as well
+pub fn write(config: &SongConfig) -> String {
+    let mut out = format!("[ti:{}]\n", config.title);
+    if let Some(artist) = &config.artist {
+        out.push_str(&format!("[ar:{}]\n", artist));
+    }
+    for line in &config.lyrics {
+        let start = to_ms(line.start_time);
+        out.push_str(&format!("[{},{}]", start, to_ms(line.end_time) - start));
+        for word in words(line) {
+            let word_start = to_ms(word.start_time);
+            out.push_str(&format!("<{},{},0>{}", word_start - start, to_ms(word.end_time) - word_start, word.text));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decrypts_and_parses_word_timing() {
+        let file = [
+            0x6b, 0x72, 0x63, 0x31, 0x38, 0x9d, 0xea, 0x59, 0x97, 0x86, 0x7e, 0x0e, 0x7c, 0x18, 0xb8, 0xc8, 0x44,
+            0xe4, 0x5a, 0x59, 0x70, 0x97, 0x50, 0x75, 0x4c, 0x83, 0x42, 0x41, 0x6b, 0x90, 0x71, 0x9b, 0x4f, 0x4f,
+            0x29, 0x03, 0xce, 0x4a, 0xe9, 0x12, 0x56, 0xd3, 0x92, 0xa3, 0x22, 0x37, 0x31, 0x16, 0x2b, 0xde, 0x6d,
+        ];
+        assert!(is_encrypted(&file));
+        let parsed = parse(&decrypt(&file).unwrap()).unwrap();
+        assert_eq!(parsed.title.as_deref(), Some("Test"));
+        let line = &parsed.lyrics[0];
+        assert_eq!(line.text, "Hello");
+        assert_eq!((line.start_time, line.end_time), (1.0, 3.0));
+        assert_eq!(line.segments[1].start_time, 1.5);
+    }
+}
@@ -0,0 +1,393 @@
+use std::sync::OnceLock;
+
+use super::{line_from_segments, shift, ParsedLyrics};
+use crate::{
+    chords,
+    song_config::SongConfig,
+    style::{self, LineStyle},
+    LyricLine, Segment,
+};
+
+// LRC only stores start times, so lines run until the next one starts, within reason
+const MAX_OPEN_LINE: f64 = 10.0;
+const LAST_LINE_LENGTH: f64 = 5.0;
+
+static FORCED_OFFSET: OnceLock<f64> = OnceLock::new();
+
+// `--lrc-offset` replaces the `[offset:]` tag of every LRC file loaded afterwards
+pub fn force_offset(milliseconds: f64) {
+    let _ = FORCED_OFFSET.set(milliseconds);
+}
+
+// Parses mm:ss, mm:ss.xx, mm:ss.xxx and mm:ss:xx
+pub fn parse_timestamp(tag: &str) -> Option<f64> {
+    let mut parts = tag.trim().split(':');
+    let minutes: f64 = parts.next()?.parse().ok()?;
+    let seconds = parts.next()?;
+    let fraction = parts.next();
+    if parts.next().is_some() || !seconds.chars().all(|c| c.is_ascii_digit() || c == '.') {
+        return None;
+    }
+
+    let mut seconds: f64 = seconds.parse().ok()?;
+    if let Some(fraction) = fraction {
+        let digits: f64 = fraction.parse().ok()?;
+        seconds += digits / 10f64.powi(fraction.len() as i32);
+    }
+    Some(minutes * 60.0 + seconds)
+}
+
+enum Token<'a> {
+    Time(f64),
+    Text(&'a str),
+}
+
+// Splits a line body into text and enhanced-LRC `<mm:ss.xx>` word timestamps
+fn tokenize(body: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut rest = body;
+
+    while !rest.is_empty() {
+        if let Some(stripped) = rest.strip_prefix('<') {
+            if let Some(close) = stripped.find('>') {
+                if let Some(time) = parse_timestamp(&stripped[..close]) {
+                    tokens.push(Token::Time(time));
+                    rest = &stripped[close + 1..];
+                    continue;
+                }
+            }
+        }
+        let first_len = rest.chars().next().map_or(1, char::len_utf8);
+        let next = rest[first_len..].find('<').map_or(rest.len(), |i| i + first_len);
+        tokens.push(Token::Text(&rest[..next]));
+        rest = &rest[next..];
+    }
+
+    tokens
+}
+
+fn parse_line(start_time: f64, body: &str, next_start: Option<f64>) -> Option<LyricLine> {
+    let open_end = match next_start {
+        Some(next) => next.min(start_time + MAX_OPEN_LINE),
+        None => start_time + LAST_LINE_LENGTH,
+    };
+
+    let tokens = tokenize(body);
+    if !tokens.iter().any(|token| matches!(token, Token::Time(_))) {
+        let text = body.trim().to_string();
+        return (!text.is_empty()).then(|| LyricLine {
+            text,
+            start_time,
+            end_time: open_end,
+            segments: Vec::new(),
+            singer: 0,
+            style: LineStyle::default(),
+            chords: Vec::new(),
+        });
+    }
+
+    // Each piece of text runs from the timestamp before it to the one after it
+    let mut segments = Vec::new();
+    let mut cursor = start_time;
+    let mut pending: Option<(f64, String)> = None;
+    for token in tokens {
+        match token {
+            Token::Time(time) => {
+                if let Some((start, text)) = pending.take() {
+                    segments.push(Segment { text, start_time: start, end_time: time });
+                }
+                cursor = time;
+            }
+            Token::Text(text) => match &mut pending {
+                Some((_, pending_text)) => pending_text.push_str(text),
+                None => pending = Some((cursor, text.to_string())),
+            },
+        }
+    }
+    if let Some((start, text)) = pending {
+        segments.push(Segment { text, start_time: start, end_time: open_end.max(start) });
+    }
+
+    line_from_segments(segments)
+}
+
+// The ID tags at the top of a file
+#[derive(Default)]
+struct Tags {
+    title: Option<String>,
+    artist: Option<String>,
+    album: Option<String>,
+    // Milliseconds; a positive offset makes every line come sooner
+    offset: f64,
+    length: Option<f64>,
+}
+
+impl Tags {
+    fn read(&mut self, key: &str, value: &str) {
+        let value = value.trim();
+        let text = || Some(value.to_string()).filter(|value| !value.is_empty());
+        match key.trim().to_ascii_lowercase().as_str() {
+            "ti" => self.title = text(),
+            "ar" => self.artist = text(),
+            "al" => self.album = text(),
+            "offset" => self.offset = value.trim_start_matches('+').parse().unwrap_or(0.0),
+            "length" => self.length = parse_timestamp(value),
+            _ => {}
+        }
+    }
+}
+
+pub fn parse(text: &str) -> Result<ParsedLyrics, String> {
+    let mut entries: Vec<(f64, &str)> = Vec::new();
+    let mut id_tags = 0;
+    let mut tags = Tags::default();
+
+    for raw in text.lines() {
+        let mut rest = raw.trim();
+        let mut times = Vec::new();
+
+        // A line may carry several time tags when the same text repeats; anything else in
+        // brackets is an ID tag, or a chord that starts the text
+        while let Some(stripped) = rest.strip_prefix('[') {
+            let Some(close) = stripped.find(']').filter(|&close| !chords::is_chord(&stripped[..close])) else {
+                break;
+            };
+            match (parse_timestamp(&stripped[..close]), stripped[..close].split_once(':')) {
+                (Some(time), _) => times.push(time),
+                (None, Some((key, value))) => {
+                    tags.read(key, value);
+                    id_tags += 1;
+                }
+                (None, None) => {}
+            }
+            rest = &stripped[close + 1..];
+        }
+
+        entries.extend(times.into_iter().map(|time| (time, rest)));
+    }
+
+    entries.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    // Lines with no text only mark where the previous line ends
+    let mut lyrics: Vec<LyricLine> = entries
+        .iter()
+        .enumerate()
+        .filter_map(|(i, &(start_time, body))| {
+            let (style, body) = style::split_hints(body);
+            let (chords, body) = chords::extract(body);
+            let line = parse_line(start_time, &body, entries.get(i + 1).map(|entry| entry.0))?;
+            Some(LyricLine { style, chords, ..line })
+        })
+        .collect();
+
+    let offset = FORCED_OFFSET.get().copied().unwrap_or(tags.offset) / 1000.0;
+    shift(&mut lyrics, -offset);
+
+    // Instrumentals have tags but no lines; the last time tag, if any, marks the end
+    let duration = match lyrics.is_empty() {
+        true if entries.is_empty() && id_tags == 0 => return Err("no timed lyric lines found".to_string()),
+        true => tags.length.or(entries.last().map(|entry| (entry.0 - offset).max(0.0))),
+        false => tags.length,
+    };
+
+    Ok(ParsedLyrics {
+        title: tags.title,
+        artist: tags.artist,
+        album: tags.album,
+        key: None,
+        duration,
+        lyrics,
+    })
+}
+
+fn is_offset_tag(line: &[u8]) -> bool {
+    line.trim_ascii_start().get(..8).is_some_and(|start| start.eq_ignore_ascii_case(b"[offset:"))
+}
+
+// The offset the file's lines were loaded with, in milliseconds
+pub fn offset_in(bytes: &[u8]) -> f64 {
+    let mut tags = Tags::default();
+    for line in bytes.split(|&b| b == b'\n').filter(|line| is_offset_tag(line)) {
+        let tag = String::from_utf8_lossy(line);
+        if let Some((key, value)) = tag.trim().trim_start_matches('[').trim_end_matches(']').split_once(':') {
+            tags.read(key, value);
+        }
+    }
+    FORCED_OFFSET.get().copied().unwrap_or(tags.offset)
+}
+
+// The file with its `[offset:]` tag set, or one added at the top. The rest is left byte for byte
+// as it was, so this works in any encoding that writes ASCII as itself.
+pub fn with_offset(bytes: &[u8], milliseconds: f64) -> Vec<u8> {
+    let newline: &[u8] = match bytes.windows(2).any(|pair| pair == b"\r\n") {
+        true => b"\r\n",
+        false => b"\n",
+    };
+    let tag = format!("[offset:{:+}]", milliseconds.round() as i64).into_bytes();
+    let (bom, body) = match bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]) {
+        Some(body) => (&bytes[..3], body),
+        None => (&bytes[..0], bytes),
+    };
+
+    let mut out = bom.to_vec();
+    if !body.split(|&b| b == b'\n').any(is_offset_tag) {
+        out.extend_from_slice(&tag);
+        out.extend_from_slice(newline);
+        out.extend_from_slice(body);
+        return out;
+    }
+    let mut lines = body.split(|&b| b == b'\n').peekable();
+    while let Some(line) = lines.next() {
+        match is_offset_tag(line) {
+            true if line.ends_with(b"\r") => out.extend(tag.iter().chain(b"\r")),
+            true => out.extend_from_slice(&tag),
+            false => out.extend_from_slice(line),
+        }
+        if lines.peek().is_some() {
+            out.push(b'\n');
+        }
+    }
+    out
+}
+
+pub fn timestamp(seconds: f64) -> String {
+    let centis = (seconds.max(0.0) * 100.0).round() as u64;
+    format!("{:02}:{:02}.{:02}", centis / 6000, centis / 100 % 60, centis % 100)
+}
+
+// Writes enhanced LRC: word tags for lines with segments, and an empty time tag wherever a line
+// ends before the next one starts
+pub fn write(lyrics: &[LyricLine]) -> String {
+    let mut out = String::new();
+    for (i, line) in lyrics.iter().enumerate() {
+        out.push_str(&format!("[{}]{}", timestamp(line.start_time), style::hints(&line.style)));
+        if line.segments.is_empty() {
+            out.push_str(&chords::insert(&line.text, &line.chords, 0));
+        } else {
+            let mut offset = 0;
+            for (j, segment) in line.segments.iter().enumerate() {
+                let text = chords::insert(&segment.text, &line.chords, offset);
+                offset += segment.text.chars().count();
+                out.push_str(&format!("<{}>{}", timestamp(segment.start_time), text));
+                let next_start = line.segments.get(j + 1).map(|next| next.start_time);
+                if next_start.is_none_or(|start| start - segment.end_time > 0.005) {
+                    out.push_str(&format!("<{}>", timestamp(segment.end_time)));
+                }
+            }
+        }
+        out.push_str(&chords::trailing(&line.chords, line.text.chars().count()));
+        out.push('\n');
+
+        let next_start = lyrics.get(i + 1).map(|next| next.start_time);
+        if next_start.is_none_or(|start| start - line.end_time > 0.005) {
+            out.push_str(&format!("[{}]\n", timestamp(line.end_time)));
+        }
+    }
+    out
+}
+
+// The lines with the song's details in ID tags above them
+pub fn write_song(config: &SongConfig) -> String {
+    let tags = [("ti", Some(&config.title)), ("ar", config.artist.as_ref()), ("al", config.album.as_ref())];
+    let mut out: String = tags
+        .into_iter()
+        .filter_map(|(tag, value)| Some(format!("[{}:{}]\n", tag, value?)))
+        .collect();
+    if let Some(duration) = config.given_duration() {
+        out.push_str(&format!("[length:{}]\n", timestamp(duration)));
+    }
+    out + &write(&config.lyrics)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timestamps_accept_common_precisions() {
+        assert_eq!(parse_timestamp("01:02"), Some(62.0));
+        assert_eq!(parse_timestamp("01:02.50"), Some(62.5));
+        assert_eq!(parse_timestamp("01:02.500"), Some(62.5));
+        assert_eq!(parse_timestamp("01:02:50"), Some(62.5));
+        assert_eq!(parse_timestamp("ar:Artist"), None);
+    }
+
+    #[test]
+    fn id_tags_fill_in_the_song_and_shift_its_lines() {
+        let text = "[ti:Song]\n[ar:Band]\n[al:Record]\n[length: 03:25]\n[offset:+500]\n[00:01.00]<00:01.00>One<00:02.00>\n[00:03.00]Two\n";
+        let parsed = parse(text).unwrap();
+        assert_eq!(parsed.title.as_deref(), Some("Song"));
+        assert_eq!((parsed.artist.as_deref(), parsed.album.as_deref()), (Some("Band"), Some("Record")));
+        assert_eq!(parsed.duration, Some(205.0));
+        let times: Vec<_> = parsed.lyrics.iter().map(|l| (l.start_time, l.end_time, l.segments.len())).collect();
+        assert_eq!(times, [(0.5, 1.5, 1), (2.5, 7.5, 0)]);
+    }
+
+    #[test]
+    fn offsets_are_written_into_the_file() {
+        assert_eq!(with_offset(b"[ti:Song]\n[00:01.00]One\n", 250.0), b"[offset:+250]\n[ti:Song]\n[00:01.00]One\n");
+        let text = b"[ti:Song]\r\n[Offset: -100]\r\n[00:01.00]Caf\xE9\r\n";
+        assert_eq!(offset_in(text), -100.0);
+        assert_eq!(with_offset(text, 400.0), b"[ti:Song]\r\n[offset:+400]\r\n[00:01.00]Caf\xE9\r\n");
+    }
+
+    #[test]
+    fn lines_run_until_the_next_line_or_an_empty_marker() {
+        let parsed = parse("[ti:Song]\n[00:01.00]One\n[00:03.00]Two\n[00:04.00]\n").unwrap();
+        let times: Vec<_> = parsed.lyrics.iter().map(|l| (l.text.as_str(), l.start_time, l.end_time)).collect();
+        assert_eq!(times, [("One", 1.0, 3.0), ("Two", 3.0, 4.0)]);
+    }
+
+    #[test]
+    fn tags_without_lines_are_an_instrumental() {
+        let parsed = parse("[ti:Interlude]\n[00:00.00]\n[02:30.00]\n").unwrap();
+        assert!(parsed.lyrics.is_empty());
+        assert_eq!(parsed.duration, Some(150.0));
+        assert!(parse("just some words\n[Chorus]\n").is_err());
+    }
+
+    #[test]
+    fn style_hints_come_before_the_text() {
+        let text = "[00:01.00]{chorus}We will\n[00:02.00]{sic} rock you\n[00:03.00]\n";
+        let lyrics = parse(text).unwrap().lyrics;
+        assert_eq!((lyrics[0].text.as_str(), lyrics[0].style.emphasis), ("We will", Some(true)));
+        assert_eq!(lyrics[1].text, "{sic} rock you");
+        assert_eq!(write(&lyrics), text);
+    }
+
+    #[test]
+    fn inline_chords_sit_on_their_characters() {
+        let text = "[00:01.00][G]Example [C]line[D]\n[00:02.00]<00:02.00>[Am]Hel<00:02.50>lo<00:03.00>\n[00:03.00]\n";
+        let lyrics = parse(text).unwrap().lyrics;
+        assert_eq!(lyrics[0].text, "Example line");
+        let positions: Vec<_> = lyrics[0].chords.iter().map(|chord| (chord.name.as_str(), chord.position)).collect();
+        assert_eq!(positions, [("G", 0), ("C", 8), ("D", 12)]);
+        assert_eq!(lyrics[1].text, "Hello");
+        assert_eq!(write(&lyrics), text);
+    }
+
+    #[test]
+    fn repeated_time_tags_duplicate_the_line_in_order() {
+        let parsed = parse("[00:05.00][00:01.00]Chorus\n[00:03.00]Verse\n").unwrap();
+        let starts: Vec<_> = parsed.lyrics.iter().map(|l| (l.text.as_str(), l.start_time)).collect();
+        assert_eq!(starts, [("Chorus", 1.0), ("Verse", 3.0), ("Chorus", 5.0)]);
+    }
+
+    #[test]
+    fn word_timestamps_become_segments() {
+        let parsed = parse("[00:01.00]<00:01.00>Hel<00:01.50>lo <00:02.00>world<00:03.00>\n").unwrap();
+        let line = &parsed.lyrics[0];
+        assert_eq!(line.text, "Hello world");
+        assert_eq!(line.end_time, 3.0);
+        let segments: Vec<_> = line.segments.iter().map(|s| (s.text.as_str(), s.start_time, s.end_time)).collect();
+        assert_eq!(segments, [("Hel", 1.0, 1.5), ("lo ", 1.5, 2.0), ("world", 2.0, 3.0)]);
+    }
+
+    #[test]
+    fn written_lrc_reads_back_the_same() {
+        let text = "[00:01.00]<00:01.00>Hel<00:01.50>lo<00:02.00>\n[00:02.00]world\n[00:04.50]\n";
+        let lyrics = parse(text).unwrap().lyrics;
+        assert_eq!(write(&lyrics), text);
+        assert_eq!(lyrics[1].end_time, 4.5);
+    }
+}
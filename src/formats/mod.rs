@@ -0,0 +1,350 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    plugin::{LyricSource, Registry},
+    song_config::SongConfig,
+    style::{LineStyle, SongStyle},
+    timeline, LyricLine, Segment,
+};
+
+mod ass;
+pub mod bundle;
+mod chordpro;
+mod des;
+pub mod encoding;
+mod inflate;
+mod krc;
+mod lrc;
+mod qrc;
+mod srt;
+mod srv3;
+mod ttml;
+mod ultrastar;
+mod zip;
+
+// What a lyric file parser produces; anything it can't know is filled in by `load`
+pub struct ParsedLyrics {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    // The key chords are written in, from formats that have chords
+    pub key: Option<String>,
+    pub duration: Option<f64>,
+    pub lyrics: Vec<LyricLine>,
+}
+
+const CHORDPRO_EXTENSIONS: &[&str] = &["cho", "chopro", "chordpro", "crd"];
+
+fn extension_of(path: &Path) -> String {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_ascii_lowercase)
+        .unwrap_or_default()
+}
+
+// A ChordPro song can be synced with an LRC file of the same name next to it
+pub fn timing_file(path: &Path) -> Option<PathBuf> {
+    CHORDPRO_EXTENSIONS.contains(&extension_of(path).as_str()).then(|| path.with_extension("lrc"))
+}
+
+// Loads any supported lyric file, picking the parser from the extension
+pub fn load(path: &Path) -> io::Result<SongConfig> {
+    let extension = extension_of(path);
+
+    if extension == "json" {
+        return SongConfig::from_file(path);
+    }
+    if extension == bundle::EXTENSION {
+        return bundle::load(path);
+    }
+
+    let bytes = fs::read(path)?;
+    let invalid = |path: &Path, e: String| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path.display(), e));
+    let mut parsed = parse_bytes(&extension, &bytes).map_err(|e| invalid(path, e))?;
+
+    if let Some(timing_path) = timing_file(path).filter(|timing_path| timing_path.is_file()) {
+        let timing = lrc::parse(&encoding::decode(&fs::read(&timing_path)?)).map_err(|e| invalid(&timing_path, e))?;
+        chordpro::apply_timing(&mut parsed.lyrics, &timing.lyrics);
+    }
+    timeline::sort_lines(&mut parsed.lyrics);
+
+    Ok(song_from(parsed, path))
+}
+
+// Songs whose file doesn't give a title are named after the file
+fn song_from(parsed: ParsedLyrics, path: &Path) -> SongConfig {
+    let title = parsed.title.unwrap_or_else(|| {
+        path.file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    });
+    let duration = parsed.duration.unwrap_or(0.0);
+
+    SongConfig {
+        title,
+        artist: parsed.artist,
+        album: parsed.album,
+        key: parsed.key,
+        duration,
+        start_position: 0.0,
+        lyrics: parsed.lyrics,
+        sections: Vec::new(),
+        style: SongStyle::default(),
+        tempo: None,
+        view: None,
+    }
+}
+
+// Stretches every line and word's times by `scale` from the start of the song, then moves them
+// by `by`, keeping them after the start
+pub fn retime(lyrics: &mut [LyricLine], scale: f64, by: f64) {
+    let retimed = |time: f64| (time * scale + by).max(0.0);
+    for line in lyrics {
+        line.start_time = retimed(line.start_time);
+        line.end_time = retimed(line.end_time);
+        for segment in &mut line.segments {
+            segment.start_time = retimed(segment.start_time);
+            segment.end_time = retimed(segment.end_time);
+        }
+    }
+}
+
+pub fn shift(lyrics: &mut [LyricLine], by: f64) {
+    retime(lyrics, 1.0, by);
+}
+
+// Adds `seconds` to an LRC file's `[offset:]` tag, returning the new offset in milliseconds.
+// Other formats have nowhere to keep one.
+pub fn save_lrc_offset(path: &Path, seconds: f64) -> io::Result<f64> {
+    let is_lrc = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("lrc"));
+    if !is_lrc {
+        return Err(io::Error::new(io::ErrorKind::Unsupported, "only LRC files can keep an offset"));
+    }
+    let bytes = fs::read(path)?;
+    if matches!(encoding::detect(&bytes), encoding::Encoding::Utf16Le | encoding::Encoding::Utf16Be) {
+        return Err(io::Error::new(io::ErrorKind::Unsupported, "can't write an offset into a UTF-16 file"));
+    }
+    let milliseconds = (lrc::offset_in(&bytes) + seconds * 1000.0).round();
+    fs::write(path, lrc::with_offset(&bytes, milliseconds))?;
+    Ok(milliseconds)
+}
+
+pub fn force_lrc_offset(milliseconds: f64) {
+    lrc::force_offset(milliseconds);
+}
+
+pub fn to_lrc(lyrics: &[LyricLine]) -> String {
+    lrc::write(lyrics)
+}
+
+fn write_as(extension: &str, config: &SongConfig) -> io::Result<String> {
+    Ok(match extension {
+        "json" => serde_json::to_string_pretty(config).map_err(io::Error::other)? + "\n",
+        "lrc" => lrc::write_song(config),
+        "srt" => srt::write(&config.lyrics),
+        "ass" | "ssa" => ass::write(config),
+        "txt" => ultrastar::write(config),
+        "krc" => krc::write(config),
+        "qrc" => qrc::write(config),
+        "ttml" | "xml" => ttml::write(config),
+        _ if CHORDPRO_EXTENSIONS.contains(&extension) => chordpro::write(config),
+        _ => {
+            let message = format!("can't write .{} files", extension);
+            return Err(io::Error::new(io::ErrorKind::Unsupported, message));
+        }
+    })
+}
+
+// Writes a song in the format the file's extension names. TXT files are written as UltraStar.
+pub fn save(config: &SongConfig, path: &Path) -> io::Result<()> {
+    fs::write(path, write_as(&extension_of(path), config)?)
+}
+
+// Whether `load` reads files with this one's extension
+pub fn is_song_file(path: &Path) -> bool {
+    let extension = extension_of(path);
+    extension == "json" || bundle::is_bundle(path) || builtin_sources().iter().any(|source| source.extensions().contains(&extension.as_str()))
+}
+
+// Whether the format can hold per-word timing
+pub fn keeps_word_timing(path: &Path) -> bool {
+    let extension = extension_of(path);
+    extension != "srt" && !CHORDPRO_EXTENSIONS.contains(&extension.as_str())
+}
+
+// Parses lyrics already in memory, e.g. downloaded from a provider
+pub fn parse_text(extension: &str, text: &str) -> Result<Vec<LyricLine>, String> {
+    parse_bytes(extension, text.as_bytes()).map(|parsed| parsed.lyrics)
+}
+
+fn parse_bytes(extension: &str, bytes: &[u8]) -> Result<ParsedLyrics, String> {
+    Registry::builtin().parse(extension, bytes)
+}
+
+// A plain text format, in whatever encoding the file turns out to be in
+struct TextFormat {
+    extensions: &'static [&'static str],
+    accepts: fn(&str) -> bool,
+    parse: fn(&str) -> Result<ParsedLyrics, String>,
+}
+
+impl LyricSource for TextFormat {
+    fn extensions(&self) -> &[&str] {
+        self.extensions
+    }
+
+    fn accepts(&self, bytes: &[u8]) -> bool {
+        (self.accepts)(&encoding::decode(bytes))
+    }
+
+    fn parse(&self, bytes: &[u8]) -> Result<ParsedLyrics, String> {
+        (self.parse)(&encoding::decode(bytes))
+    }
+}
+
+fn text_format(extensions: &'static [&'static str], parse: fn(&str) -> Result<ParsedLyrics, String>) -> Box<dyn LyricSource> {
+    Box::new(TextFormat {
+        extensions,
+        accepts: |_| true,
+        parse,
+    })
+}
+
+// KRC files usually come encrypted
+struct Krc;
+
+impl LyricSource for Krc {
+    fn extensions(&self) -> &[&str] {
+        &["krc"]
+    }
+
+    fn parse(&self, bytes: &[u8]) -> Result<ParsedLyrics, String> {
+        if krc::is_encrypted(bytes) {
+            krc::parse(&krc::decrypt(bytes)?)
+        } else {
+            krc::parse(&encoding::decode(bytes))
+        }
+    }
+}
+
+struct Qrc;
+
+impl LyricSource for Qrc {
+    fn extensions(&self) -> &[&str] {
+        &["qrc"]
+    }
+
+    fn parse(&self, bytes: &[u8]) -> Result<ParsedLyrics, String> {
+        if qrc::is_encrypted(bytes) {
+            qrc::parse(&qrc::decrypt(bytes)?)
+        } else {
+            qrc::parse(&encoding::decode(bytes))
+        }
+    }
+}
+
+pub fn builtin_sources() -> Vec<Box<dyn LyricSource>> {
+    vec![
+        text_format(&["lrc"], lrc::parse),
+        text_format(&["ass", "ssa"], ass::parse),
+        Box::new(TextFormat {
+            extensions: &["txt"],
+            accepts: ultrastar::looks_like,
+            parse: ultrastar::parse,
+        }),
+        Box::new(Krc),
+        Box::new(Qrc),
+        text_format(&["srt"], srt::parse),
+        text_format(&["ttml", "xml"], ttml::parse),
+        text_format(&["srv3", "ytt"], srv3::parse),
+        // yt-dlp can also save them as plain .xml
+        Box::new(TextFormat {
+            extensions: &["xml"],
+            accepts: srv3::looks_like,
+            parse: srv3::parse,
+        }),
+        text_format(CHORDPRO_EXTENSIONS, chordpro::parse),
+    ]
+}
+
+// A line's timed words, or the whole line as one word when it has no word timing
+fn words(line: &LyricLine) -> Vec<Segment> {
+    match line.segments.is_empty() {
+        true => vec![Segment {
+            text: line.text.clone(),
+            start_time: line.start_time,
+            end_time: line.end_time,
+        }],
+        false => line.segments.clone(),
+    }
+}
+
+// Builds a line whose text and timing span all of its segments
+pub fn line_from_segments(mut segments: Vec<Segment>) -> Option<LyricLine> {
+    if let Some(first) = segments.first_mut() {
+        first.text = first.text.trim_start().to_string();
+    }
+    if let Some(last) = segments.last_mut() {
+        last.text = last.text.trim_end().to_string();
+    }
+    segments.retain(|segment| !segment.text.is_empty());
+
+    let start_time = segments.first()?.start_time;
+    let end_time = segments.last()?.end_time;
+    Some(LyricLine {
+        text: segments.iter().map(|segment| segment.text.as_str()).collect(),
+        start_time,
+        end_time,
+        segments,
+        singer: 0,
+        style: LineStyle::default(),
+        chords: Vec::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn songs_load_with_their_lines_in_order() {
+        let path = std::env::temp_dir().join(format!("karaoke-lyric-unsorted-{}.lrc", std::process::id()));
+        fs::write(&path, "[00:05.00]second\n[00:01.00]first\n").unwrap();
+        let loaded = load(&path);
+        fs::remove_file(&path).unwrap();
+        let texts: Vec<String> = loaded.unwrap().lyrics.into_iter().map(|line| line.text).collect();
+        assert_eq!(texts, ["first", "second"]);
+    }
+
+    #[test]
+    fn conversions_keep_what_both_formats_can_hold() {
+        let segment = |text: &str, start_time, end_time| Segment { text: text.to_string(), start_time, end_time };
+        let mut config = SongConfig::load();
+        config.lyrics.truncate(2);
+        config.lyrics[0].segments = vec![segment("Exam", 0.0, 0.5), segment("ple ", 0.5, 1.0), segment("line 1", 1.5, 3.0)];
+        config.lyrics[1].singer = 1;
+
+        let times = |lyrics: &[LyricLine]| -> Vec<(String, i64, i64)> {
+            let centis = |time: f64| (time * 100.0).round() as i64;
+            lyrics.iter().map(|line| (line.text.clone(), centis(line.start_time), centis(line.end_time))).collect()
+        };
+        let words = |line: &LyricLine| -> Vec<(String, f64)> {
+            line.segments.iter().map(|s| (s.text.trim().to_string(), s.end_time)).collect()
+        };
+        for extension in ["lrc", "ass", "txt", "krc", "qrc", "ttml", "srt", "cho"] {
+            let written = write_as(extension, &config).unwrap();
+            let parsed = parse_bytes(extension, written.as_bytes()).unwrap();
+            assert_eq!(times(&parsed.lyrics)[0], times(&config.lyrics)[0], "{}", extension);
+            if keeps_word_timing(Path::new(&format!("song.{}", extension))) {
+                assert_eq!(times(&parsed.lyrics), times(&config.lyrics), "{}", extension);
+                assert_eq!(words(&parsed.lyrics[0]), words(&config.lyrics[0]), "{}", extension);
+            }
+        }
+        assert_eq!(ttml::parse(&ttml::write(&config)).unwrap().lyrics[1].singer, 1);
+        let json: SongConfig = serde_json::from_str(&write_as("json", &config).unwrap()).unwrap();
+        assert_eq!(times(&json.lyrics), times(&config.lyrics));
+    }
+}
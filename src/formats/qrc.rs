@@ -0,0 +1,204 @@
+use super::{des, inflate, line_from_segments, words, ParsedLyrics};
+use crate::{song_config::SongConfig, Segment};
+
+// QQ Music's key for the triple DES over its zlib compressed lyrics
+const KEY: &[u8; 24] = b"!@#)(*$%123ZXC!@!@#)(NHL";
+// Its lyric cache also keeps files under this XOR mask, beneath which they start with the header
+const LOCAL_HEADER: &[u8] = b"[offset:0]\n";
+const LOCAL_MASK: [u8; 128] = [
+    0xc3, 0x4a, 0xd6, 0xca, 0x90, 0x67, 0xf7, 0x52, 0xd8, 0xa1, 0x66, 0x62, 0x9f, 0x5b, 0x09, 0x00, 0xc3, 0x5e, 0x95, 0x23,
+    0x9f, 0x13, 0x11, 0x7e, 0xd8, 0x92, 0x3f, 0xbc, 0x90, 0xbb, 0x74, 0x0e, 0xc3, 0x47, 0x74, 0x3d, 0x90, 0xaa, 0x3f, 0x51,
+    0xd8, 0xf4, 0x11, 0x84, 0x9f, 0xde, 0x95, 0x1d, 0xc3, 0xc6, 0x09, 0xd5, 0x9f, 0xfa, 0x66, 0xf9, 0xd8, 0xf0, 0xf7, 0xa0,
+    0x90, 0xa1, 0xd6, 0xf3, 0xc3, 0xf3, 0xd6, 0xa1, 0x90, 0xa0, 0xf7, 0xf0, 0xd8, 0xf9, 0x66, 0xfa, 0x9f, 0xd5, 0x09, 0xc6,
+    0xc3, 0x1d, 0x95, 0xde, 0x9f, 0x84, 0x11, 0xf4, 0xd8, 0x51, 0x3f, 0xaa, 0x90, 0x3d, 0x74, 0x47, 0xc3, 0x0e, 0x74, 0xbb,
+    0x90, 0xbc, 0x3f, 0x92, 0xd8, 0x7e, 0x11, 0x13, 0x9f, 0x23, 0x95, 0x5e, 0xc3, 0x00, 0x09, 0x5b, 0x9f, 0x62, 0x66, 0xa1,
+    0xd8, 0x52, 0xf7, 0x67, 0x90, 0xca, 0xd6, 0x4a,
+];
+
+fn unmask(bytes: &[u8]) -> Vec<u8> {
+    bytes
+        .iter()
+        .enumerate()
+        .map(|(i, b)| {
+            let i = if i > 0x7fff { i % 0x7fff } else { i };
+            b ^ LOCAL_MASK[i & 0x7f]
+        })
+        .collect()
+}
+
+fn is_local(bytes: &[u8]) -> bool {
+    bytes.len() > LOCAL_HEADER.len() && unmask(&bytes[..LOCAL_HEADER.len()]) == LOCAL_HEADER
+}
+
+// Downloaded lyrics come as the encrypted bytes written out in hex
+fn hex(bytes: &[u8]) -> Option<Vec<u8>> {
+    let digits: Vec<u8> = bytes.iter().copied().filter(|b| !b.is_ascii_whitespace()).collect();
+    if digits.is_empty() || !digits.len().is_multiple_of(16) {
+        return None;
+    }
+    digits
+        .chunks(2)
+        .map(|pair| u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok())
+        .collect()
+}
+
+// Plain QRC is text in any encoding, with brackets that no hex or masked file starts with
+pub fn is_encrypted(bytes: &[u8]) -> bool {
+    is_local(bytes) || hex(bytes).is_some()
+}
+
+pub fn decrypt(bytes: &[u8]) -> Result<String, String> {
+    let encrypted = match is_local(bytes) {
+        true => unmask(bytes).split_off(LOCAL_HEADER.len()),
+        false => hex(bytes).ok_or("encrypted QRC is not hex text")?,
+    };
+    let plain = inflate::zlib_decompress(&des::decrypt(KEY, &encrypted)?, inflate::MAX_LYRICS)?;
+    String::from_utf8(plain).map_err(|_| "decrypted QRC is not valid UTF-8".to_string())
+}
+
+fn ms(value: &str) -> Option<f64> {
+    value.trim().parse::<f64>().ok().map(|v| v / 1000.0)
+}
+
+fn unescape_xml(text: &str) -> String {
+    text.replace("&#10;", "\n")
+        .replace("&#13;", "")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+// The lyric text may be wrapped in <Lyric_1 LyricContent="..."/>
+fn lyric_content(text: &str) -> String {
+    const ATTRIBUTE: &str = "LyricContent=\"";
+    match text.find(ATTRIBUTE) {
+        Some(start) => {
+            let content = &text[start + ATTRIBUTE.len()..];
+            let end = content.find('"').unwrap_or(content.len());
+            unescape_xml(&content[..end])
+        }
+        None => text.to_string(),
+    }
+}
+
+// Lines look like [start,duration]Word(start,duration)word(start,duration) with absolute times
+// in milliseconds following each word
+pub fn parse(text: &str) -> Result<ParsedLyrics, String> {
+    let content = lyric_content(text);
+    let mut title = None;
+    let mut lyrics = Vec::new();
+
+    for raw in content.lines() {
+        let line = raw.trim();
+        let Some(rest) = line.strip_prefix('[') else {
+            continue;
+        };
+        let Some((tag, body)) = rest.split_once(']') else {
+            continue;
+        };
+        if let Some(value) = tag.strip_prefix("ti:") {
+            title = Some(value.trim().to_string()).filter(|t| !t.is_empty());
+            continue;
+        }
+        if !tag.contains(',') {
+            continue;
+        }
+
+        let mut segments = Vec::new();
+        let mut pending = String::new();
+        let mut rest = body;
+        while let Some(open) = rest.find('(') {
+            let Some(close) = rest[open..].find(')').map(|i| i + open) else {
+                break;
+            };
+            let mut fields = rest[open + 1..close].split(',');
+            match (fields.next().and_then(ms), fields.next().and_then(ms)) {
+                (Some(start), Some(duration)) => {
+                    pending.push_str(&rest[..open]);
+                    segments.push(Segment {
+                        text: std::mem::take(&mut pending),
+                        start_time: start,
+                        end_time: start + duration,
+                    });
+                }
+                // Parentheses that aren't timings are part of the lyric
+                _ => pending.push_str(&rest[..=close]),
+            }
+            rest = &rest[close + 1..];
+        }
+
+        lyrics.extend(line_from_segments(segments));
+    }
+
+    if lyrics.is_empty() {
+        return Err("no timed lyric lines found".to_string());
+    }
+
+    Ok(ParsedLyrics {
+        title,
+        artist: None,
+        album: None,
+        key: None,
+        duration: None,
+        lyrics,
+    })
+}
+
+fn to_ms(seconds: f64) -> i64 {
+    (seconds.max(0.0) * 1000.0).round() as i64
+}
+
+// The bare lyric text, without the XML wrapper
+pub fn write(config: &SongConfig) -> String {
+    let mut out = format!("[ti:{}]\n", config.title);
+    for line in &config.lyrics {
+        let start = to_ms(line.start_time);
+        out.push_str(&format!("[{},{}]", start, to_ms(line.end_time) - start));
+        for word in words(line) {
+            let word_start = to_ms(word.start_time);
+            out.push_str(&format!("{}({},{})", word.text, word_start, to_ms(word.end_time) - word_start));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_lyrics_from_the_xml_wrapper() {
+        let xml = "<QrcInfos><LyricInfo><Lyric_1 LyricType=\"1\" \
+                   LyricContent=\"[ti:Song]&#10;[1000,2000](Oh) Hel(1000,500)lo(1500,1500)&#10;\"/></LyricInfo></QrcInfos>";
+        let parsed = parse(xml).unwrap();
+        assert_eq!(parsed.title.as_deref(), Some("Song"));
+        assert_eq!(parsed.lyrics[0].text, "(Oh) Hello");
+        assert_eq!(parsed.lyrics[0].end_time, 3.0);
+    }
+
+    #[test]
+    fn decrypts_downloaded_and_cached_files_and_reads_plain_ones_in_any_encoding() {
+        let xml = "<QrcInfos><LyricInfo><Lyric_1 LyricContent=\"[ti:Song]&#10;[1000,2000]Hel(1000,500)lo(1500,1500)&#10;\"/></LyricInfo></QrcInfos>";
+        // zlib with the XML in one stored block, padded to whole blocks
+        let mut compressed = vec![0x78, 0x01, 0x01];
+        compressed.extend_from_slice(&(xml.len() as u16).to_le_bytes());
+        compressed.extend_from_slice(&(!(xml.len() as u16)).to_le_bytes());
+        compressed.extend_from_slice(xml.as_bytes());
+        compressed.resize(compressed.len().div_ceil(8) * 8, 0);
+        let encrypted = des::encrypt(KEY, &compressed);
+
+        let downloaded: String = encrypted.iter().map(|b| format!("{:02X}", b)).collect();
+        let cached = unmask(&[LOCAL_HEADER, &encrypted].concat());
+        for file in [downloaded.as_bytes(), &cached] {
+            assert!(is_encrypted(file));
+            assert_eq!(parse(&decrypt(file).unwrap()).unwrap().lyrics[0].text, "Hello");
+        }
+
+        let utf16: Vec<u8> = [0xFF, 0xFE].into_iter().chain(xml.encode_utf16().flat_map(u16::to_le_bytes)).collect();
+        assert!(!is_encrypted(&utf16) && !is_encrypted(xml.as_bytes()));
+        assert_eq!(super::super::parse_bytes("qrc", &utf16).unwrap().lyrics[0].text, "Hello");
+    }
+}
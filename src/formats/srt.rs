@@ -0,0 +1,107 @@
+use super::ParsedLyrics;
+use crate::{style::LineStyle, LyricLine};
+
+// hh:mm:ss,mmm, or with a dot as some tools write it
+fn parse_time(value: &str) -> Option<f64> {
+    let mut parts = value.trim().split(':');
+    let hours: f64 = parts.next()?.parse().ok()?;
+    let minutes: f64 = parts.next()?.parse().ok()?;
+    let seconds: f64 = parts.next()?.replace(',', ".").parse().ok()?;
+    Some(hours * 3600.0 + minutes * 60.0 + seconds)
+}
+
+fn time(seconds: f64) -> String {
+    let ms = (seconds.max(0.0) * 1000.0).round() as u64;
+    format!("{:02}:{:02}:{:02},{:03}", ms / 3_600_000, ms / 60_000 % 60, ms / 1000 % 60, ms % 1000)
+}
+
+// Subtitles mark up text with HTML-like tags and `{\an8}` style placement codes
+fn strip_markup(text: &str) -> String {
+    let mut out = String::new();
+    let mut depth: Option<char> = None;
+    for c in text.chars() {
+        match (depth, c) {
+            (None, '<') => depth = Some('>'),
+            (None, '{') => depth = Some('}'),
+            (Some(close), _) if c == close => depth = None,
+            (Some(_), _) => {}
+            (None, _) => out.push(c),
+        }
+    }
+    out
+}
+
+// Numbered cues: a time range line, then one or more lines of text, then a blank line. SRT has
+// no word timing, so lines of a cue are sung as one.
+pub fn parse(text: &str) -> Result<ParsedLyrics, String> {
+    let mut lyrics = Vec::new();
+    let mut cue: Option<(f64, f64, Vec<String>)> = None;
+
+    for raw in text.lines().chain(std::iter::once("")) {
+        let line = raw.trim();
+        if let Some((start, end)) = line.split_once("-->") {
+            let (Some(start), Some(end)) = (parse_time(start), end.split_whitespace().next().and_then(parse_time)) else {
+                return Err(format!("invalid cue time: {}", line));
+            };
+            cue = Some((start, end, Vec::new()));
+            continue;
+        }
+        match &mut cue {
+            Some((_, _, texts)) if !line.is_empty() => texts.push(strip_markup(line).trim().to_string()),
+            Some(_) => {
+                let Some((start_time, end_time, texts)) = cue.take() else {
+                    continue;
+                };
+                let text = texts.into_iter().filter(|text| !text.is_empty()).collect::<Vec<_>>().join(" ");
+                if !text.is_empty() {
+                    lyrics.push(LyricLine {
+                        text,
+                        start_time,
+                        end_time,
+                        segments: Vec::new(),
+                        singer: 0,
+                        style: LineStyle::default(),
+                        chords: Vec::new(),
+                    });
+                }
+            }
+            None => {}
+        }
+    }
+
+    if lyrics.is_empty() {
+        return Err("no subtitle cues found".to_string());
+    }
+    lyrics.sort_by(|a, b| a.start_time.total_cmp(&b.start_time));
+
+    Ok(ParsedLyrics {
+        title: None,
+        artist: None,
+        album: None,
+        key: None,
+        duration: None,
+        lyrics,
+    })
+}
+
+pub fn write(lyrics: &[LyricLine]) -> String {
+    let mut out = String::new();
+    for (i, line) in lyrics.iter().enumerate() {
+        out.push_str(&format!("{}\n{} --> {}\n{}\n\n", i + 1, time(line.start_time), time(line.end_time), line.text));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cues_become_lines_and_back() {
+        let text = "1\n00:00:01,000 --> 00:00:03,500\n<i>Hello</i>\nworld\n\n2\n00:01:02.250 --> 00:01:04,000 X1:0\n{\\an8}Again\n";
+        let lyrics = parse(text).unwrap().lyrics;
+        let lines: Vec<_> = lyrics.iter().map(|l| (l.text.as_str(), l.start_time, l.end_time)).collect();
+        assert_eq!(lines, [("Hello world", 1.0, 3.5), ("Again", 62.25, 64.0)]);
+        assert_eq!(write(&lyrics), "1\n00:00:01,000 --> 00:00:03,500\nHello world\n\n2\n00:01:02,250 --> 00:01:04,000\nAgain\n\n");
+    }
+}
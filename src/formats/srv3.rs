@@ -0,0 +1,188 @@
+use super::{
+    line_from_segments,
+    ttml::{collapse_whitespace, parse_tag, unescape_xml},
+    ParsedLyrics,
+};
+use crate::{LyricLine, Segment};
+
+pub fn looks_like(text: &str) -> bool {
+    text.contains("<timedtext")
+}
+
+// A <p> as read: its time in seconds and its words, each with when it starts
+struct Cue {
+    start: f64,
+    end: f64,
+    words: Vec<(f64, String)>,
+}
+
+fn milliseconds(value: Option<&str>) -> Option<f64> {
+    value?.trim().parse::<f64>().ok().map(|ms| ms / 1000.0)
+}
+
+impl Cue {
+    fn add_text(&mut self, text: &str, start: Option<f64>) {
+        match (start, self.words.last_mut()) {
+            (None, Some((_, last))) => last.push_str(text),
+            (start, _) => self.words.push((start.unwrap_or(self.start), text.to_string())),
+        }
+    }
+
+    // Auto-generated captions roll, so a cue stays up into the next one; a line ends when the next
+    // one starts. Each word lasts until the next one.
+    fn finish(mut self, next_start: Option<f64>) -> Option<LyricLine> {
+        let end = match next_start {
+            Some(next) if next > self.start => self.end.min(next),
+            _ => self.end,
+        };
+        let untimed = self.words.len() <= 1;
+        // Words come with the space in front; segments here keep it after, as in the other formats
+        for i in 1..self.words.len() {
+            if let Some(text) = self.words[i].1.strip_prefix(' ').map(str::to_string) {
+                self.words[i].1 = text;
+                self.words[i - 1].1.push(' ');
+            }
+        }
+        let starts: Vec<f64> = self.words.iter().map(|(start, _)| *start).collect();
+        let segments = self
+            .words
+            .into_iter()
+            .enumerate()
+            .map(|(i, (start_time, text))| Segment {
+                text,
+                start_time: start_time.min(end),
+                end_time: starts.get(i + 1).copied().unwrap_or(end).min(end),
+            })
+            .collect();
+        let line = line_from_segments(segments)?;
+        Some(match untimed {
+            true => LyricLine {
+                segments: Vec::new(),
+                start_time: self.start,
+                end_time: end,
+                ..line
+            },
+            false => line,
+        })
+    }
+}
+
+// Reads YouTube's timed text (srv3, also saved as .ytt): <p t="ms" d="ms"> lines, and in
+// auto-generated captions <s t="ms"> words timed from the start of their line
+pub fn parse(text: &str) -> Result<ParsedLyrics, String> {
+    let mut cues = Vec::new();
+    let mut cue: Option<Cue> = None;
+    // The start of the <s> being read, if it has a time of its own
+    let mut word: Option<Option<f64>> = None;
+
+    let mut rest = text;
+    while !rest.is_empty() {
+        let (node, tail) = match rest.find('<') {
+            Some(0) => {
+                if let Some(comment) = rest.strip_prefix("<!--") {
+                    rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+                    continue;
+                }
+                let end = rest.find('>').ok_or("unterminated tag")?;
+                let (tag, tail) = (&rest[1..end], &rest[end + 1..]);
+                rest = tail;
+                if tag.starts_with('?') || tag.starts_with('!') {
+                    continue;
+                }
+
+                let tag = parse_tag(tag);
+                match (tag.name, tag.closing) {
+                    ("p", false) => {
+                        let start = milliseconds(tag.attribute("t")).unwrap_or(0.0);
+                        let duration = milliseconds(tag.attribute("d")).unwrap_or(0.0);
+                        cue = (!tag.self_closing).then(|| Cue {
+                            start,
+                            end: start + duration,
+                            words: Vec::new(),
+                        });
+                        word = None;
+                    }
+                    ("p", true) => cues.extend(cue.take()),
+                    ("s", false) if !tag.self_closing => {
+                        let start = cue.as_ref().map_or(0.0, |cue| cue.start);
+                        word = Some(milliseconds(tag.attribute("t")).map(|offset| start + offset));
+                    }
+                    ("s", true) => word = None,
+                    ("br", false) => {
+                        if let Some(cue) = &mut cue {
+                            cue.add_text(" ", None);
+                        }
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+            Some(start) => (&rest[..start], &rest[start..]),
+            None => (rest, ""),
+        };
+        rest = tail;
+
+        if let Some(cue) = &mut cue {
+            // The first word of a line has no time of its own, it starts with the line
+            cue.add_text(&collapse_whitespace(&unescape_xml(node)), word.flatten());
+        }
+    }
+
+    // Auto-generated captions interleave empty cues that only hold the previous line in place
+    cues.retain(|cue: &Cue| cue.words.iter().any(|(_, text)| !text.trim().is_empty()));
+    cues.sort_by(|a, b| a.start.total_cmp(&b.start));
+    let starts: Vec<f64> = cues.iter().map(|cue| cue.start).collect();
+    let lyrics: Vec<LyricLine> = cues
+        .into_iter()
+        .enumerate()
+        .filter_map(|(i, cue)| cue.finish(starts.get(i + 1).copied()))
+        .collect();
+    if lyrics.is_empty() {
+        return Err("no timed <p> lines found".to_string());
+    }
+
+    Ok(ParsedLyrics {
+        title: None,
+        artist: None,
+        album: None,
+        key: None,
+        duration: None,
+        lyrics,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AUTO: &str = r#"<?xml version="1.0" encoding="utf-8" ?><timedtext format="3">
+<head><ws id="0"/><wp id="0"/></head>
+<body>
+<w t="0" id="1" wp="1" ws="1"/>
+<p t="1000" d="4000" w="1"><s ac="0">never</s><s t="500" ac="0"> gonna</s><s t="1200" ac="0"> give</s></p>
+<p t="3000" d="2000" w="1" a="1">
+</p>
+<p t="3000" d="3000" w="1"><s ac="0">you</s><s t="400" ac="0"> up</s></p>
+<p t="7000" d="1500">[Music] don&#39;t stop</p>
+</body>
+</timedtext>"#;
+
+    #[test]
+    fn reads_word_timed_auto_captions() {
+        let lines = parse(AUTO).unwrap().lyrics;
+        assert_eq!(lines.len(), 3);
+
+        assert_eq!(lines[0].text, "never gonna give");
+        let words: Vec<(&str, f64, f64)> =
+            lines[0].segments.iter().map(|s| (s.text.as_str(), s.start_time, s.end_time)).collect();
+        assert_eq!(words, [("never ", 1.0, 1.5), ("gonna ", 1.5, 2.2), ("give", 2.2, 3.0)]);
+        // Rolling captions end when the next line comes in
+        assert_eq!((lines[0].start_time, lines[0].end_time), (1.0, 3.0));
+
+        assert_eq!((lines[1].text.as_str(), lines[1].end_time), ("you up", 6.0));
+        assert_eq!(lines[2].text, "[Music] don't stop");
+        assert!(lines[2].segments.is_empty());
+        assert_eq!((lines[2].start_time, lines[2].end_time), (7.0, 8.5));
+        assert!(looks_like(AUTO) && !looks_like("<tt></tt>"));
+    }
+}
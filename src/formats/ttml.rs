@@ -0,0 +1,380 @@
+use super::{line_from_segments, ParsedLyrics};
+use crate::{song_config::SongConfig, style::LineStyle, LyricLine, Segment};
+
+// TTML times are either clock times ("1:02.345", "01:02:03.4") or offsets ("62.3s", "450ms")
+fn parse_time(value: &str) -> Option<f64> {
+    let value = value.trim();
+    if let Some(ms) = value.strip_suffix("ms") {
+        return ms.parse::<f64>().ok().map(|v| v / 1000.0);
+    }
+    if let Some(seconds) = value.strip_suffix('s') {
+        return seconds.parse().ok();
+    }
+    value
+        .split(':')
+        .try_fold(0.0, |total, part| part.parse::<f64>().ok().map(|v| total * 60.0 + v))
+}
+
+// Named entities plus numeric ones like `&#39;`, which YouTube captions are full of
+pub(super) fn unescape_xml(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let entity = rest.find(';').map(|end| (&rest[1..end], end));
+        let decoded = entity.and_then(|(name, end)| {
+            let c = match name {
+                "quot" => '"',
+                "apos" => '\'',
+                "lt" => '<',
+                "gt" => '>',
+                "amp" => '&',
+                _ => match name.strip_prefix("#x").or_else(|| name.strip_prefix("#X")) {
+                    Some(hex) => char::from_u32(u32::from_str_radix(hex, 16).ok()?)?,
+                    None => char::from_u32(name.strip_prefix('#')?.parse().ok()?)?,
+                },
+            };
+            Some((c, end))
+        });
+        match decoded {
+            Some((c, end)) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+pub(super) struct Tag<'a> {
+    pub name: &'a str,
+    attributes: Vec<(&'a str, String)>,
+    pub closing: bool,
+    pub self_closing: bool,
+}
+
+impl Tag<'_> {
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes.iter().find(|(key, _)| *key == name).map(|(_, value)| value.as_str())
+    }
+}
+
+pub(super) fn parse_tag(inner: &str) -> Tag<'_> {
+    let closing = inner.starts_with('/');
+    let self_closing = inner.ends_with('/');
+    let inner = inner.trim_start_matches('/').trim_end_matches('/').trim();
+    let name_end = inner.find(char::is_whitespace).unwrap_or(inner.len());
+    let mut attributes = Vec::new();
+
+    let mut rest = &inner[name_end..];
+    while let Some(eq) = rest.find('=') {
+        let key = rest[..eq].trim();
+        let after = rest[eq + 1..].trim_start();
+        let Some(quote) = after.chars().next().filter(|c| *c == '"' || *c == '\'') else {
+            break;
+        };
+        let Some(end) = after[1..].find(quote) else {
+            break;
+        };
+        attributes.push((key, unescape_xml(&after[1..end + 1])));
+        rest = &after[end + 2..];
+    }
+
+    Tag {
+        name: &inner[..name_end],
+        attributes,
+        closing,
+        self_closing,
+    }
+}
+
+// Collapses whitespace the way TTML's default xml:space handling does
+pub(super) fn collapse_whitespace(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut in_space = false;
+    for c in text.chars() {
+        if c.is_whitespace() {
+            if !in_space {
+                out.push(' ');
+            }
+            in_space = true;
+        } else {
+            out.push(c);
+            in_space = false;
+        }
+    }
+    out
+}
+
+// A <p> being read: the line's own timing plus whatever timed spans it contains
+struct Paragraph {
+    begin: Option<f64>,
+    end: Option<f64>,
+    singer: usize,
+    segments: Vec<Segment>,
+    text: String,
+}
+
+impl Paragraph {
+    fn add_text(&mut self, text: &str, span: Option<(f64, f64)>) {
+        match span {
+            Some((start_time, end_time)) => {
+                // Anything before the first timed word belongs to it
+                let text = std::mem::take(&mut self.text) + text;
+                self.segments.push(Segment {
+                    text,
+                    start_time,
+                    end_time,
+                });
+            }
+            None => match self.segments.last_mut() {
+                Some(last) => last.text.push_str(text),
+                None => self.text.push_str(text),
+            },
+        }
+    }
+
+    fn finish(self) -> Option<LyricLine> {
+        if !self.segments.is_empty() {
+            return line_from_segments(self.segments).map(|line| LyricLine {
+                singer: self.singer,
+                style: LineStyle::default(),
+                ..line
+            });
+        }
+        let text = self.text.trim().to_string();
+        match (self.begin, self.end) {
+            (Some(start_time), Some(end_time)) if !text.is_empty() => Some(LyricLine {
+                text,
+                start_time,
+                end_time,
+                segments: Vec::new(),
+                singer: self.singer,
+                style: LineStyle::default(),
+                chords: Vec::new(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+// Reads Apple Music style TTML: one <p> per line with optional word-timed <span>s, and
+// ttm:agent marking who sings it. Each distinct agent becomes its own singer.
+pub fn parse(text: &str) -> Result<ParsedLyrics, String> {
+    let mut title = None;
+    let mut duration = None;
+    let mut agents: Vec<String> = Vec::new();
+    let mut div_agent: Option<String> = None;
+    let mut in_title = false;
+
+    let mut paragraph: Option<Paragraph> = None;
+    // One entry per open <span>, holding its timing if it has one
+    let mut spans: Vec<Option<(f64, f64)>> = Vec::new();
+    let mut lyrics = Vec::new();
+
+    let mut singer_for = |agent: Option<&str>| -> usize {
+        let Some(agent) = agent else {
+            return 0;
+        };
+        match agents.iter().position(|known| known == agent) {
+            Some(index) => index,
+            None => {
+                agents.push(agent.to_string());
+                agents.len() - 1
+            }
+        }
+    };
+
+    let mut rest = text;
+    while !rest.is_empty() {
+        let (node, tail) = match rest.find('<') {
+            Some(0) => {
+                if let Some(comment) = rest.strip_prefix("<!--") {
+                    rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+                    continue;
+                }
+                let end = rest.find('>').ok_or("unterminated tag")?;
+                let (tag, tail) = (&rest[1..end], &rest[end + 1..]);
+                rest = tail;
+                if tag.starts_with('?') || tag.starts_with('!') {
+                    continue;
+                }
+
+                let tag = parse_tag(tag);
+                match (tag.name, tag.closing) {
+                    ("ttm:agent", false) => {
+                        if let Some(id) = tag.attribute("xml:id") {
+                            singer_for(Some(id));
+                        }
+                    }
+                    ("ttm:title", false) => in_title = !tag.self_closing,
+                    ("ttm:title", true) => in_title = false,
+                    ("body", false) => duration = tag.attribute("dur").and_then(parse_time),
+                    ("div", false) => div_agent = tag.attribute("ttm:agent").map(str::to_string),
+                    ("div", true) => div_agent = None,
+                    ("p", false) => {
+                        let agent = tag.attribute("ttm:agent").or(div_agent.as_deref());
+                        paragraph = Some(Paragraph {
+                            begin: tag.attribute("begin").and_then(parse_time),
+                            end: tag.attribute("end").and_then(parse_time),
+                            singer: singer_for(agent),
+                            segments: Vec::new(),
+                            text: String::new(),
+                        });
+                        spans.clear();
+                    }
+                    ("p", true) => lyrics.extend(paragraph.take().and_then(Paragraph::finish)),
+                    ("span", false) if !tag.self_closing => {
+                        let begin = tag.attribute("begin").and_then(parse_time);
+                        let end = tag.attribute("end").and_then(parse_time);
+                        spans.push(begin.zip(end));
+                    }
+                    ("span", true) => {
+                        spans.pop();
+                    }
+                    ("br", false) => {
+                        if let Some(paragraph) = &mut paragraph {
+                            paragraph.add_text(" ", None);
+                        }
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+            Some(start) => (&rest[..start], &rest[start..]),
+            None => (rest, ""),
+        };
+        rest = tail;
+
+        let node = unescape_xml(node);
+        if in_title {
+            title = Some(node.trim().to_string()).filter(|title| !title.is_empty());
+        } else if let Some(paragraph) = &mut paragraph {
+            let timing = spans.iter().rev().find_map(|span| *span);
+            paragraph.add_text(&collapse_whitespace(&node), timing);
+        }
+    }
+
+    if lyrics.is_empty() {
+        return Err("no timed <p> lines found".to_string());
+    }
+    lyrics.sort_by(|a: &LyricLine, b| a.start_time.total_cmp(&b.start_time));
+
+    Ok(ParsedLyrics {
+        title,
+        artist: None,
+        album: None,
+        key: None,
+        duration,
+        lyrics,
+    })
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn clock(seconds: f64) -> String {
+    let ms = (seconds.max(0.0) * 1000.0).round() as u64;
+    format!("{}:{:02}.{:03}", ms / 60_000, ms / 1000 % 60, ms % 1000)
+}
+
+// Apple Music style, one agent per singer so duets keep their parts
+pub fn write(config: &SongConfig) -> String {
+    let singers = config.lyrics.iter().map(|line| line.singer + 1).max().unwrap_or(1);
+    let timing = match config.lyrics.iter().any(|line| !line.segments.is_empty()) {
+        true => "Word",
+        false => "Line",
+    };
+    let mut out = format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<tt xmlns=\"http://www.w3.org/ns/ttml\" \
+         xmlns:ttm=\"http://www.w3.org/ns/ttml#metadata\" xmlns:itunes=\"http://music.apple.com/lyric-ttml-internal\" \
+         itunes:timing=\"{}\">\n<head><metadata><ttm:title>{}</ttm:title>",
+        timing,
+        escape_xml(&config.title)
+    );
+    for singer in 1..=singers {
+        out.push_str(&format!("<ttm:agent type=\"person\" xml:id=\"v{}\"/>", singer));
+    }
+    let duration = config.given_duration().map_or(String::new(), |duration| format!(" dur=\"{}\"", clock(duration)));
+    out.push_str(&format!("</metadata></head>\n<body{}><div>\n", duration));
+    for line in &config.lyrics {
+        out.push_str(&format!(
+            "<p begin=\"{}\" end=\"{}\" ttm:agent=\"v{}\">",
+            clock(line.start_time),
+            clock(line.end_time),
+            line.singer + 1
+        ));
+        match line.segments.is_empty() {
+            true => out.push_str(&escape_xml(&line.text)),
+            false => {
+                for segment in &line.segments {
+                    let (text, space) = match segment.text.strip_suffix(' ') {
+                        Some(text) => (text, " "),
+                        None => (segment.text.as_str(), ""),
+                    };
+                    out.push_str(&format!(
+                        "<span begin=\"{}\" end=\"{}\">{}</span>{}",
+                        clock(segment.start_time),
+                        clock(segment.end_time),
+                        escape_xml(text),
+                        space
+                    ));
+                }
+            }
+        }
+        out.push_str("</p>\n");
+    }
+    out.push_str("</div></body></tt>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DUET: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<tt xmlns="http://www.w3.org/ns/ttml" xmlns:ttm="http://www.w3.org/ns/ttml#metadata" xmlns:itunes="http://music.apple.com/lyric-ttml-internal" itunes:timing="Word">
+<head><metadata><ttm:agent type="person" xml:id="v1"/><ttm:agent type="person" xml:id="v2"/></metadata></head>
+<body dur="1:05.500"><div begin="1.0" end="9.0">
+<p begin="1.000" end="3.000" ttm:agent="v1"><span begin="1.000" end="1.500">Hel</span><span begin="1.500" end="2.000">lo</span> <span begin="2.000" end="3.000">world</span></p>
+<p begin="0:04.000" end="0:06.000" ttm:agent="v2"><span begin="4s" end="6s">Rock &amp; roll</span></p>
+<p begin="7.0" end="9.0" ttm:agent="v1">Untimed words</p>
+</div></body></tt>"#;
+
+    #[test]
+    fn reads_word_timing_and_singers() {
+        let parsed = parse(DUET).unwrap();
+        assert_eq!(parsed.duration, Some(65.5));
+        let lines = &parsed.lyrics;
+        assert_eq!(lines.len(), 3);
+
+        assert_eq!(lines[0].text, "Hello world");
+        assert_eq!(lines[0].segments.len(), 3);
+        assert_eq!(lines[0].segments[1].text, "lo ");
+        assert_eq!((lines[0].start_time, lines[0].end_time), (1.0, 3.0));
+
+        assert_eq!(lines[1].text, "Rock & roll");
+        assert_eq!(lines[1].singer, 1);
+        assert_eq!(lines[1].start_time, 4.0);
+
+        assert_eq!(lines[2].text, "Untimed words");
+        assert!(lines[2].segments.is_empty());
+        assert_eq!(lines[2].singer, 0);
+    }
+
+    #[test]
+    fn clock_and_offset_times() {
+        assert_eq!(parse_time("01:02:03.5"), Some(3723.5));
+        assert_eq!(parse_time("2:03.25"), Some(123.25));
+        assert_eq!(parse_time("450ms"), Some(0.45));
+        assert_eq!(parse_time("bogus"), None);
+        assert_eq!(unescape_xml("don&#39;t &#x2665; &amp;c &bogus"), "don't \u{2665} &c &bogus");
+    }
+}
@@ -0,0 +1,116 @@
+use super::{line_from_segments, words, ParsedLyrics};
+use crate::{song_config::SongConfig, Segment};
+
+// Written files use beats of a hundredth of a second, which UltraStar counts as quarter notes
+const WRITTEN_BPM: f64 = 1500.0;
+
+pub fn looks_like(text: &str) -> bool {
+    text.lines().any(|line| line.trim_start().starts_with("#BPM:"))
+}
+
+fn header_number(value: &str) -> Option<f64> {
+    // Many songs are written with a decimal comma
+    value.trim().replace(',', ".").parse().ok()
+}
+
+pub fn parse(text: &str) -> Result<ParsedLyrics, String> {
+    let mut title = None;
+    let mut artist = None;
+    let mut bpm = None;
+    let mut gap = 0.0;
+
+    let mut lyrics = Vec::new();
+    let mut current: Vec<Segment> = Vec::new();
+
+    for raw in text.lines() {
+        let line = raw.trim_end_matches('\r');
+
+        if let Some(header) = line.strip_prefix('#') {
+            let Some((key, value)) = header.split_once(':') else {
+                continue;
+            };
+            match key.trim().to_ascii_uppercase().as_str() {
+                "TITLE" => title = Some(value.trim().to_string()),
+                "ARTIST" => artist = Some(value.trim().to_string()),
+                "BPM" => bpm = header_number(value),
+                "GAP" => gap = header_number(value).unwrap_or(0.0) / 1000.0,
+                _ => {}
+            }
+            continue;
+        }
+
+        let Some(bpm) = bpm.filter(|&bpm| bpm > 0.0) else {
+            return Err("missing or invalid #BPM header".to_string());
+        };
+        // UltraStar beats are quarter notes of the given BPM
+        let beat = |beats: f64| gap + beats * 60.0 / (bpm * 4.0);
+
+        let mut kind = line.chars();
+        match kind.next() {
+            Some(':' | '*' | 'F' | 'R' | 'G') => {
+                // "<type> <start> <length> <pitch> <text>"; the text keeps its leading space
+                let mut fields = kind.as_str().trim_start().splitn(4, ' ');
+                let (Some(start), Some(length), Some(_pitch), text) =
+                    (fields.next(), fields.next(), fields.next(), fields.next().unwrap_or(""))
+                else {
+                    return Err(format!("invalid note line: {}", line));
+                };
+                let (Ok(start), Ok(length)) = (start.parse::<f64>(), length.parse::<f64>()) else {
+                    return Err(format!("invalid note line: {}", line));
+                };
+                current.push(Segment {
+                    text: text.to_string(),
+                    start_time: beat(start),
+                    end_time: beat(start + length),
+                });
+            }
+            Some('-') | Some('E') => {
+                lyrics.extend(line_from_segments(std::mem::take(&mut current)));
+                if line.starts_with('E') {
+                    break;
+                }
+            }
+            _ => {}
+        }
+    }
+    lyrics.extend(line_from_segments(current));
+
+    if lyrics.is_empty() {
+        return Err("no notes found".to_string());
+    }
+
+    let title = match (artist, title) {
+        (Some(artist), Some(title)) => Some(format!("{} - {}", artist, title)),
+        (_, title) => title,
+    };
+
+    Ok(ParsedLyrics {
+        title,
+        artist: None,
+        album: None,
+        key: None,
+        duration: None,
+        lyrics,
+    })
+}
+
+// Every word becomes a note of the same pitch, since lyric files don't say what's sung
+pub fn write(config: &SongConfig) -> String {
+    let beat = |seconds: f64| (seconds.max(0.0) * WRITTEN_BPM * 4.0 / 60.0).round() as i64;
+    let mut out = format!("#TITLE:{}\n", config.title);
+    if let Some(artist) = &config.artist {
+        out.push_str(&format!("#ARTIST:{}\n", artist));
+    }
+    out.push_str(&format!("#BPM:{}\n#GAP:0\n", WRITTEN_BPM));
+    for (i, line) in config.lyrics.iter().enumerate() {
+        if i > 0 {
+            out.push_str(&format!("- {}\n", beat(config.lyrics[i - 1].end_time)));
+        }
+        for word in words(line) {
+            let start = beat(word.start_time);
+            out.push_str(&format!(": {} {} 0 {}\n", start, beat(word.end_time) - start, word.text));
+        }
+    }
+    out.push_str("E\n");
+    out
+}
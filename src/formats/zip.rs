@@ -0,0 +1,158 @@
+use super::inflate;
+
+// Just enough of the zip format for song bundles: reading stored and deflated entries, and
+// writing stored ones. No zip64, so entries and archives stay under 4 GiB.
+const LOCAL_HEADER: u32 = 0x0403_4b50;
+const CENTRAL_HEADER: u32 = 0x0201_4b50;
+const END_OF_DIRECTORY: u32 = 0x0605_4b50;
+// 1980-01-01, the earliest date zip can hold; entries aren't dated
+const DOS_DATE: u16 = 0x21;
+
+fn u16_at(data: &[u8], pos: usize) -> Result<u16, String> {
+    data.get(pos..pos + 2)
+        .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+        .ok_or_else(|| "truncated zip file".to_string())
+}
+
+fn u32_at(data: &[u8], pos: usize) -> Result<u32, String> {
+    data.get(pos..pos + 4)
+        .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .ok_or_else(|| "truncated zip file".to_string())
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+// A file in the archive, found through the central directory and read only when asked for
+pub struct Entry {
+    pub name: String,
+    method: u16,
+    crc: u32,
+    size: usize,
+    start: usize,
+    compressed: usize,
+}
+
+// Every file in the archive, in the order the central directory lists them. The sizes the
+// directory claims can't add up to more than `total`, so a tiny archive can't promise gigabytes.
+pub fn entries(data: &[u8], total: usize) -> Result<Vec<Entry>, String> {
+    // The end record sits at the very end, after a comment of up to 64 KiB
+    let end = (0..=data.len().saturating_sub(22))
+        .rev()
+        .take(65_536 + 22)
+        .find(|&pos| u32_at(data, pos) == Ok(END_OF_DIRECTORY))
+        .ok_or("not a zip file")?;
+    let count = u16_at(data, end + 10)? as usize;
+    let mut pos = u32_at(data, end + 16)? as usize;
+
+    let mut entries = Vec::with_capacity(count);
+    let mut claimed = 0usize;
+    for _ in 0..count {
+        if u32_at(data, pos)? != CENTRAL_HEADER {
+            return Err("damaged zip directory".to_string());
+        }
+        let method = u16_at(data, pos + 10)?;
+        let crc = u32_at(data, pos + 16)?;
+        let compressed = u32_at(data, pos + 20)? as usize;
+        let size = u32_at(data, pos + 24)? as usize;
+        let name_len = u16_at(data, pos + 28)? as usize;
+        let extra_len = u16_at(data, pos + 30)? as usize;
+        let comment_len = u16_at(data, pos + 32)? as usize;
+        let local = u32_at(data, pos + 42)? as usize;
+        let name = data.get(pos + 46..pos + 46 + name_len).ok_or("truncated zip file")?;
+        let name = String::from_utf8_lossy(name).into_owned();
+        pos += 46 + name_len + extra_len + comment_len;
+
+        claimed += size;
+        if claimed > total {
+            return Err(format!("the archive's files add up to more than {} bytes", total));
+        }
+        if u32_at(data, local)? != LOCAL_HEADER {
+            return Err(format!("damaged zip entry {}", name));
+        }
+        let start = local + 30 + u16_at(data, local + 26)? as usize + u16_at(data, local + 28)? as usize;
+        if data.len() < start + compressed {
+            return Err("truncated zip file".to_string());
+        }
+        if !name.ends_with('/') {
+            entries.push(Entry { name, method, crc, size, start, compressed });
+        }
+    }
+    Ok(entries)
+}
+
+// An entry's contents, as long as it's no bigger than `limit`
+pub fn extract(data: &[u8], entry: &Entry, limit: usize) -> Result<Vec<u8>, String> {
+    if entry.size > limit {
+        return Err(format!("{} is larger than {} bytes", entry.name, limit));
+    }
+    let raw = &data[entry.start..entry.start + entry.compressed];
+    let contents = match entry.method {
+        0 => raw.to_vec(),
+        // An entry can't grow past the size the directory gives for it
+        8 => inflate::inflate(raw, entry.size)?,
+        _ => return Err(format!("{} is compressed in a way that isn't supported", entry.name)),
+    };
+    if contents.len() != entry.size || crc32(&contents) != entry.crc {
+        return Err(format!("{} is damaged", entry.name));
+    }
+    Ok(contents)
+}
+
+// An archive of the files as given, without compression: most of a bundle is audio, which
+// doesn't shrink much anyway
+pub fn write(entries: &[(String, Vec<u8>)]) -> Result<Vec<u8>, String> {
+    let too_big = || "too much to fit in a zip file".to_string();
+    let mut out = Vec::new();
+    let mut directory = Vec::new();
+    for (name, contents) in entries {
+        let offset = u32::try_from(out.len()).map_err(|_| too_big())?;
+        let size = u32::try_from(contents.len()).map_err(|_| too_big())?;
+        let name_len = u16::try_from(name.len()).map_err(|_| format!("file name too long: {}", name))?;
+        let crc = crc32(contents);
+        // Version needed, flags (names are UTF-8), method, time and date, then the sizes
+        let mut common = Vec::with_capacity(26);
+        for field in [20u16, 0x0800, 0, 0, DOS_DATE] {
+            common.extend_from_slice(&field.to_le_bytes());
+        }
+        for field in [crc, size, size] {
+            common.extend_from_slice(&field.to_le_bytes());
+        }
+        common.extend_from_slice(&name_len.to_le_bytes());
+        common.extend_from_slice(&0u16.to_le_bytes());
+
+        out.extend_from_slice(&LOCAL_HEADER.to_le_bytes());
+        out.extend_from_slice(&common);
+        out.extend_from_slice(name.as_bytes());
+        out.extend_from_slice(contents);
+
+        directory.extend_from_slice(&CENTRAL_HEADER.to_le_bytes());
+        directory.extend_from_slice(&20u16.to_le_bytes());
+        directory.extend_from_slice(&common);
+        // Comment length, disk, internal and external attributes, then where the entry is
+        directory.extend_from_slice(&[0; 10]);
+        directory.extend_from_slice(&offset.to_le_bytes());
+        directory.extend_from_slice(name.as_bytes());
+    }
+
+    let count = u16::try_from(entries.len()).map_err(|_| too_big())?;
+    let directory_offset = u32::try_from(out.len()).map_err(|_| too_big())?;
+    let directory_size = u32::try_from(directory.len()).map_err(|_| too_big())?;
+    out.extend_from_slice(&directory);
+    out.extend_from_slice(&END_OF_DIRECTORY.to_le_bytes());
+    out.extend_from_slice(&[0; 4]);
+    out.extend_from_slice(&count.to_le_bytes());
+    out.extend_from_slice(&count.to_le_bytes());
+    out.extend_from_slice(&directory_size.to_le_bytes());
+    out.extend_from_slice(&directory_offset.to_le_bytes());
+    out.extend_from_slice(&0u16.to_le_bytes());
+    Ok(out)
+}
@@ -0,0 +1,65 @@
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_MAX_FPS: f64 = 60.0;
+// Nothing moves while paused, but the screen still catches up with things like a sync
+// connection coming back
+const IDLE_FRAME: Duration = Duration::from_millis(500);
+
+// Decides when the playback screen is redrawn: at most `max_fps` while something moves or has
+// changed, and at the idle rate otherwise
+#[derive(Debug, Clone)]
+pub struct FramePacer {
+    frame: Duration,
+    last_drawn: Option<Instant>,
+    dirty: bool,
+}
+
+impl FramePacer {
+    pub fn new(max_fps: f64) -> Self {
+        Self {
+            frame: Duration::from_secs_f64(1.0 / max_fps),
+            last_drawn: None,
+            dirty: true,
+        }
+    }
+
+    // How often state has to be updated to keep up with the fastest redraws
+    pub fn tick_rate(max_fps: f64, tick_rate: Duration) -> Duration {
+        tick_rate.min(Duration::from_secs_f64(1.0 / max_fps))
+    }
+
+    // Something the screen shows has changed, such as a key press or a resize
+    pub fn invalidate(&mut self) {
+        self.dirty = true;
+    }
+
+    pub fn should_draw(&self, now: Instant, animating: bool) -> bool {
+        let due = if animating || self.dirty { self.frame } else { IDLE_FRAME.max(self.frame) };
+        self.last_drawn.is_none_or(|last| now.duration_since(last) >= due)
+    }
+
+    pub fn drawn(&mut self, now: Instant) {
+        self.last_drawn = Some(now);
+        self.dirty = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn idles_until_something_changes() {
+        let start = Instant::now();
+        let mut pacer = FramePacer::new(30.0);
+        assert!(pacer.should_draw(start, false));
+        pacer.drawn(start);
+
+        let ms = |ms| start + Duration::from_millis(ms);
+        assert!(!pacer.should_draw(ms(20), true) && pacer.should_draw(ms(34), true));
+        assert!(!pacer.should_draw(ms(400), false) && pacer.should_draw(ms(500), false));
+        pacer.invalidate();
+        assert!(!pacer.should_draw(ms(20), false) && pacer.should_draw(ms(40), false));
+        assert_eq!(FramePacer::tick_rate(120.0, Duration::from_millis(16)), Duration::from_secs_f64(1.0 / 120.0));
+    }
+}
@@ -0,0 +1,86 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    thread,
+    time::Duration,
+};
+
+use crate::{cli::PlayOptions, plugin::Renderer, timeline, KaraokeApp};
+
+// Prints the current line whenever it changes instead of drawing the TUI
+struct PlainText {
+    show_progress: bool,
+    output_file: Option<PathBuf>,
+    last_output: Option<String>,
+}
+
+impl Renderer for PlainText {
+    fn render(&mut self, app: &KaraokeApp) -> io::Result<()> {
+        let output = current_output(app, app.lyric_time(), self.show_progress);
+        if self.last_output.as_ref() == Some(&output) {
+            return Ok(());
+        }
+
+        match &self.output_file {
+            Some(path) => write_atomically(path, &output)?,
+            None => {
+                let mut stdout = io::stdout();
+                writeln!(stdout, "{}", output).and_then(|_| stdout.flush())?;
+            }
+        }
+        self.last_output = Some(output);
+        Ok(())
+    }
+}
+
+pub fn run(app: &mut KaraokeApp, options: &PlayOptions, mut outputs: Vec<Box<dyn Renderer>>) -> Result<(), io::Error> {
+    let tick_rate = Duration::from_millis(16);
+    // Printed announcements take the place of the plain lines
+    if !options.announce || options.announce_command.is_some() {
+        outputs.push(Box::new(PlainText {
+            show_progress: options.show_progress,
+            output_file: options.output_file.clone(),
+            last_output: None,
+        }));
+    }
+
+    loop {
+        for output in outputs.iter_mut() {
+            // The reading end of a pipe going away just means nobody is listening anymore
+            match output.render(app) {
+                Err(e) if e.kind() == io::ErrorKind::BrokenPipe => return Ok(()),
+                result => result?,
+            }
+        }
+
+        if app.is_song_ended() {
+            return Ok(());
+        }
+        let time = app.get_current_time();
+        if let Some(audio) = &mut app.audio {
+            audio.update(time, true, 1.0);
+        }
+
+        thread::sleep(tick_rate);
+    }
+}
+
+fn current_output(app: &KaraokeApp, current_time: f64, show_progress: bool) -> String {
+    match app.active_line(current_time) {
+        Some(idx) if show_progress => {
+            let progress = timeline::line_progress(&app.lyrics[idx], current_time);
+            format!("{} ({:.0}%)", app.lyrics[idx].text, progress * 100.0)
+        }
+        Some(idx) => app.lyrics[idx].text.clone(),
+        None => String::new(),
+    }
+}
+
+// Text sources poll the file, so they must never see it half written
+fn write_atomically(path: &Path, text: &str) -> Result<(), io::Error> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    fs::write(&tmp, format!("{}\n", text))?;
+    fs::rename(&tmp, path)
+}
@@ -0,0 +1,113 @@
+use ratatui::style::Color;
+
+use crate::background::{blend, Theme};
+
+// Degrees of hue from one character to the next in the rainbow
+const RAINBOW_STEP: f64 = 30.0;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum HighlightStyle {
+    #[default]
+    Solid,
+    Gradient,
+    Rainbow,
+}
+
+impl std::str::FromStr for HighlightStyle {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "solid" => Ok(HighlightStyle::Solid),
+            "gradient" => Ok(HighlightStyle::Gradient),
+            "rainbow" => Ok(HighlightStyle::Rainbow),
+            _ => Err(()),
+        }
+    }
+}
+
+// How sung text is colored: all in the singer's color, shading along the line, or a rainbow
+// with every character a different hue
+#[derive(Debug, Clone, Default)]
+pub struct Highlight {
+    pub style: HighlightStyle,
+    // One color shades from the singer's to it; more are the gradient's stops in order
+    stops: Vec<Color>,
+}
+
+impl Highlight {
+    // Without colors of its own, the gradient ends on the background theme's accent
+    pub fn new(style: HighlightStyle, colors: &[Color], theme: Theme) -> Self {
+        Self {
+            style,
+            stops: match colors {
+                [] => vec![theme.accent()],
+                colors => colors.to_vec(),
+            },
+        }
+    }
+
+    pub fn is_solid(&self) -> bool {
+        self.style == HighlightStyle::Solid
+    }
+
+    // The color of character `index` of a line `count` characters long, sung in `base`
+    pub fn color(&self, base: Color, index: usize, count: usize) -> Color {
+        match self.style {
+            HighlightStyle::Solid => base,
+            HighlightStyle::Gradient => {
+                let stops = match self.stops.as_slice() {
+                    [end] => vec![base, *end],
+                    stops => stops.to_vec(),
+                };
+                let along = index as f64 / count.saturating_sub(1).max(1) as f64 * (stops.len() - 1) as f64;
+                let stop = (along.floor() as usize).min(stops.len() - 2);
+                blend(stops[stop], stops[stop + 1], along - stop as f64)
+            }
+            HighlightStyle::Rainbow => hue(index as f64 * RAINBOW_STEP),
+        }
+    }
+}
+
+// A fully saturated, full brightness color
+fn hue(degrees: f64) -> Color {
+    let sector = degrees.rem_euclid(360.0) / 60.0;
+    let rising = (255.0 * sector.fract()).round() as u8;
+    let falling = 255 - rising;
+    let (r, g, b) = match sector as u8 {
+        0 => (255, rising, 0),
+        1 => (falling, 255, 0),
+        2 => (0, 255, rising),
+        3 => (0, falling, 255),
+        4 => (rising, 0, 255),
+        _ => (255, 0, falling),
+    };
+    Color::Rgb(r, g, b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sung_text_shades_along_the_line() {
+        let green = Color::Rgb(0, 255, 0);
+        let solid = Highlight::default();
+        assert_eq!(solid.color(green, 3, 10), green);
+
+        let gradient = Highlight::new(HighlightStyle::Gradient, &[Color::Rgb(0, 0, 255)], Theme::Flat);
+        assert_eq!(gradient.color(green, 0, 11), green);
+        assert_eq!(gradient.color(green, 5, 11), Color::Rgb(0, 128, 128));
+        assert_eq!(gradient.color(green, 10, 11), Color::Rgb(0, 0, 255));
+        assert_eq!(gradient.color(green, 0, 1), green);
+
+        let stops = Highlight::new(HighlightStyle::Gradient, &[Color::Rgb(255, 0, 0), Color::Rgb(0, 0, 0), Color::Rgb(0, 0, 255)], Theme::Flat);
+        assert_eq!(stops.color(green, 2, 5), Color::Rgb(0, 0, 0));
+        assert_eq!(stops.color(green, 4, 5), Color::Rgb(0, 0, 255));
+
+        let rainbow = Highlight::new(HighlightStyle::Rainbow, &[], Theme::Flat);
+        assert_eq!(rainbow.color(green, 0, 5), Color::Rgb(255, 0, 0));
+        assert_eq!(rainbow.color(green, 4, 5), Color::Rgb(0, 255, 0));
+        assert_eq!(rainbow.color(green, 12, 20), Color::Rgb(255, 0, 0));
+    }
+}
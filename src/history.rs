@@ -0,0 +1,202 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+const HISTORY_FILE: &str = ".karaoke-lyric-history.json";
+// The built-in example song has no path, so it gets this key instead
+const BUILT_IN_KEY: &str = "<built-in>";
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SongStats {
+    pub title: String,
+    pub plays: u32,
+    pub completions: u32,
+    pub best_score: Option<u32>,
+    // Seconds since the Unix epoch
+    pub last_played: u64,
+}
+
+// Play counts and best scores per song, keyed by the song's absolute path
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PlayHistory {
+    pub songs: BTreeMap<String, SongStats>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortBy {
+    Plays,
+    Completions,
+    Best,
+    Recent,
+    Title,
+}
+
+impl std::str::FromStr for SortBy {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "plays" => Ok(SortBy::Plays),
+            "completions" => Ok(SortBy::Completions),
+            "best" => Ok(SortBy::Best),
+            "recent" => Ok(SortBy::Recent),
+            "title" => Ok(SortBy::Title),
+            _ => Err(()),
+        }
+    }
+}
+
+pub fn history_path() -> PathBuf {
+    crate::resume::home_file(HISTORY_FILE)
+}
+
+pub fn song_key(song: Option<&Path>) -> String {
+    song.map_or_else(|| BUILT_IN_KEY.to_string(), |path| path.display().to_string())
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs())
+}
+
+impl PlayHistory {
+    // No file yet is an empty history; a broken one is an error so it doesn't get overwritten
+    pub fn load(path: &Path) -> io::Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(json) => serde_json::from_str(&json)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path.display(), e))),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        fs::write(path, json)
+    }
+
+    pub fn record(&mut self, key: String, title: &str, completed: bool, score: Option<u32>, played_at: u64) {
+        let stats = self.songs.entry(key).or_default();
+        stats.title = title.to_string();
+        stats.plays += 1;
+        stats.last_played = played_at;
+        if completed {
+            stats.completions += 1;
+            if let Some(score) = score {
+                stats.best_score = Some(stats.best_score.map_or(score, |best| best.max(score)));
+            }
+        }
+    }
+
+    pub fn sorted(&self, sort: SortBy) -> Vec<(&String, &SongStats)> {
+        let mut songs: Vec<_> = self.songs.iter().collect();
+        match sort {
+            SortBy::Plays => songs.sort_by_key(|(_, stats)| std::cmp::Reverse(stats.plays)),
+            SortBy::Completions => songs.sort_by_key(|(_, stats)| std::cmp::Reverse(stats.completions)),
+            SortBy::Best => songs.sort_by_key(|(_, stats)| std::cmp::Reverse(stats.best_score)),
+            SortBy::Recent => songs.sort_by_key(|(_, stats)| std::cmp::Reverse(stats.last_played)),
+            SortBy::Title => songs.sort_by_key(|(_, stats)| stats.title.to_lowercase()),
+        }
+        songs
+    }
+}
+
+// Adds one play to the history file; scores only count for finished songs
+pub fn record_play(song: Option<&Path>, title: &str, completed: bool, score: Option<u32>) -> io::Result<()> {
+    let path = history_path();
+    let mut history = PlayHistory::load(&path)?;
+    history.record(song_key(song), title, completed, score, now());
+    history.save(&path)
+}
+
+// YYYY-MM-DD in UTC
+pub fn format_date(timestamp: u64) -> String {
+    // Civil-from-days, counting eras of 400 years from 0000-03-01
+    let days = (timestamp / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+// The start of a YYYY-MM-DD day in UTC, as seconds since the Unix epoch
+pub fn parse_date(date: &str) -> Option<u64> {
+    let mut parts = date.splitn(3, '-').map(|part| part.parse::<i64>().ok());
+    let (year, month, day) = (parts.next()??, parts.next()??, parts.next()??);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || year < 1970 {
+        return None;
+    }
+    // Days-from-civil, the inverse of format_date
+    let shifted = if month <= 2 { year - 1 } else { year };
+    let era = shifted.div_euclid(400);
+    let year_of_era = shifted - era * 400;
+    let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let timestamp = u64::try_from((era * 146_097 + day_of_era - 719_468) * 86_400).ok()?;
+    // Days past the end of the month roll over into the next, so they aren't real dates
+    (format_date(timestamp) == format!("{:04}-{:02}-{:02}", year, month, day)).then_some(timestamp)
+}
+
+pub fn print_stats(sort: SortBy) -> io::Result<()> {
+    let history = PlayHistory::load(&history_path())?;
+    if history.songs.is_empty() {
+        println!("No songs played yet");
+        return Ok(());
+    }
+
+    println!("{:>5}  {:>5}  {:>5}  {:<11}  Song", "Plays", "Done", "Best", "Last played");
+    for (_, stats) in history.sorted(sort) {
+        let best = stats.best_score.map_or_else(|| "-".to_string(), |score| score.to_string());
+        println!(
+            "{:>5}  {:>5}  {:>5}  {:<11}  {}",
+            stats.plays,
+            stats.completions,
+            best,
+            format_date(stats.last_played),
+            stats.title
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plays_completions_and_best_score_accumulate() {
+        let mut history = PlayHistory::default();
+        history.record("a".to_string(), "A", true, Some(5000), 10);
+        history.record("a".to_string(), "A", false, Some(9000), 20);
+        history.record("a".to_string(), "A", true, Some(7000), 30);
+        history.record("b".to_string(), "B", true, None, 5);
+
+        let a = &history.songs["a"];
+        assert_eq!((a.plays, a.completions, a.best_score, a.last_played), (3, 2, Some(7000), 30));
+        assert_eq!(history.songs["b"].best_score, None);
+
+        let by_plays: Vec<&str> = history.sorted(SortBy::Plays).iter().map(|(key, _)| key.as_str()).collect();
+        assert_eq!(by_plays, ["a", "b"]);
+        let by_recent: Vec<&str> = history.sorted(SortBy::Recent).iter().map(|(key, _)| key.as_str()).collect();
+        assert_eq!(by_recent, ["a", "b"]);
+    }
+
+    #[test]
+    fn dates_are_utc_calendar_days() {
+        assert_eq!(format_date(0), "1970-01-01");
+        assert_eq!(format_date(951_782_400), "2000-02-29");
+        assert_eq!(format_date(1_791_936_000), "2026-10-14");
+        assert_eq!(parse_date("2026-10-14"), Some(1_791_936_000));
+        assert_eq!(parse_date("2000-02-29"), Some(951_782_400));
+        assert_eq!(parse_date("2001-02-29"), None);
+        assert_eq!(parse_date("yesterday"), None);
+    }
+}
@@ -0,0 +1,258 @@
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    process::{Child, Command, Stdio},
+    sync::mpsc::{self, Sender, SyncSender, TrySendError},
+    thread,
+};
+
+use crossterm::event::{KeyCode, KeyEvent};
+use serde::{Deserialize, Serialize};
+
+use crate::{events::AppEvent, plugin::Renderer, remote::RemoteCommand, KaraokeApp};
+
+// Events waiting for a script that's slow to read them; past this they're dropped
+const QUEUED_EVENTS: usize = 64;
+
+// One JSON object per line on the script's stdin
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum HookEvent {
+    SongStart { title: String, duration: f64 },
+    LineStart { index: usize, text: String, time: f64 },
+    SongEnd { title: String },
+    Key { key: String },
+}
+
+// One JSON object per line on the script's stdout, e.g. {"command":"theme","theme":"wave"}
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(tag = "command", rename_all = "snake_case")]
+enum HookCommand {
+    Play,
+    Pause,
+    Toggle,
+    Next,
+    Seek { to: Option<f64>, by: Option<f64> },
+    Offset { set: Option<f64>, by: Option<f64> },
+    Theme { theme: String },
+}
+
+fn parse_command(line: &str) -> Result<RemoteCommand, String> {
+    let command: HookCommand = serde_json::from_str(line).map_err(|e| e.to_string())?;
+    match command {
+        HookCommand::Play => Ok(RemoteCommand::Play),
+        HookCommand::Pause => Ok(RemoteCommand::Pause),
+        HookCommand::Toggle => Ok(RemoteCommand::TogglePause),
+        HookCommand::Next => Ok(RemoteCommand::Next),
+        HookCommand::Seek { to: Some(to), by: None } => Ok(RemoteCommand::SeekTo(to)),
+        HookCommand::Seek { to: None, by: Some(by) } => Ok(RemoteCommand::SeekBy(by)),
+        HookCommand::Seek { .. } => Err("seek expects either \"to\" or \"by\"".to_string()),
+        HookCommand::Offset { set: Some(set), by: None } => Ok(RemoteCommand::SetOffset(set)),
+        HookCommand::Offset { set: None, by: Some(by) } => Ok(RemoteCommand::AdjustOffset(by)),
+        HookCommand::Offset { .. } => Err("offset expects either \"set\" or \"by\"".to_string()),
+        HookCommand::Theme { theme } => theme.parse().map(RemoteCommand::SetTheme).map_err(|_| format!("unknown theme: {}", theme)),
+    }
+}
+
+fn key_name(code: KeyCode) -> String {
+    match code {
+        KeyCode::Char(' ') => "Space".to_string(),
+        KeyCode::Char(c) => c.to_string(),
+        other => format!("{:?}", other),
+    }
+}
+
+// A user script started with `--hook-command`; it's told about the session as it plays and
+// can react however it likes (send OSC, log, drive lights), or answer with commands that play,
+// seek or change the theme like the remote API does. It's killed when this is dropped.
+pub struct HookScript {
+    child: Child,
+    // Written out on a thread of its own, so a script that stops reading can't hold up a frame
+    events: Option<SyncSender<String>>,
+    song_sent: bool,
+    line: Option<usize>,
+    ended: bool,
+}
+
+impl HookScript {
+    pub fn start(command: &str, commands: Sender<AppEvent>) -> io::Result<Self> {
+        let mut parts = command.split_whitespace();
+        let program = parts.next().ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "empty hook command"))?;
+        let mut child = Command::new(program)
+            .args(parts)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| io::Error::new(e.kind(), format!("couldn't start hook script `{}`: {}", command, e)))?;
+
+        let (events, queue) = mpsc::sync_channel::<String>(QUEUED_EVENTS);
+        if let Some(mut stdin) = child.stdin.take() {
+            thread::spawn(move || {
+                for json in queue {
+                    if let Err(e) = writeln!(stdin, "{}", json).and_then(|_| stdin.flush()) {
+                        log::warn!("the hook script stopped reading events: {}", e);
+                        break;
+                    }
+                }
+            });
+        }
+        // Anything that isn't a command is logged and skipped; the song itself can't be swapped
+        // from here
+        if let Some(stdout) = child.stdout.take() {
+            thread::spawn(move || {
+                for line in BufReader::new(stdout).lines().map_while(Result::ok).filter(|line| !line.trim().is_empty()) {
+                    match parse_command(&line) {
+                        Ok(command) => {
+                            if commands.send(AppEvent::Remote(command)).is_err() {
+                                break;
+                            }
+                        }
+                        Err(e) => log::warn!("ignoring hook script output `{}`: {}", line, e),
+                    }
+                }
+            });
+        }
+        Ok(Self {
+            child,
+            events: Some(events),
+            song_sent: false,
+            line: None,
+            ended: false,
+        })
+    }
+
+    // A script that exits stops getting events, and one that falls behind misses some
+    fn send(&mut self, event: &HookEvent) {
+        let Some(events) = &self.events else {
+            return;
+        };
+        let Ok(json) = serde_json::to_string(event) else {
+            return;
+        };
+        match events.try_send(json) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => log::warn!("the hook script is behind; dropped an event"),
+            Err(TrySendError::Disconnected(_)) => self.events = None,
+        }
+    }
+
+    fn changes(&mut self, app: &KaraokeApp) -> Vec<HookEvent> {
+        let mut events = Vec::new();
+        if !self.song_sent {
+            events.push(HookEvent::SongStart {
+                title: app.song_title.clone(),
+                duration: app.song_duration,
+            });
+            self.song_sent = true;
+        }
+
+        let time = app.lyric_time();
+        let index = app.active_line(time);
+        if let Some(i) = index.filter(|&i| self.line != Some(i)) {
+            events.push(HookEvent::LineStart {
+                index: i,
+                text: app.lyrics[i].text.clone(),
+                time,
+            });
+        }
+        if index.is_some() {
+            self.line = index;
+        }
+
+        let ended = app.is_song_ended();
+        if ended && !self.ended {
+            events.push(HookEvent::SongEnd {
+                title: app.song_title.clone(),
+            });
+        }
+        self.ended = ended;
+        events
+    }
+}
+
+impl Renderer for HookScript {
+    // Sends whatever changed since the last frame
+    fn render(&mut self, app: &KaraokeApp) -> io::Result<()> {
+        for event in self.changes(app) {
+            self.send(&event);
+        }
+        Ok(())
+    }
+
+    fn key(&mut self, key: KeyEvent) {
+        self.send(&HookEvent::Key {
+            key: key_name(key.code),
+        });
+    }
+
+    // A newly loaded song starts over from its first event
+    fn song_changed(&mut self) {
+        self.song_sent = false;
+        self.line = None;
+        self.ended = false;
+    }
+}
+
+impl Drop for HookScript {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_are_tagged_json() {
+        let event = HookEvent::LineStart {
+            index: 2,
+            text: "la la".to_string(),
+            time: 4.5,
+        };
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#"{"event":"line_start","index":2,"text":"la la","time":4.5}"#
+        );
+    }
+
+    #[test]
+    fn line_starts_and_song_end_are_sent_once() {
+        let mut app = KaraokeApp::from_config(crate::song_config::SongConfig::load(), None);
+        app.paused = true;
+        app.current_position = app.lyrics[1].start_time + 0.1;
+        let mut script = HookScript {
+            child: Command::new("true").spawn().unwrap(),
+            events: None,
+            song_sent: false,
+            line: None,
+            ended: false,
+        };
+
+        let events = script.changes(&app);
+        assert!(matches!(events[0], HookEvent::SongStart { .. }));
+        assert!(matches!(events[1], HookEvent::LineStart { index: 1, .. }));
+        assert!(script.changes(&app).is_empty());
+
+        app.current_position = app.song_duration;
+        assert!(matches!(script.changes(&app)[..], [HookEvent::SongEnd { .. }]));
+    }
+
+    #[test]
+    fn scripts_answer_with_commands() {
+        assert_eq!(parse_command(r#"{"command":"toggle"}"#), Ok(RemoteCommand::TogglePause));
+        assert_eq!(parse_command(r#"{"command":"seek","by":-5}"#), Ok(RemoteCommand::SeekBy(-5.0)));
+        assert_eq!(parse_command(r#"{"command":"theme","theme":"wave"}"#), Ok(RemoteCommand::SetTheme(crate::background::Theme::Wave)));
+        assert!(parse_command(r#"{"command":"seek","to":1,"by":2}"#).is_err());
+        assert!(parse_command(r#"{"command":"theme","theme":"plaid"}"#).is_err());
+        assert!(parse_command("hello").is_err());
+    }
+
+    #[test]
+    fn keys_have_readable_names() {
+        assert_eq!(key_name(KeyCode::Char('x')), "x");
+        assert_eq!(key_name(KeyCode::Char(' ')), "Space");
+        assert_eq!(key_name(KeyCode::Up), "Up");
+    }
+}
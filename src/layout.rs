@@ -0,0 +1,1014 @@
+use serde::{Deserialize, Serialize};
+use std::{borrow::Cow, ops::Range};
+
+use crate::{
+    bidi, bigtext,
+    style::LineStyle,
+    timeline::{self, LineState},
+    typing, KaraokeApp, LyricLine,
+};
+
+const LEFT_MARKER: &str = ">     ";
+const RIGHT_MARKER: &str = "     <";
+// Silences at least this long get a countdown instead of the stale last line
+const BREAK_THRESHOLD: f64 = 5.0;
+const BREAK_BAR_WIDTH: usize = 30;
+// The ball from landed to the top of its hop
+const BALL: [&str; 3] = ["●", "•", "˙"];
+
+// How many lines are shown at once and which of them is the current one, counted from 1 at the
+// top. Without past lines the rows above the current line stay blank.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LyricWindow {
+    pub lines: usize,
+    pub center: usize,
+    pub past_lines: bool,
+    pub fill: Fill,
+}
+
+impl Default for LyricWindow {
+    fn default() -> Self {
+        Self {
+            lines: 5,
+            center: 3,
+            past_lines: true,
+            fill: Fill::default(),
+        }
+    }
+}
+
+impl LyricWindow {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    // The current line's row from 0, kept inside a window of at least one line whatever a
+    // song file says
+    fn center_row(&self) -> usize {
+        self.center.clamp(1, self.lines.max(1)) - 1
+    }
+}
+
+// How the highlight takes over the active line: from the left as usual, from the right, from
+// the middle out, or a word at a time fading in. Right-to-left text is always sung from its
+// right end.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Fill {
+    #[default]
+    Left,
+    Right,
+    Center,
+    Fade,
+}
+
+impl std::str::FromStr for Fill {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "left" => Ok(Fill::Left),
+            "right" => Ok(Fill::Right),
+            "center" => Ok(Fill::Center),
+            "fade" => Ok(Fill::Fade),
+            _ => Err(()),
+        }
+    }
+}
+
+// What the command line changes about the song's window
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct WindowOptions {
+    pub lines: Option<usize>,
+    pub center: Option<usize>,
+    pub hide_past_lines: bool,
+    pub fill: Option<Fill>,
+}
+
+impl WindowOptions {
+    pub fn apply(&self, mut window: LyricWindow) -> LyricWindow {
+        if let Some(lines) = self.lines {
+            window.lines = lines;
+            // A shorter window keeps the current line in it
+            window.center = window.center.min(lines);
+        }
+        if let Some(center) = self.center {
+            window.center = center;
+        }
+        window.past_lines &= !self.hide_past_lines;
+        window.fill = self.fill.unwrap_or(window.fill);
+        window
+    }
+}
+
+// What a piece of text means; the UI decides which colors that gets
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpanKind {
+    Sung,
+    Unsung,
+    Completed,
+    Upcoming,
+    Marker,
+    Break,
+    BreakBar,
+    Chord,
+    // A line an open search has found
+    Match,
+    // Typing practice: what was typed right and wrong
+    Typed,
+    Mistake,
+    // The bouncing ball over the word being sung
+    Ball,
+    // A word on its way from unsung to sung, from 0 to 255
+    Fading(u8),
+}
+
+// Text borrows from the song wherever it can, so a frame only allocates for what it makes up
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StyledText<'a> {
+    pub text: Cow<'a, str>,
+    pub kind: SpanKind,
+}
+
+// An empty row is a blank line; `singer` picks the highlight color for duet parts unless
+// `style` has its own
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LyricRow<'a> {
+    pub spans: Vec<StyledText<'a>>,
+    pub singer: usize,
+    pub style: Cow<'a, LineStyle>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LyricFrame<'a> {
+    pub rows: Vec<LyricRow<'a>>,
+}
+
+fn styled<'a>(text: impl Into<Cow<'a, str>>, kind: SpanKind) -> StyledText<'a> {
+    StyledText {
+        text: text.into(),
+        kind,
+    }
+}
+
+// The characters in `range` of `text`
+fn char_slice(text: &str, range: Range<usize>) -> &str {
+    let byte = |n: usize| text.char_indices().nth(n).map_or(text.len(), |(i, _)| i);
+    &text[byte(range.start)..byte(range.end)]
+}
+
+// Only the active line changes from frame to frame, and it's split in place. A wrapped line is
+// split row by row, with the sweep carrying on from one row to the next.
+fn segment_spans(line: &LyricLine, range: Range<usize>, lyric_time: f64, fill: Fill) -> Vec<StyledText<'_>> {
+    let position = timeline::sung_position(line, lyric_time).clamp(range.start as f64, range.end as f64) - range.start as f64;
+    let split_pos = position as usize;
+    let text = char_slice(&line.text, range);
+    if bidi::has_rtl(text) {
+        return bidi_spans(text, split_pos);
+    }
+    let length = text.chars().count();
+    let byte = |n: usize| text.char_indices().nth(n).map_or(text.len(), |(i, _)| i);
+    // Each part of the row as a range of characters
+    let parts = match fill {
+        Fill::Left => vec![(0..split_pos, SpanKind::Sung), (split_pos..length, SpanKind::Unsung)],
+        Fill::Right => vec![(0..length - split_pos, SpanKind::Unsung), (length - split_pos..length, SpanKind::Sung)],
+        Fill::Center => {
+            let start = (length - split_pos) / 2;
+            vec![
+                (0..start, SpanKind::Unsung),
+                (start..start + split_pos, SpanKind::Sung),
+                (start + split_pos..length, SpanKind::Unsung),
+            ]
+        }
+        Fill::Fade => return fade_spans(text, position),
+    };
+    parts
+        .into_iter()
+        .filter(|(range, _)| !range.is_empty())
+        .map(|(range, kind)| styled(&text[byte(range.start)..byte(range.end)], kind))
+        .collect()
+}
+
+// Words take on the sung color as they're sung, each with the spaces after it
+fn fade_spans(text: &str, position: f64) -> Vec<StyledText<'_>> {
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let mut spans = Vec::new();
+    let (mut start, mut first_char, mut letters) = (0, 0, 0);
+    for (n, &(_, c)) in chars.iter().enumerate() {
+        letters += usize::from(!c.is_whitespace());
+        let next = chars.get(n + 1);
+        if next.is_some_and(|&(_, next)| !c.is_whitespace() || next.is_whitespace()) {
+            continue;
+        }
+        let end = next.map_or(text.len(), |&(i, _)| i);
+        let kind = match (position - first_char as f64) / letters.max(1) as f64 {
+            progress if progress >= 1.0 => SpanKind::Sung,
+            progress if progress <= 0.0 => SpanKind::Unsung,
+            progress => SpanKind::Fading((progress * 255.0) as u8),
+        };
+        spans.push(styled(&text[start..end], kind));
+        (start, first_char, letters) = (end, n + 1, 0);
+    }
+    spans
+}
+
+// Right-to-left text is sung from its right end, so the sweep follows the characters wherever
+// they land on screen
+fn bidi_spans(text: &str, split_pos: usize) -> Vec<StyledText<'static>> {
+    let order = bidi::visual_order(text);
+    let mut spans: Vec<StyledText> = Vec::new();
+    for (c, logical) in bidi::visual(text).chars().zip(order) {
+        let kind = if logical < split_pos { SpanKind::Sung } else { SpanKind::Unsung };
+        match spans.last_mut() {
+            Some(span) if span.kind == kind => span.text.to_mut().push(c),
+            _ => spans.push(styled(c.to_string(), kind)),
+        }
+    }
+    spans
+}
+
+fn neighbor_kind(state: LineState) -> SpanKind {
+    if state.is_done() {
+        SpanKind::Completed
+    } else {
+        SpanKind::Upcoming
+    }
+}
+
+pub struct InstrumentalBreak {
+    pub previous: Option<usize>,
+    pub next: usize,
+    pub remaining: f64,
+    pub length: f64,
+}
+
+// A long gap before the first line or between two lines, if `lyric_time` falls inside one
+pub fn instrumental_break(app: &KaraokeApp, lyric_time: f64) -> Option<InstrumentalBreak> {
+    let previous = app.focus_line(lyric_time);
+    let next = previous.map_or(0, |i| i + 1);
+    if next >= app.lyrics.len() {
+        return None;
+    }
+    let gap_start = previous.map_or(0.0, |i| app.lyrics[i].end_time);
+
+    let length = app.lyrics[next].start_time - gap_start;
+    if lyric_time < gap_start || length < BREAK_THRESHOLD {
+        return None;
+    }
+
+    Some(InstrumentalBreak {
+        previous,
+        next,
+        remaining: app.lyrics[next].start_time - lyric_time,
+        length,
+    })
+}
+
+// Typed characters are marked right or wrong, and the rest of the line follows the sweep or
+// `rest`. Right-to-left lines are left in typing order.
+fn typing_spans<'a>(
+    line: &'a LyricLine,
+    typed: &str,
+    range: Range<usize>,
+    lyric_time: f64,
+    rest: Option<SpanKind>,
+) -> Vec<StyledText<'a>> {
+    let text = char_slice(&line.text, range.clone());
+    let sung = timeline::sung_chars(line, lyric_time);
+    let mut typed = typed.chars().skip(range.start);
+    let mut spans: Vec<StyledText> = Vec::new();
+    let mut run_start = 0;
+    for (n, (i, c)) in text.char_indices().enumerate() {
+        let kind = match typed.next() {
+            Some(t) if typing::matches(c, t) => SpanKind::Typed,
+            Some(_) => SpanKind::Mistake,
+            None if range.start + n < sung => rest.unwrap_or(SpanKind::Sung),
+            None => rest.unwrap_or(SpanKind::Unsung),
+        };
+        match spans.last_mut() {
+            Some(span) if span.kind == kind => span.text = Cow::Borrowed(&text[run_start..i + c.len_utf8()]),
+            _ => {
+                run_start = i;
+                spans.push(styled(&text[i..i + c.len_utf8()], kind));
+            }
+        }
+    }
+    spans
+}
+
+// One row of a line: the characters in `range`, with markers around the active line
+fn line_row(app: &KaraokeApp, lyric_time: f64, idx: usize, range: Range<usize>, active: bool) -> LyricRow<'_> {
+    let line = &app.lyrics[idx];
+    let typed = app.typing.as_ref().map(|typing| typing.typed(idx)).filter(|typed| !typed.is_empty() || active);
+    let spans = match (active, typed) {
+        (true, typed) => {
+            let mut spans = vec![styled(LEFT_MARKER, SpanKind::Marker)];
+            match typed {
+                Some(typed) => spans.extend(typing_spans(line, typed, range, lyric_time, None)),
+                None => spans.extend(segment_spans(line, range, lyric_time, app.window.fill)),
+            }
+            spans.push(styled(RIGHT_MARKER, SpanKind::Marker));
+            spans
+        }
+        (false, Some(typed)) => {
+            let rest = neighbor_kind(timeline::line_state(line, lyric_time));
+            typing_spans(line, typed, range, lyric_time, Some(rest))
+        }
+        (false, None) => {
+            let kind = match app.find.as_ref().is_some_and(|find| find.is_match(idx)) {
+                true => SpanKind::Match,
+                false => neighbor_kind(timeline::line_state(line, lyric_time)),
+            };
+            vec![styled(bidi::visual(char_slice(&line.text, range)), kind)]
+        }
+    };
+    LyricRow {
+        spans,
+        singer: line.singer,
+        style: Cow::Borrowed(app.line_style(idx)),
+    }
+}
+
+// The active line in the built-in block letters, each row of it four rows tall and swept by
+// whole letters. None when the font lacks one of its characters or a word is too wide.
+fn big_line_rows(app: &KaraokeApp, lyric_time: f64, idx: usize, width: usize) -> Option<Vec<LyricRow<'_>>> {
+    let line = &app.lyrics[idx];
+    if width < bigtext::CHAR_COLUMNS {
+        return None;
+    }
+    let sung = timeline::sung_chars(line, lyric_time);
+    let mut rows = Vec::new();
+    for range in wrap_ranges(&line.text, (width + 1) / bigtext::CHAR_COLUMNS) {
+        let split = (sung.clamp(range.start, range.end) - range.start) * bigtext::CHAR_COLUMNS;
+        for text in bigtext::small(char_slice(&line.text, range))? {
+            let at = text.char_indices().nth(split).map_or(text.len(), |(i, _)| i);
+            let (sung_part, unsung_part) = text.split_at(at);
+            let spans = [(sung_part, SpanKind::Sung), (unsung_part, SpanKind::Unsung)]
+                .into_iter()
+                .filter(|(part, _)| !part.is_empty())
+                .map(|(part, kind)| styled(part.to_string(), kind))
+                .collect();
+            rows.push(LyricRow {
+                spans,
+                singer: line.singer,
+                style: Cow::Borrowed(app.line_style(idx)),
+            });
+        }
+    }
+    Some(rows)
+}
+
+// Where each of the five window rows comes from
+enum Slot<'a> {
+    Row(LyricRow<'a>),
+    Line { index: usize, active: bool },
+}
+
+// The countdown sits on the current row, moved up as far as it takes for its bar and the next
+// line to fit under it
+fn layout_break<'a>(gap: &InstrumentalBreak, visible_row: usize, window: &LyricWindow) -> Slot<'a> {
+    let countdown_row = window.center_row().min(window.lines.saturating_sub(3));
+    let row = match visible_row as i64 - countdown_row as i64 {
+        -1 => {
+            return match gap.previous.filter(|_| window.past_lines) {
+                Some(index) => Slot::Line { index, active: false },
+                None => Slot::Row(LyricRow::default()),
+            }
+        }
+        2 => return Slot::Line { index: gap.next, active: false },
+        0 => LyricRow {
+            spans: vec![styled(
+                format!("♪ Instrumental break - next line in {} ♪", crate::format_time(gap.remaining.ceil())),
+                SpanKind::Break,
+            )],
+            ..LyricRow::default()
+        },
+        1 => {
+            let filled = ((gap.remaining / gap.length) * BREAK_BAR_WIDTH as f64).ceil() as usize;
+            LyricRow {
+                spans: vec![styled("━".repeat(filled.min(BREAK_BAR_WIDTH)), SpanKind::BreakBar)],
+                ..LyricRow::default()
+            }
+        }
+        _ => LyricRow::default(),
+    };
+    Slot::Row(row)
+}
+
+pub fn text_width(text: &str) -> usize {
+    ratatui::text::Span::raw(text).width()
+}
+
+// Character ranges of the rows a line is wrapped onto, breaking at spaces when it can. The space
+// a row breaks at isn't shown; text without spaces, like CJK, breaks between any two characters.
+pub fn wrap_ranges(text: &str, width: usize) -> Vec<Range<usize>> {
+    let widths: Vec<usize> = text.char_indices().map(|(i, c)| text_width(&text[i..i + c.len_utf8()])).collect();
+    if width == 0 || widths.iter().sum::<usize>() <= width {
+        return std::iter::once(0..widths.len()).collect();
+    }
+
+    let mut ranges = Vec::new();
+    let (mut start, mut row_width, mut last_space) = (0, 0, None);
+    for (i, c) in text.chars().enumerate() {
+        while row_width + widths[i] > width && i > start {
+            match last_space.filter(|&space| space > start) {
+                Some(space) => {
+                    ranges.push(start..space);
+                    start = space + 1;
+                }
+                None => {
+                    ranges.push(start..i);
+                    start = i;
+                }
+            }
+            row_width = widths[start..i].iter().sum();
+            last_space = None;
+        }
+        if c == ' ' {
+            last_space = Some(i);
+        }
+        row_width += widths[i];
+    }
+    ranges.push(start..widths.len());
+    ranges
+}
+
+// Chord names over the characters they change on, keeping a space between names that would
+// otherwise run together. The shorter of the two rows is padded so centering keeps them lined up.
+// A wrapped line gets the chords of its characters in `range`, and the last row the ones after them.
+fn chord_row<'a>(app: &KaraokeApp, line: &LyricLine, range: Range<usize>, row: &mut LyricRow) -> LyricRow<'a> {
+    let indent: usize = row
+        .spans
+        .iter()
+        .take_while(|span| span.kind == SpanKind::Marker)
+        .map(|span| text_width(&span.text))
+        .sum();
+    // Columns follow the characters to where right-to-left text puts them
+    let text = char_slice(&line.text, range.clone());
+    let visual: Vec<char> = bidi::visual(text).chars().collect();
+    let order = bidi::visual_order(text);
+    let last_row = range.end >= line.text.chars().count();
+    let mut placed: Vec<(usize, String)> = line
+        .chords
+        .iter()
+        .zip(app.chord_names(line))
+        .filter(|(chord, _)| chord.position >= range.start && (chord.position < range.end || last_row))
+        .map(|(chord, name)| {
+            let position = chord.position - range.start;
+            let at = order.iter().position(|&i| i == position).unwrap_or(visual.len());
+            let before: String = visual[..at].iter().collect();
+            (indent + text_width(&before), name)
+        })
+        .collect();
+    placed.sort_by_key(|(column, _)| *column);
+
+    let mut text = String::new();
+    let mut width = 0;
+    for (column, name) in placed {
+        let column = column.max(if width == 0 { 0 } else { width + 1 });
+        text.push_str(&" ".repeat(column - width));
+        text.push_str(&name);
+        width = column + text_width(&name);
+    }
+
+    let row_width: usize = row.spans.iter().map(|span| text_width(&span.text)).sum();
+    if row_width < width {
+        row.spans.push(styled(" ".repeat(width - row_width), SpanKind::Marker));
+    }
+    text.push_str(&" ".repeat(row_width.saturating_sub(width)));
+    LyricRow {
+        spans: vec![styled(text, SpanKind::Chord)],
+        ..LyricRow::default()
+    }
+}
+
+// The ball over the character it's above, when that's on this row of the line. The row is as
+// wide as the one below it so centering keeps them lined up.
+fn ball_row<'a>(line: &LyricLine, range: Range<usize>, lyric_time: f64, row: &LyricRow) -> LyricRow<'a> {
+    let text = char_slice(&line.text, range.clone());
+    let Some((at, height)) = timeline::bounce(line, lyric_time).filter(|_| !bidi::has_rtl(text)) else {
+        return LyricRow::default();
+    };
+    let at = at as usize;
+    if !range.contains(&at) {
+        return LyricRow::default();
+    }
+    let indent: usize = row
+        .spans
+        .iter()
+        .take_while(|span| span.kind == SpanKind::Marker)
+        .map(|span| text_width(&span.text))
+        .sum();
+    let column = indent + text_width(char_slice(text, 0..at - range.start));
+    let row_width: usize = row.spans.iter().map(|span| text_width(&span.text)).sum();
+    let ball = BALL[((height * BALL.len() as f64) as usize).min(BALL.len() - 1)];
+    LyricRow {
+        spans: vec![styled(
+            format!("{}{}{}", " ".repeat(column), ball, " ".repeat(row_width.saturating_sub(column + 1))),
+            SpanKind::Ball,
+        )],
+        ..LyricRow::default()
+    }
+}
+
+// Lays out `height` rows with the active line in its place in the window, with a row of
+// chords above each line when they're shown, and the bouncing ball above the active one. Lines
+// wider than `width` wrap onto more rows, and the active line can be drawn in block letters.
+pub fn layout_lyrics(app: &KaraokeApp, lyric_time: f64, width: usize, height: usize) -> LyricFrame<'_> {
+    let current_idx = app.focus_line(lyric_time);
+    let gap = instrumental_break(app, lyric_time);
+
+    let center = app.window.center_row();
+    let window = (0..app.window.lines.max(1)).map(|visible_row| {
+        if let Some(gap) = &gap {
+            return layout_break(gap, visible_row, &app.window);
+        }
+        let Some(curr_idx) = current_idx else {
+            return Slot::Row(LyricRow::default());
+        };
+        let lyric_idx = curr_idx as i64 + visible_row as i64 - center as i64;
+        if lyric_idx < 0 || lyric_idx as usize >= app.lyrics.len() || (visible_row < center && !app.window.past_lines) {
+            return Slot::Row(LyricRow::default());
+        }
+        Slot::Line {
+            index: lyric_idx as usize,
+            active: visible_row == center,
+        }
+    });
+
+    let show_chords = app.chords_visible();
+    let mut window_rows = Vec::with_capacity(app.window.lines * 3);
+    // The first row of the active line and how many it takes up
+    let mut active_rows = None;
+    for (visible_row, slot) in window.enumerate() {
+        let (index, active) = match slot {
+            Slot::Row(row) => {
+                if show_chords {
+                    window_rows.push(LyricRow::default());
+                }
+                // The ball's row stays put when there's no line under it, so nothing jumps
+                if app.ball && visible_row == center {
+                    window_rows.push(LyricRow::default());
+                }
+                window_rows.push(row);
+                continue;
+            }
+            Slot::Line { index, active } => (index, active),
+        };
+        let line = &app.lyrics[index];
+        // The big active line has no room for chords or typing marks over it
+        let big = match active && app.big_line && app.typing.is_none() {
+            true => big_line_rows(app, lyric_time, index, width),
+            false => None,
+        };
+        if let Some(rows) = big {
+            active_rows = Some((window_rows.len(), rows.len()));
+            window_rows.extend(rows);
+            continue;
+        }
+        let room = match active {
+            true => width.saturating_sub(LEFT_MARKER.len() + RIGHT_MARKER.len()),
+            false => width,
+        };
+        let first = window_rows.len();
+        for range in wrap_ranges(&line.text, room) {
+            let mut row = line_row(app, lyric_time, index, range.clone(), active);
+            if show_chords {
+                let chords = match line.chords.is_empty() {
+                    true => LyricRow::default(),
+                    false => chord_row(app, line, range.clone(), &mut row),
+                };
+                window_rows.push(chords);
+            }
+            if app.ball && active {
+                window_rows.push(ball_row(line, range, lyric_time, &row));
+            }
+            window_rows.push(row);
+        }
+        if active {
+            active_rows = Some((first, window_rows.len() - first));
+        }
+    }
+
+    // The window sits as far down the area as the active line is down the window, so a third of
+    // the way down stays a third of the way down. Lines that wrap can overflow the area, so the
+    // active line is kept at that height.
+    let share = |room: usize| match app.window.lines {
+        0 | 1 => room / 2,
+        lines => room * center / (lines - 1),
+    };
+    let (top_padding, skip) = match window_rows.len().checked_sub(height) {
+        None | Some(0) => (share(height - window_rows.len()), 0),
+        Some(overflow) => {
+            let middle = active_rows.map_or(0, |(first, count)| first + count / 2);
+            (0, middle.saturating_sub(share(height)).min(overflow))
+        }
+    };
+    let mut rows = Vec::with_capacity(height);
+    rows.resize(top_padding, LyricRow::default());
+    rows.extend(window_rows.into_iter().skip(skip));
+    rows.resize(height, LyricRow::default());
+    LyricFrame { rows }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{song_config::SongConfig, style::SongStyle, Chord, Segment};
+
+    fn app_with(lines: &[(&str, f64, f64)]) -> KaraokeApp {
+        let lyrics = lines
+            .iter()
+            .map(|&(text, start_time, end_time)| LyricLine {
+                text: text.to_string(),
+                start_time,
+                end_time,
+                segments: Vec::new(),
+                singer: 0,
+                style: LineStyle::default(),
+                chords: Vec::new(),
+            })
+            .collect();
+        let duration = lines.last().map_or(0.0, |line| line.2);
+        KaraokeApp::from_config(
+            SongConfig {
+                title: "Test".to_string(),
+                artist: None,
+                album: None,
+                key: None,
+                duration,
+                start_position: 0.0,
+                lyrics,
+                sections: Vec::new(),
+                style: SongStyle::default(),
+                tempo: None,
+                view: None,
+            },
+            None,
+        )
+    }
+
+    // One entry per row; spans are written as <kind:text> and blank rows as ~
+    fn snapshot(frame: &LyricFrame) -> Vec<String> {
+        frame
+            .rows
+            .iter()
+            .map(|row| {
+                if row.spans.is_empty() {
+                    return "~".to_string();
+                }
+                row.spans
+                    .iter()
+                    .map(|span| {
+                        let kind = match span.kind {
+                            SpanKind::Sung => "sung",
+                            SpanKind::Unsung => "unsung",
+                            SpanKind::Completed => "done",
+                            SpanKind::Upcoming => "next",
+                            SpanKind::Marker => "marker",
+                            SpanKind::Break => "break",
+                            SpanKind::BreakBar => "bar",
+                            SpanKind::Chord => "chord",
+                            SpanKind::Match => "match",
+                            SpanKind::Typed => "typed",
+                            SpanKind::Mistake => "typo",
+                            SpanKind::Ball => "ball",
+                            SpanKind::Fading(_) => "fading",
+                        };
+                        format!("<{}:{}>", kind, span.text)
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn three_lines() -> KaraokeApp {
+        app_with(&[("one", 1.0, 2.0), ("two", 2.0, 3.0), ("three", 4.0, 5.0)])
+    }
+
+    #[test]
+    fn halfway_through_the_second_line() {
+        let app = three_lines();
+        let frame = layout_lyrics(&app, 2.5, 80, 5);
+        assert_eq!(
+            snapshot(&frame),
+            [
+                "~",
+                "<done:one>",
+                "<marker:>     ><sung:t><unsung:wo><marker:     <>",
+                "<next:three>",
+                "~",
+            ]
+        );
+    }
+
+    #[test]
+    fn window_is_centered_in_taller_areas() {
+        let app = three_lines();
+        let frame = layout_lyrics(&app, 1.0, 80, 9);
+        assert_eq!(
+            snapshot(&frame),
+            [
+                "~",
+                "~",
+                "~",
+                "~",
+                "<marker:>     ><unsung:one><marker:     <>",
+                "<next:two>",
+                "<next:three>",
+                "~",
+                "~",
+            ]
+        );
+    }
+
+    #[test]
+    fn before_the_first_line_nothing_is_shown() {
+        let app = three_lines();
+        let frame = layout_lyrics(&app, 0.5, 80, 5);
+        assert_eq!(snapshot(&frame), ["~", "~", "~", "~", "~"]);
+    }
+
+    #[test]
+    fn songs_without_lyrics_lay_out_blank_rows() {
+        let app = app_with(&[]);
+        for time in [0.0, 10.0] {
+            assert!(instrumental_break(&app, time).is_none());
+            assert_eq!(snapshot(&layout_lyrics(&app, time, 80, 3)), ["~", "~", "~"]);
+        }
+    }
+
+    #[test]
+    fn gap_keeps_the_previous_line_active_and_full() {
+        let app = three_lines();
+        let frame = layout_lyrics(&app, 3.5, 80, 5);
+        assert_eq!(
+            snapshot(&frame),
+            [
+                "~",
+                "<done:one>",
+                "<marker:>     ><sung:two><marker:     <>",
+                "<next:three>",
+                "~",
+            ]
+        );
+    }
+
+    #[test]
+    fn after_the_end_the_last_line_stays_active() {
+        let app = three_lines();
+        let frame = layout_lyrics(&app, 9.0, 80, 5);
+        assert_eq!(
+            snapshot(&frame),
+            [
+                "<done:one>",
+                "<done:two>",
+                "<marker:>     ><sung:three><marker:     <>",
+                "~",
+                "~",
+            ]
+        );
+    }
+
+    #[test]
+    fn long_gap_shows_a_countdown_instead_of_the_last_line() {
+        let app = app_with(&[("one", 1.0, 2.0), ("two", 12.0, 13.0)]);
+        let frame = layout_lyrics(&app, 4.5, 80, 5);
+        assert_eq!(
+            snapshot(&frame),
+            [
+                "~",
+                "<done:one>",
+                "<break:♪ Instrumental break - next line in 0:08 ♪>",
+                "<bar:━━━━━━━━━━━━━━━━━━━━━━━>",
+                "<next:two>",
+            ]
+        );
+    }
+
+    #[test]
+    fn long_intro_counts_down_to_the_first_line() {
+        let app = app_with(&[("one", 10.0, 12.0)]);
+        let frame = layout_lyrics(&app, 0.0, 80, 5);
+        assert_eq!(
+            snapshot(&frame),
+            [
+                "~",
+                "~",
+                "<break:♪ Instrumental break - next line in 0:10 ♪>",
+                "<bar:━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━>",
+                "<next:one>",
+            ]
+        );
+    }
+
+    #[test]
+    fn active_line_splits_by_characters_not_bytes() {
+        let app = app_with(&[("hát ca", 0.0, 2.0)]);
+        let spans = segment_spans(&app.lyrics[0], 0..6, 1.0, Fill::Left);
+        assert_eq!(
+            spans,
+            vec![styled("hát", SpanKind::Sung), styled(" ca", SpanKind::Unsung)]
+        );
+    }
+
+    #[test]
+    fn long_lines_wrap_at_spaces_and_keep_sweeping() {
+        assert_eq!(wrap_ranges("one two three", 8), [0..7, 8..13]);
+        assert_eq!(wrap_ranges("春の風が吹く", 6), [0..3, 3..6]);
+        assert_eq!(wrap_ranges("short", 0).len(), 1);
+
+        let app = app_with(&[("sing along with me", 0.0, 1.8), ("next", 2.0, 3.0)]);
+        let frame = layout_lyrics(&app, 1.2, 24, 6);
+        assert_eq!(
+            snapshot(&frame)[2..4],
+            [
+                "<marker:>     ><sung:sing along><marker:     <>",
+                "<marker:>     ><sung:w><unsung:ith me><marker:     <>",
+            ]
+        );
+    }
+
+    #[test]
+    fn typed_characters_are_marked_right_or_wrong() {
+        let mut app = app_with(&[("hello", 0.0, 5.0)]);
+        let mut typing = crate::typing::TypingPractice::new(1);
+        "Hx".chars().for_each(|c| typing.type_char(&app.lyrics, 1.0, c));
+        app.typing = Some(typing);
+        assert_eq!(
+            snapshot(&layout_lyrics(&app, 3.5, 80, 5))[2],
+            "<marker:>     ><typed:h><typo:e><sung:l><unsung:lo><marker:     <>"
+        );
+    }
+
+    #[test]
+    fn segments_highlight_whole_syllables() {
+        let mut app = app_with(&[("Hel lo", 0.0, 4.0)]);
+        app.lyrics[0].segments = vec![
+            Segment { text: "Hel".to_string(), start_time: 0.0, end_time: 1.0 },
+            Segment { text: " lo".to_string(), start_time: 3.0, end_time: 4.0 },
+        ];
+        let frame = layout_lyrics(&app, 2.0, 80, 5);
+        assert_eq!(snapshot(&frame)[2], "<marker:>     ><sung:Hel><unsung: lo><marker:     <>");
+    }
+
+    #[test]
+    fn chords_sit_above_their_characters() {
+        let mut app = three_lines();
+        app.lyrics[1].chords = vec![
+            Chord { name: "G".to_string(), position: 0 },
+            Chord { name: "Cmaj7".to_string(), position: 1 },
+            Chord { name: "Dsus4".to_string(), position: 3 },
+        ];
+        let frame = layout_lyrics(&app, 2.5, 80, 10);
+        assert_eq!(
+            snapshot(&frame)[2..6],
+            [
+                "~",
+                "<done:one>",
+                "<chord:      G Cmaj7 Dsus4>",
+                "<marker:>     ><sung:t><unsung:wo><marker:     <><marker:    >",
+            ]
+        );
+
+        app.transpose = 2;
+        app.capo = 1;
+        let frame = layout_lyrics(&app, 2.5, 80, 10);
+        assert_eq!(snapshot(&frame)[4], "<chord:      Ab Dbmaj7 Ebsus4>");
+
+        app.show_chords = false;
+        assert_eq!(snapshot(&layout_lyrics(&app, 2.5, 80, 5))[2], "<marker:>     ><sung:t><unsung:wo><marker:     <>");
+    }
+
+    #[test]
+    fn ball_bounces_over_the_active_line() {
+        let mut app = app_with(&[("one", 0.0, 2.0), ("ab cd", 2.0, 6.0), ("three", 6.0, 7.0)]);
+        app.ball = true;
+        let rows = snapshot(&layout_lyrics(&app, 2.0, 80, 7));
+        assert_eq!(
+            rows[1..4],
+            ["<done:one>", "<ball:      ●          >", "<marker:>     ><unsung:ab cd><marker:     <>"]
+        );
+        // Halfway from the first word to the second, at the top of the hop
+        let rows = snapshot(&layout_lyrics(&app, 3.2, 80, 7));
+        assert_eq!(rows[2], "<ball:       ˙         >");
+        // Between lines the row is kept, blank
+        let rows = snapshot(&layout_lyrics(&app, 9.0, 80, 7));
+        assert_eq!(rows[2], "~");
+    }
+
+    #[test]
+    fn big_line_is_drawn_in_block_letters() {
+        let mut app = three_lines();
+        app.big_line = true;
+        let rows = snapshot(&layout_lyrics(&app, 2.5, 80, 8));
+        assert_eq!(rows[1], "<done:one>");
+        assert_eq!(rows[2], "<sung:▀▀█▀▀ ><unsung:█   █ ▄▀▀▀▄>");
+        assert_eq!(rows[6], "<next:three>");
+
+        // Characters the font doesn't have keep the line at normal size
+        app.lyrics[1].text = "歌".to_string();
+        assert_eq!(snapshot(&layout_lyrics(&app, 2.5, 80, 5))[2], "<marker:>     ><unsung:歌><marker:     <>");
+    }
+
+    #[test]
+    fn window_size_and_current_row_can_change() {
+        let mut app = app_with(&[("a", 0.0, 1.0), ("b", 1.0, 2.0), ("c", 2.0, 3.0), ("d", 3.0, 4.0), ("e", 4.0, 5.0)]);
+        app.window = WindowOptions { lines: Some(4), center: Some(2), hide_past_lines: false, fill: None }.apply(app.window);
+        assert_eq!(
+            snapshot(&layout_lyrics(&app, 2.5, 80, 6)),
+            ["<done:b>", "<marker:>     ><unsung:c><marker:     <>", "<next:d>", "<next:e>", "~", "~"]
+        );
+
+        app.window = WindowOptions { lines: Some(3), center: None, hide_past_lines: true, fill: Some(Fill::Right) }.apply(app.window);
+        assert_eq!(app.window, LyricWindow { lines: 3, center: 2, past_lines: false, fill: Fill::Right });
+        assert_eq!(
+            snapshot(&layout_lyrics(&app, 2.5, 80, 3)),
+            ["~", "<marker:>     ><unsung:c><marker:     <>", "<next:d>"]
+        );
+    }
+
+    #[test]
+    fn countdown_moves_up_to_fit_in_a_bottom_anchored_window() {
+        let mut app = app_with(&[("one", 1.0, 2.0), ("two", 12.0, 13.0)]);
+        app.window = LyricWindow { lines: 3, center: 3, ..LyricWindow::default() };
+        assert_eq!(
+            snapshot(&layout_lyrics(&app, 4.5, 80, 3)),
+            [
+                "<break:♪ Instrumental break - next line in 0:08 ♪>",
+                "<bar:━━━━━━━━━━━━━━━━━━━━━━━>",
+                "<next:two>",
+            ]
+        );
+
+        app.window = LyricWindow { lines: 4, center: 4, ..LyricWindow::default() };
+        assert_eq!(
+            snapshot(&layout_lyrics(&app, 4.5, 80, 4)),
+            [
+                "<done:one>",
+                "<break:♪ Instrumental break - next line in 0:08 ♪>",
+                "<bar:━━━━━━━━━━━━━━━━━━━━━━━>",
+                "<next:two>",
+            ]
+        );
+    }
+
+    // A timing run rather than a check: cargo test --release -- --ignored --nocapture
+    #[test]
+    #[ignore]
+    fn layout_benchmark() {
+        let lines: Vec<(String, f64, f64)> =
+            (0..2000).map(|i| (format!("Line number {} of a very long libretto", i), i as f64 * 2.0, i as f64 * 2.0 + 1.8)).collect();
+        let lines: Vec<(&str, f64, f64)> = lines.iter().map(|(text, start, end)| (text.as_str(), *start, *end)).collect();
+        let mut app = app_with(&lines);
+        let mut terminal = ratatui::Terminal::new(ratatui::backend::TestBackend::new(100, 40)).unwrap();
+        let frames = 5_000;
+        let (mut layout, mut drawn) = (std::time::Duration::ZERO, std::time::Duration::ZERO);
+        for frame in 0..frames {
+            app.current_position = frame as f64 * 4000.0 / frames as f64;
+            let started = std::time::Instant::now();
+            std::hint::black_box(layout_lyrics(&app, app.current_position, 80, 40));
+            layout += started.elapsed();
+            let started = std::time::Instant::now();
+            terminal.draw(|f| crate::ui(f, &app, None)).unwrap();
+            drawn += started.elapsed();
+        }
+        let per_frame = |total: std::time::Duration| total.as_secs_f64() * 1e6 / frames as f64;
+        println!("{} lines: layout {:.2}µs, whole frame {:.2}µs", lines.len(), per_frame(layout), per_frame(drawn));
+    }
+
+    #[test]
+    fn right_to_left_lines_are_sung_from_the_right() {
+        let mut app = app_with(&[("שלום עולם", 0.0, 4.5), ("next line", 5.0, 6.0)]);
+        app.lyrics[0].chords = vec![Chord { name: "Am".to_string(), position: 0 }];
+        let frame = layout_lyrics(&app, 2.0, 80, 10);
+        assert_eq!(
+            snapshot(&frame)[4..6],
+            ["<chord:              Am     >", "<marker:>     ><unsung:םלוע ><sung:םולש><marker:     <>"]
+        );
+    }
+
+    #[test]
+    fn lines_fill_from_either_end_the_middle_or_a_word_at_a_time() {
+        let mut app = app_with(&[("ab cd ef", 0.0, 8.0), ("next", 9.0, 10.0)]);
+        let mut current = |fill, time| {
+            app.window.fill = fill;
+            let frame = layout_lyrics(&app, time, 80, 5);
+            snapshot(&frame)[2].trim_matches(|c| "<>:marker ".contains(c)).to_string()
+        };
+        assert_eq!(current(Fill::Left, 3.0), "sung:ab ><unsung:cd ef");
+        assert_eq!(current(Fill::Right, 3.0), "unsung:ab cd><sung: ef");
+        assert_eq!(current(Fill::Center, 2.0), "unsung:ab ><sung:cd><unsung: ef");
+        assert_eq!(current(Fill::Fade, 4.0), "sung:ab ><fading:cd ><unsung:ef");
+    }
+
+    #[test]
+    fn rows_carry_the_singer_of_their_line() {
+        let mut app = three_lines();
+        app.lyrics[1].singer = 1;
+        let frame = layout_lyrics(&app, 2.5, 80, 5);
+        let singers: Vec<usize> = frame.rows.iter().map(|row| row.singer).collect();
+        assert_eq!(singers, [0, 0, 1, 0, 0]);
+    }
+}
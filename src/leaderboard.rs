@@ -0,0 +1,440 @@
+use crossterm::event::KeyCode;
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    cli::LeaderboardOptions,
+    events::{AppEvent, EventBus},
+    history, resume,
+};
+
+const LEADERBOARD_FILE: &str = ".karaoke-lyric-leaderboard.json";
+const DAY: u64 = 86_400;
+
+// One singer's score for one song at a party
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Performance {
+    pub singer: String,
+    pub song: String,
+    pub title: String,
+    pub score: u32,
+    // Seconds since the Unix epoch
+    pub sung_at: u64,
+    // When the party it was sung at started, telling parties apart
+    pub party: u64,
+}
+
+// Every party's results, kept across sessions
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Leaderboard {
+    pub performances: Vec<Performance>,
+}
+
+impl Leaderboard {
+    // No file yet is an empty board; a broken one is an error so it doesn't get overwritten
+    pub fn load(path: &Path) -> io::Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(json) => serde_json::from_str(&json)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path.display(), e))),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        fs::write(path, json)
+    }
+}
+
+pub fn leaderboard_path() -> PathBuf {
+    resume::home_file(LEADERBOARD_FILE)
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs())
+}
+
+// Adds a party song's score for each of its singers
+pub fn record(singers: &[String], song: Option<&Path>, title: &str, score: u32, party: u64) -> io::Result<()> {
+    if singers.is_empty() {
+        return Ok(());
+    }
+    let path = leaderboard_path();
+    let mut board = Leaderboard::load(&path)?;
+    let sung_at = now();
+    for singer in singers {
+        board.performances.push(Performance {
+            singer: singer.clone(),
+            song: history::song_key(song),
+            title: title.to_string(),
+            score,
+            sung_at,
+            party,
+        });
+    }
+    board.save(&path)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortBy {
+    Total,
+    Best,
+    Average,
+    Songs,
+    Name,
+}
+
+impl SortBy {
+    const ALL: [SortBy; 5] = [SortBy::Total, SortBy::Best, SortBy::Average, SortBy::Songs, SortBy::Name];
+
+    fn label(self) -> &'static str {
+        match self {
+            SortBy::Total => "total",
+            SortBy::Best => "best",
+            SortBy::Average => "average",
+            SortBy::Songs => "songs",
+            SortBy::Name => "name",
+        }
+    }
+
+    fn next(self) -> Self {
+        let i = Self::ALL.iter().position(|&sort| sort == self).unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+}
+
+impl std::str::FromStr for SortBy {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL.into_iter().find(|sort| sort.label() == s).ok_or(())
+    }
+}
+
+// Which days count, as a half-open range of timestamps
+#[derive(Debug, Clone, PartialEq)]
+pub struct DateRange {
+    pub from: Option<u64>,
+    pub until: Option<u64>,
+    pub label: String,
+}
+
+impl DateRange {
+    pub fn all() -> Self {
+        Self {
+            from: None,
+            until: None,
+            label: "all time".to_string(),
+        }
+    }
+
+    fn last_days(days: u64, now: u64) -> Self {
+        Self {
+            from: Some((now / DAY + 1).saturating_sub(days) * DAY),
+            until: None,
+            label: format!("last {} days", days),
+        }
+    }
+
+    fn contains(&self, at: u64) -> bool {
+        self.from.is_none_or(|from| at >= from) && self.until.is_none_or(|until| at < until)
+    }
+
+    // All time and the last week, month and year, after the range from the command line
+    fn presets(given: &DateRange, now: u64) -> Vec<DateRange> {
+        let mut ranges = vec![Self::all(), Self::last_days(7, now), Self::last_days(30, now), Self::last_days(365, now)];
+        if !ranges.contains(given) {
+            ranges.insert(0, given.clone());
+        }
+        ranges
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Standing {
+    pub singer: String,
+    pub songs: u32,
+    pub total: u64,
+    pub best: u32,
+    pub average: u32,
+    pub parties: u32,
+}
+
+// Singers ranked by their performances in the range; names are matched whatever their case
+pub fn standings(performances: &[Performance], range: &DateRange, sort: SortBy) -> Vec<Standing> {
+    let mut standings: Vec<(Standing, Vec<u64>)> = Vec::new();
+    for performance in performances.iter().filter(|performance| range.contains(performance.sung_at)) {
+        let i = match standings.iter().position(|(standing, _)| standing.singer.eq_ignore_ascii_case(&performance.singer)) {
+            Some(i) => i,
+            None => {
+                let standing = Standing {
+                    singer: performance.singer.clone(),
+                    songs: 0,
+                    total: 0,
+                    best: 0,
+                    average: 0,
+                    parties: 0,
+                };
+                standings.push((standing, Vec::new()));
+                standings.len() - 1
+            }
+        };
+        let (standing, parties) = &mut standings[i];
+        standing.songs += 1;
+        standing.total += u64::from(performance.score);
+        standing.best = standing.best.max(performance.score);
+        if !parties.contains(&performance.party) {
+            parties.push(performance.party);
+        }
+    }
+
+    let mut standings: Vec<Standing> = standings
+        .into_iter()
+        .map(|(mut standing, parties)| {
+            standing.average = (standing.total / u64::from(standing.songs.max(1))) as u32;
+            standing.parties = parties.len() as u32;
+            standing
+        })
+        .collect();
+    standings.sort_by(|a, b| {
+        let by = match sort {
+            SortBy::Total => b.total.cmp(&a.total),
+            SortBy::Best => b.best.cmp(&a.best),
+            SortBy::Average => b.average.cmp(&a.average),
+            SortBy::Songs => b.songs.cmp(&a.songs),
+            SortBy::Name => std::cmp::Ordering::Equal,
+        };
+        by.then_with(|| a.singer.to_lowercase().cmp(&b.singer.to_lowercase()))
+    });
+    standings
+}
+
+fn csv_field(field: &str) -> String {
+    match field.contains([',', '"', '\n']) {
+        true => format!("\"{}\"", field.replace('"', "\"\"")),
+        false => field.to_string(),
+    }
+}
+
+fn csv(standings: &[Standing]) -> String {
+    let mut out = String::from("rank,singer,total,songs,average,best,parties\n");
+    for (rank, standing) in standings.iter().enumerate() {
+        out.push_str(&format!(
+            "{},{},{},{},{},{},{}\n",
+            rank + 1,
+            csv_field(&standing.singer),
+            standing.total,
+            standing.songs,
+            standing.average,
+            standing.best,
+            standing.parties
+        ));
+    }
+    out
+}
+
+// The standings as CSV or JSON, going by the output's extension
+fn export(standings: &[Standing], range: &DateRange, output: &Path) -> io::Result<()> {
+    let extension = output.extension().map(|ext| ext.to_string_lossy().to_ascii_lowercase()).unwrap_or_default();
+    let contents = match extension.as_str() {
+        "csv" => csv(standings),
+        "json" => {
+            let json = serde_json::json!({
+                "from": range.from.map(history::format_date),
+                "to": range.until.map(|until| history::format_date(until.saturating_sub(DAY))),
+                "standings": standings,
+            });
+            serde_json::to_string_pretty(&json).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?
+        }
+        _ => return Err(io::Error::new(io::ErrorKind::InvalidInput, "leaderboards are exported as .csv or .json")),
+    };
+    fs::write(output, contents)?;
+    println!("Exported {} singers ({}) to {}", standings.len(), range.label, output.display());
+    Ok(())
+}
+
+struct BoardScreen {
+    performances: Vec<Performance>,
+    sort: SortBy,
+    ranges: Vec<DateRange>,
+    range: usize,
+    scroll: usize,
+}
+
+fn ui(f: &mut Frame, screen: &BoardScreen) {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(5), Constraint::Length(1)])
+        .split(f.size());
+    let range = &screen.ranges[screen.range];
+    let standings = standings(&screen.performances, range, screen.sort);
+
+    let header = Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD);
+    let mut lines = vec![Line::styled(
+        format!("{:>4}  {:<20} {:>8} {:>6} {:>8} {:>6} {:>8}", "#", "Singer", "Total", "Songs", "Average", "Best", "Parties"),
+        header,
+    )];
+    for (rank, standing) in standings.iter().enumerate().skip(screen.scroll) {
+        let style = match rank {
+            0 => Style::default().fg(Color::Rgb(0, 255, 0)).add_modifier(Modifier::BOLD),
+            _ => Style::default().fg(Color::White),
+        };
+        lines.push(Line::styled(
+            format!(
+                "{:>4}  {:<20} {:>8} {:>6} {:>8} {:>6} {:>8}",
+                rank + 1,
+                standing.singer,
+                standing.total,
+                standing.songs,
+                standing.average,
+                standing.best,
+                standing.parties
+            ),
+            style,
+        ));
+    }
+    if standings.is_empty() {
+        lines.push(Line::styled("No party songs sung in this time", Style::default().fg(Color::Rgb(80, 80, 80))));
+    }
+
+    let title = format!(" Leaderboard - {} - by {} ", range.label, screen.sort.label());
+    f.render_widget(Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(title)), rows[0]);
+    f.render_widget(
+        Paragraph::new(Line::from(vec![Span::styled(
+            "S Sort  D Dates  ↑/↓ Scroll  Q Quit",
+            Style::default().fg(Color::White),
+        )])),
+        rows[1],
+    );
+}
+
+fn show(performances: Vec<Performance>, range: DateRange, sort: SortBy) -> io::Result<()> {
+    let ranges = DateRange::presets(&range, now());
+    let mut screen = BoardScreen {
+        performances,
+        sort,
+        range: ranges.iter().position(|preset| *preset == range).unwrap_or(0),
+        ranges,
+        scroll: 0,
+    };
+
+    let events = EventBus::new();
+    let mut terminal = crate::setup_terminal()?;
+    events.spawn_terminal_sources(crate::TICK_RATE);
+
+    let result = (|| -> Result<(), io::Error> {
+        loop {
+            terminal.draw(|f| {
+                ui(f, &screen);
+                crate::compat::adapt(f.buffer_mut());
+            })?;
+            let Ok(event) = events.receiver().recv() else {
+                return Ok(());
+            };
+            let key = match event {
+                AppEvent::Terminate => return Ok(()),
+                AppEvent::Input(key) => key,
+                _ => continue,
+            };
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Char('Q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Char('s') | KeyCode::Char('S') => screen.sort = screen.sort.next(),
+                KeyCode::Char('d') | KeyCode::Char('D') => {
+                    screen.range = (screen.range + 1) % screen.ranges.len();
+                    screen.scroll = 0;
+                }
+                KeyCode::Up => screen.scroll = screen.scroll.saturating_sub(1),
+                KeyCode::Down => screen.scroll += 1,
+                _ => {}
+            }
+        }
+    })();
+
+    crate::restore_terminal()?;
+    result
+}
+
+pub fn run(options: &LeaderboardOptions) -> io::Result<()> {
+    let board = Leaderboard::load(&leaderboard_path())?;
+    let range = match (options.from, options.until) {
+        (None, None) => DateRange::all(),
+        (from, until) => DateRange {
+            from,
+            // The last day given counts in full
+            until: until.map(|until| until + DAY),
+            label: format!(
+                "{} to {}",
+                from.map_or_else(|| "the start".to_string(), history::format_date),
+                until.map_or_else(|| "now".to_string(), history::format_date)
+            ),
+        },
+    };
+    match &options.export {
+        Some(output) => export(&standings(&board.performances, &range, options.sort), &range, output),
+        None => show(board.performances, range, options.sort),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn performance(singer: &str, score: u32, sung_at: u64, party: u64) -> Performance {
+        Performance {
+            singer: singer.to_string(),
+            song: "/songs/a.lrc".to_string(),
+            title: "A".to_string(),
+            score,
+            sung_at,
+            party,
+        }
+    }
+
+    #[test]
+    fn standings_add_up_within_the_range() {
+        let performances = [
+            performance("Ann", 8000, 100, 1),
+            performance("bob", 9500, 200, 1),
+            performance("ANN", 7000, 5 * DAY, 2),
+            performance("Bob", 1000, 10 * DAY, 3),
+        ];
+        let all = standings(&performances, &DateRange::all(), SortBy::Total);
+        assert_eq!(
+            all[0],
+            Standing {
+                singer: "Ann".to_string(),
+                songs: 2,
+                total: 15_000,
+                best: 8000,
+                average: 7500,
+                parties: 2,
+            }
+        );
+        assert_eq!(standings(&performances, &DateRange::all(), SortBy::Best)[0].singer, "bob");
+
+        let first_week = DateRange {
+            from: None,
+            until: Some(7 * DAY),
+            label: String::new(),
+        };
+        let week = standings(&performances, &first_week, SortBy::Total);
+        assert_eq!((week[0].singer.as_str(), week[0].total), ("Ann", 15_000));
+        assert_eq!((week[1].singer.as_str(), week[1].songs), ("bob", 1));
+
+        assert_eq!(DateRange::last_days(7, 10 * DAY + 5).from, Some(4 * DAY));
+        assert_eq!(csv(&week).lines().nth(1), Some("1,Ann,15000,2,7500,8000,2"));
+        assert_eq!(csv_field("Ann, \"the voice\""), "\"Ann, \"\"the voice\"\"\"");
+    }
+}
@@ -0,0 +1,317 @@
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    cli::{LibraryAction, LibraryOptions},
+    formats::{self, bundle},
+    scrobble,
+};
+
+// Library songs are kept flat, as `Artist - Title.<ext>` with their recording beside them
+// under the same name
+pub fn song_stem(artist: Option<&str>, title: &str) -> String {
+    let name = match artist.filter(|artist| !artist.is_empty()) {
+        Some(artist) => format!("{} - {}", artist, title),
+        None => title.to_string(),
+    };
+    let name: String = name
+        .chars()
+        .map(|c| if "/\\:*?\"<>|".contains(c) || c.is_control() { '_' } else { c })
+        .collect();
+    name.trim().trim_start_matches('.').to_string()
+}
+
+// Titles and artists compared without case, punctuation or spacing
+fn normalize(text: &str) -> String {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// A song found on disk, with the files that go along with it
+#[derive(Debug, Clone)]
+struct Song {
+    lyrics: PathBuf,
+    audio: Option<PathBuf>,
+    // The LRC timing a ChordPro song is synced with
+    timing: Option<PathBuf>,
+    title: String,
+    artist: Option<String>,
+    word_timed: bool,
+    audio_hash: Option<[u8; 16]>,
+}
+
+impl Song {
+    fn key(&self) -> String {
+        format!("{}\u{0}{}", normalize(self.artist.as_deref().unwrap_or_default()), normalize(&self.title))
+    }
+
+    fn stem(&self) -> String {
+        song_stem(self.artist.as_deref(), &self.title)
+    }
+
+    // Between two copies of a song, the one with a recording and then the one with word timing
+    fn better_than(&self, other: &Song) -> bool {
+        (self.audio.is_some(), self.word_timed) > (other.audio.is_some(), other.word_timed)
+    }
+}
+
+fn read_song(lyrics: &Path, hash_audio: bool) -> io::Result<Song> {
+    let config = formats::load(lyrics)?;
+    let audio = Some(lyrics.with_extension("wav")).filter(|audio| audio.is_file() && !bundle::is_bundle(lyrics));
+    let audio_hash = match (&audio, hash_audio) {
+        (Some(audio), true) => Some(scrobble::md5(&fs::read(audio)?)),
+        _ => None,
+    };
+    Ok(Song {
+        lyrics: lyrics.to_path_buf(),
+        audio,
+        timing: formats::timing_file(lyrics).filter(|timing| timing.is_file()),
+        word_timed: config.lyrics.iter().any(|line| !line.segments.is_empty()),
+        title: config.title,
+        artist: config.artist,
+        audio_hash,
+    })
+}
+
+fn song_files(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    let mut entries: Vec<PathBuf> = fs::read_dir(dir)?.flatten().map(|entry| entry.path()).collect();
+    entries.sort();
+    for path in entries {
+        let hidden = path.file_name().is_some_and(|name| name.to_string_lossy().starts_with('.'));
+        if hidden {
+            continue;
+        }
+        if path.is_dir() {
+            song_files(&path, files)?;
+        } else if formats::is_song_file(&path) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+// Every readable song under `dir`; files that turn out not to be songs are reported and left out
+fn scan(dir: &Path, hash_audio: bool, skipped: &mut Vec<String>) -> io::Result<Vec<Song>> {
+    let mut files = Vec::new();
+    song_files(dir, &mut files)?;
+    // An LRC file syncing a ChordPro song of the same name is part of that song
+    let timings: Vec<PathBuf> = files.iter().filter_map(|file| formats::timing_file(file)).collect();
+    let mut songs = Vec::new();
+    for file in files.iter().filter(|file| !timings.contains(file)) {
+        match read_song(file, hash_audio) {
+            Ok(song) => songs.push(song),
+            Err(e) => {
+                let message = e.to_string();
+                let message = message.trim_start_matches(&format!("{}: ", file.display())).to_string();
+                skipped.push(format!("skip    {} ({})", file.display(), message));
+            }
+        }
+    }
+    Ok(songs)
+}
+
+#[derive(Debug, PartialEq)]
+enum Step {
+    // Lyric, recording and timing files, from where they are to where they go
+    Import(Vec<(PathBuf, PathBuf)>),
+    Duplicate { song: PathBuf, of: PathBuf },
+}
+
+fn file_in(library: &Path, stem: &str, source: &Path) -> PathBuf {
+    let extension = source.extension().map(|ext| ext.to_string_lossy().to_ascii_lowercase()).unwrap_or_default();
+    library.join(format!("{}.{}", stem, extension))
+}
+
+// What importing `found` into a library already holding `existing` does: songs matching one
+// there by title and artist or by their recording are left out, and of several copies being
+// imported only the best is kept
+fn plan(found: Vec<Song>, existing: &[Song], library: &Path) -> Vec<Step> {
+    let mut steps = Vec::new();
+    let mut chosen: Vec<Song> = Vec::new();
+    let mut taken_stems: HashMap<String, usize> = HashMap::new();
+    for song in existing {
+        *taken_stems.entry(song.stem().to_lowercase()).or_default() += 1;
+    }
+
+    for song in found {
+        let same = |other: &Song| other.key() == song.key() || (song.audio_hash.is_some() && other.audio_hash == song.audio_hash);
+        if let Some(kept) = existing.iter().find(|other| same(other)) {
+            steps.push(Step::Duplicate { song: song.lyrics, of: kept.lyrics.clone() });
+            continue;
+        }
+        match chosen.iter().position(same) {
+            Some(i) if song.better_than(&chosen[i]) => {
+                let replaced = std::mem::replace(&mut chosen[i], song);
+                steps.push(Step::Duplicate { song: replaced.lyrics, of: chosen[i].lyrics.clone() });
+            }
+            Some(i) => steps.push(Step::Duplicate { song: song.lyrics, of: chosen[i].lyrics.clone() }),
+            None => chosen.push(song),
+        }
+    }
+
+    for song in chosen {
+        // Different songs that happen to get the same file name are numbered
+        let taken = taken_stems.entry(song.stem().to_lowercase()).or_default();
+        *taken += 1;
+        let stem = match *taken {
+            1 => song.stem(),
+            n => format!("{} ({})", song.stem(), n),
+        };
+        let files = [Some(&song.lyrics), song.audio.as_ref(), song.timing.as_ref()]
+            .into_iter()
+            .flatten()
+            .map(|source| (source.clone(), file_in(library, &stem, source)))
+            .collect();
+        steps.push(Step::Import(files));
+    }
+    steps
+}
+
+fn place(source: &Path, target: &Path, link: bool) -> io::Result<()> {
+    if target.exists() {
+        return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("{} already exists", target.display())));
+    }
+    match link {
+        true => std::os::unix::fs::symlink(fs::canonicalize(source)?, target),
+        false => fs::copy(source, target).map(|_| ()),
+    }
+}
+
+fn import(options: &LibraryOptions) -> io::Result<()> {
+    let mut report = Vec::new();
+    let found = scan(&options.dir, true, &mut report)?;
+    let existing = match options.library.is_dir() {
+        true => scan(&options.library, true, &mut Vec::new())?,
+        false => Vec::new(),
+    };
+    let steps = plan(found, &existing, &options.library);
+    if !options.dry_run {
+        fs::create_dir_all(&options.library)?;
+    }
+
+    let (mut imported, mut duplicates) = (0, 0);
+    for step in steps {
+        match step {
+            Step::Duplicate { song, of } => {
+                duplicates += 1;
+                report.push(format!("dup     {} (same song as {})", song.display(), of.display()));
+            }
+            Step::Import(files) => {
+                let placed = match options.dry_run {
+                    true => Ok(()),
+                    false => files.iter().try_for_each(|(source, target)| place(source, target, options.link)),
+                };
+                for (source, target) in &files {
+                    report.push(format!("import  {} -> {}", source.display(), target.display()));
+                }
+                match placed {
+                    Ok(()) => imported += 1,
+                    Err(e) => report.push(format!("failed  {}", e)),
+                }
+            }
+        }
+    }
+
+    for line in &report {
+        println!("{}", line);
+    }
+    let verb = if options.dry_run { "Would import" } else { "Imported" };
+    println!("{} {} songs into {}, {} duplicates left out", verb, imported, options.library.display(), duplicates);
+    Ok(())
+}
+
+// Every song in the library as a bundle in `dir`, with its recording and cover art
+fn export(options: &LibraryOptions) -> io::Result<()> {
+    let mut report = Vec::new();
+    let songs = scan(&options.library, false, &mut report)?;
+    if !options.dry_run {
+        fs::create_dir_all(&options.dir)?;
+    }
+    let mut exported = 0;
+    for song in songs.iter().filter(|song| !bundle::is_bundle(&song.lyrics)) {
+        let output = options.dir.join(format!("{}.{}", song.stem(), bundle::EXTENSION));
+        let cover = ["jpg", "jpeg", "png", "webp"].iter().map(|ext| song.lyrics.with_extension(ext)).find(|path| path.is_file());
+        let extras = bundle::Extras {
+            audio: song.audio.clone(),
+            translation: None,
+            cover,
+        };
+        let written = match options.dry_run {
+            true => Ok(()),
+            false => formats::load(&song.lyrics).and_then(|config| bundle::write(&config, &extras, &output)),
+        };
+        match written {
+            Ok(()) => {
+                exported += 1;
+                report.push(format!("export  {} -> {}", song.lyrics.display(), output.display()));
+            }
+            Err(e) => report.push(format!("failed  {}: {}", song.lyrics.display(), e)),
+        }
+    }
+
+    for line in &report {
+        println!("{}", line);
+    }
+    let verb = if options.dry_run { "Would export" } else { "Exported" };
+    println!("{} {} songs to {}", verb, exported, options.dir.display());
+    Ok(())
+}
+
+pub fn run(options: &LibraryOptions) -> io::Result<()> {
+    match options.action {
+        LibraryAction::Import => import(options),
+        LibraryAction::Export => export(options),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn song(lyrics: &str, artist: &str, title: &str, audio: Option<u8>) -> Song {
+        Song {
+            lyrics: PathBuf::from(lyrics),
+            audio: audio.map(|_| PathBuf::from(lyrics).with_extension("wav")),
+            timing: None,
+            title: title.to_string(),
+            artist: Some(artist.to_string()),
+            word_timed: false,
+            audio_hash: audio.map(|byte| [byte; 16]),
+        }
+    }
+
+    #[test]
+    fn duplicates_are_left_out() {
+        let library = Path::new("lib");
+        let existing = [song("lib/Queen - Bohemian Rhapsody.lrc", "Queen", "Bohemian Rhapsody", None)];
+        let found = vec![
+            song("in/bohemian.lrc", "QUEEN", "Bohemian  Rhapsody!", None),
+            song("in/a/take on me.lrc", "a-ha", "Take On Me", None),
+            song("in/b/take-on-me.lrc", "A-ha", "Take on me", Some(1)),
+            song("in/renamed.lrc", "Someone", "Else", Some(1)),
+            song("in/ac.lrc", "AC/DC", "T.N.T.", None),
+        ];
+        let steps = plan(found, &existing, library);
+        let duplicate = |song: &str, of: &str| Step::Duplicate { song: PathBuf::from(song), of: PathBuf::from(of) };
+        assert_eq!(
+            steps,
+            [
+                duplicate("in/bohemian.lrc", "lib/Queen - Bohemian Rhapsody.lrc"),
+                // The copy with a recording wins, and a song with the same recording is the same song
+                duplicate("in/a/take on me.lrc", "in/b/take-on-me.lrc"),
+                duplicate("in/renamed.lrc", "in/b/take-on-me.lrc"),
+                Step::Import(vec![
+                    (PathBuf::from("in/b/take-on-me.lrc"), PathBuf::from("lib/A-ha - Take on me.lrc")),
+                    (PathBuf::from("in/b/take-on-me.wav"), PathBuf::from("lib/A-ha - Take on me.wav")),
+                ]),
+                Step::Import(vec![(PathBuf::from("in/ac.lrc"), PathBuf::from("lib/AC_DC - T.N.T..lrc"))]),
+            ]
+        );
+    }
+}
@@ -0,0 +1,76 @@
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, LineWriter, Write},
+    path::Path,
+    sync::Mutex,
+    time::Instant,
+};
+
+// Writes log lines to a file, since anything printed would land in the middle of the TUI
+struct FileLogger {
+    started: Instant,
+    file: Mutex<LineWriter<File>>,
+}
+
+fn format_line(seconds: f64, level: Level, target: &str, message: &str) -> String {
+    format!("{:>9.3} {:<5} {}: {}\n", seconds, level, target, message)
+}
+
+impl Log for FileLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let line = format_line(
+            self.started.elapsed().as_secs_f64(),
+            record.level(),
+            record.target(),
+            &record.args().to_string(),
+        );
+        if let Ok(mut file) = self.file.lock() {
+            let _ = file.write_all(line.as_bytes());
+        }
+    }
+
+    fn flush(&self) {
+        if let Ok(mut file) = self.file.lock() {
+            let _ = file.flush();
+        }
+    }
+}
+
+// Appends to `path` for the rest of the run; only the first call takes effect
+pub fn init(path: &Path) -> io::Result<()> {
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| io::Error::new(e.kind(), format!("couldn't open the log {}: {}", path.display(), e)))?;
+    let logger = FileLogger {
+        started: Instant::now(),
+        file: Mutex::new(LineWriter::new(file)),
+    };
+    if log::set_boxed_logger(Box::new(logger)).is_ok() {
+        log::set_max_level(LevelFilter::Debug);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lines_line_up() {
+        assert_eq!(
+            format_line(1.5, Level::Info, "karaoke_lyric", "loaded song"),
+            "    1.500 INFO  karaoke_lyric: loaded song\n"
+        );
+        assert_eq!(format_line(62.0, Level::Debug, "sync", "drift").len(), format_line(0.0, Level::Warn, "sync", "drift").len());
+    }
+}
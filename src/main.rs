@@ -1,67 +1,829 @@
 use crossterm::{
-    event::{self, Event, KeyCode},
+    event::{EnableMouseCapture, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind},
     execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{
-    backend::CrosstermBackend,
-    layout::{Alignment, Constraint, Direction, Layout},
+    backend::Backend,
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::Paragraph,
+    widgets::{Block, Borders, Paragraph},
     Terminal,
 };
 use std::{
-    io,
+    env, io, process,
+    sync::mpsc::Receiver,
     time::{Duration, Instant},
 };
 
+mod align;
+mod announce;
+mod attract;
+mod background;
+mod beats;
+mod bidi;
+mod bigtext;
+mod cast;
+mod celebration;
+mod chords;
+mod cli;
+mod compat;
+mod daemon;
+mod debug;
+mod editor;
+mod events;
+mod fetch;
+mod find;
+mod formats;
+mod highlight;
+mod frame_rate;
+mod headless;
+mod history;
+mod hooks;
+mod layout;
+mod leaderboard;
+mod library;
+mod logging;
+mod matching;
+mod metronome;
+mod mic;
+mod midi;
+mod mixdown;
+mod monitor;
+mod notifications;
+mod now_line;
+mod osc;
+mod panes;
+mod party;
+mod playback;
+mod plugin;
+mod practice;
+mod preview;
+mod profiles;
+mod progress;
+mod providers;
+mod qr;
+mod quiz;
+mod remote;
+mod requests;
+mod repeat;
+mod resume;
+mod scoring;
+mod screen;
+mod scrobble;
+mod search;
+mod server;
+mod setlist;
 mod song_config;
-use song_config::SongConfig;
+mod status;
+mod style;
+mod sync;
+mod teleprompter;
+mod terminal;
+mod time_format;
+pub mod timeline;
+mod toast;
+mod typing;
+mod validate;
+mod vertical;
+mod visualizer;
+mod watch;
+mod wav;
+use beats::BeatGrid;
+use chords::Chord;
+use cli::{Command, OutputMode, PartyOptions, PlayOptions, SetlistAction};
+use events::{AppEvent, EventBus};
+use frame_rate::FramePacer;
+use hooks::HookScript;
+use layout::SpanKind;
+use metronome::Metronome;
+use mic::{MicCapture, MicMeter};
+use panes::Panes;
+use party::PartySession;
+use plugin::{Renderer, SystemClock, TimeSource};
+use practice::PracticeLoop;
+use progress::ProgressBar;
+use remote::{RemoteCommand, RemoteControl};
+use repeat::RepeatMode;
+use scoring::ScoreSheet;
+use screen::Screen;
+use song_config::{SongConfig, Tempo};
+use style::{LineStyle, SongStyle};
+use sync::SyncUpdate;
+use time_format::TimeFormat;
+use toast::Toast;
+use visualizer::Visualizer;
 
-#[derive(Debug, Clone)]
+// A syllable or word with its own timing inside a line
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Segment {
+    pub text: String,
+    pub start_time: f64,
+    pub end_time: f64,
+}
+
+// `text` is always the full line; when `segments` is non-empty it is their concatenation.
+// `singer` tells duet parts apart, 0 being the lead, `style` holds the line's own style hints and
+// `chords` the chord changes sung over the text.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct LyricLine {
     pub text: String,
     pub start_time: f64,
     pub end_time: f64,
+    #[serde(default)]
+    pub segments: Vec<Segment>,
+    #[serde(default)]
+    pub singer: usize,
+    #[serde(default, skip_serializing_if = "LineStyle::is_plain")]
+    pub style: LineStyle,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub chords: Vec<Chord>,
 }
 
 struct KaraokeApp {
     song_title: String,
+    song_artist: Option<String>,
+    song_album: Option<String>,
+    song_key: Option<String>,
     lyrics: Vec<LyricLine>,
-    start_time: Instant,
+    // One per line, from `song_style`, the sections and the line's own hints
+    line_styles: Vec<LineStyle>,
+    line_cursor: timeline::LineCursor,
+    song_style: SongStyle,
+    clock: Box<dyn TimeSource>,
+    start_time: f64,
     paused: bool,
     current_position: f64,
     song_duration: f64,
+    // The length the song file gave, and how long to run on after the last line without one
+    given_duration: Option<f64>,
+    outro: f64,
     seek_resume_at: Option<Instant>,
     paused_before_seek: bool,
+    score: Option<ScoreSheet>,
+    lyric_offset: f64,
+    beats: Option<BeatGrid>,
+    visualizer: Option<Visualizer>,
+    mic: Option<MicMeter>,
+    // How much echo is on the mic in the monitor, when it's on
+    echo: Option<monitor::EchoLevel>,
+    practice: Option<PracticeLoop>,
+    quiz: Option<quiz::Quiz>,
+    // Some while a search of the lyrics is open
+    find: Option<find::LyricFind>,
+    typing: Option<typing::TypingPractice>,
+    sections: Vec<song_config::Section>,
+    song_path: Option<std::path::PathBuf>,
+    // Some while the song file is reloaded whenever it's saved
+    watch: Option<watch::FileWatch>,
+    repeat: RepeatMode,
+    ended_at: Option<Instant>,
+    seek_preview: Option<SeekPreview>,
+    time_format: TimeFormat,
+    // Some while following a `--sync-join` host
+    sync_connected: Option<bool>,
+    big_text: bool,
+    // The bouncing ball over the word being sung
+    ball: bool,
+    // The active line in block letters, the rest at normal size
+    big_line: bool,
+    // The song's lyric window, with the command line's changes kept for the next song
+    window: layout::LyricWindow,
+    window_options: layout::WindowOptions,
+    // Columns read top to bottom, for Chinese and Japanese lyrics
+    vertical: bool,
+    // The lead time of the teleprompter view, when it's used
+    teleprompter: Option<f64>,
+    // Beats don't flash the markers, for singers sensitive to flicker
+    no_blink: bool,
+    // Confetti for a long streak and a high score, unless it's turned off
+    celebration: Option<celebration::Celebration>,
+    no_confetti: bool,
+    streak_celebrated: usize,
+    background: background::Background,
+    highlight: highlight::Highlight,
+    big_font: Option<bigtext::FigFont>,
+    screen: Screen,
+    // A party goes to the attract screen once nothing has been sung for this long
+    idle_after: Option<Duration>,
+    idle_since: Option<Instant>,
+    attract: Option<attract::Attract>,
+    // Where phones find the lyric page, shown as a QR code on the splash
+    page_url: Option<String>,
+    toast: Option<Toast>,
+    show_chords: bool,
+    // Semitones the chords are moved by, and the fret the capo sits on
+    transpose: i32,
+    capo: u8,
+    tempo: Option<Tempo>,
+    // The beat indicator, and the click that goes with it
+    show_beats: bool,
+    metronome: Option<Metronome>,
+    // The song's recording played along with the lyrics, and how loud
+    audio: Option<playback::AudioPlayer>,
+    volume: f64,
+    muted: bool,
+    // The recording keeps going after the lyrics end, for a party to crossfade into the next song
+    play_out: bool,
+    recording_length: Option<f64>,
+    // Who sings each duet part, in party mode
+    singers: Vec<String>,
+    // Singers whose profiles the song's score goes to
+    credited: Vec<String>,
+    // The center channel taken out of the recording, for singing over a normal stereo song
+    no_vocals: bool,
+    count_in: Option<screen::CountIn>,
+    // Picking up mid-song counts down first, as a count-in does
+    resume_countdown: bool,
+    // Some while the debug overlay is up
+    debug: Option<debug::DebugStats>,
+    // How far off the host the last sync correction found this client
+    host_drift: Option<f64>,
+}
+
+// The mouse is hovering over or dragging across the progress bar; the seek happens on release
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct SeekPreview {
+    time: f64,
+    column: u16,
+    dragging: bool,
 }
 
 impl KaraokeApp {
-    fn new() -> Self {
-        let config = SongConfig::load();
-        
-        Self {
+    fn new(options: &PlayOptions) -> Result<Self, io::Error> {
+        let mut config = match &options.song {
+            Some(path) => formats::load(path)?,
+            None => SongConfig::load(),
+        };
+        if let Some(title) = &options.title {
+            config.title = title.clone();
+        }
+        if let Some(artist) = &options.artist {
+            config.artist = Some(artist.clone());
+        }
+        let issues = validate::check(&config).len();
+        if options.fix_timing {
+            validate::fix(&mut config);
+        }
+
+        let score = if let Some(path) = &options.score_recording {
+            Some(ScoreSheet::from_recording(&config.lyrics, &wav::read(path)?))
+        } else if options.tap_score {
+            Some(ScoreSheet::for_taps(config.lyrics.len()))
+        } else {
+            None
+        };
+
+        let beats = match (&options.beats_recording, options.bpm) {
+            (Some(path), bpm) => Some(
+                BeatGrid::detect(&wav::read(path)?, bpm).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?,
+            ),
+            (None, Some(bpm)) => Some(BeatGrid::from_bpm(bpm)),
+            (None, None) => None,
+        };
+
+        let mut app = Self::from_config(config, score);
+        app.song_path = resume::absolute(options.song.as_deref());
+        app.watch = app.song_path.as_deref().filter(|_| options.watch).map(watch::FileWatch::new);
+        match issues {
+            0 => {}
+            _ if options.fix_timing => app.notify(format!("Fixed {} timing problems", issues)),
+            _ => app.notify(format!("{} timing problems - see `karaoke-lyric check`", issues)),
+        }
+        // A tempo given on the command line wins over the song's, but keeps its time signature
+        if let Some(mut beats) = beats {
+            beats.beats_per_bar = app.beats.as_ref().map_or(beats.beats_per_bar, |song| song.beats_per_bar);
+            app.beats = Some(beats);
+        }
+        app.count_in = options.count_in;
+        app.resume_countdown = options.resume_countdown;
+        app.show_beats = options.metronome || options.click_command.is_some();
+        if app.show_beats && app.beats.is_none() {
+            app.notify("The metronome needs a tempo: add one to the song or use --bpm");
+        }
+        app.time_format = options.time_format;
+        app.big_text = options.big_text;
+        app.ball = options.ball;
+        app.big_line = options.big_line;
+        app.window_options = options.window;
+        app.window = options.window.apply(app.window);
+        app.vertical = options.vertical;
+        app.teleprompter = options.teleprompter.then(|| options.lead.unwrap_or(teleprompter::DEFAULT_LEAD));
+        app.no_blink = options.no_blink;
+        app.no_confetti = options.no_confetti;
+        app.background = background::Background::new(options.background, &options.background_colors, options.background_speed.unwrap_or(1.0));
+        app.highlight = highlight::Highlight::new(options.highlight, &options.highlight_colors, options.background);
+        if options.quiz {
+            app.start_quiz();
+        }
+        if options.typing {
+            app.typing = Some(typing::TypingPractice::new(app.lyrics.len()));
+        }
+        // The singer's own key is used unless the command line gives one
+        let profile = options.singer.as_deref().map(profiles::load).transpose()?.flatten();
+        app.transpose = options.transpose.or(profile.as_ref().map(|profile| profile.transpose)).unwrap_or(0);
+        if let Some(message) = profile.as_ref().and_then(profiles::Profile::summary) {
+            app.notify(message);
+        }
+        app.credited = match (&profile, &options.singer) {
+            (Some(profile), _) => vec![profile.name.clone()],
+            (None, singer) => singer.iter().cloned().collect(),
+        };
+        app.capo = options.capo;
+        if let Some(path) = &options.font {
+            app.big_font = Some(bigtext::FigFont::load(path)?);
+        }
+        if let Some(device) = &options.midi_in {
+            app.clock = Box::new(midi::MidiClock::follow(device)?);
+            app.start_time = app.clock.now();
+        }
+        if let Some(outro) = options.outro {
+            app.outro = outro;
+            app.infer_duration();
+        }
+        let recording = match (&options.audio, &options.song) {
+            (Some(path), _) => Some(wav::read(path)?),
+            (None, Some(song)) if formats::bundle::is_bundle(song) => formats::bundle::audio(song)?.map(|bytes| wav::parse(&bytes)).transpose()?,
+            _ => None,
+        };
+        if let Some(recording) = recording {
+            let command = options
+                .play_audio
+                .then(|| playback::command(options.audio_command.as_deref(), options.audio_device.as_deref(), &recording));
+            app.use_recording(recording, command, options.audio_latency.unwrap_or(0.0));
+        }
+        app.warn_about_duration();
+        if let Some(volume) = options.volume {
+            app.set_volume(volume / 100.0);
+        }
+        if options.remove_vocals {
+            app.toggle_vocals();
+        }
+        Ok(app)
+    }
+
+    // The visualizer for the song's recording, and playback of it through `command` if given
+    fn use_recording(&mut self, recording: wav::WavData, command: Option<String>, latency: f64) {
+        self.visualizer = Some(Visualizer::new(&recording));
+        self.recording_length = Some(recording.frames() as f64 / recording.sample_rate.max(1) as f64);
+        self.infer_duration();
+        if let Some(command) = command {
+            let audio = playback::AudioPlayer::start(std::sync::Arc::new(recording), command, latency);
+            audio.set_key(self.transpose);
+            self.audio = Some(audio);
+        }
+    }
+
+    // The recording decides how long the song runs; without one, the song file does, and
+    // without that the last line and an outro after it
+    fn infer_duration(&mut self) {
+        let lyrics_end = self.lyrics.iter().map(|line| line.end_time).fold(0.0, f64::max);
+        self.song_duration = self.recording_length.or(self.given_duration).unwrap_or(lyrics_end + self.outro);
+    }
+
+    // Lengths that don't agree are logged, and the first is shown when the song opens
+    fn warn_about_duration(&mut self) {
+        let warnings = preview::warnings(&self.lyrics, self.given_duration, self.recording_length);
+        for warning in &warnings {
+            log::warn!("{}", warning);
+        }
+        if let Some(warning) = warnings.into_iter().next() {
+            self.notify(warning);
+        }
+    }
+
+    fn from_config(config: SongConfig, score: Option<ScoreSheet>) -> Self {
+        let given_duration = config.given_duration();
+        let mut sections = config.sections;
+        sections.sort_by(|a, b| a.start_time.total_cmp(&b.start_time));
+        let clock: Box<dyn TimeSource> = Box::new(SystemClock::default());
+        // Resolved once here instead of for every visible line on every frame
+        let line_styles = config
+            .lyrics
+            .iter()
+            .map(|line| {
+                let section = timeline::section_index(&sections, line.start_time).map(|i| sections[i].name.as_str());
+                style::resolve(&config.style, section, line)
+            })
+            .collect();
+        let mut app = Self {
             song_title: config.title,
+            song_artist: config.artist,
+            song_album: config.album,
+            song_key: config.key,
             lyrics: config.lyrics,
-            start_time: Instant::now(),
+            line_styles,
+            line_cursor: timeline::LineCursor::default(),
+            song_style: config.style,
+            start_time: clock.now(),
+            clock,
             paused: false,
             current_position: config.start_position,
-            song_duration: config.duration,
+            song_duration: 0.0,
+            given_duration,
+            outro: song_config::DEFAULT_OUTRO,
             seek_resume_at: None,
             paused_before_seek: false,
+            score,
+            lyric_offset: 0.0,
+            beats: config.tempo.as_ref().and_then(BeatGrid::from_tempo),
+            visualizer: None,
+            mic: None,
+            echo: None,
+            practice: None,
+            quiz: None,
+            find: None,
+            typing: None,
+            sections,
+            song_path: None,
+            watch: None,
+            repeat: RepeatMode::Off,
+            ended_at: None,
+            seek_preview: None,
+            time_format: TimeFormat::Auto,
+            sync_connected: None,
+            big_text: false,
+            ball: false,
+            big_line: false,
+            window: config.view.unwrap_or_default(),
+            window_options: layout::WindowOptions::default(),
+            vertical: false,
+            teleprompter: None,
+            no_blink: false,
+            celebration: None,
+            no_confetti: false,
+            streak_celebrated: 0,
+            background: background::Background::default(),
+            highlight: highlight::Highlight::default(),
+            big_font: None,
+            screen: Screen::Playing,
+            idle_after: None,
+            idle_since: None,
+            attract: None,
+            page_url: None,
+            toast: None,
+            show_chords: true,
+            transpose: 0,
+            capo: 0,
+            tempo: config.tempo,
+            show_beats: false,
+            metronome: None,
+            audio: None,
+            volume: 1.0,
+            muted: false,
+            play_out: false,
+            recording_length: None,
+            singers: Vec::new(),
+            credited: Vec::new(),
+            no_vocals: false,
+            count_in: None,
+            resume_countdown: false,
+            debug: None,
+            host_drift: None,
+        };
+        app.infer_duration();
+        log::info!("loaded \"{}\" ({} lines, {:.1}s)", app.song_title, app.lyrics.len(), app.song_duration);
+        app
+    }
+
+    // Times on the playback screen share the song length's layout
+    fn clock_text(&self, seconds: f64) -> String {
+        time_format::format(seconds, self.time_format, self.song_duration)
+    }
+
+    fn restart(&mut self) {
+        self.ended_at = None;
+        self.streak_celebrated = 0;
+        self.current_position = 0.0;
+        self.start_time = self.clock.now();
+        self.paused = false;
+        if let Some(sheet) = &mut self.score {
+            sheet.reset();
+        }
+        if let Some(quiz) = &mut self.quiz {
+            quiz.reset(&mut self.lyrics);
+        }
+        if let Some(typing) = &mut self.typing {
+            typing.reset();
+        }
+    }
+
+    fn open_find(&mut self) {
+        let from = self.focus_line(self.lyric_time()).unwrap_or(0);
+        self.find = Some(find::LyricFind::new(from));
+    }
+
+    // Enter: plays on from the picked line
+    fn jump_to_find(&mut self) {
+        if let Some(line) = self.find.take().and_then(|find| find.selected()) {
+            self.seek_to(self.lyrics[line].start_time - self.lyric_offset);
+        }
+    }
+
+    fn start_quiz(&mut self) {
+        self.quiz = Some(quiz::Quiz::new(&mut self.lyrics, quiz::Quiz::seed()));
+    }
+
+    fn answer_quiz(&mut self) {
+        let time = self.lyric_time();
+        let Some(quiz) = &mut self.quiz else {
+            return;
+        };
+        match quiz.guess(&mut self.lyrics, time) {
+            quiz::Guess::Correct => self.notify("Correct!"),
+            quiz::Guess::Wrong => self.notify("Not quite - try again"),
+            quiz::Guess::NoQuestion => {}
+        }
+    }
+
+    fn tap(&mut self) {
+        let time = self.lyric_time();
+        if let Some(sheet) = &mut self.score {
+            sheet.register_tap(&self.lyrics, time);
+        }
+    }
+
+    // Starts the confetti when a streak reaches a milestone or the song ends on a high score,
+    // and clears it away once it's over
+    fn celebrate(&mut self, now: Instant) {
+        if self.celebration.as_ref().is_some_and(|celebration| celebration.is_over(now)) {
+            self.celebration = None;
+        }
+        let Some(sheet) = self.score.as_ref().filter(|_| !self.no_confetti) else {
+            return;
+        };
+        if self.is_song_ended() {
+            if self.ended_at.is_none() {
+                self.celebration = celebration::Celebration::for_stars(sheet.stars(), now).or(self.celebration.take());
+            }
+            return;
+        }
+        let lyric_time = self.lyric_time();
+        let streak = sheet.streak_through(self.lyrics.partition_point(|line| line.end_time <= lyric_time));
+        if streak < self.streak_celebrated {
+            self.streak_celebrated = 0;
+        }
+        if streak > self.streak_celebrated && streak % celebration::STREAK_MILESTONE == 0 {
+            self.streak_celebrated = streak;
+            self.celebration = Some(celebration::Celebration::milestone(now));
+        }
+    }
+
+    // How strongly the accent markers light up right now
+    fn beat_pulse(&self) -> f64 {
+        match &self.beats {
+            Some(beats) if !self.paused && !self.is_song_ended() && !self.no_blink => beats.pulse(self.get_current_time()),
+            _ => 0.0,
         }
     }
 
+    // Only a practice loop changes the playback speed
+    fn speed(&self) -> f64 {
+        self.practice.as_ref().map_or(1.0, |practice| practice.speed)
+    }
+
     fn get_current_time(&self) -> f64 {
         if self.paused {
             self.current_position
         } else {
-            let time = self.current_position + self.start_time.elapsed().as_secs_f64();
+            let time = self.current_position + (self.clock.now() - self.start_time) * self.speed();
             time.min(self.song_duration)
         }
     }
 
+    // Restarts the clock from the current position, so a speed change only affects what follows
+    fn rebase_clock(&mut self) {
+        self.current_position = self.get_current_time();
+        self.start_time = self.clock.now();
+    }
+
+    // The style a line is drawn in once the song, singer and section styles are applied
+    fn line_style(&self, index: usize) -> &LineStyle {
+        &self.line_styles[index]
+    }
+
+    // The line lookups for the current time; their cursor makes them cheap frame after frame
+    fn active_line(&self, time: f64) -> Option<usize> {
+        self.line_cursor.active_index(&self.lyrics, time)
+    }
+
+    fn focus_line(&self, time: f64) -> Option<usize> {
+        self.line_cursor.focus_index(&self.lyrics, time)
+    }
+
+    // Songs without chords don't get the extra rows
+    fn chords_visible(&self) -> bool {
+        self.show_chords && self.lyrics.iter().any(|line| !line.chords.is_empty())
+    }
+
+    fn toggle_chords(&mut self) {
+        self.show_chords = !self.show_chords;
+        self.notify(if self.show_chords { "Chords on" } else { "Chords off" });
+    }
+
+    // Moves the chords and the recording together, so they stay in the same key
+    fn transpose_by(&mut self, semitones: i32) {
+        if !self.chords_visible() && self.audio.is_none() {
+            return;
+        }
+        self.transpose = (self.transpose + semitones).clamp(-11, 11);
+        if let Some(audio) = &self.audio {
+            audio.set_key(self.transpose);
+        }
+        self.notify(format!("Transpose {:+}", self.transpose));
+    }
+
+    // Chords are shown as shapes to play with the capo on, spelled for the key those shapes are in
+    fn chord_names(&self, line: &LyricLine) -> Vec<String> {
+        let shift = self.transpose - self.capo as i32;
+        let key = self
+            .song_key
+            .clone()
+            .or_else(|| chords::guess_key(self.lyrics.iter().flat_map(|line| &line.chords)));
+        let flats = key.is_some_and(|key| chords::uses_flats(&chords::transpose_key(&key, shift)));
+        line.chords.iter().map(|chord| chords::transpose(&chord.name, shift, flats)).collect()
+    }
+
+    // Key, transposition and capo for the header
+    fn chord_info(&self) -> Vec<String> {
+        let mut info = Vec::new();
+        if let Some(key) = &self.song_key {
+            info.push(format!("Key of {}", chords::transpose_key(key, self.transpose)));
+        }
+        if self.transpose != 0 {
+            info.push(format!("Transpose {:+}", self.transpose));
+        }
+        if self.capo > 0 {
+            info.push(format!("Capo {}", self.capo));
+        }
+        info
+    }
+
+    fn current_section(&self) -> Option<&str> {
+        timeline::section_index(&self.sections, self.lyric_time()).map(|i| self.sections[i].name.as_str())
+    }
+
+    // Sections are in lyric time, so the playback target is shifted back by the offset
+    fn jump_to_section(&mut self, index: Option<usize>) {
+        if let Some(i) = index {
+            self.seek_to(self.sections[i].start_time - self.lyric_offset);
+        }
+    }
+
+    fn next_section(&mut self) {
+        self.jump_to_section(timeline::next_section(&self.sections, self.lyric_time()));
+    }
+
+    fn previous_section(&mut self) {
+        self.jump_to_section(timeline::previous_section(&self.sections, self.lyric_time()));
+    }
+
+    fn resume_from(&mut self, state: resume::ResumeState) {
+        self.current_position = state.position.clamp(0.0, self.song_duration);
+        self.start_time = self.clock.now();
+        self.lyric_offset = state.lyric_offset;
+        self.practice = state.practice;
+    }
+
+    fn resume_state(&self) -> resume::ResumeState {
+        resume::ResumeState {
+            song: self.song_path.clone(),
+            title: self.song_title.clone(),
+            position: self.get_current_time(),
+            lyric_offset: self.lyric_offset,
+            practice: self.practice.clone(),
+        }
+    }
+
+    // How far the end-of-song fade has got, from 0.0 to 1.0, once the song is over
+    fn outro_progress(&self) -> Option<f64> {
+        self.ended_at
+            .map(|at| (at.elapsed().as_secs_f64() / OUTRO_FADE.as_secs_f64()).min(1.0))
+    }
+
+    // The song has been over long enough that the repeat mode should take over
+    fn repeat_due(&self) -> bool {
+        self.repeat != RepeatMode::Off && self.ended_at.is_some_and(|at| at.elapsed() >= repeat::REPEAT_DELAY)
+    }
+
+    fn record_play(&self) -> Result<(), io::Error> {
+        let score = self.score.as_ref().map(ScoreSheet::total_score);
+        history::record_play(self.song_path.as_deref(), &self.song_title, self.is_song_ended(), score)?;
+        match score.filter(|_| self.is_song_ended()) {
+            Some(score) => profiles::record_score(&self.credited, self.song_path.as_deref(), &self.song_title, score),
+            None => Ok(()),
+        }
+    }
+
+    fn toggle_favorite(&mut self) {
+        if self.credited.is_empty() {
+            self.notify("Give a --singer to keep favorites");
+            return;
+        }
+        let message = match profiles::toggle_favorite(&self.credited, self.song_path.as_deref(), &self.song_title) {
+            Ok(true) => format!("Added to {}'s favorites", self.credited.join(" & ")),
+            Ok(false) => format!("Removed from {}'s favorites", self.credited.join(" & ")),
+            Err(e) => format!("Couldn't save the favorite: {}", e),
+        };
+        self.notify(message);
+    }
+
+    fn mark_loop_start(&mut self) {
+        self.rebase_clock();
+        self.practice = Some(PracticeLoop::new(self.current_position));
+        self.notify(format!("Loop start {}", format_time(self.current_position)));
+    }
+
+    fn mark_loop_end(&mut self) {
+        let time = self.get_current_time();
+        let message = match self.practice.as_mut().map(|practice| practice.set_end(time)) {
+            Some(true) => format!("Loop end {}", format_time(time)),
+            Some(false) => "B has to come after A".to_string(),
+            None => "Press A to start a loop first".to_string(),
+        };
+        self.notify(message);
+    }
+
+    fn clear_loop(&mut self) {
+        self.rebase_clock();
+        if self.practice.take().is_some() {
+            self.notify("Loop cleared");
+        }
+    }
+
+    fn adjust_speed(&mut self, delta: f64) {
+        self.rebase_clock();
+        let message = match &mut self.practice {
+            Some(practice) => {
+                practice.adjust_speed(delta);
+                format!("Speed {:.0}%", practice.speed * 100.0)
+            }
+            None => "Speed changes need an A-B loop".to_string(),
+        };
+        self.notify(message);
+    }
+
+    fn toggle_debug(&mut self) {
+        self.debug = match self.debug {
+            Some(_) => None,
+            None => Some(debug::DebugStats::new(self.clock.now(), Instant::now())),
+        };
+    }
+
+    // What the debug overlay shows, worked out the same way the screen does
+    fn debug_rows(&self, stats: &debug::DebugStats) -> Vec<(&'static str, String)> {
+        let time = self.lyric_time();
+        let index = self.active_line(time);
+        let line_progress = index.map_or(0.0, |i| timeline::line_progress(&self.lyrics[i], time));
+        let song_progress = match self.song_duration {
+            duration if duration > 0.0 => (self.get_current_time() / duration).min(1.0),
+            _ => 0.0,
+        };
+        let mut rows = vec![
+            (
+                "Frame",
+                format!("{:.1}ms ({:.0} fps)", stats.frame_time.as_secs_f64() * 1000.0, stats.fps()),
+            ),
+            ("Time", format!("{:.3}s", self.get_current_time())),
+            (
+                "Line",
+                match index {
+                    Some(i) => format!("{} of {}", i + 1, self.lyrics.len()),
+                    None => format!("- of {}", self.lyrics.len()),
+                },
+            ),
+            ("Progress", format!("line {:.0}%, song {:.1}%", line_progress * 100.0, song_progress * 100.0)),
+            ("Drift", format!("{:+.3}s", stats.clock_drift(self.clock.now(), Instant::now()))),
+        ];
+        if let Some(drift) = self.host_drift {
+            rows.push(("Host", format!("{:+.3}s", drift)));
+        }
+        rows
+    }
+
+    fn notify(&mut self, message: impl Into<String>) {
+        self.toast = Some(Toast::new(message, Instant::now()));
+    }
+
+    fn cycle_repeat(&mut self) {
+        self.repeat = self.repeat.cycle();
+        self.notify(match self.repeat {
+            RepeatMode::Off => "Repeat off",
+            RepeatMode::One => "Repeat one",
+            RepeatMode::All => "Repeat all",
+        });
+    }
+
+    // Positive offsets show lyrics earlier than the playback clock
+    fn lyric_time(&self) -> f64 {
+        self.get_current_time() + self.lyric_offset
+    }
+
+    // Paused and staying that way, rather than held for a moment after a seek
+    fn is_paused(&self) -> bool {
+        self.paused && self.seek_resume_at.is_none()
+    }
+
     fn is_song_ended(&self) -> bool {
         self.get_current_time() >= self.song_duration
     }
@@ -73,67 +835,436 @@ impl KaraokeApp {
 
         if self.paused {
             self.paused = false;
-            self.start_time = Instant::now();
+            self.start_time = self.clock.now();
         } else {
-            let now = self.current_position + self.start_time.elapsed().as_secs_f64();
-            self.current_position = now.min(self.song_duration);
+            self.rebase_clock();
             self.paused = true;
         }
     }
 
-    fn get_current_line_index(&self, current_time: f64) -> Option<usize> {
-        for (i, line) in self.lyrics.iter().enumerate() {
-            let adjusted_start = line.start_time;
-            let adjusted_end = line.end_time;
-            if current_time >= adjusted_start && current_time < adjusted_end {
-                return Some(i);
+    // Whether the screen changes on its own right now, so it has to be redrawn at full speed
+    fn is_animating(&self, now: Instant) -> bool {
+        let fading = self.toast.as_ref().is_some_and(|toast| toast.fade(now).is_some())
+            || self.outro_progress().is_some_and(|progress| progress < 1.0)
+            || self.celebration.is_some()
+            || self.attract.is_some();
+        let waiting = matches!(self.screen, Screen::Playing | Screen::Preview { .. });
+        !self.paused || self.seek_resume_at.is_some() || !waiting || fading
+    }
+
+    // The song stays paused behind the splash and starts when it's done
+    fn show_splash(&mut self, countdown: u64) {
+        self.pause();
+        self.screen = Screen::splash(countdown, Instant::now());
+    }
+
+    fn show_preview(&mut self, countdown: Option<u64>) {
+        self.pause();
+        self.screen = Screen::Preview { scroll: 0, countdown };
+    }
+
+    fn scroll_preview(&mut self, by: isize) {
+        if let Screen::Preview { scroll, .. } = &mut self.screen {
+            *scroll = scroll.saturating_add_signed(by).min(self.lyrics.len());
+        }
+    }
+
+    fn leave_preview(&mut self) {
+        match self.screen {
+            Screen::Preview { countdown: Some(countdown), .. } => self.show_splash(countdown),
+            _ => self.start_after_splash(),
+        }
+    }
+
+    // Passes each duet part to the next singer
+    fn swap_parts(&mut self) {
+        if self.singers.len() > 1 {
+            self.singers.rotate_left(1);
+        }
+    }
+
+    // A mic check still waits for Space after the splash; a count-in was started by it
+    fn start_after_splash(&mut self) {
+        let counted_in = matches!(self.screen, Screen::CountIn { .. });
+        self.screen = Screen::Playing;
+        if self.mic.is_none() || counted_in {
+            self.play();
+        }
+    }
+
+    // Space: pauses, or plays once the count-in has run
+    fn press_play(&mut self) {
+        let resuming = self.resume_countdown && self.get_current_time() > 0.0;
+        let count_in = self.count_in.or(resuming.then_some(screen::CountIn::Seconds(screen::DEFAULT_COUNTDOWN)));
+        let Some(count_in) = count_in.filter(|_| self.paused && !self.is_song_ended()) else {
+            self.toggle_pause();
+            return;
+        };
+        let (beats, beat_length) = match count_in {
+            screen::CountIn::Seconds(seconds) => (seconds, Duration::from_secs(1)),
+            screen::CountIn::Beats(beats) => {
+                let period = self.beats.as_ref().map_or(1.0, |grid| grid.period);
+                (beats, Duration::from_secs_f64(period))
+            }
+        };
+        self.screen = Screen::count_in(beats, beat_length, Instant::now());
+    }
+
+    // Advances time-driven state; called on every tick
+    fn tick(&mut self) {
+        if self.screen.is_finished(Instant::now()) {
+            self.start_after_splash();
+        }
+
+        let loop_start = self.practice.as_ref().and_then(|practice| practice.wrap(self.get_current_time()));
+        if let Some(start) = loop_start {
+            self.current_position = start;
+            self.start_time = self.clock.now();
+        }
+
+        let time = self.get_current_time();
+        let clicking = !self.paused && !self.is_song_ended() && self.screen == Screen::Playing;
+        let speed = self.speed();
+        let playing_out = self.play_out && self.is_song_ended();
+        if let (Some(audio), false) = (&mut self.audio, playing_out) {
+            audio.update(time, clicking, speed);
+        }
+        if let (Some(metronome), Some(beats), true) = (&mut self.metronome, &self.beats, clicking) {
+            let beat = beats.beat(time);
+            if beat >= 0 {
+                metronome.update(beat, beats.beat_in_bar(beat) == 0);
+            }
+        }
+        // Count-in beats click too, numbered below zero so they never clash with the song's
+        if let (Some(metronome), Screen::CountIn { beats, .. }) = (&mut self.metronome, self.screen) {
+            if let Some(left) = self.screen.countdown_left(Instant::now()) {
+                metronome.update(-(left as i64), left == beats);
             }
         }
-        
-        if current_time >= self.lyrics.last().unwrap().end_time {
-            return Some(self.lyrics.len() - 1);
+
+        let lyric_time = self.lyric_time();
+        if let Some(missed) = self.quiz.as_mut().and_then(|quiz| quiz.update(&mut self.lyrics, lyric_time)) {
+            self.notify(format!("The word was \"{}\"", missed));
+        }
+
+        if self.is_song_ended() && !self.paused {
+            self.paused = true;
+            self.current_position = self.song_duration;
+        }
+        self.celebrate(Instant::now());
+        if self.is_song_ended() {
+            self.ended_at.get_or_insert_with(Instant::now);
+        } else {
+            self.ended_at = None;
         }
-        
-        for i in (0..self.lyrics.len()).rev() {
-            let adjusted_end = self.lyrics[i].end_time;
-            if current_time >= adjusted_end {
-                return Some(i);
+
+        self.update_idle(Instant::now());
+
+        if let Some(resume_at) = self.seek_resume_at {
+            if Instant::now() >= resume_at {
+                self.paused = false;
+                self.start_time = self.clock.now();
+                self.seek_resume_at = None;
             }
         }
-        
-        None
     }
 
-    fn get_line_progress(&self, current_time: f64, line_idx: usize) -> f64 {
-        if line_idx >= self.lyrics.len() {
-            return 0.0;
+    // Sitting paused, on the results or before the first Space, counts as idle; playing or a
+    // splash counting down doesn't
+    fn update_idle(&mut self, now: Instant) {
+        let waiting = self.paused && matches!(self.screen, Screen::Playing | Screen::Preview { .. });
+        let Some(idle_after) = self.idle_after.filter(|_| waiting) else {
+            self.idle_since = None;
+            self.attract = None;
+            return;
+        };
+        let since = *self.idle_since.get_or_insert(now);
+        if self.attract.is_none() && now.duration_since(since) >= idle_after {
+            self.attract = Some(attract::Attract::new(now));
+        }
+    }
+
+    // A key press puts the attract screen away and starts the wait for it over
+    fn wake(&mut self) -> bool {
+        self.idle_since = self.idle_since.map(|_| Instant::now());
+        self.attract.take().is_some()
+    }
+
+    fn seek_to(&mut self, position: f64) {
+        self.seek(position - self.get_current_time());
+    }
+
+    fn play(&mut self) {
+        if self.paused {
+            self.toggle_pause();
+        }
+    }
+
+    fn pause(&mut self) {
+        if !self.paused {
+            self.toggle_pause();
+        }
+    }
+
+    fn set_volume(&mut self, volume: f64) {
+        self.volume = ((volume * 100.0).round() / 100.0).clamp(0.0, 1.0);
+        let heard = if self.muted { 0.0 } else { self.volume };
+        if let Some(audio) = &self.audio {
+            audio.set_volume(heard);
+        }
+    }
+
+    fn adjust_volume(&mut self, delta: f64) {
+        self.muted = false;
+        self.set_volume(self.volume + delta);
+        self.notify(format!("Volume {:.0}%", self.volume * 100.0));
+    }
+
+    fn toggle_mute(&mut self) {
+        self.muted = !self.muted;
+        self.set_volume(self.volume);
+        self.notify(if self.muted { "Muted" } else { "Sound on" });
+    }
+
+    // Steps the mic's echo up, back to dry after the wettest
+    fn step_echo(&mut self) {
+        let Some(echo) = &self.echo else {
+            return;
+        };
+        let next = match echo.get() + monitor::ECHO_STEP {
+            level if level > 0.8 + 1e-6 => 0.0,
+            level => (level * 10.0).round() / 10.0,
+        };
+        echo.set(next);
+        self.notify(format!("Echo {:.0}%", next * 100.0));
+    }
+
+    fn toggle_vocals(&mut self) {
+        let Some(audio) = &self.audio else {
+            return;
+        };
+        if !audio.remove_vocals(!self.no_vocals) {
+            self.notify("Vocals can only be taken out of a stereo recording");
+            return;
         }
-        let line = &self.lyrics[line_idx];
-        let adjusted_start = line.start_time;
-        let adjusted_end = line.end_time;
-        
-        if current_time < adjusted_start {
-            return 0.0;
+        self.no_vocals = !self.no_vocals;
+        self.notify(if self.no_vocals { "Vocals removed" } else { "Vocals back" });
+    }
+
+    fn adjust_offset(&mut self, delta: f64) {
+        self.lyric_offset = ((self.lyric_offset + delta) * 10.0).round() / 10.0;
+        self.notify(format!("Offset {:+.1}s", self.lyric_offset));
+    }
+
+    // The offset moves into the lyric file, and so out of the timing here, so nothing that
+    // reloads the song counts it twice
+    fn save_offset(&mut self) {
+        let Some(path) = self.song_path.clone() else {
+            self.notify("No lyric file to save the offset to");
+            return;
+        };
+        match formats::save_lrc_offset(&path, self.lyric_offset) {
+            Ok(milliseconds) => {
+                formats::shift(&mut self.lyrics, -self.lyric_offset);
+                self.lyric_offset = 0.0;
+                self.line_cursor = timeline::LineCursor::default();
+                self.notify(format!("Saved [offset:{:+}] to {}", milliseconds, path.display()));
+            }
+            Err(e) => {
+                log::warn!("couldn't save the offset to {}: {}", path.display(), e);
+                self.notify(format!("Offset not saved: {}", e));
+            }
         }
-        if current_time >= adjusted_end {
-            return 1.0;
+    }
+
+    fn load_song(&mut self, path: &std::path::Path) -> Result<(), io::Error> {
+        self.load_config(formats::load(path)?);
+        self.song_path = resume::absolute(Some(path));
+        if let (Some(watch), Some(song)) = (&mut self.watch, &self.song_path) {
+            if watch.song() != song {
+                *watch = watch::FileWatch::new(song);
+            }
         }
-        (current_time - adjusted_start) / (adjusted_end - adjusted_start)
+        Ok(())
     }
 
-    fn is_line_completed(&self, current_time: f64, line_idx: usize) -> bool {
-        if line_idx >= self.lyrics.len() {
+    fn reload_if_saved(&mut self) -> bool {
+        self.watch.as_mut().is_some_and(|watch| watch.changed(Instant::now())) && self.reload_song()
+    }
+
+    // The song file was saved: the new lyrics pick up where the old ones were, and the recording
+    // and everything set up for it carry on as they were
+    fn reload_song(&mut self) -> bool {
+        let Some(path) = self.song_path.clone() else {
             return false;
+        };
+        let config = match formats::load(&path) {
+            Ok(config) => config,
+            Err(e) => {
+                // Likely saved half way through an edit; the next save tries again
+                log::warn!("couldn't reload {}: {}", path.display(), e);
+                self.notify(format!("Not reloaded: {}", e));
+                return false;
+            }
+        };
+        let position = self.get_current_time();
+        let paused = self.paused;
+        let screen = self.screen;
+        let beats = self.beats.take();
+        let practice = self.practice.take();
+        let audio = self.audio.take();
+        let visualizer = self.visualizer.take();
+        let recording_length = self.recording_length;
+        let volume = self.volume;
+        let muted = self.muted;
+        let no_vocals = self.no_vocals;
+        let transpose = self.transpose;
+        let singers = std::mem::take(&mut self.singers);
+        let play_out = self.play_out;
+        self.load_config(config);
+        if self.beats.is_none() {
+            self.beats = beats;
+        }
+        self.practice = practice;
+        self.audio = audio;
+        self.visualizer = visualizer;
+        self.recording_length = recording_length;
+        self.volume = volume;
+        self.muted = muted;
+        self.no_vocals = no_vocals;
+        self.transpose = transpose;
+        self.singers = singers;
+        self.play_out = play_out;
+        self.infer_duration();
+        self.screen = screen;
+        self.current_position = position.min(self.song_duration);
+        self.start_time = self.clock.now();
+        self.paused = paused;
+        log::info!("reloaded {} at {:.1}s", path.display(), self.current_position);
+        self.notify(format!("Reloaded {}", path.file_name().unwrap_or_default().to_string_lossy()));
+        true
+    }
+
+    fn load_config(&mut self, config: SongConfig) {
+        // A recording only matches the song it was made for, so just tap scoring carries over
+        let score = match &self.score {
+            Some(sheet) if sheet.accepts_taps() => Some(ScoreSheet::for_taps(config.lyrics.len())),
+            _ => None,
+        };
+        let lyric_offset = self.lyric_offset;
+        let mic = self.mic.take();
+        let echo = self.echo.take();
+        let watch = self.watch.take();
+        let repeat = self.repeat;
+        let time_format = self.time_format;
+        let sync_connected = self.sync_connected;
+        let big_text = self.big_text;
+        let ball = self.ball;
+        let big_line = self.big_line;
+        let window_options = self.window_options;
+        let vertical = self.vertical;
+        let teleprompter = self.teleprompter;
+        let no_blink = self.no_blink;
+        let no_confetti = self.no_confetti;
+        let idle_after = self.idle_after;
+        let page_url = self.page_url.take();
+        let background = std::mem::take(&mut self.background);
+        let highlight = std::mem::take(&mut self.highlight);
+        let outro = self.outro;
+        let quiz = self.quiz.is_some();
+        let typing = self.typing.is_some();
+        let big_font = self.big_font.take();
+        let show_chords = self.show_chords;
+        let capo = self.capo;
+        let show_beats = self.show_beats;
+        let metronome = self.metronome.take();
+        let count_in = self.count_in;
+        let resume_countdown = self.resume_countdown;
+        let credited = std::mem::take(&mut self.credited);
+        let debug = self.debug.take();
+        let clock = std::mem::replace(&mut self.clock, Box::new(SystemClock::default()));
+        *self = Self::from_config(config, score);
+        self.time_format = time_format;
+        self.clock = clock;
+        self.start_time = self.clock.now();
+        self.lyric_offset = lyric_offset;
+        self.mic = mic;
+        self.echo = echo;
+        self.watch = watch;
+        self.repeat = repeat;
+        self.sync_connected = sync_connected;
+        self.big_text = big_text;
+        self.ball = ball;
+        self.big_line = big_line;
+        self.window_options = window_options;
+        self.window = window_options.apply(self.window);
+        self.vertical = vertical;
+        self.teleprompter = teleprompter;
+        self.no_blink = no_blink;
+        self.no_confetti = no_confetti;
+        self.idle_after = idle_after;
+        self.page_url = page_url;
+        self.background = background;
+        self.highlight = highlight;
+        self.outro = outro;
+        self.infer_duration();
+        if quiz {
+            self.start_quiz();
+        }
+        if typing {
+            self.typing = Some(typing::TypingPractice::new(self.lyrics.len()));
+        }
+        self.big_font = big_font;
+        self.show_chords = show_chords;
+        self.capo = capo;
+        self.show_beats = show_beats;
+        self.metronome = metronome;
+        self.count_in = count_in;
+        self.resume_countdown = resume_countdown;
+        self.credited = credited;
+        self.debug = debug;
+    }
+
+    // What `--sync-join` clients need to show this song
+    fn song_config(&self) -> SongConfig {
+        SongConfig {
+            title: self.song_title.clone(),
+            artist: self.song_artist.clone(),
+            album: self.song_album.clone(),
+            key: self.song_key.clone(),
+            duration: self.song_duration,
+            start_position: 0.0,
+            lyrics: self.lyrics.clone(),
+            sections: self.sections.clone(),
+            style: self.song_style.clone(),
+            tempo: self.tempo.clone(),
+            view: (!self.window.is_default()).then_some(self.window),
+        }
+    }
+
+    // Small drift is left alone so the display doesn't stutter on every heartbeat
+    fn follow_host(&mut self, position: f64, paused: bool) {
+        let drift = self.get_current_time() - position;
+        self.host_drift = Some(drift);
+        if paused == self.paused && drift.abs() < 0.05 {
+            return;
+        }
+        log::debug!("following the host to {:.3}s (drift {:+.3}s, paused {})", position, drift, paused);
+        self.current_position = position.clamp(0.0, self.song_duration);
+        self.start_time = self.clock.now();
+        self.paused = paused;
+        self.seek_resume_at = None;
+        if position < self.song_duration {
+            self.ended_at = None;
         }
-        let line = &self.lyrics[line_idx];
-        current_time >= line.end_time
     }
 
     fn seek(&mut self, delta: f64) {
         let new_pos = (self.get_current_time() + delta).clamp(0.0, self.song_duration);
+        log::debug!("seek from {:.3}s to {:.3}s", self.get_current_time(), new_pos);
         self.paused_before_seek = self.paused || self.seek_resume_at.is_some();
         self.current_position = new_pos;
-        self.start_time = Instant::now();
+        self.start_time = self.clock.now();
         if new_pos >= self.song_duration {
             self.paused = true;
             self.seek_resume_at = None;
@@ -146,255 +1277,1627 @@ impl KaraokeApp {
     }
 }
 
-fn render_lyric_content(text: &str, progress: f64, is_active: bool, is_completed: bool) -> Vec<Span> {
-    if is_active {
-        let chars: Vec<char> = text.chars().collect();
-        let split_pos = (chars.len() as f64 * progress) as usize;
-
-        let sung_part: String = chars.iter().take(split_pos).collect();
-        let unsung_part: String = chars.iter().skip(split_pos).collect();
+const OUTRO_FADE: Duration = Duration::from_secs(2);
+// Columns the splash keeps for the title when a QR code is beside it
+const SPLASH_TITLE_WIDTH: usize = 30;
+// How far a paused screen is faded towards the background
+const PAUSE_DIM: f64 = 0.6;
+const PAUSED_BOX: (u16, u16) = (14, 3);
+const BACKGROUND: (u8, u8, u8) = (20, 24, 40);
 
-        let mut spans = Vec::new();
-
-        if !sung_part.is_empty() {
-            spans.push(Span::styled(
-                sung_part,
-                Style::default().fg(Color::Rgb(0, 255, 0)).add_modifier(Modifier::BOLD) // Green color for sung/completed lyrics
-            ));
-        }
+// Blends a text color into the background; 1.0 makes it disappear completely
+fn fade_color(color: Color, amount: f64) -> Color {
+    let (r, g, b) = match color {
+        Color::Rgb(r, g, b) => (r, g, b),
+        Color::Red => (255, 0, 0),
+        Color::Magenta => (255, 0, 255),
+        Color::Yellow => (255, 255, 0),
+        _ => (255, 255, 255),
+    };
+    let mix = |from: u8, to: u8| (from as f64 + (to as f64 - from as f64) * amount).round() as u8;
+    Color::Rgb(mix(r, BACKGROUND.0), mix(g, BACKGROUND.1), mix(b, BACKGROUND.2))
+}
 
-        if !unsung_part.is_empty() {
-            spans.push(Span::styled(
-                unsung_part,
-                Style::default()
-                    .fg(Color::White) // White color for unsung part of current line
-                    .add_modifier(Modifier::BOLD) 
-            ));
+// Dims whatever is under it, then says so in a box in the middle
+fn render_paused(f: &mut ratatui::Frame, area: Rect) {
+    let buf = f.buffer_mut();
+    for y in area.top()..area.bottom() {
+        for x in area.left()..area.right() {
+            let cell = buf.get_mut(x, y);
+            cell.fg = fade_color(cell.fg, PAUSE_DIM);
+            cell.bg = background::blend(cell.bg, Color::Rgb(0, 0, 0), PAUSE_DIM);
         }
-
-        spans
-    } else if is_completed {
-        vec![Span::styled(
-            text,
-            Style::default().fg(Color::Rgb(0, 255, 0)) // Green color for completed lines
-        )]
-    } else {
-        vec![Span::styled(
-            text,
-            Style::default().fg(Color::White) // White color for upcoming/unplayed lines
-        )]
     }
+    let (width, height) = (PAUSED_BOX.0.min(area.width), PAUSED_BOX.1.min(area.height));
+    let middle = Rect::new(area.x + (area.width - width) / 2, area.y + (area.height - height) / 2, width, height);
+    let widget = Paragraph::new("PAUSED")
+        .style(Style::default().fg(Color::White).bg(Color::Rgb(40, 44, 64)).add_modifier(Modifier::BOLD))
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(Color::Rgb(0, 200, 255))));
+    f.render_widget(widget, middle);
 }
 
-fn create_progress_bar(progress: f64, width: usize) -> Line<'static> {
-    let total_sub_blocks = (width * 100) as f64;
-    let filled_sub_blocks = (total_sub_blocks * progress) as usize;
-    
-    let dot_position = ((width as f64 * progress) as usize).min(width.saturating_sub(1));
-    
-    let mut spans = Vec::new();
-    
-    for i in 0..width {
-        let start_block = i * 100;
-        let blocks_in_this_char = filled_sub_blocks.saturating_sub(start_block).min(100);
-        
-        if i == dot_position {
-            spans.push(Span::styled(
-                "●",
-                Style::default().fg(Color::White).add_modifier(Modifier::BOLD)
-            ));
-        } else {
-            let color = if blocks_in_this_char > 0 {
-                Color::White // White for played portion
-            } else {
-                Color::Rgb(80, 80, 80) // Gray for unplayed portion of progress bar
-            };
-            
-            spans.push(Span::styled(
-                "━",
-                Style::default().fg(color)
-            ));
-        }
+fn fade_line(mut line: Line<'_>, amount: f64) -> Line<'_> {
+    for span in &mut line.spans {
+        span.style = span.style.fg(fade_color(span.style.fg.unwrap_or(Color::White), amount));
     }
-    
-    Line::from(spans)
+    line
 }
 
-fn format_time(seconds: f64) -> String {
-    let mins = (seconds as i32) / 60;
-    let secs = (seconds as i32) % 60;
-    format!("{}:{:02}", mins, secs)
+fn next_up_line(party: Option<&PartySession>) -> Option<Line<'static>> {
+    let (song, singers) = party?.next_up()?;
+    Some(Line::from(Span::styled(
+        format!("Next up: {} - {}", song, singers),
+        Style::default().fg(Color::Rgb(0, 200, 255)),
+    )))
 }
 
-fn ui(f: &mut ratatui::Frame, app: &KaraokeApp) {
-    let size = f.size();
-    
-    let chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([
+// The singers in the order of the parts they sing, once a duet's parts have been handed out
+fn singing(app: &KaraokeApp, session: &PartySession) -> String {
+    match app.singers.is_empty() {
+        true => session.singer_names(),
+        false => app.singers.join(" & "),
+    }
+}
+
+// Everything to check before singing: warnings about the song's length, who sings which part,
+// and every line with its start time
+fn render_preview(f: &mut ratatui::Frame, area: Rect, app: &KaraokeApp) {
+    let Screen::Preview { scroll, .. } = app.screen else {
+        return;
+    };
+    let mut lines = vec![Line::styled(app.song_title.clone(), Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD))];
+    for warning in preview::warnings(&app.lyrics, app.given_duration, app.recording_length) {
+        lines.push(Line::styled(format!("⚠ {}", warning), Style::default().fg(Color::Yellow)));
+    }
+    let parts = app.lyrics.iter().map(|line| line.singer + 1).max().unwrap_or(0);
+    if parts > 1 {
+        for part in 0..parts {
+            let count = app.lyrics.iter().filter(|line| line.singer == part).count();
+            let singer = app.singers.get(part).map_or(String::new(), |name| format!(": {}", name));
+            let style = Style::default().fg(SINGER_COLORS[part % SINGER_COLORS.len()]);
+            lines.push(Line::styled(format!("Part {}{} ({} lines)", part + 1, singer, count), style));
+        }
+        if app.singers.len() > 1 {
+            lines.push(Line::styled("S to swap parts", Style::default().fg(Color::DarkGray)));
+        }
+    }
+    lines.push(Line::default());
+    for line in &app.lyrics {
+        let time = time_format::format(line.start_time, app.time_format, app.song_duration);
+        let color = SINGER_COLORS[line.singer % SINGER_COLORS.len()];
+        lines.push(Line::from(vec![
+            Span::styled(format!("{}  ", time), Style::default().fg(Color::DarkGray)),
+            Span::styled(line.text.clone(), Style::default().fg(color)),
+        ]));
+    }
+
+    let widget = Paragraph::new(lines)
+        .scroll((scroll.min(u16::MAX as usize) as u16, 0));
+    f.render_widget(widget, area);
+}
+
+// The song title in big letters with the artist and album under it, then a countdown to the
+// first line. A count-in shows the line that's coming up instead of the title.
+fn render_splash(f: &mut ratatui::Frame, area: Rect, app: &KaraokeApp, party: Option<&PartySession>) {
+    // The request page or the lyric page goes beside the title when there's room for it
+    let url = party.and_then(|session| session.request_page.as_deref()).or(app.page_url.as_deref());
+    let area = match url.and_then(qr::lines) {
+        Some(code) if area.width as usize >= code_width(&code) + SPLASH_TITLE_WIDTH && area.height as usize >= code.len() => {
+            let panes = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Min(0), Constraint::Length(code_width(&code) as u16 + 2)])
+                .split(area);
+            let padding = (area.height as usize - code.len()) / 2;
+            let lines: Vec<Line> = std::iter::repeat_n(Line::default(), padding).chain(code).collect();
+            f.render_widget(Paragraph::new(lines).alignment(Alignment::Center), panes[1]);
+            panes[0]
+        }
+        _ => area,
+    };
+    let countdown = app.screen.countdown_left(Instant::now()).map(|n| n.to_string()).unwrap_or_default();
+    let lyric_time = app.lyric_time();
+    let coming_up = match app.screen {
+        Screen::CountIn { .. } => app.lyrics.iter().find(|line| line.end_time > lyric_time).map(|line| line.text.as_str()),
+        _ => None,
+    };
+    let heading = coming_up.unwrap_or(&app.song_title);
+    let credits: Vec<&str> = [&app.song_artist, &app.song_album].into_iter().flatten().map(String::as_str).collect();
+    let credits = (coming_up.is_none() && !credits.is_empty()).then(|| credits.join(" · "));
+    let subtitle = party.map(|session| format!("{} singing", singing(app, session)));
+    let title_style = Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD);
+    let count_style = Style::default().fg(Color::White).add_modifier(Modifier::BOLD);
+
+    let credits: Vec<Line> = credits.map(|credits| Line::styled(credits, Style::default().fg(Color::White))).into_iter().collect();
+    let height = (area.height as usize).saturating_sub(2 + credits.len());
+    let mut lines: Vec<Line> = match bigtext::layout(app.big_font.as_ref(), heading, &countdown, area.width as usize, height) {
+        Some(big) => {
+            let gap = usize::from(!big.next.is_empty()) * 2;
+            big.current
+                .into_iter()
+                .map(|row| Line::styled(row, title_style))
+                .chain(credits)
+                .chain(std::iter::repeat_n(Line::default(), gap))
+                .chain(big.next.into_iter().map(|row| Line::styled(row, count_style)))
+                .collect()
+        }
+        None => std::iter::once(Line::styled(heading.to_string(), title_style))
+            .chain(credits)
+            .chain([Line::default(), Line::styled(countdown, count_style)])
+            .collect(),
+    };
+    if let Some(subtitle) = subtitle {
+        lines.push(Line::default());
+        lines.push(Line::styled(subtitle, Style::default().fg(Color::Rgb(0, 200, 255))));
+    }
+
+    let padding = (area.height as usize).saturating_sub(lines.len()) / 2;
+    let lines: Vec<Line> = std::iter::repeat_n(Line::default(), padding).chain(lines).collect();
+    let widget = Paragraph::new(lines).alignment(Alignment::Center);
+    f.render_widget(widget, area);
+}
+
+fn code_width(code: &[Line]) -> usize {
+    code.iter().map(Line::width).max().unwrap_or(0)
+}
+
+// Songs without lyrics: the visualizer when there is one, otherwise a quiet card
+fn render_instrumental(f: &mut ratatui::Frame, area: Rect, app: &KaraokeApp, fade: f64) {
+    if let Some(visualizer) = app.visualizer.as_ref().filter(|visualizer| visualizer.is_visible()) {
+        visualizer::render(f, area, visualizer, app.get_current_time());
+        return;
+    }
+    let padding = area.height.saturating_sub(1) / 2;
+    let lines: Vec<Line> = std::iter::repeat_n(Line::default(), padding as usize)
+        .chain(std::iter::once(fade_line(
+            Line::styled("♪ Instrumental ♪", Style::default().fg(Color::Rgb(0, 200, 255))),
+            fade,
+        )))
+        .collect();
+    let widget = Paragraph::new(lines).alignment(Alignment::Center);
+    f.render_widget(widget, area);
+}
+
+// Projector mode: the current and next line as large as the screen allows
+fn render_big_text(f: &mut ratatui::Frame, area: Rect, app: &KaraokeApp, fade: f64) {
+    let time = app.lyric_time();
+    let (current, next) = match app.active_line(time) {
+        Some(i) => (Some(i), Some(i + 1)),
+        // Between lines the upcoming one waits on its own
+        None => (None, Some(app.lyrics.partition_point(|line| line.start_time <= time))),
+    };
+    let text = |index: Option<usize>| index.and_then(|i| app.lyrics.get(i)).map_or("".into(), |line| bidi::visual(&line.text));
+    let singer = current.map_or(0, |i| app.lyrics[i].singer);
+    let color = current
+        .and_then(|i| app.line_style(i).color())
+        .unwrap_or(SINGER_COLORS[singer % SINGER_COLORS.len()]);
+    let current_style = Style::default().fg(fade_color(color, fade));
+    let next_style = Style::default().fg(fade_color(Color::Rgb(120, 120, 140), fade));
+
+    let width = area.width as usize;
+    let height = area.height as usize;
+    let (current_text, next_text) = (text(current), text(next));
+    let lines: Vec<Line> = match bigtext::layout(app.big_font.as_ref(), &current_text, &next_text, width, height) {
+        Some(big) => {
+            let gap = usize::from(!big.current.is_empty() && !big.next.is_empty()) * 2;
+            let padding = height.saturating_sub(big.current.len() + gap + big.next.len()) / 2;
+            std::iter::repeat_n(Line::default(), padding)
+                .chain(big.current.into_iter().map(|row| Line::styled(row, current_style)))
+                .chain(std::iter::repeat_n(Line::default(), gap))
+                .chain(big.next.into_iter().map(|row| Line::styled(row, next_style)))
+                .collect()
+        }
+        // Text the font can't show stays readable at normal size
+        None => {
+            let padding = height.saturating_sub(3) / 2;
+            std::iter::repeat_n(Line::default(), padding)
+                .chain([
+                    Line::styled(current_text.to_string(), current_style.add_modifier(Modifier::BOLD)),
+                    Line::default(),
+                    Line::styled(next_text.to_string(), next_style),
+                ])
+                .collect()
+        }
+    };
+
+    let widget = Paragraph::new(lines).alignment(Alignment::Center);
+    f.render_widget(widget, area);
+}
+
+// The current line takes the top two thirds of the screen and the next line the rest, each as
+// big as it fits
+fn render_teleprompter(f: &mut ratatui::Frame, area: Rect, app: &KaraokeApp, fade: f64) {
+    let lead = app.teleprompter.unwrap_or(teleprompter::DEFAULT_LEAD);
+    let prompt = teleprompter::prompt(app, app.lyric_time(), lead);
+    let text = |index: Option<usize>| index.map_or("".into(), |i| bidi::visual(&app.lyrics[i].text));
+    let singer = prompt.current.map_or(0, |i| app.lyrics[i].singer);
+    let color = prompt
+        .current
+        .and_then(|i| app.line_style(i).color())
+        .unwrap_or(SINGER_COLORS[singer % SINGER_COLORS.len()]);
+    // A line shown early is dimmed until it starts
+    let current_color = match prompt.starts_in {
+        Some(_) => fade_color(color, 0.5 + fade / 2.0),
+        None => fade_color(color, fade),
+    };
+    let current_style = Style::default().fg(current_color).add_modifier(Modifier::BOLD);
+    let next_style = Style::default().fg(fade_color(Color::Rgb(120, 120, 140), fade));
+
+    let width = area.width as usize;
+    let height = area.height as usize;
+    let (current_text, next_text) = (text(prompt.current), text(prompt.next));
+    let big = |text: &str, rows: usize, style: Style| -> Vec<Line<'static>> {
+        match bigtext::fit(app.big_font.as_ref(), text, width, rows) {
+            Some(rows) => rows.into_iter().map(|row| Line::styled(row, style)).collect(),
+            // Text the font can't show stays readable at normal size
+            None => vec![Line::styled(text.to_string(), style)],
+        }
+    };
+
+    let current_rows = height * 2 / 3;
+    let mut current = big(&current_text, current_rows.saturating_sub(1), current_style);
+    if let Some(seconds) = prompt.starts_in {
+        current.push(Line::styled(format!("in {:.0}s", seconds.ceil()), next_style));
+    }
+    let mut lines = vec![Line::default(); current_rows.saturating_sub(current.len()) / 2];
+    lines.extend(current);
+    lines.resize(current_rows, Line::default());
+    lines.extend(big(&next_text, height - current_rows, next_style));
+
+    let widget = Paragraph::new(lines).alignment(Alignment::Center);
+    f.render_widget(widget, area);
+}
+
+fn render_outro(f: &mut ratatui::Frame, area: Rect, app: &KaraokeApp, party: Option<&PartySession>) {
+    let top = (area.height as usize).saturating_sub(5) / 2;
+    let mut lines = vec![Line::from(""); top];
+    lines.push(Line::from(Span::styled(
+        "♫ Thanks for singing ♫",
+        Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD),
+    )));
+    lines.push(Line::from(Span::styled(
+        app.song_title.clone(),
+        Style::default().fg(Color::White).add_modifier(Modifier::BOLD),
+    )));
+    if let Some(quiz) = &app.quiz {
+        let (correct, asked) = quiz.score();
+        lines.push(Line::from(Span::styled(
+            format!("Quiz: {} of {} words", correct, asked),
+            Style::default().fg(Color::Rgb(0, 255, 0)),
+        )));
+    }
+    if let Some(typing) = &app.typing {
+        let (wpm, song) = typing.wpm(&app.lyrics, app.lyric_time());
+        lines.push(Line::from(Span::styled(
+            format!("Typing: {:.0} WPM at {:.0}% accuracy, the song sings {:.0}", wpm, typing.accuracy() * 100.0, song),
+            Style::default().fg(Color::Rgb(0, 255, 0)),
+        )));
+    }
+    lines.push(Line::from(""));
+    lines.extend(next_up_line(party));
+
+    let widget = Paragraph::new(lines).alignment(Alignment::Center);
+    f.render_widget(widget, area);
+}
+
+// Highlight color per duet part; the lead singer keeps the original green
+const SINGER_COLORS: [Color; 4] = [
+    Color::Rgb(0, 255, 0),
+    Color::Rgb(0, 200, 255),
+    Color::Rgb(255, 100, 200),
+    Color::Rgb(255, 220, 0),
+];
+
+fn lyric_row_to_line<'a>(row: layout::LyricRow<'a>, pulse: f64, highlight: &highlight::Highlight) -> Line<'a> {
+    let sung_color = row.style.color().unwrap_or(SINGER_COLORS[row.singer % SINGER_COLORS.len()]);
+    // Emphasized lines, like a chorus, are bold with warmer unsung text
+    let unsung_color = match row.style.is_emphasized() {
+        true => Color::Rgb(255, 235, 160),
+        false => Color::White,
+    };
+    let mut text_modifiers = Modifier::empty();
+    if row.style.is_bold() {
+        text_modifiers |= Modifier::BOLD;
+    }
+    if row.style.is_italic() {
+        text_modifiers |= Modifier::ITALIC;
+    }
+    // Markers flash from red towards white on each beat
+    let glow = (200.0 * pulse) as u8;
+    let is_lyric = |kind: SpanKind| matches!(kind, SpanKind::Sung | SpanKind::Unsung | SpanKind::Completed | SpanKind::Upcoming | SpanKind::Fading(_));
+    let length: usize = row.spans.iter().filter(|span| is_lyric(span.kind)).map(|span| span.text.chars().count()).sum();
+    let mut position = 0;
+    let mut spans = Vec::with_capacity(row.spans.len());
+    for span in row.spans {
+        // A gradient or rainbow colors sung text a character at a time, by its place in the line
+        if !highlight.is_solid() && matches!(span.kind, SpanKind::Sung | SpanKind::Completed) {
+            let modifiers = match span.kind {
+                SpanKind::Sung => Modifier::BOLD | text_modifiers,
+                _ => text_modifiers,
+            };
+            for c in span.text.chars() {
+                let color = highlight.color(sung_color, position, length);
+                spans.push(Span::styled(c.to_string(), Style::default().fg(color).add_modifier(modifiers)));
+                position += 1;
+            }
+            continue;
+        }
+        if is_lyric(span.kind) {
+            position += span.text.chars().count();
+        }
+        let style = match span.kind {
+            SpanKind::Sung => Style::default().fg(sung_color).add_modifier(Modifier::BOLD | text_modifiers), // Singer's color for sung/completed lyrics
+            SpanKind::Unsung => Style::default().fg(unsung_color).add_modifier(Modifier::BOLD | text_modifiers), // White color for unsung part of current line
+            SpanKind::Completed => Style::default().fg(sung_color).add_modifier(text_modifiers), // Singer's color for completed lines
+            SpanKind::Upcoming => Style::default().fg(unsung_color).add_modifier(text_modifiers), // White color for upcoming/unplayed lines
+            SpanKind::Marker => Style::default().fg(Color::Rgb(255, glow, glow)).add_modifier(Modifier::BOLD),
+            SpanKind::Break => Style::default().fg(Color::Magenta).add_modifier(Modifier::ITALIC),
+            SpanKind::BreakBar => Style::default().fg(Color::White),
+            SpanKind::Chord => Style::default().fg(Color::Rgb(255, 200, 0)).add_modifier(Modifier::BOLD),
+            SpanKind::Match => Style::default().fg(Color::Black).bg(Color::Rgb(255, 220, 0)),
+            SpanKind::Typed => Style::default().fg(Color::Rgb(0, 255, 0)).add_modifier(Modifier::BOLD),
+            SpanKind::Mistake => Style::default().fg(Color::Rgb(255, 60, 60)).add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            SpanKind::Ball => Style::default().fg(Color::Rgb(255, 220, 0)).add_modifier(Modifier::BOLD),
+            // Blending needs the white spelled out
+            SpanKind::Fading(amount) => {
+                let unsung = match unsung_color {
+                    Color::White => Color::Rgb(255, 255, 255),
+                    color => color,
+                };
+                let color = background::blend(unsung, sung_color, f64::from(amount) / 255.0);
+                Style::default().fg(color).add_modifier(Modifier::BOLD | text_modifiers)
+            }
+        };
+        spans.push(Span::styled(span.text, style));
+    }
+    Line::from(spans)
+}
+
+// One dot per beat of the bar, the current one lit and flashing on the beat
+fn beat_indicator(app: &KaraokeApp) -> Vec<Span<'static>> {
+    let Some(beats) = app.beats.as_ref().filter(|_| app.show_beats) else {
+        return Vec::new();
+    };
+    let current = beats.beat_in_bar(beats.beat(app.get_current_time()));
+    let glow = (155.0 * app.beat_pulse()) as u8;
+    let mut spans: Vec<Span> = (0..beats.beats_per_bar)
+        .map(|i| match (i == current, i == 0) {
+            (true, true) => Span::styled("● ", Style::default().fg(Color::Rgb(255, 100 + glow, 100 + glow))),
+            (true, false) => Span::styled("● ", Style::default().fg(Color::Rgb(100 + glow, 255, 100 + glow))),
+            (false, _) => Span::styled("○ ", Style::default().fg(Color::Rgb(80, 80, 80))),
+        })
+        .collect();
+    spans.push(Span::raw(" "));
+    spans
+}
+
+fn format_time(seconds: f64) -> String {
+    time_format::format(seconds, TimeFormat::Auto, seconds)
+}
+
+fn render_results(f: &mut ratatui::Frame, area: Rect, app: &KaraokeApp, sheet: &ScoreSheet, party: Option<&PartySession>) {
+    const BAR_WIDTH: usize = 20;
+    let highlight = Style::default().fg(Color::Rgb(0, 255, 0)).add_modifier(Modifier::BOLD);
+    let dim = Style::default().fg(Color::Rgb(80, 80, 80));
+
+    let stars: String = (0..5).map(|i| if i < sheet.stars() { '★' } else { '☆' }).collect();
+
+    let mut lines = vec![
+        Line::from(""),
+        Line::from(Span::styled("Results", Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD))),
+        Line::from(""),
+        Line::from(Span::styled(format!("Score  {}", sheet.total_score()), highlight)),
+        Line::from(Span::styled(stars, Style::default().fg(Color::Yellow))),
+        Line::from(format!(
+            "Accuracy {:.0}%   Longest streak {}",
+            sheet.accuracy() * 100.0,
+            sheet.longest_streak()
+        )),
+        Line::from(""),
+    ];
+    if let Some(next) = next_up_line(party) {
+        lines.push(next);
+        lines.push(Line::from(""));
+    }
+
+    let footer = 2;
+    let room = (area.height as usize).saturating_sub(lines.len() + footer);
+    let text_width = app.lyrics.iter().map(|line| line.text.chars().count()).max().unwrap_or(0);
+
+    for (line, &accuracy) in app.lyrics.iter().zip(&sheet.line_accuracy).take(room) {
+        let filled = (accuracy * BAR_WIDTH as f64).round() as usize;
+        lines.push(Line::from(vec![
+            Span::styled(format!("{:<width$}  ", line.text, width = text_width), Style::default().fg(Color::White)),
+            Span::styled("━".repeat(filled), Style::default().fg(Color::Rgb(0, 255, 0))),
+            Span::styled("━".repeat(BAR_WIDTH - filled), dim),
+            Span::styled(format!(" {:>3.0}%", accuracy * 100.0), Style::default().fg(Color::White)),
+        ]));
+    }
+
+    lines.push(Line::from(""));
+    let options = match party {
+        Some(session) if session.next_song + 1 < session.songs.len() => "R Retry    N Next song    Q Quit",
+        Some(_) => "R Retry    N Finish party    Q Quit",
+        None => "R Retry    Q Quit",
+    };
+    lines.push(Line::from(Span::styled(options, Style::default().fg(Color::White))));
+
+    let results_widget = Paragraph::new(lines).alignment(Alignment::Center);
+    f.render_widget(results_widget, area);
+}
+
+// Waiting requests go under the leaderboard unless the queue pane shows them
+fn render_leaderboard(f: &mut ratatui::Frame, area: Rect, session: &PartySession, requests: bool) {
+    let singers: Vec<&str> = session
+        .singers()
+        .into_iter()
+        .map(|i| session.players[i].name.as_str())
+        .collect();
+
+    let mut lines = vec![
+        Line::from(""),
+        Line::from(Span::styled("Leaderboard", Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD))),
+        Line::from(""),
+    ];
+
+    for (rank, player) in session.leaderboard().into_iter().enumerate() {
+        let style = if singers.contains(&player.name.as_str()) {
+            Style::default().fg(Color::Rgb(0, 255, 0)).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::White)
+        };
+        lines.push(Line::from(Span::styled(
+            format!("{}. {:<12} {:>6}", rank + 1, player.name, player.score),
+            style,
+        )));
+    }
+
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        format!("Song {} of {}", (session.next_song + 1).min(session.songs.len()), session.songs.len()),
+        Style::default().fg(Color::Rgb(80, 80, 80)),
+    )));
+    if requests {
+        lines.extend(request_lines(session));
+    }
+
+    let leaderboard_widget = Paragraph::new(lines)
+        .alignment(Alignment::Center)
+        .style(Style::default().bg(Color::Rgb(20, 24, 40)));
+    f.render_widget(leaderboard_widget, area);
+}
+
+// The songs still to come and who sings them, as many as fit above the requests
+fn render_queue(f: &mut ratatui::Frame, area: Rect, session: &PartySession) {
+    let dim = Style::default().fg(Color::Rgb(80, 80, 80));
+    let mut lines = vec![
+        Line::from(""),
+        Line::from(Span::styled("Up next", Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD))),
+        Line::from(""),
+    ];
+    let requests = request_lines(session);
+    let queue = session.queue();
+    let room = (area.height as usize).saturating_sub(lines.len() + requests.len() + 1) / 2;
+    if queue.is_empty() {
+        lines.push(Line::styled("No more songs", dim));
+    }
+    for (song, singers) in queue.iter().take(room) {
+        lines.push(Line::styled(song.clone(), Style::default().fg(Color::White)));
+        lines.push(Line::styled(singers.clone(), dim));
+    }
+    if queue.len() > room {
+        lines.push(Line::styled(format!("and {} more", queue.len() - room), dim));
+    }
+    lines.extend(requests);
+
+    let queue_widget = Paragraph::new(lines)
+        .alignment(Alignment::Center)
+        .style(Style::default().bg(Color::Rgb(20, 24, 40)));
+    f.render_widget(queue_widget, area);
+}
+
+const REQUESTS_SHOWN: usize = 3;
+
+// Guests' requests waiting on the host, and where guests make them
+fn request_lines(session: &PartySession) -> Vec<Line<'static>> {
+    let Some(page) = &session.request_page else {
+        return Vec::new();
+    };
+    let dim = Style::default().fg(Color::Rgb(80, 80, 80));
+    let mut lines = vec![Line::from("")];
+    if session.requests.is_empty() {
+        lines.push(Line::styled("Requests at", dim));
+        lines.push(Line::styled(page.clone(), dim));
+        return lines;
+    }
+    lines.push(Line::styled(
+        format!("Requests ({})", session.requests.len()),
+        Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD),
+    ));
+    for (i, request) in session.requests.iter().take(REQUESTS_SHOWN).enumerate() {
+        let style = match i {
+            0 => Style::default().fg(Color::Rgb(0, 200, 255)),
+            _ => Style::default().fg(Color::White),
+        };
+        lines.push(Line::styled(request.name.clone(), style));
+        lines.push(Line::styled(format!("  from {}", request.guest), dim));
+    }
+    lines.push(Line::styled("Y Accept  Bksp Decline", Style::default().fg(Color::White)));
+    lines
+}
+
+fn render_mic_meter(f: &mut ratatui::Frame, area: Rect, meter: &MicMeter) {
+    let clipping = meter.is_clipping(Instant::now());
+    let level_color = if clipping {
+        Color::Red
+    } else if meter.level_db > -12.0 {
+        Color::Yellow
+    } else {
+        Color::Rgb(0, 255, 0)
+    };
+
+    let mut spans = vec![
+        Span::styled("Mic ", Style::default().fg(Color::White)),
+        Span::styled(meter.bar(30), Style::default().fg(level_color)),
+        Span::styled(format!(" {:>4.0} dB", meter.level_db), Style::default().fg(Color::White)),
+    ];
+    if clipping {
+        spans.push(Span::styled("  CLIP", Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)));
+    }
+    f.render_widget(Paragraph::new(Line::from(spans)).alignment(Alignment::Center), area);
+}
+
+const PROGRESS_BAR_WIDTH: usize = 30;
+
+// Lyrics area, then the time row, title and controls
+fn screen_chunks(size: Rect) -> std::rc::Rc<[Rect]> {
+    Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
             Constraint::Min(10),
             Constraint::Length(1),
             Constraint::Length(1),
             Constraint::Length(1),
         ])
-        .split(size);
+        .split(size)
+}
 
-    let current_time = app.get_current_time();
-    let current_idx = app.get_current_line_index(current_time);
-    
-    const VISIBLE_LINES: usize = 5;
-    const CENTER_LINE: usize = 2;
-    
-    let lyrics_height = chunks[0].height as usize;
-    let mut lines = Vec::new();
-    
-    let top_padding = (lyrics_height.saturating_sub(VISIBLE_LINES)) / 2;
-    
-    for display_row in 0..lyrics_height {
-        if display_row >= top_padding && display_row < top_padding + VISIBLE_LINES {
-            let visible_row = display_row - top_padding;
-            
-            if visible_row == CENTER_LINE {
-                if let Some(curr_idx) = current_idx {
-                    let line = &app.lyrics[curr_idx];
-                    let progress = app.get_line_progress(current_time, curr_idx);
-                    let is_completed = app.is_line_completed(current_time, curr_idx);
-                    
-                    let lyric_spans = render_lyric_content(&line.text, progress, true, is_completed);
-                    
-                    let mut full_spans = vec![
-                        Span::styled(">     ", Style::default().fg(Color::Red).add_modifier(Modifier::BOLD))
-                    ];
-                    full_spans.extend(lyric_spans);
-                    full_spans.push(Span::styled("     <", Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)));
-                    
-                    lines.push(Line::from(full_spans));
-                } else {
-                    lines.push(Line::from(""));
+// Where the bar itself sits inside the centered "0:12  ━━━●━━  3:45" time row
+fn progress_bar_rect(time_row: Rect, app: &KaraokeApp) -> Rect {
+    let left = app.clock_text(app.get_current_time()).len() + 2;
+    let right = app.clock_text(app.song_duration).len() + 2;
+    let total = (left + PROGRESS_BAR_WIDTH + right) as u16;
+    let x = time_row.x + time_row.width.saturating_sub(total) / 2 + left as u16;
+    Rect {
+        x,
+        y: time_row.y,
+        width: (PROGRESS_BAR_WIDTH as u16).min((time_row.x + time_row.width).saturating_sub(x)),
+        height: 1,
+    }
+}
+
+fn handle_mouse(app: &mut KaraokeApp, mouse: MouseEvent, size: Rect) {
+    let bar = progress_bar_rect(screen_chunks(size)[1], app);
+    // Too narrow a terminal leaves no bar to seek on
+    if bar.width == 0 {
+        app.seek_preview = None;
+        return;
+    }
+    let on_bar = mouse.row == bar.y && mouse.column >= bar.x && mouse.column < bar.x + bar.width;
+    let dragging = app.seek_preview.is_some_and(|preview| preview.dragging);
+
+    let column = mouse.column.clamp(bar.x, bar.x + bar.width - 1);
+    let ratio = ((column - bar.x) as f64 + 0.5) / bar.width as f64;
+    let preview = |dragging| SeekPreview {
+        time: (ratio * app.song_duration).clamp(0.0, app.song_duration),
+        column,
+        dragging,
+    };
+
+    match mouse.kind {
+        MouseEventKind::Down(MouseButton::Left) if on_bar => app.seek_preview = Some(preview(true)),
+        MouseEventKind::Drag(MouseButton::Left) if dragging => app.seek_preview = Some(preview(true)),
+        MouseEventKind::Up(MouseButton::Left) if dragging => {
+            let target = preview(false).time;
+            app.seek_preview = None;
+            app.seek_to(target);
+        }
+        MouseEventKind::Moved if on_bar => app.seek_preview = Some(preview(false)),
+        MouseEventKind::Moved if !dragging => app.seek_preview = None,
+        _ => {}
+    }
+}
+
+// A one-line tooltip just above the progress bar: the time and the lyric line there
+fn render_seek_preview(f: &mut ratatui::Frame, time_row: Rect, app: &KaraokeApp, preview: &SeekPreview) {
+    if time_row.y == 0 {
+        return;
+    }
+    let line = timeline::focus_index(&app.lyrics, preview.time + app.lyric_offset)
+        .map_or("", |i| app.lyrics[i].text.as_str());
+    let span = Span::styled(
+        format!(" {} │ {} ", app.clock_text(preview.time), line),
+        Style::default().fg(Color::White).bg(Color::Rgb(60, 60, 90)),
+    );
+
+    let width = (span.width() as u16).min(time_row.width);
+    let left = preview.column.saturating_sub(width / 2).max(time_row.x);
+    let x = left.min((time_row.x + time_row.width).saturating_sub(width));
+    let area = Rect {
+        x,
+        y: time_row.y - 1,
+        width,
+        height: 1,
+    };
+    f.render_widget(Paragraph::new(Line::from(span)), area);
+}
+
+fn ui(f: &mut ratatui::Frame, app: &KaraokeApp, party: Option<&PartySession>) {
+    let size = f.size();
+    let chunks = screen_chunks(size);
+
+    // Instrumentals give the visualizer the whole lyrics area instead
+    let panel = app.visualizer.as_ref().filter(|visualizer| visualizer.is_visible() && !app.lyrics.is_empty());
+    let lyrics_area = match panel {
+        Some(visualizer) => {
+            let rows = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(5), Constraint::Length(visualizer::PANEL_HEIGHT)])
+                .split(chunks[0]);
+            visualizer::render(f, rows[1], visualizer, app.get_current_time());
+            rows[0]
+        }
+        None => chunks[0],
+    };
+
+    let lyrics_area = match &app.mic {
+        Some(meter) => {
+            let rows = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(5), Constraint::Length(1)])
+                .split(lyrics_area);
+            render_mic_meter(f, rows[1], meter);
+            rows[0]
+        }
+        None => lyrics_area,
+    };
+
+    let lyrics_area = match party {
+        Some(session) => match session.panes {
+            Some(panes) => {
+                let zones = panes::split(lyrics_area, panes);
+                if let Some(area) = zones.scoreboard {
+                    render_leaderboard(f, area, session, zones.queue.is_none());
                 }
-            } else {
-                if let Some(curr_idx) = current_idx {
-                    let offset = visible_row as i32 - CENTER_LINE as i32;
-                    let lyric_idx = curr_idx as i32 + offset;
-                    
-                    if lyric_idx >= 0 && (lyric_idx as usize) < app.lyrics.len() {
-                        let lyric_idx = lyric_idx as usize;
-                        let line = &app.lyrics[lyric_idx];
-                        let is_completed = app.is_line_completed(current_time, lyric_idx);
-                        
-                        let lyric_spans = render_lyric_content(&line.text, 0.0, false, is_completed);
-                        lines.push(Line::from(lyric_spans));
-                    } else {
-                        lines.push(Line::from(""));
-                    }
-                } else {
-                    lines.push(Line::from(""));
+                if let Some(area) = zones.queue {
+                    render_queue(f, area, session);
                 }
+                zones.lyrics
             }
-        } else {
-            lines.push(Line::from(""));
+            None => {
+                let panes = Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints([Constraint::Min(20), Constraint::Length(28)])
+                    .split(lyrics_area);
+                render_leaderboard(f, panes[1], session, true);
+                panes[0]
+            }
+        },
+        None => lyrics_area,
+    };
+
+    let current_time = app.get_current_time();
+    let lyric_time = app.lyric_time();
+    
+    let fade = app.outro_progress().unwrap_or(0.0);
+
+    background::render(f.buffer_mut(), lyrics_area, &app.background, Instant::now());
+    if let Some(attract) = &app.attract {
+        attract::render(f, lyrics_area, attract, party, Instant::now());
+    } else {
+        match &app.score {
+            // The lyrics fade out first, then the results or the outro card take over
+            _ if matches!(app.screen, Screen::Preview { .. }) => render_preview(f, lyrics_area, app),
+            _ if app.screen != Screen::Playing => render_splash(f, lyrics_area, app, party),
+            _ if fade < 1.0 && app.lyrics.is_empty() => render_instrumental(f, lyrics_area, app, fade),
+            _ if fade < 1.0 && app.big_text => render_big_text(f, lyrics_area, app, fade),
+            _ if fade < 1.0 && app.teleprompter.is_some() => render_teleprompter(f, lyrics_area, app, fade),
+            _ if fade < 1.0 && app.vertical => {
+                let pulse = app.beat_pulse();
+                let frame = vertical::layout_vertical(app, lyric_time, lyrics_area.width as usize, lyrics_area.height as usize);
+                let lines: Vec<Line> = frame.rows.into_iter().map(|row| fade_line(lyric_row_to_line(row, pulse, &app.highlight), fade)).collect();
+                f.render_widget(Paragraph::new(lines).alignment(Alignment::Center), lyrics_area);
+            }
+            _ if fade < 1.0 => {
+                let pulse = app.beat_pulse();
+                // An open search shows the picked match where the current line would be
+                let view_time = app.find.as_ref().and_then(|find| find.selected()).map_or(lyric_time, |i| app.lyrics[i].start_time);
+                let lines: Vec<Line> = layout::layout_lyrics(app, view_time, lyrics_area.width as usize, lyrics_area.height as usize)
+                    .rows
+                    .into_iter()
+                    .map(|row| fade_line(lyric_row_to_line(row, pulse, &app.highlight), fade))
+                    .collect();
+                f.render_widget(Paragraph::new(lines).alignment(Alignment::Center), lyrics_area);
+            }
+            Some(sheet) => render_results(f, lyrics_area, app, sheet, party),
+            None => render_outro(f, lyrics_area, app, party),
         }
     }
-
-    let lyrics_widget = Paragraph::new(lines)
-        .alignment(Alignment::Center)
-        .style(Style::default().bg(Color::Rgb(20, 24, 40))); // Background color
-    f.render_widget(lyrics_widget, chunks[0]);
+    // Not before the song has started, and not over a search's results
+    if app.is_paused() && app.screen == Screen::Playing && !app.is_song_ended() && current_time > 0.0 && app.find.is_none() && app.attract.is_none() {
+        render_paused(f, lyrics_area);
+    }
+    if let Some(celebration) = &app.celebration {
+        celebration::render(f.buffer_mut(), lyrics_area, celebration, Instant::now());
+    }
+    if let Some(toast) = &app.toast {
+        toast::render(f, lyrics_area, toast, Instant::now());
+    }
+    if let Some(stats) = &app.debug {
+        debug::render(f, lyrics_area, &app.debug_rows(stats));
+    }
 
     let progress_ratio = (current_time / app.song_duration).min(1.0);
-    let current_time_str = format_time(current_time);
-    let duration_str = format_time(app.song_duration);
-    
-    let progress_bar_width = 30;
-    let progress_bar = create_progress_bar(progress_ratio, progress_bar_width);
-    
-    let mut time_spans = vec![
-        Span::styled(format!("{}  ", current_time_str), Style::default().fg(Color::White))
-    ];
-    time_spans.extend(progress_bar.spans);
-    time_spans.push(Span::styled(format!("  {}", duration_str), Style::default().fg(Color::White)));
-    
-    let time_widget = Paragraph::new(Line::from(time_spans))
+    let time_line = format!(
+        "{}  {}  {}",
+        app.clock_text(current_time),
+        " ".repeat(PROGRESS_BAR_WIDTH),
+        app.clock_text(app.song_duration)
+    );
+    let time_widget = Paragraph::new(time_line)
+        .style(Style::default().fg(Color::White))
         .alignment(Alignment::Center);
     f.render_widget(time_widget, chunks[1]);
+    f.render_widget(
+        ProgressBar::new(progress_ratio).gradient(Color::Rgb(0, 200, 255)),
+        progress_bar_rect(chunks[1], app),
+    );
+    if let Some(preview) = &app.seek_preview {
+        render_seek_preview(f, chunks[1], app, preview);
+    }
 
-    let song_title = Paragraph::new(app.song_title.as_str())
+    let mut title = match party {
+        Some(session) => format!("{} - {} singing", app.song_title, singing(app, session)),
+        None => app.song_title.clone(),
+    };
+    if app.chords_visible() {
+        for info in app.chord_info() {
+            title = format!("{} · {}", title, info);
+        }
+    } else if app.audio.is_some() && app.transpose != 0 {
+        title = format!("{} · Transpose {:+}", title, app.transpose);
+    }
+    if let Some(section) = app.current_section() {
+        title = format!("{} · {}", title, section);
+    }
+    if app.muted && app.audio.is_some() {
+        title = format!("{} · muted", title);
+    }
+    if app.no_vocals {
+        title = format!("{} · no vocals", title);
+    }
+    let mut title_spans = beat_indicator(app);
+    title_spans.push(Span::raw(title));
+    let song_title = Paragraph::new(Line::from(title_spans))
         .style(Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD))
         .alignment(Alignment::Center);
     f.render_widget(song_title, chunks[2]);
 
-    let controls = if app.is_song_ended() {
-        "♫ Song Ended - Press R to Restart ♫"
+    let mut controls = if app.sync_connected == Some(false) {
+        "Connecting to the host...".to_string()
+    } else if matches!(app.screen, Screen::Preview { .. }) {
+        "Preview - ↑↓ to scroll, Enter to start".to_string()
+    } else if app.screen != Screen::Playing {
+        "Get ready - press Space to skip".to_string()
+    } else if app.is_song_ended() {
+        match app.repeat {
+            RepeatMode::Off => "♫ Song Ended - Press R to Restart ♫".to_string(),
+            _ => "♫ Song Ended - Repeating shortly ♫".to_string(),
+        }
+    } else if app.mic.is_some() && app.paused && app.get_current_time() == 0.0 {
+        "Mic check - sing a few words, then press Space to start".to_string()
+    } else if let Some(find) = &app.find {
+        find.describe()
+    } else if let Some(quiz) = &app.quiz {
+        quiz.describe(&app.lyrics, lyric_time)
+    } else if let Some(typing) = &app.typing {
+        typing.describe(&app.lyrics, lyric_time)
+    } else if let Some(practice) = &app.practice {
+        practice.describe()
     } else {
-        "⇄  ◀  ‖  ▶  ⟲"
+        "⇄  ◀  ‖  ▶  ⟲".to_string()
     };
-    
+    if let Some(label) = app.repeat.label() {
+        controls = format!("{}   [{}]", controls, label);
+    }
+
     let controls_widget = Paragraph::new(controls)
         .style(Style::default().fg(Color::White))
         .alignment(Alignment::Center);
     f.render_widget(controls_widget, chunks[3]);
 }
 
+fn run_mixdown(vocal: &std::path::Path, backing: &std::path::Path, output: &std::path::Path, options: &mixdown::MixOptions) -> Result<(), io::Error> {
+    let vocal = wav::read(vocal)?;
+    let backing = wav::read(backing)?;
+    let mixed = mixdown::mix(&vocal, &backing, options)?;
+    wav::write(output, &mixed)?;
+    println!("Wrote {}", output.display());
+    Ok(())
+}
+
+fn run_align(lyrics: &std::path::Path, audio: &std::path::Path, output: &std::path::Path) -> Result<(), io::Error> {
+    let text = std::fs::read_to_string(lyrics)?;
+    let recording = wav::read(audio)?;
+    let aligned = align::align(&text, &recording).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    std::fs::write(output, formats::to_lrc(&aligned))?;
+    println!("Wrote {} ({} lines); the timing is an estimate, so check it before singing", output.display(), aligned.len());
+    Ok(())
+}
+
+fn run_convert(song: &std::path::Path, output: &std::path::Path) -> Result<(), io::Error> {
+    let config = formats::load(song)?;
+    formats::save(&config, output)?;
+    println!("Wrote {} ({} lines)", output.display(), config.lyrics.len());
+    if !formats::keeps_word_timing(output) && config.lyrics.iter().any(|line| !line.segments.is_empty()) {
+        println!("Word timing was left out; {} only keeps line times", output.display());
+    }
+    Ok(())
+}
+
+// Songs in the library keep their recording and cover art next to them under the same name
+fn run_bundle(song: &std::path::Path, output: &std::path::Path, mut extras: formats::bundle::Extras) -> Result<(), io::Error> {
+    let config = formats::load(song)?;
+    let beside = |extensions: &[&str]| extensions.iter().map(|ext| song.with_extension(ext)).find(|path| path.is_file());
+    extras.audio = extras.audio.or_else(|| beside(&["wav"]));
+    extras.cover = extras.cover.or_else(|| beside(&["jpg", "jpeg", "png", "webp"]));
+    formats::bundle::write(&config, &extras, output)?;
+    println!("Wrote {} ({} lines)", output.display(), config.lyrics.len());
+    for (what, path) in [("audio", &extras.audio), ("translation", &extras.translation), ("cover art", &extras.cover)] {
+        if let Some(path) = path {
+            println!("  with {} from {}", what, path.display());
+        }
+    }
+    Ok(())
+}
+
+fn run_retime(song: &std::path::Path, output: &std::path::Path, shift: f64, scale: f64) -> Result<(), io::Error> {
+    let mut config = formats::load(song)?;
+    formats::retime(&mut config.lyrics, scale, shift);
+    std::fs::write(output, formats::to_lrc(&config.lyrics))?;
+    println!("Wrote {} ({} lines)", output.display(), config.lyrics.len());
+    Ok(())
+}
+
+fn run_check(song: &std::path::Path, fix: bool, output: Option<&std::path::Path>) -> Result<(), io::Error> {
+    let mut config = formats::load(song)?;
+    let issues = validate::check(&config);
+    for issue in &issues {
+        println!("{}", issue);
+    }
+    if issues.is_empty() {
+        println!("No timing problems in {}", song.display());
+    }
+
+    if fix {
+        validate::fix(&mut config);
+        let output = output.map_or_else(|| song.with_extension("lrc"), std::path::Path::to_path_buf);
+        std::fs::write(&output, formats::to_lrc(&config.lyrics))?;
+        println!("Wrote {}", output.display());
+    }
+    Ok(())
+}
+
 fn main() -> Result<(), io::Error> {
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen)?;
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
+    let command = match cli::parse(env::args().skip(1)) {
+        Ok(command) => command,
+        Err(message) => {
+            eprintln!("{}\n\n{}", message, cli::USAGE);
+            process::exit(2);
+        }
+    };
+
+    match command {
+        Command::Play(options) => run_tui(&options),
+        Command::Party(options) => run_party(&options),
+        Command::Search(options) => search::run(&options),
+        Command::Fetch(options) => {
+            let (song, audio) = fetch::run(&options)?;
+            match options.play {
+                true => run_tui(&PlayOptions {
+                    song: Some(song),
+                    audio: Some(audio),
+                    play_audio: true,
+                    ..PlayOptions::default()
+                }),
+                false => Ok(()),
+            }
+        }
+        Command::Library(options) => library::run(&options),
+        Command::Singer(options) => profiles::run(&options),
+        Command::Leaderboard(options) => leaderboard::run(&options),
+        Command::Setlist { setlist, action } => match action {
+            SetlistAction::Edit(songs) => setlist::edit(&setlist, &songs),
+            SetlistAction::Export(output) => setlist::export(&setlist, &output),
+        },
+        Command::NowLine(options) => now_line::run(&options),
+        Command::Stats { sort } => history::print_stats(sort),
+        Command::Mixdown { vocal, backing, output, options } => run_mixdown(&vocal, &backing, &output, &options),
+        Command::Align { lyrics, audio, output } => run_align(&lyrics, &audio, &output),
+        Command::Edit { song, output, encoding } => {
+            encoding.into_iter().for_each(formats::encoding::force);
+            editor::run(&song, output.as_deref())
+        }
+        Command::Check { song, fix, output, encoding } => {
+            encoding.into_iter().for_each(formats::encoding::force);
+            run_check(&song, fix, output.as_deref())
+        }
+        Command::Convert { song, output, encoding } => {
+            encoding.into_iter().for_each(formats::encoding::force);
+            run_convert(&song, &output)
+        }
+        Command::Bundle { song, output, extras, encoding } => {
+            encoding.into_iter().for_each(formats::encoding::force);
+            run_bundle(&song, &output, extras)
+        }
+        Command::Retime { song, output, shift, scale, encoding } => {
+            encoding.into_iter().for_each(formats::encoding::force);
+            run_retime(&song, &output, shift, scale)
+        }
+    }
+}
+
+const TICK_RATE: Duration = Duration::from_millis(16);
+
+enum SongOutcome {
+    Quit,
+    Next,
+}
+
+fn setup_terminal() -> Result<terminal::Tui, io::Error> {
+    terminal::Tui::enter()
+}
+
+// Also happens when the terminal is dropped or on a panic, but errors only show up here
+fn restore_terminal() -> Result<(), io::Error> {
+    terminal::restore()
+}
+
+// Offers to pick up where the last session stopped; only asked on an interactive terminal
+fn ask_to_resume(state_path: &std::path::Path, options: &PlayOptions) -> Result<Option<resume::ResumeState>, io::Error> {
+    use std::io::{BufRead, IsTerminal, Write};
+
+    let Some(state) = resume::load(state_path).filter(|state| resume::matches(state, options.song.as_deref())) else {
+        return Ok(None);
+    };
+    if !io::stdin().is_terminal() {
+        return Ok(None);
+    }
+
+    print!("Resume \"{}\" at {}? [Y/n] ", state.title, format_time(state.position));
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+    let restart = matches!(answer.trim().to_ascii_lowercase().as_str(), "n" | "no");
+    Ok((!restart).then_some(state))
+}
+
+fn run_tui(options: &PlayOptions) -> Result<(), io::Error> {
+    if let Some(path) = &options.log_file {
+        logging::init(path)?;
+    }
+    compat::init(options.colors, options.ascii, options.high_contrast);
+    if let Some(encoding) = &options.encoding {
+        formats::encoding::force(encoding.clone());
+    }
+    if let Some(offset) = options.lrc_offset {
+        formats::force_lrc_offset(offset);
+    }
+    let mut options = options.clone();
+    if let (None, Some(audio), None) = (&options.song, &options.audio, &options.sync_join) {
+        let library = options.library.clone().unwrap_or_else(|| std::path::PathBuf::from("library"));
+        options.song = Some(matching::find_lyrics(audio, &library)?);
+    }
+    let options = &options;
+    let state_path = resume::state_path();
+    let resumed = match options.output {
+        OutputMode::Tui if options.sync_join.is_none() && options.daemon.is_none() => ask_to_resume(&state_path, options)?,
+        _ => None,
+    };
+
+    let mut options = options.clone();
+    if let Some(state) = &resumed {
+        options.song = state.song.clone();
+    }
+    let options = &options;
+
+    let mut app = KaraokeApp::new(options)?;
+    if let Some(state) = resumed {
+        app.resume_from(state);
+    }
+    let events = EventBus::new();
+
+    // cli makes sure there's a token whenever --remote is given
+    let remote = options.token.clone().filter(|_| options.remote).map(|token| RemoteControl {
+        token,
+        events: events.sender(),
+    });
+
+    let mut outputs: Vec<Box<dyn Renderer>> = Vec::new();
+    if let Some(bind) = &options.serve {
+        outputs.push(Box::new(server::EventTracker::new(server::start(bind, remote)?)));
+        app.page_url = Some(server::page_url(bind));
+        if let Some(command) = &options.cast_command {
+            let page = format!("{}{}", server::page_url(bind), cast::PAGE_PATH);
+            cast::start(command, options.cast.as_deref(), &page)?;
+            app.notify(format!("Casting {}", page));
+        }
+    }
+    if let Some(command) = &options.hook_command {
+        outputs.push(Box::new(HookScript::start(command, events.sender())?));
+    }
+    if let Some(target) = &options.status_fd {
+        outputs.push(Box::new(status::StatusStream::open(target)?));
+    }
+    if let Some(command) = &options.notify_command {
+        outputs.push(Box::new(notifications::DesktopNotifier::new(command)));
+    }
+    // The host scrobbles for everyone following it
+    if options.scrobble && options.sync_join.is_none() {
+        outputs.push(Box::new(scrobble::Scrobbler::start()?));
+    }
+    if options.announce {
+        outputs.push(Box::new(announce::Announcer::start(options.announce_command.as_deref())?));
+    }
+    if let Some(command) = &options.guide_command {
+        let lead = options.guide_lead.unwrap_or(announce::DEFAULT_GUIDE_LEAD);
+        outputs.push(Box::new(announce::GuideVocal::new(announce::Speech::start(command)?, lead)));
+    }
+    if let (Some(device), Some(bpm)) = (&options.midi_out, options.bpm) {
+        outputs.push(Box::new(midi::MidiOut::open(device, bpm)?));
+    }
+    if let Some(bind) = &options.sync_host {
+        outputs.push(Box::new(sync::SyncHost::start(bind)?));
+    }
+    if let Some(address) = &options.sync_join {
+        app.pause();
+        app.song_title = "Waiting for the host".to_string();
+        app.sync_connected = Some(false);
+        sync::join(address.clone(), events.sender());
+    }
+    if let Some(target) = &options.osc {
+        outputs.push(Box::new(osc::OscOutput::connect(
+            target,
+            options.osc_prefix.as_deref().unwrap_or(osc::DEFAULT_PREFIX),
+        )?));
+    }
+
+    if let Some(socket) = &options.daemon {
+        return daemon::run(&mut app, socket, &events, outputs);
+    }
+    if options.output == OutputMode::Plain {
+        return headless::run(&mut app, options, outputs);
+    }
 
-    let mut app = KaraokeApp::new();
-    let tick_rate = Duration::from_millis(16);
-    let mut last_tick = Instant::now();
+    // The song waits for Space so the singer can check the mic first
+    let _capture = match &options.mic_command {
+        Some(command) => {
+            let monitor = match &options.monitor_command {
+                Some(monitor_command) => Some(monitor::Monitor::start(monitor_command, options.echo.map_or(monitor::DEFAULT_ECHO, |echo| echo / 100.0))?),
+                None => None,
+            };
+            app.echo = monitor.as_ref().map(monitor::Monitor::level);
+            let profile = options.singer.as_deref().map(profiles::load).transpose()?.flatten();
+            let gain = options.mic_gain.or(profile.map(|profile| profile.mic_gain)).unwrap_or(0.0);
+            let capture = MicCapture::start(command, gain, events.sender(), monitor)?;
+            app.mic = Some(MicMeter::default());
+            app.pause();
+            Some(capture)
+        }
+        None => None,
+    };
 
+    if let Some(command) = &options.click_command {
+        app.metronome = Some(Metronome::start(command)?);
+    }
+
+    let countdown = (!options.no_splash && options.sync_join.is_none()).then(|| options.countdown.unwrap_or(screen::DEFAULT_COUNTDOWN));
+    match (options.preview, countdown) {
+        (true, countdown) => app.show_preview(countdown),
+        (false, Some(countdown)) => app.show_splash(countdown),
+        (false, None) => {}
+    }
+
+    let mut terminal = setup_terminal()?;
+    if options.mouse {
+        execute!(terminal.backend_mut(), EnableMouseCapture)?;
+    }
+    let max_fps = options.max_fps.unwrap_or(frame_rate::DEFAULT_MAX_FPS);
+    events.spawn_terminal_sources(FramePacer::tick_rate(max_fps, TICK_RATE));
+    let result = run_song(&mut terminal, &mut app, events.receiver(), None, &mut outputs, max_fps);
+    restore_terminal()?;
+    result?;
+
+    // Play history and the resume point belong to the host
+    if options.sync_join.is_some() {
+        return Ok(());
+    }
+    app.record_play()?;
+    if app.is_song_ended() {
+        resume::clear(&state_path)
+    } else {
+        resume::save(&state_path, &app.resume_state())
+    }
+}
+
+fn run_party(options: &PartyOptions) -> Result<(), io::Error> {
+    let mut session = if options.resume {
+        PartySession::load(&options.session)?
+    } else if let Some(setlist) = &options.setlist {
+        setlist::Setlist::load(setlist)?.to_party()?
+    } else {
+        PartySession::new(options.players.clone(), options.songs.clone(), options.duet)
+    };
+    let events = EventBus::new();
+    if let Some(bind) = &options.requests {
+        requests::start(bind, &options.library, events.sender())?;
+        session.request_page = Some(server::page_url(bind));
+    }
+    session.panes = options.panes.then(Panes::default);
+
+    let mut terminal = setup_terminal()?;
+    events.spawn_terminal_sources(TICK_RATE);
+    let result = run_party_songs(&mut terminal, &mut session, options, events.receiver());
+    restore_terminal()?;
+    result?;
+
+    println!("Final leaderboard:");
+    for (rank, player) in session.leaderboard().into_iter().enumerate() {
+        println!("{}. {} - {} points ({} songs)", rank + 1, player.name, player.score, player.songs_sung);
+    }
+    Ok(())
+}
+
+type PartySong = (SongConfig, Option<wav::WavData>);
+
+// A party song, with the WAV file of the same name next to it (or in it, for a bundle) when the
+// party plays audio
+fn load_party_song(path: &std::path::Path, with_audio: bool) -> Result<PartySong, io::Error> {
+    let config = formats::load(path)?;
+    if with_audio && formats::bundle::is_bundle(path) {
+        return Ok((config, formats::bundle::audio(path)?.map(|bytes| wav::parse(&bytes)).transpose()?));
+    }
+    let recording = path.with_extension("wav");
+    let recording = match (with_audio, recording.exists()) {
+        (true, true) => Some(wav::read(&recording)?),
+        (true, false) => {
+            log::warn!("no recording for {} at {}", path.display(), recording.display());
+            None
+        }
+        (false, _) => None,
+    };
+    Ok((config, recording))
+}
+
+fn run_party_songs<B: Backend>(
+    terminal: &mut Terminal<B>,
+    session: &mut PartySession,
+    options: &PartyOptions,
+    events: &Receiver<AppEvent>,
+) -> Result<(), io::Error> {
+    let mut repeat = RepeatMode::Off;
+    // The last song's recording, still playing out, and the next song loading in the background
+    let mut outgoing: Option<playback::AudioPlayer> = None;
+    let mut preloaded: Option<(std::path::PathBuf, std::thread::JoinHandle<Result<PartySong, io::Error>>)> = None;
     loop {
-        terminal.draw(|f| ui(f, &app))?;
+        // Repeat-all starts the list over once the last song is done
+        if session.current_song().is_none() && repeat == RepeatMode::All {
+            session.restart_playlist();
+        }
+        let Some(song) = session.current_song() else {
+            break;
+        };
+        let (config, recording) = match preloaded.take() {
+            Some((path, loading)) if path == song => {
+                loading.join().unwrap_or_else(|_| Err(io::Error::other(format!("couldn't load {}", path.display()))))?
+            }
+            _ => load_party_song(song, options.play_audio)?,
+        };
+        if let Some(next) = session.upcoming_song() {
+            let (path, with_audio) = (next.to_path_buf(), options.play_audio);
+            preloaded = Some((path.clone(), std::thread::spawn(move || load_party_song(&path, with_audio))));
+        }
 
-        if app.is_song_ended() && !app.paused {
-            app.paused = true;
-            app.current_position = app.song_duration;
+        let sheet = ScoreSheet::for_taps(config.lyrics.len());
+        let mut app = KaraokeApp::from_config(config, Some(sheet));
+        app.song_path = resume::absolute(Some(song));
+        app.repeat = repeat;
+        app.no_confetti = options.no_confetti;
+        app.idle_after = options.attract.map(|minutes| Duration::from_secs_f64(minutes * 60.0));
+        if let Some(recording) = recording {
+            let command = playback::command(None, None, &recording);
+            app.use_recording(recording, Some(command), 0.0);
+            app.play_out = options.crossfade > 0.0;
+        }
+        app.warn_about_duration();
+        // With the last song still playing out, this one starts under it rather than after the splash
+        let crossfading = outgoing.is_some() && app.audio.is_some();
+        if let Some(previous) = outgoing.take() {
+            previous.fade_out(options.crossfade);
+        }
+        if session.duet || session.singers().len() > 1 {
+            app.singers = session.singer_list();
+        }
+        app.credited = session.singer_list();
+        apply_profile(&mut app);
+        match (crossfading, &mut app.audio) {
+            (true, Some(audio)) => {
+                audio.fade_in(options.crossfade);
+                app.play();
+            }
+            _ => app.show_splash(screen::DEFAULT_COUNTDOWN),
+        }
+        if options.preview {
+            app.show_preview((!crossfading).then_some(screen::DEFAULT_COUNTDOWN));
         }
 
-        if let Some(resume_at) = app.seek_resume_at {
-            if Instant::now() >= resume_at {
-                app.paused = false;
-                app.start_time = Instant::now();
-                app.seek_resume_at = None;
-            }
-        }
-
-        let timeout = tick_rate
-            .checked_sub(last_tick.elapsed())
-            .unwrap_or_else(|| Duration::from_secs(0));
-
-        if event::poll(timeout)? {
-            if let Event::Key(key) = event::read()? {
-                match key.code {
-                    KeyCode::Char('q') | KeyCode::Char('Q') => break,
-                    KeyCode::Char(' ') => app.toggle_pause(),
-                    KeyCode::Char('r') | KeyCode::Char('R') => {
-                        app.current_position = 0.0;
-                        app.start_time = Instant::now();
-                        app.paused = false;
-                    },
-                    KeyCode::Left => {
-                        app.seek(-0.3);
-                    },
-                    KeyCode::Right => {
-                        app.seek(0.3);
-                    },
-                    _ => {}
+        let outcome = run_song(terminal, &mut app, events, Some(&mut *session), &mut [], frame_rate::DEFAULT_MAX_FPS)?;
+        repeat = app.repeat;
+        app.record_play()?;
+        match outcome {
+            SongOutcome::Quit => break,
+            SongOutcome::Next => {
+                let score = app.score.as_ref().map_or(0, ScoreSheet::total_score);
+                leaderboard::record(&session.singer_list(), app.song_path.as_deref(), &app.song_title, score, session.started)?;
+                session.record_result(score);
+                session.save(&options.session)?;
+                if app.play_out {
+                    outgoing = app.audio.take();
                 }
             }
         }
+    }
 
-        if last_tick.elapsed() >= tick_rate {
-            last_tick = Instant::now();
+    session.save(&options.session)
+}
+
+// A solo singer's key in party mode; duets sing in the song's own
+fn apply_profile(app: &mut KaraokeApp) {
+    let [singer] = app.credited.as_slice() else {
+        return;
+    };
+    let profile = match profiles::load(singer) {
+        Ok(profile) => profile,
+        Err(e) => {
+            log::warn!("couldn't read singer profiles: {}", e);
+            None
         }
+    };
+    let Some(profile) = profile else {
+        return;
+    };
+    app.transpose = profile.transpose;
+    if let Some(audio) = &app.audio {
+        audio.set_key(app.transpose);
+    }
+    if let Some(message) = profile.summary() {
+        app.notify(message);
     }
+}
 
-    disable_raw_mode()?;
-    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
-    terminal.show_cursor()?;
+fn run_song<B: Backend>(
+    terminal: &mut Terminal<B>,
+    app: &mut KaraokeApp,
+    events: &Receiver<AppEvent>,
+    mut party: Option<&mut PartySession>,
+    outputs: &mut [Box<dyn Renderer>],
+    max_fps: f64,
+) -> Result<SongOutcome, io::Error> {
+    let mut pacer = FramePacer::new(max_fps);
+    loop {
+        let started = Instant::now();
+        // Only the terminal is throttled; outputs keep their own pace
+        if pacer.should_draw(started, app.is_animating(started)) {
+            terminal.draw(|f| {
+                ui(f, app, party.as_deref());
+                compat::adapt(f.buffer_mut());
+            })?;
+            pacer.drawn(started);
+            if let Some(stats) = &mut app.debug {
+                stats.frame_drawn(started, Instant::now());
+            }
+        }
+        for output in outputs.iter_mut() {
+            output.render(app)?;
+        }
 
-    Ok(())
+        // Every producer shutting down means nothing can drive the session anymore
+        let Ok(event) = events.recv() else {
+            return Ok(SongOutcome::Quit);
+        };
+        if !matches!(event, AppEvent::Tick) {
+            pacer.invalidate();
+        }
+
+        let outcome = match event {
+            AppEvent::Tick => {
+                if app.reload_if_saved() {
+                    for output in outputs.iter_mut() {
+                        output.song_changed();
+                    }
+                }
+                app.tick();
+                match app.repeat {
+                    RepeatMode::All if party.is_some() && app.repeat_due() => Some(SongOutcome::Next),
+                    _ if app.repeat_due() => {
+                        app.restart();
+                        None
+                    }
+                    _ => None,
+                }
+            }
+            AppEvent::Resize => None,
+            AppEvent::Terminate => {
+                log::info!("quitting on a signal");
+                return Ok(SongOutcome::Quit);
+            }
+            AppEvent::Input(key) => {
+                for output in outputs.iter_mut() {
+                    output.key(key);
+                }
+                // The first key on the attract screen only puts it away
+                let answered = app.wake()
+                    || party
+                        .as_deref_mut()
+                        .is_some_and(|session| handle_request_key(app, session, key) || handle_pane_key(app, session, key));
+                match answered {
+                    true => None,
+                    false => handle_key(app, key, party.is_some()),
+                }
+            }
+            AppEvent::Mouse(mouse) => {
+                handle_mouse(app, mouse, terminal.size()?);
+                None
+            }
+            AppEvent::Remote(RemoteCommand::Load(path)) => {
+                // A bad path from the remote shouldn't take down the session
+                match app.load_song(&path) {
+                    Ok(()) => {
+                        for output in outputs.iter_mut() {
+                            output.song_changed();
+                        }
+                    }
+                    Err(e) => log::warn!("couldn't load {} from the remote: {}", path.display(), e),
+                }
+                None
+            }
+            AppEvent::Remote(command) => {
+                log::debug!("remote command {:?}", command);
+                handle_remote(app, command)
+            }
+            AppEvent::Sync(update) => {
+                match update {
+                    SyncUpdate::Connected(connected) => {
+                        log::info!("{} the sync host", if connected { "connected to" } else { "lost" });
+                        app.sync_connected = Some(connected);
+                    }
+                    SyncUpdate::Song(config) => {
+                        app.load_config(*config);
+                        for output in outputs.iter_mut() {
+                            output.song_changed();
+                        }
+                    }
+                    SyncUpdate::Position { position, paused } => app.follow_host(position, paused),
+                }
+                None
+            }
+            AppEvent::SongRequest(request) => {
+                if let Some(session) = party.as_deref_mut() {
+                    let message = format!("{} requested {}", request.guest, request.name);
+                    if session.add_request(request) {
+                        app.notify(message);
+                    }
+                }
+                None
+            }
+            // Only the daemon listens for these
+            AppEvent::Daemon(_) => None,
+            AppEvent::MicLevel(level) => {
+                if let Some(meter) = &mut app.mic {
+                    meter.update(level, Instant::now());
+                }
+                None
+            }
+        };
+
+        if let Some(outcome) = outcome {
+            return Ok(outcome);
+        }
+    }
+}
+
+fn handle_key(app: &mut KaraokeApp, key: KeyEvent, in_party: bool) -> Option<SongOutcome> {
+    // Raw mode turns Ctrl+C into a key press instead of SIGINT
+    if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+        return Some(SongOutcome::Quit);
+    }
+    if matches!(app.screen, Screen::Preview { .. }) {
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Char('Q') => return Some(SongOutcome::Quit),
+            KeyCode::Up => app.scroll_preview(-1),
+            KeyCode::Down => app.scroll_preview(1),
+            KeyCode::PageUp => app.scroll_preview(-10),
+            KeyCode::PageDown => app.scroll_preview(10),
+            KeyCode::Char('s') | KeyCode::Char('S') => app.swap_parts(),
+            KeyCode::Enter => app.leave_preview(),
+            _ => {}
+        }
+        return None;
+    }
+    if app.screen != Screen::Playing {
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Char('Q') => return Some(SongOutcome::Quit),
+            KeyCode::Char(' ') | KeyCode::Enter => app.start_after_splash(),
+            _ => {}
+        }
+        return None;
+    }
+
+    if let Some(find) = &mut app.find {
+        match key.code {
+            KeyCode::Char(c) => find.push(c, &app.lyrics),
+            KeyCode::Backspace => find.pop(&app.lyrics),
+            KeyCode::Up => find.step(-1),
+            KeyCode::Down | KeyCode::Tab => find.step(1),
+            KeyCode::Enter => app.jump_to_find(),
+            KeyCode::Esc => app.find = None,
+            _ => {}
+        }
+        return None;
+    }
+    if key.code == KeyCode::Char('/') && !app.lyrics.is_empty() && app.typing.is_none() {
+        app.open_find();
+        return None;
+    }
+
+    // Every character is typed into the line, so Esc quits and Tab pauses instead
+    if app.typing.is_some() {
+        let time = app.lyric_time();
+        match key.code {
+            KeyCode::Char(c) => {
+                if let Some(typing) = &mut app.typing {
+                    typing.type_char(&app.lyrics, time, c);
+                }
+                return None;
+            }
+            KeyCode::Backspace => {
+                if let Some(typing) = &mut app.typing {
+                    typing.backspace(&app.lyrics, time);
+                }
+                return None;
+            }
+            KeyCode::Tab => {
+                app.press_play();
+                return None;
+            }
+            KeyCode::Esc => return Some(SongOutcome::Quit),
+            _ => {}
+        }
+    }
+
+    // Letters are typed into the quiz answer, so Esc quits instead of Q
+    if let Some(quiz) = &mut app.quiz {
+        match key.code {
+            KeyCode::Char(c) if c.is_alphabetic() || c == '\'' => {
+                quiz.input.push(c);
+                return None;
+            }
+            KeyCode::Backspace => {
+                quiz.input.pop();
+                return None;
+            }
+            KeyCode::Enter => {
+                app.answer_quiz();
+                return None;
+            }
+            KeyCode::Esc => return Some(SongOutcome::Quit),
+            _ => {}
+        }
+    }
+
+    match key.code {
+        KeyCode::Char('q') | KeyCode::Char('Q') => return Some(SongOutcome::Quit),
+        KeyCode::Char(' ') => app.press_play(),
+        KeyCode::Char('r') | KeyCode::Char('R') => app.restart(),
+        KeyCode::Char('n') | KeyCode::Char('N') if in_party && app.is_song_ended() => {
+            return Some(SongOutcome::Next);
+        },
+        KeyCode::Char('n') | KeyCode::Char('N') => app.next_section(),
+        KeyCode::Char('p') | KeyCode::Char('P') => app.previous_section(),
+        KeyCode::Enter => app.tap(),
+        KeyCode::Char('v') | KeyCode::Char('V') => {
+            if let Some(visualizer) = &mut app.visualizer {
+                visualizer.cycle();
+                let message = match visualizer.mode {
+                    visualizer::VisualizerMode::Spectrum => "Spectrum",
+                    visualizer::VisualizerMode::Waveform => "Waveform",
+                    visualizer::VisualizerMode::Hidden => "Visualizer hidden",
+                };
+                app.notify(message);
+            }
+        }
+        KeyCode::Char('a') | KeyCode::Char('A') => app.mark_loop_start(),
+        KeyCode::Char('b') | KeyCode::Char('B') => app.mark_loop_end(),
+        KeyCode::Char('l') | KeyCode::Char('L') => app.clear_loop(),
+        KeyCode::Char('m') | KeyCode::Char('M') => app.cycle_repeat(),
+        KeyCode::Char('c') | KeyCode::Char('C') => app.toggle_chords(),
+        KeyCode::Char('d') | KeyCode::Char('D') => app.toggle_debug(),
+        KeyCode::Char('+') | KeyCode::Char('=') => app.transpose_by(1),
+        KeyCode::Char('-') => app.transpose_by(-1),
+        KeyCode::Char('[') => app.adjust_speed(-0.1),
+        KeyCode::Char(']') => app.adjust_speed(0.1),
+        KeyCode::Up => app.adjust_offset(0.1),
+        KeyCode::Down => app.adjust_offset(-0.1),
+        KeyCode::Char('o') | KeyCode::Char('O') => app.save_offset(),
+        KeyCode::Char('9') => app.adjust_volume(-playback::VOLUME_STEP),
+        KeyCode::Char('0') => app.adjust_volume(playback::VOLUME_STEP),
+        KeyCode::Char('x') | KeyCode::Char('X') => app.toggle_mute(),
+        KeyCode::Char('k') | KeyCode::Char('K') => app.toggle_vocals(),
+        KeyCode::Char('e') | KeyCode::Char('E') => app.step_echo(),
+        KeyCode::Char('f') | KeyCode::Char('F') => app.toggle_favorite(),
+        KeyCode::Left => {
+            app.seek(-0.3);
+        },
+        KeyCode::Right => {
+            app.seek(0.3);
+        },
+        _ => {}
+    }
+    None
+}
+
+// The host answers the oldest song request from anywhere in the party; returns true when the
+// key was for that
+fn handle_request_key(app: &mut KaraokeApp, session: &mut PartySession, key: KeyEvent) -> bool {
+    if session.requests.is_empty() || app.typing.is_some() || app.quiz.is_some() || app.find.is_some() {
+        return false;
+    }
+    let message = match key.code {
+        KeyCode::Char('y') | KeyCode::Char('Y') => session.accept_request().map(|request| format!("Added {} to the playlist", request.name)),
+        KeyCode::Backspace => session.decline_request().map(|request| format!("Declined {}", request.name)),
+        _ => return false,
+    };
+    if let Some(message) = message {
+        app.notify(message);
+    }
+    true
+}
+
+// , and . switch the panes on the left and right
+fn handle_pane_key(app: &KaraokeApp, session: &mut PartySession, key: KeyEvent) -> bool {
+    let Some(panes) = session.panes.as_mut().filter(|_| app.typing.is_none() && app.quiz.is_none() && app.find.is_none()) else {
+        return false;
+    };
+    match key.code {
+        KeyCode::Char(',') => panes.scoreboard = !panes.scoreboard,
+        KeyCode::Char('.') => panes.queue = !panes.queue,
+        _ => return false,
+    }
+    true
+}
+
+fn handle_remote(app: &mut KaraokeApp, command: RemoteCommand) -> Option<SongOutcome> {
+    match command {
+        RemoteCommand::Play => app.play(),
+        RemoteCommand::Pause => app.pause(),
+        RemoteCommand::TogglePause => app.toggle_pause(),
+        RemoteCommand::SeekTo(position) => app.seek_to(position),
+        RemoteCommand::SeekBy(delta) => app.seek(delta),
+        RemoteCommand::Next => return Some(SongOutcome::Next),
+        RemoteCommand::Load(_) => {}
+        RemoteCommand::SetOffset(offset) => app.adjust_offset(offset - app.lyric_offset),
+        RemoteCommand::AdjustOffset(delta) => app.adjust_offset(delta),
+        RemoteCommand::SetTheme(theme) => app.background.set_theme(theme),
+    }
+    None
 }
@@ -0,0 +1,182 @@
+use std::{
+    fs,
+    io::{self, BufRead, IsTerminal, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{formats, library, wav};
+
+// A match this good is taken without asking, unless another comes close to it
+const SURE: f64 = 0.6;
+const CLOSE: f64 = 0.15;
+// Anything below this isn't offered at all
+const POSSIBLE: f64 = 0.3;
+const OFFERED: usize = 5;
+
+// What's known about the recording to go on
+#[derive(Debug)]
+struct Recording {
+    name: String,
+    duration: f64,
+}
+
+#[derive(Debug)]
+struct Candidate {
+    path: PathBuf,
+    score: f64,
+}
+
+fn words(text: &str) -> Vec<String> {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+// How many words two names share, from 0 to 1
+fn name_similarity(a: &str, b: &str) -> f64 {
+    let (a, b) = (words(a), words(b));
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    let shared = a.iter().filter(|word| b.contains(word)).count() + b.iter().filter(|word| a.contains(word)).count();
+    shared as f64 / (a.len() + b.len()) as f64
+}
+
+// A song's given length should be the recording's; without one, the lyrics shouldn't run past it
+fn duration_similarity(recording: f64, song: f64, lyrics_end: f64) -> f64 {
+    match (recording > 0.0, song > 0.0) {
+        (false, _) => 0.5,
+        (true, true) => (1.0 - (recording - song).abs() / 30.0).max(0.0),
+        (true, false) if lyrics_end > recording + 5.0 => 0.0,
+        (true, false) => 0.5,
+    }
+}
+
+fn score(recording: &Recording, path: &Path) -> Option<f64> {
+    let config = formats::load(path).ok()?;
+    let stem = path.file_stem()?.to_string_lossy();
+    let tagged = library::song_stem(config.artist.as_deref(), &config.title);
+    let name = name_similarity(&recording.name, &stem).max(name_similarity(&recording.name, &tagged));
+    let lyrics_end = config.lyrics.iter().map(|line| line.end_time).fold(0.0, f64::max);
+    Some(0.8 * name + 0.2 * duration_similarity(recording.duration, config.duration, lyrics_end))
+}
+
+fn describe(audio: &Path) -> io::Result<Recording> {
+    let bytes = fs::read(audio)?;
+    let recording = wav::parse(&bytes)?;
+    let tags = wav::info_tags(&bytes);
+    let stem = audio.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+    let name = match (tags.artist, tags.title) {
+        (artist, Some(title)) => library::song_stem(artist.as_deref(), &title),
+        (_, None) => stem,
+    };
+    Ok(Recording {
+        name,
+        duration: recording.frames() as f64 / recording.sample_rate.max(1) as f64,
+    })
+}
+
+fn song_files(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries.flatten().map(|entry| entry.path()).filter(|path| path.is_file() && formats::is_song_file(path)).collect()
+}
+
+// The lyric files next to the recording and in the library, best match first
+fn candidates(audio: &Path, library: &Path) -> io::Result<Vec<Candidate>> {
+    let recording = describe(audio)?;
+    let dir = audio.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let mut paths = song_files(dir);
+    paths.extend(song_files(library));
+    paths.sort();
+    paths.dedup();
+
+    let mut found: Vec<Candidate> = paths
+        .into_iter()
+        .filter_map(|path| {
+            // A lyric file named like the recording is what the rest of the app pairs it with
+            let score = match path.file_stem() == audio.file_stem() && path.parent() == audio.parent() {
+                true => 1.0,
+                false => score(&recording, &path)?,
+            };
+            Some(Candidate { path, score })
+        })
+        .filter(|candidate| candidate.score >= POSSIBLE)
+        .collect();
+    found.sort_by(|a, b| b.score.total_cmp(&a.score));
+    Ok(found)
+}
+
+fn is_clear(found: &[Candidate]) -> bool {
+    match found {
+        [best, next, ..] => best.score >= SURE && best.score - next.score >= CLOSE,
+        [best] => best.score >= SURE,
+        [] => false,
+    }
+}
+
+fn ask(audio: &Path, found: &[Candidate]) -> io::Result<Option<PathBuf>> {
+    println!("Lyrics for {}:", audio.display());
+    for (i, candidate) in found.iter().take(OFFERED).enumerate() {
+        println!("  {}) {} ({:.0}%)", i + 1, candidate.path.display(), candidate.score * 100.0);
+    }
+    print!("Play with which? [1] ");
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+    let pick = match answer.trim() {
+        "" => Some(0),
+        answer => answer.parse::<usize>().ok().and_then(|n| n.checked_sub(1)),
+    };
+    Ok(pick.and_then(|i| found.get(i)).map(|candidate| candidate.path.clone()))
+}
+
+// Looks for the lyrics to a recording given without them. A clear winner is used straight away;
+// otherwise the singer picks from the likely ones, or off a terminal the best is used if it's good
+pub fn find_lyrics(audio: &Path, library: &Path) -> io::Result<PathBuf> {
+    let found = candidates(audio, library)?;
+    let not_found = || {
+        let message = format!("no lyrics found for {}; give the lyric file as the song", audio.display());
+        io::Error::new(io::ErrorKind::NotFound, message)
+    };
+    let picked = match (is_clear(&found), io::stdin().is_terminal()) {
+        (true, _) => found.first().map(|candidate| candidate.path.clone()),
+        (false, true) if !found.is_empty() => ask(audio, &found)?,
+        (false, _) => found.first().filter(|candidate| candidate.score >= SURE).map(|candidate| candidate.path.clone()),
+    };
+    let picked = picked.ok_or_else(not_found)?;
+    log::info!("playing {} with lyrics from {}", audio.display(), picked.display());
+    Ok(picked)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_and_lengths_are_compared() {
+        assert_eq!(name_similarity("Queen - Bohemian Rhapsody", "bohemian_rhapsody (queen)"), 1.0);
+        assert!(name_similarity("a-ha - Take On Me", "Take on me") > name_similarity("a-ha - Take On Me", "Take Five"));
+        assert_eq!(name_similarity("", "anything"), 0.0);
+
+        assert_eq!(duration_similarity(200.0, 200.0, 190.0), 1.0);
+        assert_eq!(duration_similarity(200.0, 260.0, 250.0), 0.0);
+        // Lyrics that run well past the end of the recording are for some other song
+        assert_eq!(duration_similarity(100.0, 0.0, 190.0), 0.0);
+
+        let found = |scores: &[f64]| -> Vec<Candidate> {
+            scores.iter().map(|&score| Candidate { path: PathBuf::new(), score }).collect()
+        };
+        assert!(is_clear(&found(&[0.9, 0.5])));
+        assert!(!is_clear(&found(&[0.9, 0.85])));
+        assert!(!is_clear(&found(&[0.5])));
+
+        let mut tagged = b"RIFF\0\0\0\0WAVE".to_vec();
+        tagged.extend_from_slice(b"LIST\x1e\0\0\0INFOINAM\x05\0\0\0Song\0\0IART\x03\0\0\0Me\0\0");
+        let tags = wav::info_tags(&tagged);
+        assert_eq!((tags.title.as_deref(), tags.artist.as_deref()), (Some("Song"), Some("Me")));
+    }
+}
@@ -0,0 +1,109 @@
+use std::{
+    f64::consts::TAU,
+    io::{self, Write},
+    process::{Child, Command, Stdio},
+    sync::mpsc::{self, SyncSender, TrySendError},
+    thread,
+};
+
+// Any command that plays raw signed 16-bit little-endian mono PCM at this rate from stdin works
+pub const DEFAULT_COMMAND: &str = "aplay -q -f S16_LE -r 16000 -c 1 -t raw";
+const SAMPLE_RATE: usize = 16_000;
+const CLICK_SECONDS: f64 = 0.03;
+// A click that can't be played by the next few beats would only be late, so it's dropped
+const QUEUED_CLICKS: usize = 4;
+
+// A short decaying beep, higher on the downbeat
+fn click(accent: bool) -> Vec<u8> {
+    let pitch = if accent { 1760.0 } else { 1320.0 };
+    let samples = (CLICK_SECONDS * SAMPLE_RATE as f64) as usize;
+    (0..samples)
+        .flat_map(|i| {
+            let time = i as f64 / SAMPLE_RATE as f64;
+            let envelope = (-time / (CLICK_SECONDS / 5.0)).exp();
+            let sample = (TAU * pitch * time).sin() * envelope * 0.8;
+            ((sample * i16::MAX as f64) as i16).to_le_bytes()
+        })
+        .collect()
+}
+
+// Feeds a click to the playback command on every new beat; the command is killed when this
+// is dropped
+pub struct Metronome {
+    child: Child,
+    clicks: Option<SyncSender<bool>>,
+    last_beat: Option<i64>,
+}
+
+impl Metronome {
+    pub fn start(command: &str) -> io::Result<Self> {
+        let mut parts = command.split_whitespace();
+        let program = parts.next().ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "empty click command"))?;
+        let mut child = Command::new(program)
+            .args(parts)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| io::Error::new(e.kind(), format!("couldn't start the metronome `{}`: {}", command, e)))?;
+        let (clicks, queue) = mpsc::sync_channel::<bool>(QUEUED_CLICKS);
+        if let Some(mut stdin) = child.stdin.take() {
+            thread::spawn(move || {
+                for accent in queue {
+                    if let Err(e) = stdin.write_all(&click(accent)).and_then(|_| stdin.flush()) {
+                        log::warn!("the click command stopped playing: {}", e);
+                        break;
+                    }
+                }
+            });
+        }
+        Ok(Self {
+            child,
+            clicks: Some(clicks),
+            last_beat: None,
+        })
+    }
+
+    // Clicks when `beat` is one it hasn't clicked yet; a player that has gone away is left alone
+    pub fn update(&mut self, beat: i64, downbeat: bool) {
+        if self.last_beat == Some(beat) {
+            return;
+        }
+        self.last_beat = Some(beat);
+        let Some(clicks) = &self.clicks else {
+            return;
+        };
+        match clicks.try_send(downbeat) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => log::warn!("the click command is behind; dropped a click"),
+            Err(TrySendError::Disconnected(_)) => self.clicks = None,
+        }
+    }
+}
+
+impl Drop for Metronome {
+    fn drop(&mut self) {
+        self.clicks = None;
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clicks_fade_out_and_accent_the_downbeat() {
+        let samples = |accent| -> Vec<i16> {
+            click(accent).chunks_exact(2).map(|b| i16::from_le_bytes([b[0], b[1]])).collect()
+        };
+        let (plain, accent) = (samples(false), samples(true));
+        assert_eq!(plain.len(), SAMPLE_RATE * 3 / 100);
+
+        let crossings = |s: &[i16]| s.windows(2).filter(|w| (w[0] < 0) != (w[1] < 0)).count();
+        assert!(crossings(&accent) > crossings(&plain));
+        let loudest = |s: &[i16]| s.iter().map(|s| s.unsigned_abs()).max().unwrap();
+        assert!(loudest(&plain[plain.len() - 50..]) < loudest(&plain[..50]) / 20);
+    }
+}
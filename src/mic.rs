@@ -0,0 +1,184 @@
+use std::{
+    io::{self, Read},
+    process::{Child, Command, Stdio},
+    sync::mpsc::Sender,
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{events::AppEvent, monitor::{self, Monitor}};
+
+// Any command that writes raw signed 16-bit little-endian mono PCM at this rate to stdout works
+pub const DEFAULT_COMMAND: &str = "arecord -q -f S16_LE -r 16000 -c 1 -t raw";
+pub const SAMPLE_RATE: usize = 16_000;
+// One level reading per 50ms of audio
+const BLOCK_SAMPLES: usize = SAMPLE_RATE / 20;
+const FLOOR_DB: f64 = -60.0;
+const CLIP_LEVEL: f32 = 0.99;
+const CLIP_HOLD: Duration = Duration::from_secs(1);
+// Peak marker falls this many dB per second
+const PEAK_FALL: f64 = 20.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MicLevel {
+    pub rms: f32,
+    pub peak: f32,
+}
+
+fn level(samples: &[i16]) -> MicLevel {
+    let normalized = samples.iter().map(|&s| s as f32 / i16::MAX as f32);
+    let (energy, peak) = normalized.fold((0.0, 0.0_f32), |(energy, peak), s| (energy + s * s, peak.max(s.abs())));
+    MicLevel {
+        rms: (energy / samples.len().max(1) as f32).sqrt(),
+        peak,
+    }
+}
+
+fn to_db(amplitude: f32) -> f64 {
+    (20.0 * (amplitude.max(1e-6) as f64).log10()).max(FLOOR_DB)
+}
+
+// Runs the capture command and turns its audio into MicLevel events, passing it on to the
+// monitor if there is one; the command is killed when this is dropped
+pub struct MicCapture {
+    child: Child,
+}
+
+impl MicCapture {
+    // `gain` is in decibels, applied before the meter and the monitor hear the mic
+    pub fn start(command: &str, gain: f64, events: Sender<AppEvent>, monitor: Option<Monitor>) -> io::Result<Self> {
+        let mut parts = command.split_whitespace();
+        let program = parts.next().ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "empty mic command"))?;
+        let mut child = Command::new(program)
+            .args(parts)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| io::Error::new(e.kind(), format!("couldn't start mic capture `{}`: {}", command, e)))?;
+
+        let mut stdout = child.stdout.take().expect("stdout is piped");
+        let gain = 10f32.powf(gain as f32 / 20.0);
+        thread::spawn(move || {
+            // Read in the monitor's smaller blocks so it isn't held up for a whole reading
+            let mut bytes = vec![0u8; monitor::BLOCK_SAMPLES * 2];
+            let mut block = Vec::with_capacity(BLOCK_SAMPLES);
+            while stdout.read_exact(&mut bytes).is_ok() {
+                let samples: Vec<i16> = bytes
+                    .chunks_exact(2)
+                    .map(|b| i16::from_le_bytes([b[0], b[1]]))
+                    .map(|sample| (sample as f32 * gain).clamp(i16::MIN as f32, i16::MAX as f32) as i16)
+                    .collect();
+                if let Some(monitor) = &monitor {
+                    monitor.send(&samples);
+                }
+                block.extend(samples);
+                if block.len() < BLOCK_SAMPLES {
+                    continue;
+                }
+                if events.send(AppEvent::MicLevel(level(&block))).is_err() {
+                    break;
+                }
+                block.clear();
+            }
+        });
+
+        Ok(Self { child })
+    }
+}
+
+impl Drop for MicCapture {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+// What the input meter shows: the current level, a slowly falling peak and a clip warning
+#[derive(Debug, Clone)]
+pub struct MicMeter {
+    pub level_db: f64,
+    pub peak_db: f64,
+    peak_at: Instant,
+    clipped_at: Option<Instant>,
+}
+
+impl Default for MicMeter {
+    fn default() -> Self {
+        Self {
+            level_db: FLOOR_DB,
+            peak_db: FLOOR_DB,
+            peak_at: Instant::now(),
+            clipped_at: None,
+        }
+    }
+}
+
+impl MicMeter {
+    pub fn update(&mut self, level: MicLevel, now: Instant) {
+        self.level_db = to_db(level.rms);
+        let fallen = self.peak_db - PEAK_FALL * now.duration_since(self.peak_at).as_secs_f64();
+        if to_db(level.peak) >= fallen {
+            self.peak_db = to_db(level.peak);
+            self.peak_at = now;
+        }
+        if level.peak >= CLIP_LEVEL {
+            self.clipped_at = Some(now);
+        }
+    }
+
+    pub fn is_clipping(&self, now: Instant) -> bool {
+        self.clipped_at.is_some_and(|at| now.duration_since(at) < CLIP_HOLD)
+    }
+
+    // The meter bar: `width` cells, filled up to the level, with the peak marked by `|`
+    pub fn bar(&self, width: usize) -> String {
+        let cells = |db: f64| (((db - FLOOR_DB) / -FLOOR_DB) * width as f64).round() as usize;
+        let filled = cells(self.level_db).min(width);
+        let peak = cells(self.peak_db).min(width);
+        (0..width)
+            .map(|i| {
+                if i < filled {
+                    '█'
+                } else if i + 1 == peak {
+                    '|'
+                } else {
+                    '░'
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn full_scale_samples_clip() {
+        let mut meter = MicMeter::default();
+        let start = Instant::now();
+        meter.update(level(&[i16::MAX, i16::MIN, i16::MAX]), start);
+        assert!(meter.is_clipping(start));
+        assert!(!meter.is_clipping(start + Duration::from_secs(2)));
+        assert!(meter.level_db > -0.1);
+    }
+
+    #[test]
+    fn peak_holds_then_falls() {
+        let mut meter = MicMeter::default();
+        let start = Instant::now();
+        meter.update(MicLevel { rms: 0.1, peak: 0.5 }, start);
+        meter.update(MicLevel { rms: 0.01, peak: 0.02 }, start + Duration::from_millis(50));
+        assert!((meter.peak_db - to_db(0.5)).abs() < 1e-9);
+        meter.update(MicLevel { rms: 0.01, peak: 0.02 }, start + Duration::from_secs(3));
+        assert!((meter.peak_db - to_db(0.02)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn bar_shows_level_and_peak() {
+        let mut meter = MicMeter::default();
+        meter.update(MicLevel { rms: 0.001, peak: 0.1 }, Instant::now());
+        // -60dB..0dB over 6 cells: level at -60dB is empty, peak at -20dB is the fourth cell
+        assert_eq!(meter.bar(6), "░░░|░░");
+    }
+}
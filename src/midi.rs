@@ -0,0 +1,264 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Read, Write},
+    path::Path,
+    sync::{
+        mpsc::{self, SyncSender, TrySendError},
+        Arc, Mutex,
+    },
+    thread,
+    time::Instant,
+};
+
+use crate::{plugin::{Renderer, TimeSource}, KaraokeApp};
+
+// MIDI clock runs at 24 pulses per quarter note; Song Position Pointer counts sixteenth notes
+const PULSES_PER_BEAT: f64 = 24.0;
+const PULSES_PER_SIXTEENTH: i64 = 6;
+const TIMING_CLOCK: u8 = 0xF8;
+const START: u8 = 0xFA;
+const CONTINUE: u8 = 0xFB;
+const STOP: u8 = 0xFC;
+const SONG_POSITION: u8 = 0xF2;
+// Until the first two pulses arrive, assume 120 BPM
+const DEFAULT_PULSE_SECONDS: f64 = 0.5 / PULSES_PER_BEAT;
+// Messages for a device that's stopped taking them are dropped past this
+const QUEUED_MESSAGES: usize = 64;
+
+// Song time driven by incoming clock pulses. The tempo is measured from the pulse spacing, so
+// the display follows tempo changes in the sequencer without being told the BPM.
+#[derive(Debug)]
+struct ClockState {
+    seconds: f64,
+    running: bool,
+    last_pulse: Option<Instant>,
+    pulse_seconds: f64,
+    pending_position: Option<Vec<u8>>,
+}
+
+impl Default for ClockState {
+    fn default() -> Self {
+        Self {
+            seconds: 0.0,
+            running: false,
+            last_pulse: None,
+            pulse_seconds: DEFAULT_PULSE_SECONDS,
+            pending_position: None,
+        }
+    }
+}
+
+impl ClockState {
+    fn handle(&mut self, byte: u8, now: Instant) {
+        if let Some(data) = &mut self.pending_position {
+            if byte < 0x80 {
+                data.push(byte);
+                if let [lsb, msb] = data[..] {
+                    let sixteenths = ((msb as i64) << 7) | lsb as i64;
+                    self.seconds = (sixteenths * PULSES_PER_SIXTEENTH) as f64 * self.pulse_seconds;
+                    self.pending_position = None;
+                }
+                return;
+            }
+            // Real-time bytes can interleave with the pointer's data; anything else cancels it
+            if byte < TIMING_CLOCK {
+                self.pending_position = None;
+            }
+        }
+
+        match byte {
+            TIMING_CLOCK if self.running => {
+                if let Some(last) = self.last_pulse {
+                    let interval = now.duration_since(last).as_secs_f64();
+                    // Smoothed, and ignoring gaps so long they can only be a stall
+                    if interval < self.pulse_seconds * 4.0 {
+                        self.pulse_seconds = self.pulse_seconds * 0.9 + interval * 0.1;
+                    }
+                }
+                self.seconds += self.pulse_seconds;
+                self.last_pulse = Some(now);
+            }
+            TIMING_CLOCK => self.last_pulse = Some(now),
+            START => {
+                self.seconds = 0.0;
+                self.running = true;
+            }
+            CONTINUE => self.running = true,
+            STOP => self.running = false,
+            SONG_POSITION => self.pending_position = Some(Vec::new()),
+            _ => {}
+        }
+    }
+
+    // Between pulses time moves on smoothly, but never past where the next pulse would land
+    fn now(&self, now: Instant) -> f64 {
+        let since_pulse = match (self.running, self.last_pulse) {
+            (true, Some(last)) => now.duration_since(last).as_secs_f64().min(self.pulse_seconds),
+            _ => 0.0,
+        };
+        self.seconds + since_pulse
+    }
+}
+
+pub struct MidiClock {
+    state: Arc<Mutex<ClockState>>,
+}
+
+impl MidiClock {
+    // Reads raw MIDI from a device such as /dev/snd/midiC1D0
+    pub fn follow(device: &Path) -> io::Result<Self> {
+        let mut input = File::open(device)
+            .map_err(|e| io::Error::new(e.kind(), format!("couldn't open MIDI input {}: {}", device.display(), e)))?;
+        let state = Arc::new(Mutex::new(ClockState::default()));
+        let reader_state = state.clone();
+        thread::spawn(move || {
+            let mut buf = [0u8; 64];
+            while let Ok(len) = input.read(&mut buf) {
+                if len == 0 {
+                    break;
+                }
+                let now = Instant::now();
+                let mut state = reader_state.lock().unwrap_or_else(|e| e.into_inner());
+                for &byte in &buf[..len] {
+                    state.handle(byte, now);
+                }
+            }
+        });
+        Ok(Self { state })
+    }
+}
+
+impl TimeSource for MidiClock {
+    fn now(&self) -> f64 {
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).now(Instant::now())
+    }
+}
+
+// Drives a sequencer or DAW from the song: clock pulses at the song's BPM, a Song Position
+// Pointer after every seek, and stop/continue with pause
+pub struct MidiOut {
+    device: Option<SyncSender<Vec<u8>>>,
+    beat_seconds: f64,
+    running: bool,
+    sent_pulse: i64,
+}
+
+impl MidiOut {
+    pub fn open(device: &Path, bpm: f64) -> io::Result<Self> {
+        let mut file = OpenOptions::new()
+            .write(true)
+            .open(device)
+            .map_err(|e| io::Error::new(e.kind(), format!("couldn't open MIDI output {}: {}", device.display(), e)))?;
+        let (device, queue) = mpsc::sync_channel::<Vec<u8>>(QUEUED_MESSAGES);
+        thread::spawn(move || {
+            for bytes in queue {
+                if let Err(e) = file.write_all(&bytes) {
+                    log::warn!("stopped sending MIDI: {}", e);
+                    break;
+                }
+            }
+        });
+        Ok(Self {
+            device: Some(device),
+            beat_seconds: 60.0 / bpm,
+            running: false,
+            sent_pulse: 0,
+        })
+    }
+
+    fn messages(&mut self, time: f64, playing: bool) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let target = (time / self.beat_seconds * PULSES_PER_BEAT).floor() as i64;
+
+        if !playing {
+            if self.running {
+                bytes.push(STOP);
+                self.running = false;
+            }
+            return bytes;
+        }
+
+        // Starting, resuming, or a seek the sequencer can't catch up with by pulses alone
+        let jumped = target < self.sent_pulse || target - self.sent_pulse > PULSES_PER_BEAT as i64;
+        if !self.running || jumped {
+            let sixteenths = (target / PULSES_PER_SIXTEENTH).clamp(0, 0x3FFF);
+            if self.running {
+                bytes.push(STOP);
+            }
+            bytes.extend([SONG_POSITION, (sixteenths & 0x7F) as u8, (sixteenths >> 7) as u8, CONTINUE]);
+            self.sent_pulse = sixteenths * PULSES_PER_SIXTEENTH;
+            self.running = true;
+        }
+
+        while self.sent_pulse < target {
+            bytes.push(TIMING_CLOCK);
+            self.sent_pulse += 1;
+        }
+        bytes
+    }
+}
+
+impl Renderer for MidiOut {
+    fn render(&mut self, app: &KaraokeApp) -> io::Result<()> {
+        let playing = !app.paused && !app.is_song_ended();
+        let bytes = self.messages(app.get_current_time(), playing);
+        // An unplugged or stalled device shouldn't stop the song
+        let Some(device) = self.device.as_ref().filter(|_| !bytes.is_empty()) else {
+            return Ok(());
+        };
+        match device.try_send(bytes) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => log::warn!("the MIDI output is behind; dropped messages"),
+            Err(TrySendError::Disconnected(_)) => self.device = None,
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn follows_pulses_and_song_position() {
+        let mut state = ClockState::default();
+        let start = Instant::now();
+        state.handle(START, start);
+        // 100 BPM is 25ms per pulse
+        for i in 0..=96 {
+            state.handle(TIMING_CLOCK, start + Duration::from_millis(25 * i));
+        }
+        assert!((state.pulse_seconds - 0.025).abs() < 0.001, "pulse {}", state.pulse_seconds);
+
+        // Sixteenth 130 (0x82) with a clock pulse in the middle of the pointer
+        state.handle(STOP, start);
+        state.handle(SONG_POSITION, start);
+        state.handle(0x02, start);
+        state.handle(TIMING_CLOCK, start);
+        state.handle(0x01, start);
+        assert!((state.seconds - 130.0 * 6.0 * state.pulse_seconds).abs() < 1e-9);
+        assert_eq!(state.now(start + Duration::from_secs(1)), state.seconds);
+    }
+
+    fn open_null(bpm: f64) -> MidiOut {
+        MidiOut::open(Path::new("/dev/null"), bpm).unwrap()
+    }
+
+    #[test]
+    fn sends_position_then_pulses() {
+        let mut out = open_null(120.0);
+        // Half a beat at 120 BPM is 12 pulses, two sixteenths
+        assert_eq!(out.messages(0.25, true), [SONG_POSITION, 2, 0, CONTINUE]);
+        assert_eq!(out.messages(0.3, true), [TIMING_CLOCK; 2]);
+        assert_eq!(out.messages(0.3, false), [STOP]);
+        assert!(out.messages(0.3, false).is_empty());
+    }
+
+    #[test]
+    fn seeking_back_repositions() {
+        let mut out = open_null(120.0);
+        out.messages(10.0, true);
+        assert_eq!(out.messages(1.0, true), [STOP, SONG_POSITION, 8, 0, CONTINUE]);
+    }
+}
@@ -0,0 +1,62 @@
+use std::io;
+
+use crate::wav::WavData;
+
+pub struct MixOptions {
+    pub vocal_gain: f32,
+    pub backing_gain: f32,
+    // Seconds the recorded vocal lags behind the backing track; negative values delay the vocal
+    pub latency_offset: f64,
+}
+
+impl Default for MixOptions {
+    fn default() -> Self {
+        Self {
+            vocal_gain: 1.0,
+            backing_gain: 1.0,
+            latency_offset: 0.0,
+        }
+    }
+}
+
+pub fn mix(vocal: &WavData, backing: &WavData, options: &MixOptions) -> io::Result<WavData> {
+    if vocal.sample_rate != backing.sample_rate {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "sample rate mismatch: vocal {} Hz, backing {} Hz",
+                vocal.sample_rate, backing.sample_rate
+            ),
+        ));
+    }
+
+    let channels = backing.channels.max(vocal.channels);
+    let offset_frames = (options.latency_offset * backing.sample_rate as f64).round() as i64;
+    let vocal_end = vocal.frames() as i64 - offset_frames;
+    let total_frames = (backing.frames() as i64).max(vocal_end).max(0) as usize;
+
+    let mut samples = Vec::with_capacity(total_frames * channels as usize);
+    for frame in 0..total_frames {
+        let vocal_frame = frame as i64 + offset_frames;
+        for ch in 0..channels as usize {
+            let mut value = sample_at(backing, frame as i64, ch) * options.backing_gain;
+            value += sample_at(vocal, vocal_frame, ch) * options.vocal_gain;
+            samples.push(value.clamp(-1.0, 1.0));
+        }
+    }
+
+    Ok(WavData {
+        sample_rate: backing.sample_rate,
+        channels,
+        samples,
+    })
+}
+
+// Mono sources are spread to every output channel; missing frames are silence
+fn sample_at(wav: &WavData, frame: i64, channel: usize) -> f32 {
+    if frame < 0 || frame as usize >= wav.frames() {
+        return 0.0;
+    }
+    let frame_samples = wav.frame(frame as usize);
+    frame_samples[channel.min(frame_samples.len() - 1)]
+}
@@ -0,0 +1,154 @@
+use std::{
+    io::{self, Write},
+    process::{Child, Command, Stdio},
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        mpsc::{self, Receiver, SyncSender, TrySendError},
+        Arc,
+    },
+    thread,
+};
+
+use crate::mic;
+
+// Plays the mic's raw 16 kHz mono audio with a short output buffer to keep the delay down
+pub const DEFAULT_COMMAND: &str = "aplay -q -t raw -f S16_LE -r 16000 -c 1 --buffer-time=40000";
+pub const DEFAULT_ECHO: f64 = 0.3;
+pub const ECHO_STEP: f64 = 0.1;
+// The mic is passed on in blocks this long, and the player is allowed to fall this many blocks
+// behind before blocks are dropped, so the singer never hears themselves more than ~150ms late
+pub const BLOCK_SAMPLES: usize = mic::SAMPLE_RATE / 100;
+const MAX_QUEUED_BLOCKS: usize = 15;
+// Seconds and feedback of each delay line: a slapback echo, and two short ones that spread it
+// out like a small room
+const LINES: [(f32, f32); 3] = [(0.11, 0.35), (0.037, 0.55), (0.043, 0.5)];
+
+// The default player with the device added
+pub fn command(custom: Option<&str>, device: Option<&str>) -> String {
+    match (custom, device) {
+        (Some(custom), _) => custom.to_string(),
+        (None, Some(device)) => format!("{} -D {}", DEFAULT_COMMAND, device),
+        (None, None) => DEFAULT_COMMAND.to_string(),
+    }
+}
+
+// How much of what's heard is the echo, from 0 for the dry mic to 1; shared with the player
+#[derive(Debug, Clone)]
+pub struct EchoLevel(Arc<AtomicU32>);
+
+impl EchoLevel {
+    pub fn get(&self) -> f64 {
+        f32::from_bits(self.0.load(Ordering::Relaxed)) as f64
+    }
+
+    pub fn set(&self, level: f64) {
+        self.0.store((level.clamp(0.0, 1.0) as f32).to_bits(), Ordering::Relaxed);
+    }
+}
+
+struct Echo {
+    // Each line's samples, where it's at, and how much of it goes round again
+    lines: Vec<(Vec<f32>, usize, f32)>,
+}
+
+impl Echo {
+    fn new(sample_rate: usize) -> Self {
+        let lines = LINES
+            .iter()
+            .map(|&(seconds, feedback)| (vec![0.0; ((sample_rate as f32 * seconds) as usize).max(1)], 0, feedback))
+            .collect();
+        Self { lines }
+    }
+
+    fn apply(&mut self, sample: f32, wet: f32) -> f32 {
+        let mut echo = 0.0;
+        for (line, at, feedback) in &mut self.lines {
+            let delayed = line[*at];
+            line[*at] = sample + delayed * *feedback;
+            *at = (*at + 1) % line.len();
+            echo += delayed;
+        }
+        sample * (1.0 - wet) + echo / self.lines.len() as f32 * wet
+    }
+}
+
+fn play(mut child: Child, blocks: Receiver<Vec<i16>>, level: EchoLevel) {
+    let mut echo = Echo::new(mic::SAMPLE_RATE);
+    if let Some(mut stdin) = child.stdin.take() {
+        while let Ok(block) = blocks.recv() {
+            let wet = level.get() as f32;
+            let bytes: Vec<u8> = block
+                .iter()
+                .flat_map(|&s| {
+                    let out = echo.apply(s as f32 / i16::MAX as f32, wet);
+                    ((out.clamp(-1.0, 1.0) * i16::MAX as f32) as i16).to_le_bytes()
+                })
+                .collect();
+            if let Err(e) = stdin.write_all(&bytes) {
+                log::warn!("the mic monitor stopped playing: {}", e);
+                break;
+            }
+        }
+    }
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+// Sends the mic to a second player, with the echo on it. The player is stopped once the mic's
+// capture ends and this is dropped.
+pub struct Monitor {
+    blocks: SyncSender<Vec<i16>>,
+    level: EchoLevel,
+}
+
+impl Monitor {
+    pub fn start(command: &str, echo: f64) -> io::Result<Self> {
+        let mut parts = command.split_whitespace();
+        let program = parts.next().ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "empty monitor command"))?;
+        let child = Command::new(program)
+            .args(parts)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| io::Error::new(e.kind(), format!("couldn't start the mic monitor `{}`: {}", command, e)))?;
+
+        let level = EchoLevel(Arc::new(AtomicU32::new(0)));
+        level.set(echo);
+        let (blocks, receiver) = mpsc::sync_channel(MAX_QUEUED_BLOCKS);
+        let player_level = level.clone();
+        thread::spawn(move || play(child, receiver, player_level));
+        Ok(Self { blocks, level })
+    }
+
+    pub fn level(&self) -> EchoLevel {
+        self.level.clone()
+    }
+
+    // A block the player is too far behind for is dropped rather than heard late
+    pub fn send(&self, samples: &[i16]) {
+        if let Err(TrySendError::Full(_)) = self.blocks.try_send(samples.to_vec()) {
+            log::debug!("mic monitor is behind, dropped {} samples", samples.len());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_echo_comes_back_after_each_delay() {
+        let mut echo = Echo::new(1000);
+        let out: Vec<f32> = (0..200).map(|i| echo.apply(if i == 0 { 1.0 } else { 0.0 }, 0.3)).collect();
+        assert!((out[0] - 0.7).abs() < 1e-6);
+        // The room's lines come back first, then the slapback
+        let heard: Vec<usize> = (1..200).filter(|&i| out[i] > 0.0).collect();
+        assert_eq!(heard[..3], [37, 43, 74]);
+        assert!((out[110] - 0.1).abs() < 1e-6);
+
+        let mut dry = Echo::new(1000);
+        assert_eq!((0..50).map(|i| dry.apply(i as f32 / 50.0, 0.0)).last(), Some(0.98));
+        assert_eq!(command(None, Some("hw:2")), format!("{} -D hw:2", DEFAULT_COMMAND));
+    }
+}
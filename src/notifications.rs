@@ -0,0 +1,113 @@
+use std::{
+    io,
+    process::{Child, Command, Stdio},
+    time::{Duration, Instant},
+};
+
+use crate::{plugin::Renderer, KaraokeApp};
+
+// The song title and the line are added as the summary and body. The hint has notification
+// daemons that know it replace the last line instead of stacking every line of the song.
+pub const DEFAULT_COMMAND: &str = "notify-send -a karaoke-lyric -u low -t 4000 -h string:x-canonical-private-synchronous:karaoke-lyric";
+// Lines coming quicker than this are skipped so the notifications stay readable; whatever line
+// is being sung once it has passed is shown
+const MIN_GAP: Duration = Duration::from_millis(1500);
+
+// Shows each line as a desktop notification, for following along with the terminal minimized
+// or the daemon in the background
+pub struct DesktopNotifier {
+    command: String,
+    line: Option<usize>,
+    sent_at: Option<Instant>,
+    running: Vec<Child>,
+    failed: bool,
+}
+
+impl DesktopNotifier {
+    pub fn new(command: &str) -> Self {
+        Self {
+            command: command.to_string(),
+            line: None,
+            sent_at: None,
+            running: Vec::new(),
+            failed: false,
+        }
+    }
+
+    // The line to show now, if it hasn't been shown and the last one has been up long enough
+    fn due(&mut self, app: &KaraokeApp, now: Instant) -> Option<usize> {
+        let index = app.active_line(app.lyric_time())?;
+        let rested = self.sent_at.is_none_or(|at| now.duration_since(at) >= MIN_GAP);
+        if self.line == Some(index) || !rested {
+            return None;
+        }
+        self.line = Some(index);
+        self.sent_at = Some(now);
+        Some(index)
+    }
+
+    // A command that can't be started is logged once and then left out
+    fn send(&mut self, summary: &str, body: &str) {
+        self.running.retain_mut(|child| matches!(child.try_wait(), Ok(None)));
+        let mut parts = self.command.split_whitespace();
+        let Some(program) = parts.next() else {
+            return;
+        };
+        // So a line starting with a dash isn't taken for an option
+        let spawned = Command::new(program)
+            .args(parts)
+            .arg("--")
+            .arg(summary)
+            .arg(body)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+        match spawned {
+            Ok(child) => self.running.push(child),
+            Err(e) => {
+                log::warn!("couldn't run the notification command `{}`: {}", self.command, e);
+                self.failed = true;
+            }
+        }
+    }
+}
+
+impl Renderer for DesktopNotifier {
+    fn render(&mut self, app: &KaraokeApp) -> io::Result<()> {
+        if self.failed {
+            return Ok(());
+        }
+        if let Some(i) = self.due(app, Instant::now()) {
+            self.send(&app.song_title, &app.lyrics[i].text);
+        }
+        Ok(())
+    }
+
+    fn song_changed(&mut self) {
+        self.line = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lines_are_shown_once_and_not_too_often() {
+        let mut app = KaraokeApp::from_config(crate::song_config::SongConfig::load(), None);
+        app.paused = true;
+        let mut notifier = DesktopNotifier::new(DEFAULT_COMMAND);
+        let start = Instant::now();
+
+        app.current_position = app.lyrics[0].start_time + 0.1;
+        assert_eq!(notifier.due(&app, start), Some(0));
+        assert_eq!(notifier.due(&app, start + MIN_GAP * 2), None);
+
+        // The next line comes too soon, so it waits until the gap has passed
+        app.current_position = app.lyrics[1].start_time + 0.1;
+        let soon = start + MIN_GAP / 2;
+        assert_eq!(notifier.due(&app, soon), None);
+        assert_eq!(notifier.due(&app, start + MIN_GAP), Some(1));
+    }
+}
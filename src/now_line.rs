@@ -0,0 +1,176 @@
+use serde::Deserialize;
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    os::unix::net::UnixStream,
+    time::Duration,
+};
+
+use crate::{cli::NowLineOptions, layout};
+
+// How far a line too long for the bar moves along, in characters per second of the song, once
+// it has been shown from its start for a moment
+const SCROLL_SPEED: f64 = 3.0;
+const SCROLL_HOLD: f64 = 1.0;
+const SCROLL_GAP: &str = "   ";
+// A daemon that doesn't answer in this long leaves the bar empty rather than hanging it
+const TIMEOUT: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum BarFormat {
+    #[default]
+    Plain,
+    // JSON with Pango markup escaped, for a custom module with `"return-type": "json"`
+    Waybar,
+    // Plain text with polybar's `%{...}` tags escaped
+    Polybar,
+}
+
+impl std::str::FromStr for BarFormat {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "plain" => Ok(BarFormat::Plain),
+            "waybar" => Ok(BarFormat::Waybar),
+            "polybar" => Ok(BarFormat::Polybar),
+            _ => Err(()),
+        }
+    }
+}
+
+// The parts of the daemon's status answer the bar needs
+#[derive(Debug, Deserialize)]
+struct Status {
+    title: String,
+    artist: Option<String>,
+    position: f64,
+    paused: bool,
+    ended: bool,
+    text: Option<String>,
+    line_start: Option<f64>,
+}
+
+fn status(options: &NowLineOptions) -> io::Result<Status> {
+    let stream = UnixStream::connect(&options.socket)?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    (&stream).write_all(b"{\"command\":\"status\"}\n")?;
+    let mut line = String::new();
+    BufReader::new(&stream).read_line(&mut line)?;
+    serde_json::from_str(&line).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+// Cuts `text` to `max_width` cells: with an ellipsis, or when scrolling, as the window `offset`
+// characters into the text going round
+fn fit(text: &str, max_width: usize, scroll: bool, offset: usize) -> String {
+    let width = |c: char| layout::text_width(c.encode_utf8(&mut [0; 4]));
+    if max_width == 0 || layout::text_width(text) <= max_width {
+        return text.to_string();
+    }
+    if !scroll {
+        let mut out = String::new();
+        let mut used = 1;
+        for c in text.chars() {
+            if used + width(c) > max_width {
+                break;
+            }
+            used += width(c);
+            out.push(c);
+        }
+        out.push('…');
+        return out;
+    }
+    let looped: Vec<char> = text.chars().chain(SCROLL_GAP.chars()).collect();
+    let mut out = String::new();
+    let mut used = 0;
+    for &c in looped.iter().cycle().skip(offset % looped.len()) {
+        if used + width(c) > max_width {
+            break;
+        }
+        used += width(c);
+        out.push(c);
+    }
+    out
+}
+
+fn escape_markup(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+// Between lines the bar shows what's playing; with no song going it's left empty
+fn output(status: Option<&Status>, options: &NowLineOptions) -> String {
+    let shown = match status {
+        Some(status) if !status.ended => {
+            let text = status.text.clone().unwrap_or_else(|| format!("♪ {}", status.title));
+            let into_line = status.position - status.line_start.unwrap_or(0.0);
+            let offset = ((into_line - SCROLL_HOLD).max(0.0) * SCROLL_SPEED) as usize;
+            fit(&text, options.max_width.unwrap_or(0), options.scroll, offset)
+        }
+        _ => String::new(),
+    };
+    match options.format {
+        BarFormat::Plain => shown,
+        BarFormat::Polybar => shown.replace('%', "%%"),
+        BarFormat::Waybar => {
+            let tooltip = status.map_or(String::new(), |status| match &status.artist {
+                Some(artist) => format!("{} - {}", status.title, artist),
+                None => status.title.clone(),
+            });
+            let class = match status {
+                Some(status) if status.ended => "ended",
+                Some(status) if status.paused => "paused",
+                Some(_) => "playing",
+                None => "stopped",
+            };
+            serde_json::json!({ "text": escape_markup(&shown), "tooltip": escape_markup(&tooltip), "class": class }).to_string()
+        }
+    }
+}
+
+// Asks a running `--daemon` for its current line and prints it once, for a status bar to poll
+pub fn run(options: &NowLineOptions) -> io::Result<()> {
+    let status = match status(options) {
+        Ok(status) => Some(status),
+        Err(e) => {
+            log::debug!("no daemon answered on {}: {}", options.socket.display(), e);
+            None
+        }
+    };
+    println!("{}", output(status.as_ref(), options));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lines_fit_the_bar() {
+        assert_eq!(fit("short", 10, false, 0), "short");
+        assert_eq!(fit("a much longer line", 8, false, 0), "a much …");
+        assert_eq!(fit("a much longer line", 8, true, 0), "a much l");
+        assert_eq!(fit("abcdef", 4, true, 5), "f   ");
+        assert_eq!(fit("abcdef", 4, true, 8), " abc");
+        assert_eq!(fit("日本語の歌", 5, false, 0), "日本…");
+
+        let status = Status {
+            title: "Song".to_string(),
+            artist: None,
+            position: 1.0,
+            paused: true,
+            ended: false,
+            text: Some("Rock & <roll> 100%".to_string()),
+            line_start: Some(0.5),
+        };
+        let options = |format| NowLineOptions {
+            format,
+            ..NowLineOptions::default()
+        };
+        assert_eq!(output(Some(&status), &options(BarFormat::Polybar)), "Rock & <roll> 100%%");
+        assert_eq!(
+            output(Some(&status), &options(BarFormat::Waybar)),
+            r#"{"class":"paused","text":"Rock &amp; &lt;roll&gt; 100%","tooltip":"Song"}"#
+        );
+        assert_eq!(output(None, &options(BarFormat::Plain)), "");
+    }
+}
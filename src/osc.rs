@@ -0,0 +1,132 @@
+use std::{
+    io,
+    net::UdpSocket,
+    time::{Duration, Instant},
+};
+
+use crate::{plugin::Renderer, timeline, KaraokeApp};
+
+pub const DEFAULT_PREFIX: &str = "/karaoke";
+// Lighting consoles smooth between updates, so a few per second is plenty
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+pub enum OscArg {
+    Str(String),
+    Float(f32),
+}
+
+// OSC strings are null terminated and padded to a multiple of four bytes
+fn push_padded(packet: &mut Vec<u8>, bytes: &[u8]) {
+    packet.extend_from_slice(bytes);
+    packet.push(0);
+    while !packet.len().is_multiple_of(4) {
+        packet.push(0);
+    }
+}
+
+pub fn encode(address: &str, args: &[OscArg]) -> Vec<u8> {
+    let mut packet = Vec::new();
+    push_padded(&mut packet, address.as_bytes());
+
+    let tags: String = std::iter::once(',')
+        .chain(args.iter().map(|arg| match arg {
+            OscArg::Str(_) => 's',
+            OscArg::Float(_) => 'f',
+        }))
+        .collect();
+    push_padded(&mut packet, tags.as_bytes());
+
+    for arg in args {
+        match arg {
+            OscArg::Str(text) => push_padded(&mut packet, text.as_bytes()),
+            OscArg::Float(value) => packet.extend_from_slice(&value.to_be_bytes()),
+        }
+    }
+    packet
+}
+
+// Sends the current line, section and progress to a lighting console or VJ software:
+// `<prefix>/line <text>`, `<prefix>/section <name>` and `<prefix>/progress <time> <line progress>`
+pub struct OscOutput {
+    socket: UdpSocket,
+    prefix: String,
+    line: Option<Option<usize>>,
+    section: Option<Option<String>>,
+    last_progress: Option<Instant>,
+}
+
+impl OscOutput {
+    pub fn connect(target: &str, prefix: &str) -> io::Result<Self> {
+        let socket = UdpSocket::bind("0.0.0.0:0")?;
+        socket
+            .connect(target)
+            .map_err(|e| io::Error::new(e.kind(), format!("couldn't reach OSC target {}: {}", target, e)))?;
+        Ok(Self {
+            socket,
+            prefix: prefix.trim_end_matches('/').to_string(),
+            line: None,
+            section: None,
+            last_progress: None,
+        })
+    }
+
+    // Nobody listening on the other end isn't an error worth stopping the song for
+    fn send(&self, name: &str, args: &[OscArg]) {
+        let _ = self.socket.send(&encode(&format!("{}/{}", self.prefix, name), args));
+    }
+}
+
+impl Renderer for OscOutput {
+    fn render(&mut self, app: &KaraokeApp) -> io::Result<()> {
+        let time = app.lyric_time();
+        let index = app.active_line(time);
+        if self.line != Some(index) {
+            let text = index.map(|i| app.lyrics[i].text.clone()).unwrap_or_default();
+            self.send("line", &[OscArg::Str(text)]);
+            self.line = Some(index);
+        }
+
+        let section = app.current_section().map(str::to_string);
+        if self.section.as_ref() != Some(&section) {
+            self.send("section", &[OscArg::Str(section.clone().unwrap_or_default())]);
+            self.section = Some(section);
+        }
+
+        if self.last_progress.is_none_or(|at| at.elapsed() >= PROGRESS_INTERVAL) {
+            let line_progress = index.map_or(0.0, |i| timeline::line_progress(&app.lyrics[i], time));
+            self.send("progress", &[OscArg::Float(time as f32), OscArg::Float(line_progress as f32)]);
+            self.last_progress = Some(Instant::now());
+        }
+        Ok(())
+    }
+
+    fn song_changed(&mut self) {
+        self.line = None;
+        self.section = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_padded_strings_and_big_endian_floats() {
+        let packet = encode("/k/line", &[OscArg::Str("hey".to_string()), OscArg::Float(1.0)]);
+        let mut expected = b"/k/line\0,sf\0hey\0".to_vec();
+        expected.extend_from_slice(&[0x3f, 0x80, 0, 0]);
+        assert_eq!(packet, expected);
+    }
+
+    #[test]
+    fn sends_to_the_target() {
+        let listener = UdpSocket::bind("127.0.0.1:0").unwrap();
+        listener.set_read_timeout(Some(Duration::from_secs(1))).unwrap();
+        let output = OscOutput::connect(&listener.local_addr().unwrap().to_string(), "/show/").unwrap();
+        output.send("section", &[OscArg::Str("Chorus".to_string())]);
+
+        let mut buf = [0; 64];
+        let len = listener.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..len], &encode("/show/section", &[OscArg::Str("Chorus".to_string())])[..]);
+    }
+}
@@ -0,0 +1,75 @@
+use ratatui::layout::Rect;
+
+pub const SIDE_WIDTH: u16 = 28;
+// The lyrics keep at least this many columns; side panes that don't fit beside them are
+// left out, the queue first
+const MIN_LYRICS: u16 = 40;
+
+// Which side panes a party shows on wide terminals, switched on and off while it runs
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Panes {
+    pub scoreboard: bool,
+    pub queue: bool,
+}
+
+impl Default for Panes {
+    fn default() -> Self {
+        Self { scoreboard: true, queue: true }
+    }
+}
+
+// Where each pane goes; a pane that's off or doesn't fit has no place
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Zones {
+    pub scoreboard: Option<Rect>,
+    pub lyrics: Rect,
+    pub queue: Option<Rect>,
+}
+
+// The scoreboard on the left, the lyrics in the middle and the queue on the right
+pub fn split(area: Rect, panes: Panes) -> Zones {
+    let mut room = area.width.saturating_sub(MIN_LYRICS);
+    let mut fits = |shown: bool| {
+        let fits = shown && room >= SIDE_WIDTH;
+        if fits {
+            room -= SIDE_WIDTH;
+        }
+        fits
+    };
+    let scoreboard = fits(panes.scoreboard);
+    let queue = fits(panes.queue);
+
+    let left = if scoreboard { SIDE_WIDTH } else { 0 };
+    let right = if queue { SIDE_WIDTH } else { 0 };
+    let lyrics = Rect { x: area.x + left, width: area.width - left - right, ..area };
+    Zones {
+        scoreboard: scoreboard.then_some(Rect { width: SIDE_WIDTH, ..area }),
+        lyrics,
+        queue: queue.then_some(Rect { x: lyrics.right(), width: SIDE_WIDTH, ..area }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn side_panes_fit_around_the_lyrics_or_give_way() {
+        let wide = Rect::new(0, 1, 120, 30);
+        let zones = split(wide, Panes::default());
+        assert_eq!(zones.scoreboard, Some(Rect::new(0, 1, 28, 30)));
+        assert_eq!(zones.lyrics, Rect::new(28, 1, 64, 30));
+        assert_eq!(zones.queue, Some(Rect::new(92, 1, 28, 30)));
+
+        let zones = split(wide, Panes { scoreboard: false, queue: true });
+        assert_eq!((zones.scoreboard, zones.lyrics), (None, Rect::new(0, 1, 92, 30)));
+        assert_eq!(zones.queue, Some(Rect::new(92, 1, 28, 30)));
+
+        // Too narrow for both: the scoreboard stays, then neither
+        let zones = split(Rect::new(0, 0, 80, 30), Panes::default());
+        assert_eq!((zones.scoreboard.is_some(), zones.queue), (true, None));
+        assert_eq!(zones.lyrics, Rect::new(28, 0, 52, 30));
+        let zones = split(Rect::new(0, 0, 60, 30), Panes::default());
+        assert_eq!((zones.scoreboard, zones.lyrics, zones.queue), (None, Rect::new(0, 0, 60, 30), None));
+    }
+}
@@ -0,0 +1,181 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{panes::Panes, requests::SongRequest};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Player {
+    pub name: String,
+    pub score: u32,
+    pub songs_sung: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PartySession {
+    pub players: Vec<Player>,
+    pub songs: Vec<PathBuf>,
+    pub next_song: usize,
+    pub duet: bool,
+    // When the party started, in seconds since the Unix epoch, telling it apart on the leaderboard
+    #[serde(default)]
+    pub started: u64,
+    // Who sings each song, when a setlist says; songs left out go to whoever's turn it is
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub lineup: Vec<Vec<usize>>,
+    // Guests' requests waiting on the host, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub requests: Vec<SongRequest>,
+    // Where guests send them, shown to the host
+    #[serde(skip)]
+    pub request_page: Option<String>,
+    // The side panes shown beside the lyrics on wide terminals, when asked for
+    #[serde(skip)]
+    pub panes: Option<Panes>,
+}
+
+impl PartySession {
+    pub fn new(names: Vec<String>, songs: Vec<PathBuf>, duet: bool) -> Self {
+        let players = names
+            .into_iter()
+            .map(|name| Player {
+                name,
+                score: 0,
+                songs_sung: 0,
+            })
+            .collect();
+
+        Self {
+            players,
+            songs,
+            next_song: 0,
+            duet,
+            started: SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs()),
+            lineup: Vec::new(),
+            requests: Vec::new(),
+            request_page: None,
+            panes: None,
+        }
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let json = fs::read_to_string(path)?;
+        serde_json::from_str(&json).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        fs::write(path, json)
+    }
+
+    pub fn current_song(&self) -> Option<&Path> {
+        self.songs.get(self.next_song).map(PathBuf::as_path)
+    }
+
+    // Solo turns rotate through the players; duets pair each player with the next one
+    pub fn singers(&self) -> Vec<usize> {
+        self.singers_for(self.next_song)
+    }
+
+    fn singers_for(&self, song: usize) -> Vec<usize> {
+        let count = self.players.len();
+        if count == 0 {
+            return Vec::new();
+        }
+        if let Some(singers) = self.lineup.get(song).filter(|singers| !singers.is_empty()) {
+            return singers.clone();
+        }
+        if self.duet && count > 1 {
+            let first = (song * 2) % count;
+            vec![first, (first + 1) % count]
+        } else {
+            vec![song % count]
+        }
+    }
+
+    fn names(&self, singers: Vec<usize>) -> String {
+        singers
+            .into_iter()
+            .map(|i| self.players[i].name.as_str())
+            .collect::<Vec<_>>()
+            .join(" & ")
+    }
+
+    pub fn singer_list(&self) -> Vec<String> {
+        self.singers().into_iter().map(|i| self.players[i].name.clone()).collect()
+    }
+
+    pub fn singer_names(&self) -> String {
+        self.names(self.singers())
+    }
+
+    pub fn upcoming_song(&self) -> Option<&Path> {
+        self.songs.get(self.next_song + 1).map(PathBuf::as_path)
+    }
+
+    // The song after the current one and who will sing it
+    pub fn next_up(&self) -> Option<(String, String)> {
+        self.queue().into_iter().next()
+    }
+
+    // Every song still to come after the current one, with its singers
+    pub fn queue(&self) -> Vec<(String, String)> {
+        (self.next_song + 1..self.songs.len())
+            .map(|song| {
+                let path = &self.songs[song];
+                let name = path.file_stem().map_or_else(|| path.display().to_string(), |stem| stem.to_string_lossy().into_owned());
+                (name, self.names(self.singers_for(song)))
+            })
+            .collect()
+    }
+
+    pub fn record_result(&mut self, score: u32) {
+        for i in self.singers() {
+            self.players[i].score += score;
+            self.players[i].songs_sung += 1;
+        }
+        self.next_song += 1;
+    }
+
+    pub fn restart_playlist(&mut self) {
+        self.next_song = 0;
+    }
+
+    // Asking twice for a song that's already waiting doesn't queue it again
+    pub fn add_request(&mut self, request: SongRequest) -> bool {
+        if self.requests.iter().any(|waiting| waiting.song == request.song) {
+            return false;
+        }
+        self.requests.push(request);
+        true
+    }
+
+    // The oldest request joins the end of the playlist, sung by the guest who asked for it when
+    // they're one of the players
+    pub fn accept_request(&mut self) -> Option<SongRequest> {
+        if self.requests.is_empty() {
+            return None;
+        }
+        let request = self.requests.remove(0);
+        let singer = self.players.iter().position(|player| player.name.eq_ignore_ascii_case(&request.guest));
+        if let Some(singer) = singer {
+            self.lineup.resize(self.songs.len(), Vec::new());
+            self.lineup.push(vec![singer]);
+        }
+        self.songs.push(request.song.clone());
+        Some(request)
+    }
+
+    pub fn decline_request(&mut self) -> Option<SongRequest> {
+        (!self.requests.is_empty()).then(|| self.requests.remove(0))
+    }
+
+    pub fn leaderboard(&self) -> Vec<&Player> {
+        let mut ranked: Vec<&Player> = self.players.iter().collect();
+        ranked.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.name.cmp(&b.name)));
+        ranked
+    }
+}
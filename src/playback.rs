@@ -0,0 +1,438 @@
+use std::{
+    f32::consts::{PI, TAU},
+    io::{self, Write},
+    process::{Child, Command, Stdio},
+    sync::{
+        atomic::{AtomicBool, AtomicI32, AtomicU32, Ordering},
+        mpsc::{self, Receiver, RecvTimeoutError, Sender},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::wav::WavData;
+
+// Any command that plays raw signed 16-bit little-endian PCM from stdin works; {rate} and
+// {channels} are filled in from the song's recording
+pub const DEFAULT_COMMAND: &str = "aplay -q -t raw -f S16_LE -r {rate} -c {channels}";
+pub const VOLUME_STEP: f64 = 0.1;
+pub const DEFAULT_CROSSFADE: f64 = 3.0;
+// Playback further than this from the lyrics' clock is restarted where the lyrics are
+const MAX_DRIFT: f64 = 0.25;
+// Small writes keep pausing and seeking quick, since the player is only stopped between them
+const CHUNK_SECONDS: f64 = 0.05;
+// Audio is written at most this far ahead of when it's heard, so a fade or a change of effect is
+// heard soon after it's asked for rather than after all the player has been sent
+const MAX_AHEAD: f64 = 0.2;
+// Below this the song is left as it is when vocals are removed, since bass and kick drums are
+// mixed in the center too
+const KEEP_BASS_BELOW: f32 = 150.0;
+// How much of the recording the pitch shifter reads back over. Longer is smoother on held notes
+// and blurs drums more.
+const SHIFT_WINDOW_SECONDS: f32 = 0.06;
+
+enum Control {
+    // Fading in over `fade_in` seconds, if it's more than 0
+    Play { from: f64, speed: f64, fade_in: f64 },
+    Stop,
+    // Keeps playing what's playing while it fades out, then finishes
+    FadeOut { seconds: f64 },
+}
+
+// A change of volume over a number of frames, for crossfading between songs
+#[derive(Debug, Clone, Copy)]
+struct Fade {
+    from: f32,
+    to: f32,
+    frames: usize,
+    done: usize,
+}
+
+impl Fade {
+    fn new(from: f32, to: f32, seconds: f64, sample_rate: u32) -> Self {
+        let frames = (seconds.max(0.0) * sample_rate as f64) as usize;
+        Self { from, to, frames, done: 0 }
+    }
+
+    fn gain(&self) -> f32 {
+        let through = match self.frames {
+            0 => 1.0,
+            frames => (self.done as f32 / frames as f32).min(1.0),
+        };
+        self.from + (self.to - self.from) * through
+    }
+
+    fn advance(&mut self, frames: usize) {
+        self.done += frames;
+    }
+
+    fn is_done(&self) -> bool {
+        self.done >= self.frames
+    }
+}
+
+// The player command with the device added, for the default `aplay`
+pub fn command(custom: Option<&str>, device: Option<&str>, wav: &WavData) -> String {
+    let mut command = custom.unwrap_or(DEFAULT_COMMAND).to_string();
+    if let (None, Some(device)) = (custom, device) {
+        command.push_str(&format!(" -D {}", device));
+    }
+    command.replace("{rate}", &wav.sample_rate.to_string()).replace("{channels}", &wav.channels.to_string())
+}
+
+fn spawn(command: &str) -> io::Result<Child> {
+    let mut parts = command.split_whitespace();
+    let program = parts.next().ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "empty audio command"))?;
+    Command::new(program)
+        .args(parts)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| io::Error::new(e.kind(), format!("couldn't start the audio player `{}`: {}", command, e)))
+}
+
+// Center channel cancellation: lead vocals are usually mixed equally into both channels, so
+// taking the difference of the channels leaves what's panned to the sides. The bass of the
+// center is added back from a low-pass of the two channels together.
+struct VocalRemover {
+    // A one-pole low-pass of the center
+    low: f32,
+    smoothing: f32,
+}
+
+impl VocalRemover {
+    fn new(sample_rate: u32) -> Self {
+        Self {
+            low: 0.0,
+            smoothing: 1.0 - (-TAU * KEEP_BASS_BELOW / sample_rate.max(1) as f32).exp(),
+        }
+    }
+
+    fn apply(&mut self, left: f32, right: f32) -> [f32; 2] {
+        let (center, side) = ((left + right) / 2.0, (left - right) / 2.0);
+        self.low += self.smoothing * (center - self.low);
+        [self.low + side, self.low - side]
+    }
+}
+
+// Changes the key without changing the tempo: the recording is read back from a short delay
+// line at the new speed, by two read heads half a window apart. Each fades out as it's about to
+// jump back across the window, while the other is at full volume.
+struct PitchShifter {
+    semitones: i32,
+    ratio: f32,
+    window: f32,
+    // Where the first head is across the window, from 0 to 1
+    phase: f32,
+    history: Vec<f32>,
+    channels: usize,
+    // The frame in `history` written next
+    write: usize,
+}
+
+impl PitchShifter {
+    fn new(semitones: i32, sample_rate: u32, channels: usize) -> Self {
+        let window = (sample_rate as f32 * SHIFT_WINDOW_SECONDS).max(2.0);
+        let channels = channels.max(1);
+        Self {
+            semitones,
+            ratio: 2f32.powf(semitones as f32 / 12.0),
+            window,
+            phase: 0.0,
+            history: vec![0.0; (window as usize + 2) * channels],
+            channels,
+            write: 0,
+        }
+    }
+
+    fn apply(&mut self, frame: &mut [f32]) {
+        let len = self.history.len() / self.channels;
+        for (channel, &sample) in frame.iter().enumerate() {
+            self.history[self.write * self.channels + channel] = sample;
+        }
+        // Reading faster than the recording comes in is a higher pitch, so the delay shrinks
+        self.phase = (self.phase + (1.0 - self.ratio) / self.window).rem_euclid(1.0);
+        let heads = [self.phase, (self.phase + 0.5) % 1.0].map(|phase| (phase * self.window, (PI * phase).sin().powi(2)));
+        for (channel, sample) in frame.iter_mut().enumerate() {
+            *sample = heads
+                .iter()
+                .map(|&(delay, gain)| {
+                    let back = self.write as f32 - delay + len as f32;
+                    let (before, between) = (back as usize % len, back.fract());
+                    let at = |frame: usize| self.history[frame % len * self.channels + channel];
+                    gain * (at(before) * (1.0 - between) + at(before + 1) * between)
+                })
+                .sum();
+        }
+        self.write = (self.write + 1) % len;
+    }
+}
+
+// `frames` of the recording from `position`, stepping `speed` frames at a time, as 16-bit PCM
+fn pcm(
+    wav: &WavData,
+    position: &mut f64,
+    frames: usize,
+    speed: f64,
+    volume: f32,
+    mut remover: Option<&mut VocalRemover>,
+    mut shifter: Option<&mut PitchShifter>,
+) -> Vec<u8> {
+    let mut out = Vec::with_capacity(frames * wav.channels as usize * 2);
+    for _ in 0..frames {
+        let frame = *position as usize;
+        if frame >= wav.frames() {
+            break;
+        }
+        let mut samples = wav.frame(frame).to_vec();
+        if let (&mut [left, right], Some(remover)) = (samples.as_mut_slice(), remover.as_deref_mut()) {
+            samples = remover.apply(left, right).to_vec();
+        }
+        if let Some(shifter) = shifter.as_deref_mut() {
+            shifter.apply(&mut samples);
+        }
+        for sample in samples {
+            out.extend_from_slice(&(((sample * volume).clamp(-1.0, 1.0) * i16::MAX as f32) as i16).to_le_bytes());
+        }
+        *position += speed;
+    }
+    out
+}
+
+fn kill(child: Option<Child>) {
+    if let Some(mut child) = child {
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+}
+
+// Runs the player for each Play and kills it on the next message. Writes block while the
+// player's buffer is full, so the recording goes out at the speed it's heard.
+fn feed(wav: Arc<WavData>, command: String, effects: Effects, control: Receiver<Control>) {
+    let chunk = ((wav.sample_rate as f64 * CHUNK_SECONDS) as usize).max(1);
+    let mut remover = VocalRemover::new(wav.sample_rate);
+    let mut next = control.recv().ok();
+    while let Some(message) = next.take() {
+        let Control::Play { from, speed, fade_in } = message else {
+            next = control.recv().ok();
+            continue;
+        };
+        let mut child = match spawn(&command) {
+            Ok(child) => Some(child),
+            Err(e) => {
+                log::warn!("{}", e);
+                None
+            }
+        };
+        let mut stdin = child.as_mut().and_then(|child| child.stdin.take());
+        let mut position = from.max(0.0) * wav.sample_rate as f64;
+        // Started fresh so the sound from before a seek isn't read back
+        let mut shifter: Option<PitchShifter> = None;
+        let mut fade = (fade_in > 0.0).then(|| Fade::new(0.0, 1.0, fade_in, wav.sample_rate));
+        let mut fading_out = false;
+        let (started, mut written) = (Instant::now(), 0.0);
+        next = loop {
+            // Past the end, or with no player, wait for the next instruction
+            let Some(writer) = stdin.as_mut().filter(|_| (position as usize) < wav.frames()) else {
+                match control.recv_timeout(Duration::from_secs(1)) {
+                    Ok(message) => break Some(message),
+                    Err(RecvTimeoutError::Timeout) => continue,
+                    Err(RecvTimeoutError::Disconnected) => break None,
+                }
+            };
+            match control.try_recv() {
+                Ok(Control::FadeOut { seconds }) => {
+                    let from = fade.as_ref().map_or(1.0, Fade::gain);
+                    fade = Some(Fade::new(from, 0.0, seconds, wav.sample_rate));
+                    fading_out = true;
+                }
+                // Once it's fading out, the song it was playing for has moved on
+                Ok(_) | Err(mpsc::TryRecvError::Disconnected) if fading_out => {}
+                Ok(message) => break Some(message),
+                Err(mpsc::TryRecvError::Disconnected) => break None,
+                Err(mpsc::TryRecvError::Empty) => {}
+            }
+            let volume = f32::from_bits(effects.volume.load(Ordering::Relaxed)) * fade.as_ref().map_or(1.0, Fade::gain);
+            if let Some(ramp) = &mut fade {
+                ramp.advance(chunk);
+                match (ramp.is_done(), fading_out) {
+                    (true, true) => break None,
+                    (true, false) => fade = None,
+                    (false, _) => {}
+                }
+            }
+            let remover = effects.no_vocals.load(Ordering::Relaxed).then_some(&mut remover);
+            let semitones = effects.semitones.load(Ordering::Relaxed);
+            if shifter.as_ref().map_or(0, |shifter| shifter.semitones) != semitones {
+                shifter = (semitones != 0).then(|| PitchShifter::new(semitones, wav.sample_rate, wav.channels as usize));
+            }
+            let samples = pcm(&wav, &mut position, chunk, speed, volume, remover, shifter.as_mut());
+            if let Err(e) = writer.write_all(&samples) {
+                log::warn!("the audio player stopped playing: {}", e);
+                stdin = None;
+            }
+            written += chunk as f64 / wav.sample_rate as f64;
+            let ahead = written - started.elapsed().as_secs_f64();
+            if ahead > MAX_AHEAD {
+                thread::sleep(Duration::from_secs_f64(ahead - MAX_AHEAD));
+            }
+        };
+        drop(stdin);
+        // A fade out lets the player finish what it has been given, rather than cutting it off
+        match (fading_out, child) {
+            (true, Some(mut child)) => {
+                let _ = child.wait();
+            }
+            (_, child) => kill(child),
+        }
+    }
+}
+
+// What's done to the recording on the way out, changed live from the app
+#[derive(Clone, Default)]
+struct Effects {
+    volume: Arc<AtomicU32>,
+    no_vocals: Arc<AtomicBool>,
+    semitones: Arc<AtomicI32>,
+}
+
+// Plays the song's recording in step with the lyrics: started where the song is when it plays,
+// stopped when it pauses, and restarted after a seek
+pub struct AudioPlayer {
+    control: Sender<Control>,
+    effects: Effects,
+    stereo: bool,
+    // Seconds the audio is heard after it's written, so it's written that much early
+    latency: f64,
+    // Where playback started, when, and how fast, while it's playing
+    playing: Option<(f64, Instant, f64)>,
+    // Seconds the next start fades in over
+    fade_in: f64,
+}
+
+impl AudioPlayer {
+    pub fn start(wav: Arc<WavData>, command: String, latency: f64) -> Self {
+        let (control, receiver) = mpsc::channel();
+        let effects = Effects::default();
+        effects.volume.store(1.0f32.to_bits(), Ordering::Relaxed);
+        let stereo = wav.channels == 2;
+        let feeder_effects = effects.clone();
+        thread::spawn(move || feed(wav, command, feeder_effects, receiver));
+        Self { control, effects, stereo, latency, playing: None, fade_in: 0.0 }
+    }
+
+    pub fn set_volume(&self, volume: f64) {
+        self.effects.volume.store((volume as f32).to_bits(), Ordering::Relaxed);
+    }
+
+    // Only stereo recordings have a center to take the vocals out of
+    pub fn remove_vocals(&self, on: bool) -> bool {
+        self.effects.no_vocals.store(on && self.stereo, Ordering::Relaxed);
+        self.stereo
+    }
+
+    pub fn set_key(&self, semitones: i32) {
+        self.effects.semitones.store(semitones, Ordering::Relaxed);
+    }
+
+    // For the song coming in during a crossfade
+    pub fn fade_in(&mut self, seconds: f64) {
+        self.fade_in = seconds;
+    }
+
+    // For the song going out: the recording carries on from where it is, fading out, after the
+    // player is gone
+    pub fn fade_out(self, seconds: f64) {
+        let _ = self.control.send(Control::FadeOut { seconds });
+    }
+
+    // Called every tick with where the song is and whether it's running
+    pub fn update(&mut self, position: f64, running: bool, speed: f64) {
+        let now = Instant::now();
+        let in_step = self.playing.is_some_and(|(from, since, playing_speed)| {
+            playing_speed == speed && (from + now.duration_since(since).as_secs_f64() * speed - position).abs() < MAX_DRIFT
+        });
+        match (running, in_step) {
+            (true, true) => {}
+            (true, false) => {
+                self.playing = Some((position, now, speed));
+                let fade_in = std::mem::take(&mut self.fade_in);
+                let _ = self.control.send(Control::Play { from: position + self.latency, speed, fade_in });
+            }
+            (false, _) if self.playing.take().is_some() => {
+                let _ = self.control.send(Control::Stop);
+            }
+            (false, _) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recordings_are_sent_as_scaled_pcm() {
+        let wav = WavData { sample_rate: 4, channels: 2, samples: vec![0.5, -0.5, 1.0, -1.0, 0.25, 0.0, 0.0, 0.0] };
+        assert_eq!(command(None, Some("hw:1"), &wav), "aplay -q -t raw -f S16_LE -r 4 -c 2 -D hw:1");
+        assert_eq!(command(Some("play -r {rate} -"), Some("hw:1"), &wav), "play -r 4 -");
+
+        let mut position = 0.0;
+        let samples = |pcm: Vec<u8>| -> Vec<i16> { pcm.chunks_exact(2).map(|b| i16::from_le_bytes([b[0], b[1]])).collect() };
+        assert_eq!(samples(pcm(&wav, &mut position, 2, 2.0, 0.5, None, None)), [8191, -8191, 4095, 0]);
+        assert_eq!(position, 4.0);
+        assert!(pcm(&wav, &mut position, 2, 1.0, 1.0, None, None).is_empty());
+    }
+
+    #[test]
+    fn fades_ramp_from_and_to_their_volumes() {
+        let mut fade = Fade::new(1.0, 0.0, 2.0, 10);
+        assert_eq!(fade.gain(), 1.0);
+        fade.advance(5);
+        assert_eq!(fade.gain(), 0.75);
+        fade.advance(20);
+        assert!(fade.is_done());
+        assert_eq!(fade.gain(), 0.0);
+        assert!(Fade::new(0.0, 1.0, 0.0, 10).is_done());
+    }
+
+    #[test]
+    fn removing_vocals_cancels_the_center_but_keeps_the_bass() {
+        let mut remover = VocalRemover::new(44_100);
+        // A voice in the middle at 1kHz, a guitar on the left and a steady bass in the middle
+        let (mut voice, mut guitar, mut bass) = (0.0f32, 0.0f32, 0.0f32);
+        for i in 0..44_100 {
+            let t = i as f32 / 44_100.0;
+            let (v, g, b) = ((TAU * 1000.0 * t).sin() * 0.5, (TAU * 440.0 * t).sin() * 0.3, 0.2);
+            let [left, right] = remover.apply(v + g + b, v + b);
+            if i > 22_050 {
+                voice += (left + right) * v;
+                guitar += left * g;
+                bass += (left + right) / 2.0;
+            }
+        }
+        let frames = 22_049.0;
+        assert!((bass / frames - 0.2).abs() < 0.01);
+        assert!(guitar / frames > 0.02);
+        assert!(voice.abs() / frames < 0.01);
+    }
+
+    #[test]
+    fn shifting_the_key_changes_the_pitch_but_not_the_tempo() {
+        // An A4 up a fifth and down an octave, counted by its rising zero crossings over a second
+        for (semitones, heard) in [(7, 659.0), (-12, 220.0)] {
+            let mut shifter = PitchShifter::new(semitones, 44_100, 1);
+            let out: Vec<f32> = (0..88_200)
+                .map(|i| {
+                    let mut frame = [(TAU * 440.0 * i as f32 / 44_100.0).sin()];
+                    shifter.apply(&mut frame);
+                    frame[0]
+                })
+                .collect();
+            let crossings = out[44_100..].windows(2).filter(|pair| pair[0] < 0.0 && pair[1] >= 0.0).count();
+            assert!((crossings as f32 - heard).abs() < heard * 0.03, "{} semitones: {} crossings", semitones, crossings);
+        }
+    }
+}
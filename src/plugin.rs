@@ -0,0 +1,120 @@
+use std::{io, time::Instant};
+
+use crossterm::event::KeyEvent;
+
+use crate::{formats::ParsedLyrics, KaraokeApp};
+
+// Reads one lyric file format
+pub trait LyricSource {
+    // Lowercase, without the dot
+    fn extensions(&self) -> &[&str];
+
+    // For extensions shared by several formats, whether this file is one of ours
+    fn accepts(&self, _bytes: &[u8]) -> bool {
+        true
+    }
+
+    fn parse(&self, bytes: &[u8]) -> Result<ParsedLyrics, String>;
+}
+
+// Where playback time comes from. Only the difference between two readings matters.
+pub trait TimeSource {
+    fn now(&self) -> f64;
+}
+
+// An output that follows playback alongside the TUI, called once per frame
+pub trait Renderer {
+    fn render(&mut self, app: &KaraokeApp) -> io::Result<()>;
+
+    fn key(&mut self, _key: KeyEvent) {}
+
+    // A different song was loaded into the same session
+    fn song_changed(&mut self) {}
+}
+
+pub struct SystemClock {
+    origin: Instant,
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self { origin: Instant::now() }
+    }
+}
+
+impl TimeSource for SystemClock {
+    fn now(&self) -> f64 {
+        self.origin.elapsed().as_secs_f64()
+    }
+}
+
+// The lyric formats `load` knows about; sources registered later take priority, so a new
+// source can replace a built-in one for the same extension
+pub struct Registry {
+    sources: Vec<Box<dyn LyricSource>>,
+}
+
+impl Registry {
+    pub fn builtin() -> Self {
+        let mut registry = Self { sources: Vec::new() };
+        for source in crate::formats::builtin_sources() {
+            registry.register_source(source);
+        }
+        registry
+    }
+
+    pub fn register_source(&mut self, source: Box<dyn LyricSource>) {
+        self.sources.push(source);
+    }
+
+    pub fn parse(&self, extension: &str, bytes: &[u8]) -> Result<ParsedLyrics, String> {
+        self.sources
+            .iter()
+            .rev()
+            .find(|source| source.extensions().contains(&extension) && source.accepts(bytes))
+            .ok_or_else(|| format!("unsupported lyric format: .{}", extension))?
+            .parse(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Shouting;
+
+    impl LyricSource for Shouting {
+        fn extensions(&self) -> &[&str] {
+            &["lrc"]
+        }
+
+        fn accepts(&self, bytes: &[u8]) -> bool {
+            bytes.starts_with(b"!")
+        }
+
+        fn parse(&self, _bytes: &[u8]) -> Result<ParsedLyrics, String> {
+            Ok(ParsedLyrics {
+                title: Some("LOUD".to_string()),
+                artist: None,
+                album: None,
+                key: None,
+                duration: None,
+                lyrics: Vec::new(),
+            })
+        }
+    }
+
+    #[test]
+    fn later_sources_override_built_in_ones() {
+        let mut registry = Registry::builtin();
+        registry.register_source(Box::new(Shouting));
+        assert_eq!(registry.parse("lrc", b"!").unwrap().title.as_deref(), Some("LOUD"));
+        // Files it doesn't accept still go to the built-in parser
+        assert_eq!(registry.parse("lrc", b"[00:01.00]hi").unwrap().lyrics[0].text, "hi");
+    }
+
+    #[test]
+    fn unknown_extensions_are_an_error() {
+        assert!(Registry::builtin().parse("doc", b"").is_err());
+    }
+}
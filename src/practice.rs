@@ -0,0 +1,77 @@
+const MIN_SPEED: f64 = 0.5;
+const MAX_SPEED: f64 = 1.5;
+
+use serde::{Deserialize, Serialize};
+
+// An A-B section repeated for practice, optionally slowed down or sped up
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PracticeLoop {
+    pub start: f64,
+    pub end: Option<f64>,
+    pub speed: f64,
+}
+
+impl PracticeLoop {
+    pub fn new(start: f64) -> Self {
+        Self {
+            start,
+            end: None,
+            speed: 1.0,
+        }
+    }
+
+    // B has to come after A; returns whether it was accepted
+    pub fn set_end(&mut self, end: f64) -> bool {
+        if end <= self.start {
+            return false;
+        }
+        self.end = Some(end);
+        true
+    }
+
+    // Where playback should jump back to once it has run past B
+    pub fn wrap(&self, time: f64) -> Option<f64> {
+        self.end.filter(|&end| time >= end).map(|_| self.start)
+    }
+
+    pub fn adjust_speed(&mut self, delta: f64) {
+        self.speed = (((self.speed + delta) * 10.0).round() / 10.0).clamp(MIN_SPEED, MAX_SPEED);
+    }
+
+    pub fn describe(&self) -> String {
+        let end = self.end.map_or_else(|| "?".to_string(), crate::format_time);
+        format!(
+            "⟲ A {} - B {}  speed {:.0}%",
+            crate::format_time(self.start),
+            end,
+            self.speed * 100.0
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wraps_back_to_a_after_b() {
+        let mut practice = PracticeLoop::new(10.0);
+        assert_eq!(practice.wrap(30.0), None);
+        assert!(!practice.set_end(5.0));
+        assert!(practice.set_end(20.0));
+        assert_eq!(practice.wrap(19.9), None);
+        assert_eq!(practice.wrap(20.0), Some(10.0));
+    }
+
+    #[test]
+    fn speed_moves_in_tenths_within_limits() {
+        let mut practice = PracticeLoop::new(0.0);
+        practice.adjust_speed(-0.1);
+        assert_eq!(practice.speed, 0.9);
+        for _ in 0..10 {
+            practice.adjust_speed(-0.1);
+        }
+        assert_eq!(practice.speed, 0.5);
+        assert_eq!(practice.describe(), "⟲ A 0:00 - B ?  speed 50%");
+    }
+}
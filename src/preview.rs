@@ -0,0 +1,56 @@
+use crate::{
+    time_format::{self, TimeFormat},
+    LyricLine,
+};
+
+// Lengths further apart than this are worth a warning, in seconds
+const MISMATCH: f64 = 5.0;
+
+// Whether the length the song file gives, its recording and its lyrics agree
+pub fn warnings(lyrics: &[LyricLine], given: Option<f64>, recording: Option<f64>) -> Vec<String> {
+    let lyrics_end = lyrics.iter().map(|line| line.end_time).fold(0.0, f64::max);
+    let longest = lyrics_end.max(given.unwrap_or(0.0)).max(recording.unwrap_or(0.0));
+    let time = |seconds: f64| time_format::format(seconds, TimeFormat::Auto, longest);
+    let mut warnings = Vec::new();
+
+    if let (Some(given), Some(recording)) = (given, recording) {
+        if (recording - given).abs() > MISMATCH {
+            warnings.push(format!("The song file says it's {} long but its recording is {}", time(given), time(recording)));
+        }
+    }
+    if let Some(given) = given.filter(|&given| lyrics_end > given) {
+        warnings.push(format!("The lyrics run to {}, past the song file's length of {}", time(lyrics_end), time(given)));
+    }
+    if let Some(recording) = recording.filter(|&recording| lyrics_end > recording) {
+        warnings.push(format!("The lyrics run to {}, past the end of the recording at {}", time(lyrics_end), time(recording)));
+    }
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn warns_when_the_lengths_disagree() {
+        let lyrics = vec![LyricLine {
+            text: "last".to_string(),
+            start_time: 190.0,
+            end_time: 200.0,
+            segments: Vec::new(),
+            singer: 0,
+            style: Default::default(),
+            chords: Vec::new(),
+        }];
+        assert!(warnings(&lyrics, Some(203.0), Some(206.0)).is_empty());
+        assert!(warnings(&lyrics, None, None).is_empty());
+        assert_eq!(
+            warnings(&lyrics, Some(180.0), Some(195.0)),
+            [
+                "The song file says it's 3:00 long but its recording is 3:15",
+                "The lyrics run to 3:20, past the song file's length of 3:00",
+                "The lyrics run to 3:20, past the end of the recording at 3:15",
+            ]
+        );
+    }
+}
@@ -0,0 +1,256 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{cli::SingerOptions, formats, history, resume};
+
+const PROFILES_FILE: &str = ".karaoke-lyric-singers.json";
+// Older scores are dropped past this many, keeping the file small
+const KEPT_SCORES: usize = 200;
+const RECENT_SHOWN: usize = 5;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Score {
+    // The song's history key, so scores line up with `stats`
+    pub song: String,
+    pub title: String,
+    pub score: u32,
+    // Seconds since the Unix epoch
+    pub sung_at: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Favorite {
+    pub song: String,
+    pub title: String,
+}
+
+// How a singer likes their songs set up, and how they've done
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Profile {
+    pub name: String,
+    #[serde(default)]
+    pub transpose: i32,
+    // Decibels added to the mic, or taken off it for loud singers
+    #[serde(default)]
+    pub mic_gain: f64,
+    #[serde(default)]
+    pub favorites: Vec<Favorite>,
+    #[serde(default)]
+    pub scores: Vec<Score>,
+}
+
+impl Profile {
+    pub fn best(&self) -> Option<&Score> {
+        self.scores.iter().max_by_key(|score| score.score)
+    }
+
+    fn record(&mut self, song: String, title: &str, score: u32, sung_at: u64) {
+        self.scores.push(Score {
+            song,
+            title: title.to_string(),
+            score,
+            sung_at,
+        });
+        let excess = self.scores.len().saturating_sub(KEPT_SCORES);
+        self.scores.drain(..excess);
+    }
+
+    // Returns whether the song is a favorite now
+    fn toggle_favorite(&mut self, song: String, title: &str) -> bool {
+        match self.favorites.iter().position(|favorite| favorite.song == song) {
+            Some(i) => {
+                self.favorites.remove(i);
+                false
+            }
+            None => {
+                self.favorites.push(Favorite { song, title: title.to_string() });
+                true
+            }
+        }
+    }
+
+    // What the singer's settings change, for telling them when they're applied
+    pub fn summary(&self) -> Option<String> {
+        let mut settings = Vec::new();
+        if self.transpose != 0 {
+            settings.push(format!("transpose {:+}", self.transpose));
+        }
+        if self.mic_gain != 0.0 {
+            settings.push(format!("mic {:+.1} dB", self.mic_gain));
+        }
+        (!settings.is_empty()).then(|| format!("{}: {}", self.name, settings.join(", ")))
+    }
+}
+
+// Singers are found by name whatever its case
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Profiles {
+    pub singers: BTreeMap<String, Profile>,
+}
+
+impl Profiles {
+    // No file yet is no profiles; a broken one is an error so it doesn't get overwritten
+    pub fn load(path: &Path) -> io::Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(json) => serde_json::from_str(&json)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path.display(), e))),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        fs::write(path, json)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Profile> {
+        self.singers.get(&name.to_lowercase())
+    }
+
+    fn entry(&mut self, name: &str) -> &mut Profile {
+        self.singers.entry(name.to_lowercase()).or_insert_with(|| Profile {
+            name: name.to_string(),
+            ..Profile::default()
+        })
+    }
+}
+
+pub fn profiles_path() -> PathBuf {
+    resume::home_file(PROFILES_FILE)
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs())
+}
+
+// The singer's settings, or none for someone without a profile yet
+pub fn load(name: &str) -> io::Result<Option<Profile>> {
+    Ok(Profiles::load(&profiles_path())?.get(name).cloned())
+}
+
+// A finished song's score goes to everyone who sang it
+pub fn record_score(singers: &[String], song: Option<&Path>, title: &str, score: u32) -> io::Result<()> {
+    if singers.is_empty() {
+        return Ok(());
+    }
+    let path = profiles_path();
+    let mut profiles = Profiles::load(&path)?;
+    for singer in singers {
+        profiles.entry(singer).record(history::song_key(song), title, score, now());
+    }
+    profiles.save(&path)
+}
+
+pub fn toggle_favorite(singers: &[String], song: Option<&Path>, title: &str) -> io::Result<bool> {
+    let path = profiles_path();
+    let mut profiles = Profiles::load(&path)?;
+    let mut favorite = false;
+    for singer in singers {
+        favorite = profiles.entry(singer).toggle_favorite(history::song_key(song), title);
+    }
+    profiles.save(&path)?;
+    Ok(favorite)
+}
+
+fn print_profile(profile: &Profile) {
+    println!("{}", profile.name);
+    println!("  Transpose: {:+}", profile.transpose);
+    println!("  Mic gain:  {:+.1} dB", profile.mic_gain);
+    match profile.best() {
+        Some(best) => {
+            let average = profile.scores.iter().map(|score| u64::from(score.score)).sum::<u64>() / profile.scores.len() as u64;
+            println!("  Songs sung: {}, average {}, best {} ({})", profile.scores.len(), average, best.score, best.title);
+        }
+        None => println!("  No songs sung yet"),
+    }
+    if !profile.favorites.is_empty() {
+        println!("  Favorites:");
+        for favorite in &profile.favorites {
+            println!("    {}  {}", favorite.title, favorite.song);
+        }
+    }
+    if !profile.scores.is_empty() {
+        println!("  Recent scores:");
+        for score in profile.scores.iter().rev().take(RECENT_SHOWN) {
+            println!("    {}  {:>6}  {}", history::format_date(score.sung_at), score.score, score.title);
+        }
+    }
+}
+
+// Lists the singers, or shows one after making any changes asked for
+pub fn run(options: &SingerOptions) -> io::Result<()> {
+    let path = profiles_path();
+    let mut profiles = Profiles::load(&path)?;
+    let Some(name) = &options.name else {
+        if profiles.singers.is_empty() {
+            println!("No singer profiles yet");
+        }
+        for profile in profiles.singers.values() {
+            let best = profile.best().map_or_else(|| "-".to_string(), |best| best.score.to_string());
+            println!("{:<20} {:>3} songs  best {:>6}  {} favorites", profile.name, profile.scores.len(), best, profile.favorites.len());
+        }
+        return Ok(());
+    };
+
+    let changing = options.transpose.is_some() || options.mic_gain.is_some() || !options.favorite.is_empty() || !options.unfavorite.is_empty();
+    if !changing {
+        let profile = profiles.get(name).ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("no profile for {}", name)))?;
+        print_profile(profile);
+        return Ok(());
+    }
+
+    let profile = profiles.entry(name);
+    if let Some(transpose) = options.transpose {
+        profile.transpose = transpose;
+    }
+    if let Some(gain) = options.mic_gain {
+        profile.mic_gain = gain;
+    }
+    for (songs, wanted) in [(&options.favorite, true), (&options.unfavorite, false)] {
+        for song in songs {
+            let title = formats::load(song)?.title;
+            let key = history::song_key(resume::absolute(Some(song)).as_deref());
+            let is_favorite = profile.favorites.iter().any(|favorite| favorite.song == key);
+            if is_favorite != wanted {
+                profile.toggle_favorite(key, &title);
+            }
+        }
+    }
+    print_profile(profile);
+    profiles.save(&path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profiles_keep_scores_and_favorites_by_name() {
+        let mut profiles = Profiles::default();
+        profiles.entry("Ann").transpose = -2;
+        assert_eq!(profiles.get("ANN").map(|profile| profile.transpose), Some(-2));
+        assert!(profiles.get("Bob").is_none());
+
+        let ann = profiles.entry("ann");
+        assert_eq!(ann.name, "Ann");
+        ann.record("/songs/a.lrc".to_string(), "A", 7000, 10);
+        ann.record("/songs/b.lrc".to_string(), "B", 9000, 20);
+        assert_eq!(ann.best().map(|best| best.title.as_str()), Some("B"));
+        assert!(ann.toggle_favorite("/songs/a.lrc".to_string(), "A"));
+        assert!(!ann.toggle_favorite("/songs/a.lrc".to_string(), "A"));
+        assert!(ann.favorites.is_empty());
+        assert_eq!(ann.summary().as_deref(), Some("Ann: transpose -2"));
+
+        for i in 0..KEPT_SCORES as u32 {
+            ann.record("/songs/c.lrc".to_string(), "C", i, 30);
+        }
+        assert_eq!(ann.scores.len(), KEPT_SCORES);
+        assert_eq!(ann.scores[0].score, 0);
+    }
+}
@@ -0,0 +1,107 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style},
+    widgets::Widget,
+};
+
+// Left-aligned partial blocks, one to seven eighths of a cell wide
+const EIGHTHS: [char; 8] = [' ', '▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+const TRACK: Color = Color::Rgb(60, 60, 70);
+
+// The characters of a bar `width` cells wide filled to `ratio`, at an eighth of a cell resolution
+pub fn bar_chars(ratio: f64, width: usize) -> String {
+    let eighths = (ratio.clamp(0.0, 1.0) * (width * 8) as f64).round() as usize;
+    (0..width)
+        .map(|cell| match eighths.saturating_sub(cell * 8) {
+            0 => ' ',
+            n if n >= 8 => '█',
+            n => EIGHTHS[n],
+        })
+        .collect()
+}
+
+fn blend(from: Color, to: Color, t: f64) -> Color {
+    match (from, to) {
+        (Color::Rgb(r1, g1, b1), Color::Rgb(r2, g2, b2)) => {
+            let mix = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+            Color::Rgb(mix(r1, r2), mix(g1, g2), mix(b1, b2))
+        }
+        _ => from,
+    }
+}
+
+// A one-row bar whose played part is drawn over a dim track, optionally shading from the
+// elapsed color at the left edge to the gradient color at the right
+pub struct ProgressBar {
+    ratio: f64,
+    elapsed: Color,
+    gradient: Option<Color>,
+}
+
+impl ProgressBar {
+    pub fn new(ratio: f64) -> Self {
+        Self {
+            ratio,
+            elapsed: Color::Rgb(255, 255, 255),
+            gradient: None,
+        }
+    }
+
+    pub fn gradient(mut self, to: Color) -> Self {
+        self.gradient = Some(to);
+        self
+    }
+
+    fn color_at(&self, cell: usize, width: usize) -> Color {
+        match self.gradient {
+            Some(to) => blend(self.elapsed, to, cell as f64 / width.saturating_sub(1).max(1) as f64),
+            None => self.elapsed,
+        }
+    }
+}
+
+impl Widget for ProgressBar {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let width = area.width as usize;
+        for (cell, ch) in bar_chars(self.ratio, width).chars().enumerate() {
+            let style = Style::default().fg(self.color_at(cell, width)).bg(TRACK);
+            buf.get_mut(area.x + cell as u16, area.y)
+                .set_char(ch)
+                .set_style(style);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fills_in_eighths_of_a_cell() {
+        assert_eq!(bar_chars(0.0, 4), "    ");
+        assert_eq!(bar_chars(0.5, 4), "██  ");
+        // 5 of 16 eighths
+        assert_eq!(bar_chars(0.3125, 2), "▋ ");
+        assert_eq!(bar_chars(1.0, 3), "███");
+        assert_eq!(bar_chars(2.0, 2), "██");
+    }
+
+    #[test]
+    fn gradient_runs_edge_to_edge() {
+        let bar = ProgressBar::new(1.0).gradient(Color::Rgb(0, 0, 0));
+        assert_eq!(bar.color_at(0, 5), Color::Rgb(255, 255, 255));
+        assert_eq!(bar.color_at(2, 5), Color::Rgb(128, 128, 128));
+        assert_eq!(bar.color_at(4, 5), Color::Rgb(0, 0, 0));
+        assert_eq!(ProgressBar::new(1.0).color_at(4, 5), Color::Rgb(255, 255, 255));
+    }
+
+    #[test]
+    fn renders_over_the_track() {
+        let area = Rect::new(0, 0, 3, 1);
+        let mut buf = Buffer::empty(area);
+        ProgressBar::new(0.5).render(area, &mut buf);
+        assert_eq!(buf.get(1, 0).symbol(), "▌");
+        assert_eq!(buf.get(2, 0).bg, TRACK);
+    }
+}
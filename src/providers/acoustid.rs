@@ -0,0 +1,99 @@
+use std::{io, path::Path, process::Command};
+
+use super::{get_json, percent_encode, provider_error};
+
+const API: &str = "https://api.acoustid.org/v2/lookup";
+// AcoustID rates how sure it is of each match; below this it's more likely a different song
+const MIN_SCORE: f64 = 0.5;
+
+#[derive(Debug, PartialEq)]
+pub struct Identity {
+    pub title: String,
+    pub artist: String,
+}
+
+// Chromaprint's `fpcalc` gives the recording's length and its fingerprint
+fn fingerprint(audio: &Path) -> io::Result<(f64, String)> {
+    let output = Command::new("fpcalc").arg("-json").arg(audio).output().map_err(|e| match e.kind() {
+        io::ErrorKind::NotFound => provider_error("fpcalc (from Chromaprint) is required to identify recordings"),
+        _ => e,
+    })?;
+    if !output.status.success() {
+        return Err(provider_error(format!("fpcalc failed: {}", String::from_utf8_lossy(&output.stderr).trim())));
+    }
+    let json: serde_json::Value =
+        serde_json::from_slice(&output.stdout).map_err(|e| provider_error(format!("unexpected fpcalc output: {}", e)))?;
+    match (json["duration"].as_f64(), json["fingerprint"].as_str()) {
+        (Some(duration), Some(fingerprint)) => Ok((duration, fingerprint.to_string())),
+        _ => Err(provider_error("unexpected fpcalc output")),
+    }
+}
+
+// The best scoring MusicBrainz recording among AcoustID's matches, with its artists credited
+// the way MusicBrainz writes them, e.g. "Queen & David Bowie"
+fn best_match(json: &serde_json::Value) -> Option<Identity> {
+    let mut results: Vec<&serde_json::Value> = json["results"].as_array()?.iter().collect();
+    results.sort_by(|a, b| b["score"].as_f64().unwrap_or(0.0).total_cmp(&a["score"].as_f64().unwrap_or(0.0)));
+    results
+        .into_iter()
+        .filter(|result| result["score"].as_f64().unwrap_or(0.0) >= MIN_SCORE)
+        .flat_map(|result| result["recordings"].as_array().into_iter().flatten())
+        .find_map(|recording| {
+            let title = recording["title"].as_str()?.to_string();
+            let artist = recording["artists"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .map(|artist| {
+                    let name = artist["name"].as_str().unwrap_or_default();
+                    format!("{}{}", name, artist["joinphrase"].as_str().unwrap_or(""))
+                })
+                .collect::<String>();
+            Some(Identity { title, artist })
+        })
+}
+
+// Looks a recording up by how it sounds, for when its file says nothing about what it is.
+// AcoustID needs an application API key, read from ACOUSTID_API_KEY.
+pub fn identify(audio: &Path) -> io::Result<Option<Identity>> {
+    let key = std::env::var("ACOUSTID_API_KEY")
+        .ok()
+        .filter(|key| !key.is_empty())
+        .ok_or_else(|| provider_error("set ACOUSTID_API_KEY to identify recordings"))?;
+    let (duration, fingerprint) = fingerprint(audio)?;
+    let url = format!(
+        "{}?client={}&meta=recordings&duration={}&fingerprint={}",
+        API,
+        percent_encode(&key),
+        duration.round() as u64,
+        percent_encode(&fingerprint)
+    );
+    let json = get_json(&url, &[])?;
+    if json["status"].as_str() != Some("ok") {
+        let message = json["error"]["message"].as_str().unwrap_or("unexpected response");
+        return Err(provider_error(format!("acoustid: {}", message)));
+    }
+    Ok(best_match(&json))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_the_surest_recording() {
+        let json = serde_json::json!({
+            "status": "ok",
+            "results": [
+                { "score": 0.3, "recordings": [{ "title": "Wrong", "artists": [{ "name": "Nobody" }] }] },
+                { "score": 0.6, "recordings": [{ "id": "no title" }] },
+                { "score": 0.9, "recordings": [{ "title": "Under Pressure", "artists": [
+                    { "name": "Queen", "joinphrase": " & " }, { "name": "David Bowie" }
+                ] }] }
+            ]
+        });
+        let identity = best_match(&json).unwrap();
+        assert_eq!((identity.title.as_str(), identity.artist.as_str()), ("Under Pressure", "Queen & David Bowie"));
+        assert_eq!(best_match(&serde_json::json!({ "status": "ok", "results": [] })), None);
+    }
+}
@@ -0,0 +1,64 @@
+use std::io;
+
+use super::{get_json, percent_encode, provider_error, LyricsProvider, SearchResult};
+
+pub struct Lrclib;
+
+fn parse_results(json: &serde_json::Value) -> Vec<SearchResult> {
+    json.as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|track| {
+            // Plain lyrics without timing aren't any use for karaoke
+            let lyrics = track["syncedLyrics"].as_str().filter(|lyrics| !lyrics.is_empty())?;
+            Some(SearchResult {
+                provider: "lrclib",
+                id: track["id"].to_string(),
+                title: track["trackName"].as_str().unwrap_or_default().to_string(),
+                artist: track["artistName"].as_str().unwrap_or_default().to_string(),
+                duration: track["duration"].as_f64(),
+                lyrics: Some(lyrics.to_string()),
+            })
+        })
+        .collect()
+}
+
+impl LyricsProvider for Lrclib {
+    fn name(&self) -> &'static str {
+        "lrclib"
+    }
+
+    fn search(&self, query: &str) -> io::Result<Vec<SearchResult>> {
+        let json = get_json(&format!("https://lrclib.net/api/search?q={}", percent_encode(query)), &[])?;
+        Ok(parse_results(&json))
+    }
+
+    fn fetch(&self, result: &SearchResult) -> io::Result<String> {
+        if let Some(lyrics) = &result.lyrics {
+            return Ok(lyrics.clone());
+        }
+        let json = get_json(&format!("https://lrclib.net/api/get/{}", result.id), &[])?;
+        json["syncedLyrics"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| provider_error("lrclib has no synced lyrics for this track"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_synced_results_are_kept() {
+        let json = serde_json::json!([
+            { "id": 7, "trackName": "Song", "artistName": "Band", "duration": 201.0, "syncedLyrics": "[00:01.00]Hi" },
+            { "id": 8, "trackName": "Plain", "artistName": "Band", "plainLyrics": "Hi", "syncedLyrics": null }
+        ]);
+        let results = parse_results(&json);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, "7");
+        assert_eq!(results[0].display_name(), "Band - Song");
+        assert_eq!(results[0].duration, Some(201.0));
+    }
+}
@@ -0,0 +1,123 @@
+use std::{io, process::Command};
+
+pub mod acoustid;
+mod lrclib;
+mod musixmatch;
+mod netease;
+
+pub const DEFAULT_PRIORITY: &str = "lrclib,netease,musixmatch";
+pub const PROVIDER_NAMES: [&str; 3] = ["lrclib", "netease", "musixmatch"];
+
+#[derive(Debug, Clone)]
+pub struct SearchResult {
+    pub provider: &'static str,
+    pub id: String,
+    pub title: String,
+    pub artist: String,
+    pub duration: Option<f64>,
+    // Filled in when the search response already carries the synced lyrics
+    pub lyrics: Option<String>,
+}
+
+impl SearchResult {
+    pub fn display_name(&self) -> String {
+        if self.artist.is_empty() {
+            self.title.clone()
+        } else {
+            format!("{} - {}", self.artist, self.title)
+        }
+    }
+}
+
+pub trait LyricsProvider {
+    fn name(&self) -> &'static str;
+    fn search(&self, query: &str) -> io::Result<Vec<SearchResult>>;
+    // Synced lyrics in LRC format for one of this provider's results
+    fn fetch(&self, result: &SearchResult) -> io::Result<String>;
+}
+
+// Providers in the order they were listed, which is also the order results are shown in
+pub fn by_priority(names: &[String]) -> Vec<Box<dyn LyricsProvider>> {
+    names
+        .iter()
+        .filter_map(|name| -> Option<Box<dyn LyricsProvider>> {
+            match name.as_str() {
+                "lrclib" => Some(Box::new(lrclib::Lrclib)),
+                "netease" => Some(Box::new(netease::NetEase)),
+                "musixmatch" => Some(Box::new(musixmatch::Musixmatch::from_env())),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+fn provider_error(message: impl Into<String>) -> io::Error {
+    io::Error::other(message.into())
+}
+
+// Requests go through curl so HTTPS works without pulling a TLS stack into the build
+fn http_get(url: &str, headers: &[&str]) -> io::Result<String> {
+    let mut command = Command::new("curl");
+    command.args(["--silent", "--show-error", "--fail", "--location", "--max-time", "15"]);
+    for header in headers {
+        command.args(["--header", header]);
+    }
+    let output = command.arg(url).output().map_err(|e| match e.kind() {
+        io::ErrorKind::NotFound => provider_error("curl is required to search online providers"),
+        _ => e,
+    })?;
+
+    if !output.status.success() {
+        return Err(provider_error(String::from_utf8_lossy(&output.stderr).trim().to_string()));
+    }
+    String::from_utf8(output.stdout).map_err(|_| provider_error("response is not valid UTF-8"))
+}
+
+fn get_json(url: &str, headers: &[&str]) -> io::Result<serde_json::Value> {
+    let body = http_get(url, headers)?;
+    serde_json::from_str(&body).map_err(|e| provider_error(format!("invalid response: {}", e)))
+}
+
+fn percent_encode(text: &str) -> String {
+    text.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (byte as char).to_string(),
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+// A file name for the library that is safe on every platform
+pub fn file_name(result: &SearchResult) -> String {
+    format!("{}.lrc", crate::library::song_stem(Some(&result.artist), &result.title))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn queries_are_percent_encoded() {
+        assert_eq!(percent_encode("hát ca & co"), "h%C3%A1t%20ca%20%26%20co");
+    }
+
+    #[test]
+    fn library_file_names_drop_path_separators() {
+        let result = SearchResult {
+            provider: "lrclib",
+            id: "1".to_string(),
+            title: "Yes/No?".to_string(),
+            artist: "AC/DC".to_string(),
+            duration: None,
+            lyrics: None,
+        };
+        assert_eq!(file_name(&result), "AC_DC - Yes_No_.lrc");
+    }
+
+    #[test]
+    fn unknown_provider_names_are_skipped() {
+        let names: Vec<String> = ["netease", "nope", "lrclib"].iter().map(|s| s.to_string()).collect();
+        let providers: Vec<&str> = by_priority(&names).iter().map(|p| p.name()).collect();
+        assert_eq!(providers, ["netease", "lrclib"]);
+    }
+}
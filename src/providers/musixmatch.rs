@@ -0,0 +1,82 @@
+use std::io;
+
+use super::{get_json, percent_encode, provider_error, LyricsProvider, SearchResult};
+
+const API: &str = "https://api.musixmatch.com/ws/1.1";
+
+// Musixmatch needs a developer API key, read from MUSIXMATCH_API_KEY
+pub struct Musixmatch {
+    api_key: Option<String>,
+}
+
+impl Musixmatch {
+    pub fn from_env() -> Self {
+        Self {
+            api_key: std::env::var("MUSIXMATCH_API_KEY").ok().filter(|key| !key.is_empty()),
+        }
+    }
+
+    fn api_key(&self) -> io::Result<&str> {
+        self.api_key
+            .as_deref()
+            .ok_or_else(|| provider_error("set MUSIXMATCH_API_KEY to search Musixmatch"))
+    }
+}
+
+// Every response is wrapped in message.header / message.body
+fn body(json: &serde_json::Value) -> io::Result<&serde_json::Value> {
+    match json["message"]["header"]["status_code"].as_u64() {
+        Some(200) => Ok(&json["message"]["body"]),
+        Some(code) => Err(provider_error(format!("musixmatch answered with status {}", code))),
+        None => Err(provider_error("unexpected musixmatch response")),
+    }
+}
+
+fn parse_results(body: &serde_json::Value) -> Vec<SearchResult> {
+    body["track_list"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| {
+            let track = &entry["track"];
+            Some(SearchResult {
+                provider: "musixmatch",
+                id: track["track_id"].as_u64()?.to_string(),
+                title: track["track_name"].as_str().unwrap_or_default().to_string(),
+                artist: track["artist_name"].as_str().unwrap_or_default().to_string(),
+                duration: track["track_length"].as_f64(),
+                lyrics: None,
+            })
+        })
+        .collect()
+}
+
+impl LyricsProvider for Musixmatch {
+    fn name(&self) -> &'static str {
+        "musixmatch"
+    }
+
+    fn search(&self, query: &str) -> io::Result<Vec<SearchResult>> {
+        let url = format!(
+            "{}/track.search?q={}&f_has_subtitle=1&page_size=10&apikey={}",
+            API,
+            percent_encode(query),
+            self.api_key()?
+        );
+        Ok(parse_results(body(&get_json(&url, &[])?)?))
+    }
+
+    fn fetch(&self, result: &SearchResult) -> io::Result<String> {
+        let url = format!(
+            "{}/track.subtitle.get?track_id={}&subtitle_format=lrc&apikey={}",
+            API,
+            result.id,
+            self.api_key()?
+        );
+        let json = get_json(&url, &[])?;
+        body(&json)?["subtitle"]["subtitle_body"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| provider_error("musixmatch has no synced lyrics for this track"))
+    }
+}
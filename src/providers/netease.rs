@@ -0,0 +1,72 @@
+use std::io;
+
+use super::{get_json, percent_encode, provider_error, LyricsProvider, SearchResult};
+
+pub struct NetEase;
+
+// NetEase rejects requests that don't look like they come from its web player
+const HEADERS: [&str; 2] = ["Referer: https://music.163.com/", "User-Agent: Mozilla/5.0"];
+
+fn parse_results(json: &serde_json::Value) -> Vec<SearchResult> {
+    json["result"]["songs"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|song| {
+            let artists: Vec<&str> = song["artists"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|artist| artist["name"].as_str())
+                .collect();
+            Some(SearchResult {
+                provider: "netease",
+                id: song["id"].as_u64()?.to_string(),
+                title: song["name"].as_str().unwrap_or_default().to_string(),
+                artist: artists.join(", "),
+                duration: song["duration"].as_f64().map(|ms| ms / 1000.0),
+                lyrics: None,
+            })
+        })
+        .collect()
+}
+
+impl LyricsProvider for NetEase {
+    fn name(&self) -> &'static str {
+        "netease"
+    }
+
+    fn search(&self, query: &str) -> io::Result<Vec<SearchResult>> {
+        let url = format!("https://music.163.com/api/search/get?s={}&type=1&limit=10", percent_encode(query));
+        Ok(parse_results(&get_json(&url, &HEADERS)?))
+    }
+
+    fn fetch(&self, result: &SearchResult) -> io::Result<String> {
+        let url = format!("https://music.163.com/api/song/lyric?id={}&lv=1", result.id);
+        let json = get_json(&url, &HEADERS)?;
+        json["lrc"]["lyric"]
+            .as_str()
+            .filter(|lyrics| lyrics.contains('['))
+            .map(str::to_string)
+            .ok_or_else(|| provider_error("netease has no synced lyrics for this track"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_songs_and_joins_artists() {
+        let json = serde_json::json!({
+            "result": { "songs": [
+                { "id": 42, "name": "Duet", "artists": [{ "name": "A" }, { "name": "B" }], "duration": 180500 }
+            ] },
+            "code": 200
+        });
+        let results = parse_results(&json);
+        assert_eq!(results[0].id, "42");
+        assert_eq!(results[0].display_name(), "A, B - Duet");
+        assert_eq!(results[0].duration, Some(180.5));
+    }
+}
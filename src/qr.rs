@@ -0,0 +1,361 @@
+// QR codes for the addresses phones open, drawn in the terminal two modules to a cell. Only
+// what a URL needs: byte mode at the lowest error correction level, versions 1 to 6, which
+// hold up to 134 bytes.
+
+use ratatui::{
+    style::{Color, Style},
+    text::Line,
+};
+
+// Per version: all its codewords, the error correction codewords of each block, and the blocks
+const VERSIONS: [(usize, usize, usize); 6] = [(26, 7, 1), (44, 10, 1), (70, 15, 1), (100, 20, 1), (134, 26, 1), (172, 18, 2)];
+// Error correction level L, as the format information writes it
+const LEVEL_BITS: u32 = 0b01;
+// Light modules around the code; scanners want four, but two do on a lit screen
+const QUIET_ZONE: usize = 2;
+
+#[derive(Debug, Clone)]
+pub struct QrCode {
+    size: usize,
+    // Row by row, true for dark
+    modules: Vec<bool>,
+    // Finder, timing and format modules, which the data and the mask leave alone
+    reserved: Vec<bool>,
+}
+
+impl QrCode {
+    // None when the text is too long for the versions here
+    pub fn encode(text: &str) -> Option<Self> {
+        let bytes = text.as_bytes();
+        let version = (1..=VERSIONS.len()).find(|&version| bytes.len() <= capacity(version))?;
+        let codewords = interleave(version, &data_codewords(bytes, version));
+
+        let mut code = Self::blank(version);
+        code.place(&codewords);
+        let best = (0..8)
+            .min_by_key(|&mask| {
+                let mut masked = code.clone();
+                masked.apply_mask(mask);
+                masked.draw_format(mask);
+                masked.penalty()
+            })
+            .unwrap_or(0);
+        code.apply_mask(best);
+        code.draw_format(best);
+        Some(code)
+    }
+
+    pub fn is_dark(&self, x: usize, y: usize) -> bool {
+        self.modules[y * self.size + x]
+    }
+
+    fn set(&mut self, x: usize, y: usize, dark: bool) {
+        self.modules[y * self.size + x] = dark;
+        self.reserved[y * self.size + x] = true;
+    }
+
+    // The finder patterns, timing lines, alignment pattern and dark module, with room kept for
+    // the format information
+    fn blank(version: usize) -> Self {
+        let size = version * 4 + 17;
+        let mut code = Self {
+            size,
+            modules: vec![false; size * size],
+            reserved: vec![false; size * size],
+        };
+        for i in 0..size {
+            code.set(6, i, i % 2 == 0);
+            code.set(i, 6, i % 2 == 0);
+        }
+        for (x, y) in [(3, 3), (size - 4, 3), (3, size - 4)] {
+            // Out to the light separator around each finder
+            for dy in -4..=4i32 {
+                for dx in -4..=4i32 {
+                    let (cx, cy) = (x as i32 + dx, y as i32 + dy);
+                    if (0..size as i32).contains(&cx) && (0..size as i32).contains(&cy) {
+                        let ring = dx.abs().max(dy.abs());
+                        code.set(cx as usize, cy as usize, ring != 2 && ring != 4);
+                    }
+                }
+            }
+        }
+        if version > 1 {
+            let center = size - 7;
+            for dy in -2..=2i32 {
+                for dx in -2..=2i32 {
+                    let ring = dx.abs().max(dy.abs());
+                    code.set((center as i32 + dx) as usize, (center as i32 + dy) as usize, ring != 1);
+                }
+            }
+        }
+        code.draw_format(0);
+        code
+    }
+
+    // Fifteen bits of level and mask, twice: around the top left finder, and split between
+    // the other two
+    fn draw_format(&mut self, mask: u32) {
+        let data = LEVEL_BITS << 3 | mask;
+        let mut remainder = data;
+        for _ in 0..10 {
+            remainder = (remainder << 1) ^ ((remainder >> 9) * 0x537);
+        }
+        let bits = (data << 10 | remainder) ^ 0x5412;
+        let bit = |i: usize| (bits >> i) & 1 == 1;
+        let size = self.size;
+        for i in 0..6 {
+            self.set(8, i, bit(i));
+        }
+        self.set(8, 7, bit(6));
+        self.set(8, 8, bit(7));
+        self.set(7, 8, bit(8));
+        for i in 9..15 {
+            self.set(14 - i, 8, bit(i));
+        }
+        for i in 0..8 {
+            self.set(size - 1 - i, 8, bit(i));
+        }
+        for i in 8..15 {
+            self.set(8, size - 15 + i, bit(i));
+        }
+        self.set(8, size - 8, true);
+    }
+
+    // Up and down two columns at a time from the bottom right, around the reserved modules
+    fn place(&mut self, codewords: &[u8]) {
+        let mut bit = 0;
+        let mut right = self.size - 1;
+        loop {
+            if right == 6 {
+                right = 5;
+            }
+            let upward = (right + 1) & 2 == 0;
+            for vertical in 0..self.size {
+                let y = if upward { self.size - 1 - vertical } else { vertical };
+                for x in [right, right - 1] {
+                    let i = y * self.size + x;
+                    if !self.reserved[i] && bit < codewords.len() * 8 {
+                        self.modules[i] = (codewords[bit / 8] >> (7 - bit % 8)) & 1 == 1;
+                        bit += 1;
+                    }
+                }
+            }
+            if right < 2 {
+                break;
+            }
+            right -= 2;
+        }
+    }
+
+    fn apply_mask(&mut self, mask: u32) {
+        for y in 0..self.size {
+            for x in 0..self.size {
+                let flip = match mask {
+                    0 => (x + y) % 2 == 0,
+                    1 => y % 2 == 0,
+                    2 => x % 3 == 0,
+                    3 => (x + y) % 3 == 0,
+                    4 => (x / 3 + y / 2) % 2 == 0,
+                    5 => x * y % 2 + x * y % 3 == 0,
+                    6 => (x * y % 2 + x * y % 3) % 2 == 0,
+                    _ => ((x + y) % 2 + x * y % 3) % 2 == 0,
+                };
+                let i = y * self.size + x;
+                self.modules[i] ^= flip && !self.reserved[i];
+            }
+        }
+    }
+
+    // The standard's score for how hard a masked code is to read; lower is better
+    fn penalty(&self) -> usize {
+        let size = self.size;
+        let lines = (0..size).flat_map(|i| {
+            [
+                (0..size).map(|j| self.is_dark(j, i)).collect::<Vec<_>>(),
+                (0..size).map(|j| self.is_dark(i, j)).collect::<Vec<_>>(),
+            ]
+        });
+        let finder = [true, false, true, true, true, false, true, false, false, false, false];
+        let mut penalty = 0;
+        for line in lines {
+            // Runs of five or more of the same color
+            let mut run = 1;
+            for j in 1..=size {
+                if j < size && line[j] == line[j - 1] {
+                    run += 1;
+                    continue;
+                }
+                if run >= 5 {
+                    penalty += run - 2;
+                }
+                run = 1;
+            }
+            // Anything that looks like a finder
+            for window in line.windows(finder.len()) {
+                if window == finder || window.iter().rev().eq(finder.iter()) {
+                    penalty += 40;
+                }
+            }
+        }
+        // Blocks of one color
+        for y in 0..size - 1 {
+            for x in 0..size - 1 {
+                let dark = self.is_dark(x, y);
+                if self.is_dark(x + 1, y) == dark && self.is_dark(x, y + 1) == dark && self.is_dark(x + 1, y + 1) == dark {
+                    penalty += 3;
+                }
+            }
+        }
+        // Far from half dark
+        let dark = self.modules.iter().filter(|&&dark| dark).count();
+        let percent = dark * 100 / self.modules.len();
+        penalty + percent.abs_diff(50) / 5 * 10
+    }
+
+    // Rows of half blocks, each cell a module above another, light on dark with a quiet zone
+    pub fn rows(&self) -> Vec<String> {
+        let width = self.size + QUIET_ZONE * 2;
+        let light = |x: usize, y: usize| {
+            let inside = |n: usize| (QUIET_ZONE..QUIET_ZONE + self.size).contains(&n);
+            !(inside(x) && inside(y) && self.is_dark(x - QUIET_ZONE, y - QUIET_ZONE))
+        };
+        (0..width)
+            .step_by(2)
+            .map(|y| {
+                (0..width)
+                    .map(|x| match (light(x, y), y + 1 < width && light(x, y + 1)) {
+                        (true, true) => '█',
+                        (true, false) => '▀',
+                        (false, true) => '▄',
+                        (false, false) => ' ',
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+// The code with its address under it. Terminals without block characters get nothing, as the
+// code couldn't be scanned there.
+pub fn lines(url: &str) -> Option<Vec<Line<'static>>> {
+    if crate::compat::is_ascii() {
+        return None;
+    }
+    let code = QrCode::encode(url)?;
+    let style = Style::default().fg(Color::White).bg(Color::Black);
+    let mut lines: Vec<Line> = code.rows().into_iter().map(|row| Line::styled(row, style)).collect();
+    lines.push(Line::styled(url.to_string(), Style::default().fg(Color::Rgb(0, 200, 255))));
+    Some(lines)
+}
+
+fn capacity(version: usize) -> usize {
+    let (total, ec, blocks) = VERSIONS[version - 1];
+    // Less the mode and length, a byte and a half
+    total - ec * blocks - 2
+}
+
+// The mode, length and text, then padding out to the version's data codewords
+fn data_codewords(bytes: &[u8], version: usize) -> Vec<u8> {
+    let (total, ec, blocks) = VERSIONS[version - 1];
+    let length = total - ec * blocks;
+    let mut bits: Vec<bool> = Vec::with_capacity(length * 8);
+    let push = |bits: &mut Vec<bool>, value: u32, count: usize| bits.extend((0..count).rev().map(|i| (value >> i) & 1 == 1));
+    push(&mut bits, 0b0100, 4);
+    push(&mut bits, bytes.len() as u32, 8);
+    for &byte in bytes {
+        push(&mut bits, u32::from(byte), 8);
+    }
+    // Up to four zero bits end the data
+    let end = 4.min(length * 8 - bits.len());
+    push(&mut bits, 0, end);
+    let mut codewords: Vec<u8> = bits.chunks(8).map(|chunk| chunk.iter().enumerate().fold(0, |byte, (i, &bit)| byte | u8::from(bit) << (7 - i))).collect();
+    for pad in [0xEC, 0x11].into_iter().cycle() {
+        if codewords.len() >= length {
+            break;
+        }
+        codewords.push(pad);
+    }
+    codewords
+}
+
+// Splits the data into blocks, adds each one's error correction, and takes a codeword from
+// each block in turn
+fn interleave(version: usize, data: &[u8]) -> Vec<u8> {
+    let (_, ec, blocks) = VERSIONS[version - 1];
+    let blocks: Vec<&[u8]> = data.chunks(data.len() / blocks).collect();
+    let corrections: Vec<Vec<u8>> = blocks.iter().map(|block| error_correction(block, ec)).collect();
+    let mut codewords = Vec::new();
+    for i in 0..blocks[0].len() {
+        codewords.extend(blocks.iter().map(|block| block[i]));
+    }
+    for i in 0..ec {
+        codewords.extend(corrections.iter().map(|correction| correction[i]));
+    }
+    codewords
+}
+
+// Multiplication in the field the codes use, GF(256) modulo x^8 + x^4 + x^3 + x^2 + 1
+fn multiply(a: u8, b: u8) -> u8 {
+    let mut product = 0u16;
+    for i in (0..8).rev() {
+        product = (product << 1) ^ ((product >> 7) * 0x11D);
+        if (b >> i) & 1 == 1 {
+            product ^= u16::from(a);
+        }
+    }
+    product as u8
+}
+
+// Reed-Solomon: the remainder of the block divided by the generator with roots 2^0..2^(count-1)
+fn error_correction(block: &[u8], count: usize) -> Vec<u8> {
+    let mut generator = vec![0u8; count];
+    generator[count - 1] = 1;
+    let mut root = 1u8;
+    for _ in 0..count {
+        for j in 0..count {
+            generator[j] = multiply(generator[j], root) ^ generator.get(j + 1).copied().unwrap_or(0);
+        }
+        root = multiply(root, 2);
+    }
+    let mut remainder = vec![0u8; count];
+    for &byte in block {
+        let factor = byte ^ remainder.remove(0);
+        remainder.push(0);
+        for (r, &g) in remainder.iter_mut().zip(&generator) {
+            *r ^= multiply(g, factor);
+        }
+    }
+    remainder
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_correction_and_format_match_the_standard() {
+        // "HELLO WORLD" at version 1-M, the worked example everyone uses
+        let data = [32, 91, 11, 120, 209, 114, 220, 77, 67, 64, 236, 17, 236, 17, 236, 17];
+        assert_eq!(error_correction(&data, 10), [196, 35, 39, 119, 235, 215, 231, 226, 93, 23]);
+
+        let code = QrCode::blank(1);
+        let top: Vec<bool> = (0..6).map(|x| code.is_dark(x, 8)).collect();
+        // Level L with mask 0 is 111011111000100, its first bits running down beside the finder
+        assert_eq!((0..6).map(|y| code.is_dark(8, y)).collect::<Vec<_>>(), [false, false, true, false, false, false]);
+        assert_eq!(top, [true, true, true, false, true, true]);
+    }
+
+    #[test]
+    fn urls_fit_in_small_codes() {
+        let code = QrCode::encode("http://192.168.1.20:8090").unwrap();
+        assert_eq!(code.size, 25);
+        // The finders' corners, and the light separators inside them
+        assert!(code.is_dark(0, 0) && code.is_dark(24, 0) && code.is_dark(0, 24));
+        assert!(!code.is_dark(7, 7) && !code.is_dark(17, 7) && !code.is_dark(7, 17));
+        assert_eq!(code.rows().len(), 15);
+        assert!(code.rows().iter().all(|row| row.chars().count() == 29));
+
+        assert_eq!(QrCode::encode(&"a".repeat(134)).map(|code| code.size), Some(41));
+        assert!(QrCode::encode(&"a".repeat(135)).is_none());
+    }
+}
@@ -0,0 +1,202 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::LyricLine;
+
+const BLANK: char = '_';
+// Short words give too little away, and long runs are usually a line written without spaces
+const MIN_WORD: usize = 3;
+const MAX_WORD: usize = 12;
+
+// A word taken out of a line, by character position in its text
+#[derive(Debug, Clone, PartialEq)]
+struct Blank {
+    line: usize,
+    start: usize,
+    word: String,
+    // None until it's guessed or its line has gone by
+    correct: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Guess {
+    Correct,
+    Wrong,
+    NoQuestion,
+}
+
+// Fill in the blank: a word is taken out of about every other line, and the singer types it
+// before the line is over. The lyrics show underscores until the word is guessed or missed.
+#[derive(Debug, Clone)]
+pub struct Quiz {
+    blanks: Vec<Blank>,
+    pub input: String,
+}
+
+// Runs of letters that could be taken out, with where they start
+fn candidates(text: &str) -> Vec<(usize, String)> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    for (i, c) in text.chars().chain(std::iter::once(' ')).enumerate() {
+        if c.is_alphabetic() || (c == '\'' && !word.is_empty()) {
+            word.push(c);
+            continue;
+        }
+        let letters = word.trim_end_matches('\'').chars().count();
+        if (MIN_WORD..=MAX_WORD).contains(&letters) {
+            words.push((i - word.chars().count(), word.trim_end_matches('\'').to_string()));
+        }
+        word.clear();
+    }
+    words
+}
+
+// Writes `with` over the characters of `text` from `start`
+fn overwrite(text: &str, start: usize, with: impl IntoIterator<Item = char>) -> String {
+    let mut with = with.into_iter();
+    text.chars()
+        .enumerate()
+        .map(|(i, c)| match i >= start {
+            true => with.next().unwrap_or(c),
+            false => c,
+        })
+        .collect()
+}
+
+fn same_word(a: &str, b: &str) -> bool {
+    a.trim().to_lowercase() == b.to_lowercase()
+}
+
+impl Quiz {
+    pub fn new(lyrics: &mut [LyricLine], seed: u64) -> Self {
+        // Xorshift is plenty for picking words
+        let mut state = seed | 1;
+        let mut random = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        let mut blanks = Vec::new();
+        for (line, lyric) in lyrics.iter().enumerate() {
+            let words = candidates(&lyric.text);
+            if words.is_empty() || random() % 2 == 0 {
+                continue;
+            }
+            let (start, word) = words[(random() % words.len() as u64) as usize].clone();
+            blanks.push(Blank { line, start, word, correct: None });
+        }
+        let mut quiz = Self { blanks, input: String::new() };
+        quiz.reset(lyrics);
+        quiz
+    }
+
+    pub fn seed() -> u64 {
+        SystemTime::now().duration_since(UNIX_EPOCH).map_or(1, |elapsed| elapsed.as_nanos() as u64)
+    }
+
+    // Blanks every word out again, for a restart
+    pub fn reset(&mut self, lyrics: &mut [LyricLine]) {
+        for blank in &mut self.blanks {
+            blank.correct = None;
+            let line = &mut lyrics[blank.line];
+            line.text = overwrite(&line.text, blank.start, std::iter::repeat_n(BLANK, blank.word.chars().count()));
+        }
+        self.input.clear();
+    }
+
+    fn reveal(blank: &Blank, lyrics: &mut [LyricLine]) {
+        let line = &mut lyrics[blank.line];
+        line.text = overwrite(&line.text, blank.start, blank.word.chars());
+    }
+
+    // The earliest word still to be guessed whose line hasn't ended
+    fn question(&self, lyrics: &[LyricLine], lyric_time: f64) -> Option<usize> {
+        self.blanks
+            .iter()
+            .position(|blank| blank.correct.is_none() && lyrics[blank.line].end_time > lyric_time)
+    }
+
+    // Words whose lines have ended are missed and shown; returns the last of them
+    pub fn update(&mut self, lyrics: &mut [LyricLine], lyric_time: f64) -> Option<String> {
+        let mut missed = None;
+        for blank in &mut self.blanks {
+            if blank.correct.is_none() && lyrics[blank.line].end_time <= lyric_time {
+                blank.correct = Some(false);
+                Self::reveal(blank, lyrics);
+                missed = Some(blank.word.clone());
+            }
+        }
+        missed
+    }
+
+    // Checks what's been typed against the current question; a wrong guess can be tried again
+    pub fn guess(&mut self, lyrics: &mut [LyricLine], lyric_time: f64) -> Guess {
+        let input = std::mem::take(&mut self.input);
+        let Some(index) = self.question(lyrics, lyric_time) else {
+            return Guess::NoQuestion;
+        };
+        if !same_word(&input, &self.blanks[index].word) {
+            return Guess::Wrong;
+        }
+        self.blanks[index].correct = Some(true);
+        Self::reveal(&self.blanks[index], lyrics);
+        Guess::Correct
+    }
+
+    // Words guessed out of those asked so far
+    pub fn score(&self) -> (usize, usize) {
+        let asked = self.blanks.iter().filter(|blank| blank.correct.is_some());
+        let correct = asked.clone().filter(|blank| blank.correct == Some(true)).count();
+        (correct, asked.count())
+    }
+
+    pub fn describe(&self, lyrics: &[LyricLine], lyric_time: f64) -> String {
+        let (correct, asked) = self.score();
+        match self.question(lyrics, lyric_time) {
+            Some(index) => format!(
+                "? Missing word ({} letters): {}▏  {}/{}",
+                self.blanks[index].word.chars().count(),
+                self.input,
+                correct,
+                asked
+            ),
+            None => format!("Quiz {}/{}", correct, asked),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blanked_words_are_guessed_or_missed() {
+        assert_eq!(candidates("Don't stop, me now"), [(0, "Don't".to_string()), (6, "stop".to_string()), (15, "now".to_string())]);
+
+        let mut lyrics: Vec<LyricLine> = (0..8)
+            .map(|i| LyricLine {
+                text: format!("oh {} la", ["hello", "world", "again", "there"][i % 4]),
+                start_time: i as f64,
+                end_time: i as f64 + 1.0,
+                segments: Vec::new(),
+                singer: 0,
+                style: Default::default(),
+                chords: Vec::new(),
+            })
+            .collect();
+        let mut quiz = Quiz::new(&mut lyrics, 7);
+        let first = quiz.blanks[0].clone();
+        assert_eq!(lyrics[first.line].text, "oh _____ la");
+
+        quiz.input = "nope".to_string();
+        assert_eq!(quiz.guess(&mut lyrics, first.line as f64), Guess::Wrong);
+        quiz.input = first.word.to_uppercase();
+        assert_eq!(quiz.guess(&mut lyrics, first.line as f64), Guess::Correct);
+        assert_eq!(lyrics[first.line].text, format!("oh {} la", first.word));
+
+        let missed = quiz.update(&mut lyrics, 10.0);
+        assert_eq!(missed, quiz.blanks.last().map(|blank| blank.word.clone()));
+        assert_eq!(quiz.score(), (1, quiz.blanks.len()));
+        assert!(lyrics.iter().all(|line| !line.text.contains(BLANK)));
+    }
+}
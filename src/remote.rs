@@ -0,0 +1,129 @@
+use std::{path::PathBuf, sync::mpsc::Sender};
+
+use crate::{background::Theme, events::AppEvent};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RemoteCommand {
+    Play,
+    Pause,
+    TogglePause,
+    SeekTo(f64),
+    SeekBy(f64),
+    Next,
+    Load(PathBuf),
+    SetOffset(f64),
+    AdjustOffset(f64),
+    SetTheme(Theme),
+}
+
+// Every command needs the token, so a page open in the browser can't send one
+pub struct RemoteControl {
+    pub token: String,
+    pub events: Sender<AppEvent>,
+}
+
+impl RemoteControl {
+    // Returns the HTTP status and JSON body for an /api request. Commands change what's playing,
+    // so they're only taken as POSTs.
+    pub fn handle(&self, method: &str, path: &str, query: &str, bearer: Option<&str>) -> (u16, String) {
+        if method != "POST" {
+            return (405, error_body("commands must be sent with POST"));
+        }
+        let params = parse_query(query);
+
+        let supplied = bearer.or_else(|| param(&params, "token"));
+        if supplied != Some(self.token.as_str()) {
+            return (401, error_body("missing or invalid token"));
+        }
+
+        let command = match parse_command(path, &params) {
+            Ok(command) => command,
+            Err(message) => return (400, error_body(&message)),
+        };
+
+        match self.events.send(AppEvent::Remote(command)) {
+            Ok(()) => (200, "{\"ok\":true}".to_string()),
+            Err(_) => (503, error_body("player is not running")),
+        }
+    }
+}
+
+fn error_body(message: &str) -> String {
+    serde_json::json!({ "ok": false, "error": message }).to_string()
+}
+
+fn parse_command(path: &str, params: &[(String, String)]) -> Result<RemoteCommand, String> {
+    let number = |name: &str| -> Result<Option<f64>, String> {
+        param(params, name)
+            .map(|value| {
+                value
+                    .parse()
+                    .ok()
+                    .filter(|number: &f64| number.is_finite())
+                    .ok_or_else(|| format!("invalid number for {}: {}", name, value))
+            })
+            .transpose()
+    };
+
+    match path {
+        "/api/play" => Ok(RemoteCommand::Play),
+        "/api/pause" => Ok(RemoteCommand::Pause),
+        "/api/toggle" => Ok(RemoteCommand::TogglePause),
+        "/api/next" => Ok(RemoteCommand::Next),
+        "/api/seek" => match (number("to")?, number("by")?) {
+            (Some(to), None) => Ok(RemoteCommand::SeekTo(to)),
+            (None, Some(by)) => Ok(RemoteCommand::SeekBy(by)),
+            _ => Err("seek expects either ?to=<seconds> or ?by=<seconds>".to_string()),
+        },
+        "/api/offset" => match (number("set")?, number("by")?) {
+            (Some(set), None) => Ok(RemoteCommand::SetOffset(set)),
+            (None, Some(by)) => Ok(RemoteCommand::AdjustOffset(by)),
+            _ => Err("offset expects either ?set=<seconds> or ?by=<seconds>".to_string()),
+        },
+        "/api/load" => param(params, "path")
+            .map(|path| RemoteCommand::Load(PathBuf::from(path)))
+            .ok_or_else(|| "load expects ?path=<song file>".to_string()),
+        _ => Err(format!("unknown endpoint: {}", path)),
+    }
+}
+
+pub fn param<'a>(params: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    params.iter().find(|(key, _)| key == name).map(|(_, value)| value.as_str())
+}
+
+pub fn parse_query(query: &str) -> Vec<(String, String)> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (percent_decode(key), percent_decode(value))
+        })
+        .collect()
+}
+
+fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => out.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+                match hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                    Some(byte) => {
+                        out.push(byte);
+                        i += 2;
+                    }
+                    None => out.push(b'%'),
+                }
+            }
+            byte => out.push(byte),
+        }
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&out).into_owned()
+}
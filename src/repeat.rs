@@ -0,0 +1,30 @@
+use std::time::Duration;
+
+// How long the end of a song (and its results) stays up before a repeat kicks in
+pub const REPEAT_DELAY: Duration = Duration::from_secs(3);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RepeatMode {
+    #[default]
+    Off,
+    One,
+    All,
+}
+
+impl RepeatMode {
+    pub fn cycle(self) -> Self {
+        match self {
+            RepeatMode::Off => RepeatMode::One,
+            RepeatMode::One => RepeatMode::All,
+            RepeatMode::All => RepeatMode::Off,
+        }
+    }
+
+    pub fn label(self) -> Option<&'static str> {
+        match self {
+            RepeatMode::Off => None,
+            RepeatMode::One => Some("repeat one"),
+            RepeatMode::All => Some("repeat all"),
+        }
+    }
+}
@@ -0,0 +1,183 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    io::{self, Write},
+    net::{TcpListener, TcpStream},
+    path::{Path, PathBuf},
+    sync::{mpsc::Sender, Arc},
+    thread,
+};
+
+use crate::{
+    events::AppEvent,
+    formats, library,
+    remote::{param, parse_query},
+    server,
+};
+
+const PAGE: &str = include_str!("requests_page.html");
+const MAX_RESULTS: usize = 30;
+const MAX_GUEST_NAME: usize = 40;
+
+// A song a guest asked for, waiting on the host
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SongRequest {
+    pub song: PathBuf,
+    pub name: String,
+    pub guest: String,
+}
+
+// What guests can pick from. Requests name a song by its place here, so nothing outside the
+// library can be asked for.
+struct Catalog {
+    songs: Vec<(String, PathBuf)>,
+}
+
+impl Catalog {
+    fn scan(library: &Path) -> io::Result<Self> {
+        let mut paths: Vec<PathBuf> = fs::read_dir(library)?
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.is_file() && formats::is_song_file(path))
+            .collect();
+        paths.sort();
+        // The LRC file syncing a ChordPro song isn't a song of its own
+        let timings: Vec<PathBuf> = paths.iter().filter_map(|path| formats::timing_file(path)).collect();
+        let songs = paths
+            .into_iter()
+            .filter(|path| !timings.contains(path))
+            .filter_map(|path| {
+                let config = formats::load(&path).ok()?;
+                Some((library::song_stem(config.artist.as_deref(), &config.title), path))
+            })
+            .collect();
+        Ok(Self { songs })
+    }
+
+    // Songs with every word of the query somewhere in their title or artist
+    fn search(&self, query: &str) -> Vec<(usize, &str)> {
+        let words: Vec<String> = query.to_lowercase().split_whitespace().map(str::to_string).collect();
+        self.songs
+            .iter()
+            .enumerate()
+            .filter(|(_, (name, _))| {
+                let name = name.to_lowercase();
+                words.iter().all(|word| name.contains(word.as_str()))
+            })
+            .take(MAX_RESULTS)
+            .map(|(id, (name, _))| (id, name.as_str()))
+            .collect()
+    }
+
+    fn request(&self, params: &[(String, String)]) -> Result<SongRequest, String> {
+        let id: usize = param(params, "song").and_then(|id| id.parse().ok()).ok_or("request expects ?song=<id>")?;
+        let (name, song) = self.songs.get(id).ok_or("no such song")?;
+        let guest: String = param(params, "guest").unwrap_or_default().trim().chars().take(MAX_GUEST_NAME).collect();
+        if guest.is_empty() {
+            return Err("say who the request is from".to_string());
+        }
+        Ok(SongRequest {
+            song: song.clone(),
+            name: name.clone(),
+            guest,
+        })
+    }
+}
+
+// Serves the request page for guests; their requests arrive as events for the host to accept
+pub fn start(bind: &str, library: &Path, events: Sender<AppEvent>) -> io::Result<()> {
+    let catalog = Catalog::scan(library).map_err(|e| io::Error::new(e.kind(), format!("{}: {}", library.display(), e)))?;
+    let listener = TcpListener::bind(bind)?;
+    log::info!("taking song requests on http://{} for {} songs", bind, catalog.songs.len());
+
+    let catalog = Arc::new(catalog);
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let (catalog, events) = (catalog.clone(), events.clone());
+            thread::spawn(move || {
+                if let Err(e) = handle_connection(stream, &catalog, &events) {
+                    log::debug!("request page client dropped: {}", e);
+                }
+            });
+        }
+    });
+    Ok(())
+}
+
+fn respond(stream: &mut TcpStream, status: &str, content_type: &str, body: &str) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )
+}
+
+fn handle_connection(mut stream: TcpStream, catalog: &Catalog, events: &Sender<AppEvent>) -> io::Result<()> {
+    let (request_line, _) = server::read_head(&stream)?;
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or("GET");
+    let target = parts.next().unwrap_or("/");
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let params = parse_query(query);
+    match (method, path) {
+        ("GET", "/" | "/index.html") => respond(&mut stream, "200 OK", "text/html; charset=utf-8", PAGE),
+        ("GET", "/search") => {
+            let songs: Vec<serde_json::Value> = catalog
+                .search(param(&params, "q").unwrap_or_default())
+                .into_iter()
+                .map(|(id, name)| serde_json::json!({ "id": id, "name": name }))
+                .collect();
+            respond(&mut stream, "200 OK", "application/json", &serde_json::json!({ "songs": songs }).to_string())
+        }
+        ("POST", "/request") => {
+            let (status, body) = match catalog.request(&params) {
+                Ok(request) => match events.send(AppEvent::SongRequest(request)) {
+                    Ok(()) => ("200 OK", serde_json::json!({ "ok": true })),
+                    Err(_) => ("503 Service Unavailable", serde_json::json!({ "ok": false, "error": "the party is over" })),
+                },
+                Err(message) => ("400 Bad Request", serde_json::json!({ "ok": false, "error": message })),
+            };
+            respond(&mut stream, status, "application/json", &body.to_string())
+        }
+        _ => respond(&mut stream, "404 Not Found", "text/plain", ""),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn guests_search_and_request_from_the_library() {
+        let catalog = Catalog {
+            songs: vec![
+                ("Queen - Bohemian Rhapsody".to_string(), PathBuf::from("library/queen.lrc")),
+                ("a-ha - Take On Me".to_string(), PathBuf::from("library/take on me.lrc")),
+            ],
+        };
+        assert_eq!(catalog.search("take ON"), [(1, "a-ha - Take On Me")]);
+        assert_eq!(catalog.search("").len(), 2);
+        assert!(catalog.search("queen pressure").is_empty());
+
+        let params = |query: &str| parse_query(query);
+        let request = catalog.request(&params("song=0&guest=%20Ann%20")).unwrap();
+        assert_eq!((request.song.as_path(), request.guest.as_str()), (Path::new("library/queen.lrc"), "Ann"));
+        assert!(catalog.request(&params("song=7&guest=Ann")).is_err());
+        assert!(catalog.request(&params("song=0&guest=")).is_err());
+
+        // Accepted requests join the playlist, sung by the guest when they're playing
+        let mut session = crate::party::PartySession::new(vec!["Bob".to_string(), "Ann".to_string()], vec![PathBuf::from("first.lrc")], false);
+        assert!(session.add_request(request.clone()));
+        assert!(!session.add_request(request));
+        assert!(session.add_request(catalog.request(&params("song=1&guest=Zed")).unwrap()));
+        assert_eq!(session.accept_request().map(|request| request.guest), Some("Ann".to_string()));
+        assert_eq!(session.decline_request().map(|request| request.guest), Some("Zed".to_string()));
+        assert_eq!(session.songs, [PathBuf::from("first.lrc"), PathBuf::from("library/queen.lrc")]);
+        session.next_song = 1;
+        assert_eq!(session.singer_names(), "Ann");
+    }
+}
@@ -0,0 +1,86 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::practice::PracticeLoop;
+
+const STATE_FILE: &str = ".karaoke-lyric-state.json";
+
+// Where playback stopped when the user last quit mid-song. `song` is None for the built-in song.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResumeState {
+    pub song: Option<PathBuf>,
+    pub title: String,
+    pub position: f64,
+    pub lyric_offset: f64,
+    #[serde(default)]
+    pub practice: Option<PracticeLoop>,
+}
+
+// Per-user files live in the home directory so they work wherever the player is started from
+pub fn home_file(name: &str) -> PathBuf {
+    env::var_os("HOME")
+        .or_else(|| env::var_os("USERPROFILE"))
+        .map(PathBuf::from)
+        .unwrap_or_default()
+        .join(name)
+}
+
+pub fn state_path() -> PathBuf {
+    home_file(STATE_FILE)
+}
+
+// Song paths are stored absolute so a different working directory still finds them
+pub fn absolute(song: Option<&Path>) -> Option<PathBuf> {
+    song.map(|path| fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf()))
+}
+
+// A missing or unreadable state file just means there's nothing to resume
+pub fn load(path: &Path) -> Option<ResumeState> {
+    let json = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&json).ok()
+}
+
+pub fn save(path: &Path, state: &ResumeState) -> io::Result<()> {
+    let json = serde_json::to_string_pretty(state).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    fs::write(path, json)
+}
+
+pub fn clear(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+// Whether a saved state belongs to the song being opened; with no song given the last one counts
+pub fn matches(state: &ResumeState, requested: Option<&Path>) -> bool {
+    requested.is_none() || absolute(requested) == state.song
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saved_state_reads_back_and_clears() {
+        let path = env::temp_dir().join(format!("karaoke-resume-test-{}.json", std::process::id()));
+        let state = ResumeState {
+            song: Some(PathBuf::from("/songs/a.lrc")),
+            title: "A".to_string(),
+            position: 42.5,
+            lyric_offset: -0.3,
+            practice: Some(PracticeLoop::new(10.0)),
+        };
+        save(&path, &state).unwrap();
+        assert_eq!(load(&path), Some(state.clone()));
+        assert!(matches(&state, None));
+        assert!(matches(&state, Some(Path::new("/songs/a.lrc"))));
+        assert!(!matches(&state, Some(Path::new("/songs/b.lrc"))));
+        clear(&path).unwrap();
+        assert_eq!(load(&path), None);
+        clear(&path).unwrap();
+    }
+}
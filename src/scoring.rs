@@ -0,0 +1,131 @@
+use crate::{wav::WavData, LyricLine};
+
+const FRAME_SECONDS: f64 = 0.02;
+// A line counts toward the streak once at least this much of it was sung
+const STREAK_THRESHOLD: f64 = 0.6;
+// Singers naturally breathe between words, so ~70% voiced time already counts as a full line
+const FULL_CREDIT_RATIO: f64 = 0.7;
+// Taps further than this from a line start don't count for that line
+const TAP_WINDOW: f64 = 0.5;
+
+pub struct ScoreSheet {
+    pub line_accuracy: Vec<f64>,
+    // Lines whose accuracy can no longer change
+    locked: Vec<bool>,
+    tap_scored: bool,
+}
+
+impl ScoreSheet {
+    // Empty sheet filled in live as the singer taps along with each line start
+    pub fn for_taps(line_count: usize) -> Self {
+        Self {
+            line_accuracy: vec![0.0; line_count],
+            locked: vec![false; line_count],
+            tap_scored: true,
+        }
+    }
+
+    // Only the first tap inside a line's window counts, so mashing the key earns nothing
+    pub fn register_tap(&mut self, lyrics: &[LyricLine], time: f64) {
+        if !self.tap_scored {
+            return;
+        }
+        let candidate = lyrics
+            .iter()
+            .enumerate()
+            .filter(|(i, line)| !self.locked[*i] && (line.start_time - time).abs() <= TAP_WINDOW)
+            .min_by(|(_, a), (_, b)| {
+                (a.start_time - time).abs().total_cmp(&(b.start_time - time).abs())
+            });
+
+        if let Some((idx, line)) = candidate {
+            self.line_accuracy[idx] = 1.0 - (line.start_time - time).abs() / TAP_WINDOW;
+            self.locked[idx] = true;
+        }
+    }
+
+    pub fn accepts_taps(&self) -> bool {
+        self.tap_scored
+    }
+
+    // Recording-based scores describe a fixed take, so only tap scores start over
+    pub fn reset(&mut self) {
+        if !self.tap_scored {
+            return;
+        }
+        self.line_accuracy.iter_mut().for_each(|a| *a = 0.0);
+        self.locked.iter_mut().for_each(|l| *l = false);
+    }
+
+    // Scores each line by how much of its window contains voice activity in the recording
+    pub fn from_recording(lyrics: &[LyricLine], recording: &WavData) -> Self {
+        let levels = recording.rms_levels(FRAME_SECONDS);
+        let peak = levels.iter().cloned().fold(0.0_f32, f32::max);
+        let threshold = (peak * 0.1).max(0.01);
+
+        let line_accuracy = lyrics
+            .iter()
+            .map(|line| {
+                let first = (line.start_time / FRAME_SECONDS).floor().max(0.0) as usize;
+                let last = ((line.end_time / FRAME_SECONDS).ceil() as usize).min(levels.len());
+                if last <= first {
+                    return 0.0;
+                }
+                let voiced = levels[first..last].iter().filter(|&&level| level >= threshold).count();
+                let ratio = voiced as f64 / (last - first) as f64;
+                (ratio / FULL_CREDIT_RATIO).min(1.0)
+            })
+            .collect();
+
+        Self {
+            line_accuracy,
+            locked: vec![true; lyrics.len()],
+            tap_scored: false,
+        }
+    }
+
+    pub fn accuracy(&self) -> f64 {
+        if self.line_accuracy.is_empty() {
+            return 0.0;
+        }
+        self.line_accuracy.iter().sum::<f64>() / self.line_accuracy.len() as f64
+    }
+
+    pub fn total_score(&self) -> u32 {
+        (self.accuracy() * 10_000.0).round() as u32
+    }
+
+    pub fn stars(&self) -> usize {
+        match self.accuracy() {
+            a if a >= 0.9 => 5,
+            a if a >= 0.75 => 4,
+            a if a >= 0.55 => 3,
+            a if a >= 0.35 => 2,
+            a if a > 0.0 => 1,
+            _ => 0,
+        }
+    }
+
+    pub fn longest_streak(&self) -> usize {
+        let mut best = 0;
+        let mut current = 0;
+        for &accuracy in &self.line_accuracy {
+            if accuracy >= STREAK_THRESHOLD {
+                current += 1;
+                best = best.max(current);
+            } else {
+                current = 0;
+            }
+        }
+        best
+    }
+
+    // The run of well-sung lines that ends with the last of the first `lines` lines
+    pub fn streak_through(&self, lines: usize) -> usize {
+        self.line_accuracy[..lines.min(self.line_accuracy.len())]
+            .iter()
+            .rev()
+            .take_while(|&&accuracy| accuracy >= STREAK_THRESHOLD)
+            .count()
+    }
+}
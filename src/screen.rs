@@ -0,0 +1,110 @@
+use std::{
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+pub const DEFAULT_COUNTDOWN: u64 = 3;
+// The title is shown on its own for this long before the countdown starts
+const TITLE_TIME: Duration = Duration::from_millis(1500);
+
+// What the lyrics area shows. Screens other than Playing hold the song paused and hand over
+// to the next screen on their own when they run out.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Screen {
+    Splash { started: Instant, countdown: u64 },
+    // Counts down the beats after Space is pressed, before the clock starts
+    CountIn { started: Instant, beats: u64, beat_length: Duration },
+    // The whole lyric sheet, scrolled down `scroll` rows, until Enter hands over to the splash
+    // with `countdown`, or to the song when there's no splash
+    Preview { scroll: usize, countdown: Option<u64> },
+    Playing,
+}
+
+// A count-in given as `3` or `3s` for seconds, or `4b` for beats of the song's tempo
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CountIn {
+    Seconds(u64),
+    Beats(u64),
+}
+
+impl FromStr for CountIn {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, String> {
+        let (number, count_in): (&str, fn(u64) -> CountIn) = match value.strip_suffix('b') {
+            Some(number) => (number, CountIn::Beats),
+            None => (value.strip_suffix('s').unwrap_or(value), CountIn::Seconds),
+        };
+        match number.parse() {
+            Ok(count) if count > 0 => Ok(count_in(count)),
+            _ => Err(format!("invalid count-in: {}", value)),
+        }
+    }
+}
+
+impl Screen {
+    pub fn splash(countdown: u64, now: Instant) -> Self {
+        Screen::Splash { started: now, countdown }
+    }
+
+    pub fn count_in(beats: u64, beat_length: Duration, now: Instant) -> Self {
+        Screen::CountIn { started: now, beats, beat_length }
+    }
+
+    // The countdown number to show, or None while the title is on its own
+    pub fn countdown_left(&self, now: Instant) -> Option<u64> {
+        match *self {
+            Screen::Splash { started, countdown } => {
+                let counted = now.duration_since(started).checked_sub(TITLE_TIME)?.as_secs();
+                countdown.checked_sub(counted).filter(|&left| left > 0)
+            }
+            Screen::CountIn { started, beats, beat_length } => {
+                let counted = now.duration_since(started).as_secs_f64() / beat_length.as_secs_f64();
+                beats.checked_sub(counted as u64).filter(|&left| left > 0)
+            }
+            Screen::Preview { .. } | Screen::Playing => None,
+        }
+    }
+
+    // True once the screen has run out and playback should begin
+    pub fn is_finished(&self, now: Instant) -> bool {
+        match *self {
+            Screen::Splash { started, countdown } => {
+                now.duration_since(started) >= TITLE_TIME + Duration::from_secs(countdown)
+            }
+            Screen::CountIn { started, beats, beat_length } => now.duration_since(started) >= beat_length * beats as u32,
+            Screen::Preview { .. } | Screen::Playing => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn title_then_counts_down_then_plays() {
+        let start = Instant::now();
+        let screen = Screen::splash(3, start);
+        assert_eq!(screen.countdown_left(start + Duration::from_millis(500)), None);
+        assert_eq!(screen.countdown_left(start + Duration::from_millis(1600)), Some(3));
+        assert_eq!(screen.countdown_left(start + Duration::from_millis(4400)), Some(1));
+
+        assert!(!screen.is_finished(start + Duration::from_millis(4400)));
+        assert!(screen.is_finished(start + Duration::from_millis(4500)));
+        assert!(!Screen::Playing.is_finished(start + Duration::from_secs(10)));
+    }
+
+    #[test]
+    fn count_in_counts_the_beats_down() {
+        assert_eq!(("4b".parse(), "3s".parse(), "3".parse()), (Ok(CountIn::Beats(4)), Ok(CountIn::Seconds(3)), Ok(CountIn::Seconds(3))));
+        assert!("0".parse::<CountIn>().is_err() && "four".parse::<CountIn>().is_err());
+
+        let start = Instant::now();
+        let screen = Screen::count_in(4, Duration::from_millis(500), start);
+        assert_eq!(screen.countdown_left(start), Some(4));
+        assert_eq!(screen.countdown_left(start + Duration::from_millis(1600)), Some(1));
+        assert!(!screen.is_finished(start + Duration::from_millis(1999)));
+        assert!(screen.is_finished(start + Duration::from_secs(2)));
+    }
+}
@@ -0,0 +1,388 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::{Arc, Mutex},
+    thread,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{plugin::Renderer, resume, KaraokeApp};
+
+const CREDENTIALS_FILE: &str = ".karaoke-lyric-scrobble.json";
+const QUEUE_FILE: &str = ".karaoke-lyric-scrobble-queue.json";
+const LISTENBRAINZ_URL: &str = "https://api.listenbrainz.org/1/submit-listens";
+const LASTFM_URL: &str = "https://ws.audioscrobbler.com/2.0/";
+// The most scrobbles each service takes in one request
+const LISTENBRAINZ_BATCH: usize = 100;
+const LASTFM_BATCH: usize = 50;
+// The services' own rules: songs shorter than this aren't scrobbled, and longer ones count once
+// half of them, or this much, has been played
+const MIN_LENGTH: f64 = 30.0;
+const MAX_WAIT: f64 = 240.0;
+
+// Appending and taking off the queue happen on different threads, and one send at a time keeps
+// the same scrobble from going out twice
+static QUEUE_LOCK: Mutex<()> = Mutex::new(());
+static SEND_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, Deserialize)]
+struct ListenBrainz {
+    token: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct LastFm {
+    api_key: String,
+    api_secret: String,
+    session_key: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Credentials {
+    listenbrainz: Option<ListenBrainz>,
+    lastfm: Option<LastFm>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Scrobble {
+    artist: String,
+    title: String,
+    album: Option<String>,
+    duration: f64,
+    // When the song started, in seconds since the Unix epoch
+    timestamp: u64,
+}
+
+// What each service still has to be sent, kept until it takes it
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Queue {
+    #[serde(default)]
+    listenbrainz: Vec<Scrobble>,
+    #[serde(default)]
+    lastfm: Vec<Scrobble>,
+}
+
+impl Queue {
+    fn load(path: &Path) -> io::Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(json) => serde_json::from_str(&json)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path.display(), e))),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn save(&self, path: &Path) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        fs::write(path, json)
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs())
+}
+
+fn service_error(message: impl Into<String>) -> io::Error {
+    io::Error::other(message.into())
+}
+
+// One curl config line, quoted so any text survives
+fn config_line(option: &str, value: &str) -> String {
+    let escaped = value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n").replace('\r', "\\r");
+    format!("{} = \"{}\"\n", option, escaped)
+}
+
+// Through curl like the lyric providers. Tokens, keys and the body go in a config on its stdin,
+// where other users can't read them the way they can read its arguments.
+fn http_post(url: &str, config: &str) -> io::Result<String> {
+    let mut child = Command::new("curl")
+        .args(["--silent", "--show-error", "--fail", "--max-time", "15", "--config", "-"])
+        .arg(url)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => service_error("curl is required to scrobble"),
+            _ => e,
+        })?;
+    // curl giving up before it has read the config says why in its own error
+    let written = match child.stdin.take() {
+        Some(mut stdin) => stdin.write_all(config.as_bytes()),
+        None => Ok(()),
+    };
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(service_error(String::from_utf8_lossy(&output.stderr).trim().to_string()));
+    }
+    written?;
+    String::from_utf8(output.stdout).map_err(|_| service_error("response is not valid UTF-8"))
+}
+
+fn submit_listenbrainz(account: &ListenBrainz, scrobbles: &[Scrobble]) -> io::Result<()> {
+    let payload: Vec<_> = scrobbles
+        .iter()
+        .map(|scrobble| {
+            let mut track = serde_json::json!({
+                "artist_name": scrobble.artist,
+                "track_name": scrobble.title,
+                "additional_info": { "duration_ms": (scrobble.duration * 1000.0) as u64 },
+            });
+            if let Some(album) = &scrobble.album {
+                track["release_name"] = album.as_str().into();
+            }
+            serde_json::json!({ "listened_at": scrobble.timestamp, "track_metadata": track })
+        })
+        .collect();
+    let listen_type = if scrobbles.len() == 1 { "single" } else { "import" };
+    let body = serde_json::json!({ "listen_type": listen_type, "payload": payload }).to_string();
+    let config = [
+        config_line("header", &format!("Authorization: Token {}", account.token)),
+        config_line("header", "Content-Type: application/json"),
+        config_line("data-binary", &body),
+    ]
+    .concat();
+    http_post(LISTENBRAINZ_URL, &config).map(|_| ())
+}
+
+// Every parameter but the format, sorted and run together with the secret on the end
+fn lastfm_signature(params: &[(String, String)], secret: &str) -> String {
+    let mut sorted: Vec<_> = params.iter().filter(|(key, _)| key != "format").collect();
+    sorted.sort();
+    let mut text: String = sorted.iter().map(|(key, value)| format!("{}{}", key, value)).collect();
+    text.push_str(secret);
+    md5(text.as_bytes()).iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn submit_lastfm(account: &LastFm, batch: &[Scrobble]) -> io::Result<()> {
+    let mut params = vec![
+        ("method".to_string(), "track.scrobble".to_string()),
+        ("api_key".to_string(), account.api_key.clone()),
+        ("sk".to_string(), account.session_key.clone()),
+        ("format".to_string(), "json".to_string()),
+    ];
+    for (i, scrobble) in batch.iter().enumerate() {
+        params.push((format!("artist[{}]", i), scrobble.artist.clone()));
+        params.push((format!("track[{}]", i), scrobble.title.clone()));
+        params.push((format!("timestamp[{}]", i), scrobble.timestamp.to_string()));
+        params.push((format!("duration[{}]", i), (scrobble.duration.round() as u64).to_string()));
+        if let Some(album) = &scrobble.album {
+            params.push((format!("album[{}]", i), album.clone()));
+        }
+    }
+    let signature = lastfm_signature(&params, &account.api_secret);
+    params.push(("api_sig".to_string(), signature));
+    let config: String = params.iter().map(|(key, value)| config_line("data-urlencode", &format!("{}={}", key, value))).collect();
+    let response = http_post(LASTFM_URL, &config)?;
+    // Last.fm reports bad keys and the like with a 200 and an error in the body
+    let json: serde_json::Value = serde_json::from_str(&response).map_err(|e| service_error(format!("invalid response: {}", e)))?;
+    if let Some(message) = json.get("message").filter(|_| json.get("error").is_some()) {
+        return Err(service_error(message.as_str().unwrap_or("scrobble rejected").to_string()));
+    }
+    Ok(())
+}
+
+// The scrobbles a service took, batch by batch until one fails
+fn send_batches(name: &str, scrobbles: &[Scrobble], size: usize, submit: impl Fn(&[Scrobble]) -> io::Result<()>) -> Vec<Scrobble> {
+    let mut sent = Vec::new();
+    for batch in scrobbles.chunks(size) {
+        if let Err(e) = submit(batch) {
+            log::warn!("couldn't scrobble to {}, keeping {} queued: {}", name, scrobbles.len() - sent.len(), e);
+            break;
+        }
+        sent.extend_from_slice(batch);
+    }
+    if !sent.is_empty() {
+        log::info!("sent {} scrobbles to {}", sent.len(), name);
+    }
+    sent
+}
+
+fn append(path: &Path, credentials: &Credentials, scrobble: Scrobble) -> io::Result<()> {
+    let _lock = QUEUE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut queue = Queue::load(path)?;
+    if credentials.listenbrainz.is_some() {
+        queue.listenbrainz.push(scrobble.clone());
+    }
+    if credentials.lastfm.is_some() {
+        queue.lastfm.push(scrobble);
+    }
+    queue.save(path)
+}
+
+// Sends whatever is queued. What a service doesn't take stays queued for next time, along with
+// anything queued while this was sending.
+fn flush(path: &Path, credentials: &Credentials) -> io::Result<()> {
+    let _sending = SEND_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let queue = {
+        let _lock = QUEUE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        Queue::load(path)?
+    };
+    let listenbrainz = match &credentials.listenbrainz {
+        Some(account) => send_batches("ListenBrainz", &queue.listenbrainz, LISTENBRAINZ_BATCH, |batch| submit_listenbrainz(account, batch)),
+        None => Vec::new(),
+    };
+    let lastfm = match &credentials.lastfm {
+        Some(account) => send_batches("Last.fm", &queue.lastfm, LASTFM_BATCH, |batch| submit_lastfm(account, batch)),
+        None => Vec::new(),
+    };
+    if listenbrainz.is_empty() && lastfm.is_empty() {
+        return Ok(());
+    }
+
+    let _lock = QUEUE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut latest = Queue::load(path)?;
+    latest.listenbrainz.retain(|scrobble| !listenbrainz.contains(scrobble));
+    latest.lastfm.retain(|scrobble| !lastfm.contains(scrobble));
+    latest.save(path)
+}
+
+// Whether a song this long has been played far enough to count
+fn counts(duration: f64, position: f64) -> bool {
+    duration >= MIN_LENGTH && position >= (duration / 2.0).min(MAX_WAIT)
+}
+
+// Scrobbles each song once it's been played far enough, to the services with credentials in
+// the home folder. Songs without an artist can't be scrobbled and are skipped.
+pub struct Scrobbler {
+    credentials: Arc<Credentials>,
+    queue_path: PathBuf,
+    done: bool,
+}
+
+impl Scrobbler {
+    pub fn start() -> io::Result<Self> {
+        let path = resume::home_file(CREDENTIALS_FILE);
+        let json = fs::read_to_string(&path)
+            .map_err(|e| io::Error::new(e.kind(), format!("--scrobble reads its credentials from {}: {}", path.display(), e)))?;
+        let credentials: Credentials =
+            serde_json::from_str(&json).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path.display(), e)))?;
+        if credentials.listenbrainz.is_none() && credentials.lastfm.is_none() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} has neither listenbrainz nor lastfm credentials", path.display()),
+            ));
+        }
+        let scrobbler = Self {
+            credentials: Arc::new(credentials),
+            queue_path: resume::home_file(QUEUE_FILE),
+            done: false,
+        };
+        // Anything left from a session that was offline goes now
+        scrobbler.send_queued();
+        Ok(scrobbler)
+    }
+
+    fn send_queued(&self) {
+        let credentials = self.credentials.clone();
+        let path = self.queue_path.clone();
+        thread::spawn(move || {
+            if let Err(e) = flush(&path, &credentials) {
+                log::warn!("couldn't send queued scrobbles: {}", e);
+            }
+        });
+    }
+}
+
+impl Renderer for Scrobbler {
+    fn render(&mut self, app: &KaraokeApp) -> io::Result<()> {
+        let position = app.get_current_time();
+        if self.done || !counts(app.song_duration, position) {
+            return Ok(());
+        }
+        self.done = true;
+        let Some(artist) = app.song_artist.clone() else {
+            log::info!("not scrobbling \"{}\", it has no artist", app.song_title);
+            return Ok(());
+        };
+        let scrobble = Scrobble {
+            artist,
+            title: app.song_title.clone(),
+            album: app.song_album.clone(),
+            duration: app.song_duration,
+            timestamp: now().saturating_sub(position as u64),
+        };
+        // Queued first, so a scrobble isn't lost if the network or the session goes away
+        if let Err(e) = append(&self.queue_path, &self.credentials, scrobble) {
+            log::warn!("couldn't queue a scrobble: {}", e);
+        }
+        self.send_queued();
+        Ok(())
+    }
+
+    fn song_changed(&mut self) {
+        self.done = false;
+    }
+}
+
+// RFC 1321, for Last.fm's request signatures and telling library recordings apart
+pub fn md5(input: &[u8]) -> [u8; 16] {
+    const SHIFTS: [u32; 64] = [
+        7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 5, 9, 14, 20, 5, 9, 14, 20, 5, 9, 14, 20, 5, 9, 14, 20, 4,
+        11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21,
+    ];
+    let constants: Vec<u32> = (0..64).map(|i| ((i as f64 + 1.0).sin().abs() * 4_294_967_296.0) as u32).collect();
+
+    let mut message = input.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&((input.len() as u64).wrapping_mul(8)).to_le_bytes());
+
+    let mut state: [u32; 4] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476];
+    for chunk in message.chunks(64) {
+        let words: Vec<u32> = chunk.chunks(4).map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]])).collect();
+        let [mut a, mut b, mut c, mut d] = state;
+        for i in 0..64 {
+            let (f, g) = match i / 16 {
+                0 => ((b & c) | (!b & d), i),
+                1 => ((d & b) | (!d & c), (5 * i + 1) % 16),
+                2 => (b ^ c ^ d, (3 * i + 5) % 16),
+                _ => (c ^ (b | !d), (7 * i) % 16),
+            };
+            let rotated = a.wrapping_add(f).wrapping_add(constants[i]).wrapping_add(words[g]).rotate_left(SHIFTS[i]);
+            a = d;
+            d = c;
+            c = b;
+            b = b.wrapping_add(rotated);
+        }
+        for (value, add) in state.iter_mut().zip([a, b, c, d]) {
+            *value = value.wrapping_add(add);
+        }
+    }
+
+    let mut digest = [0; 16];
+    for (out, value) in digest.chunks_mut(4).zip(state) {
+        out.copy_from_slice(&value.to_le_bytes());
+    }
+    digest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn songs_count_halfway_and_requests_are_signed() {
+        assert!(!counts(20.0, 20.0));
+        assert!(!counts(200.0, 99.0));
+        assert!(counts(200.0, 100.0));
+        assert!(counts(600.0, 240.0));
+
+        let hex = |bytes: [u8; 16]| bytes.iter().map(|byte| format!("{:02x}", byte)).collect::<String>();
+        assert_eq!(hex(md5(b"")), "d41d8cd98f00b204e9800998ecf8427e");
+        assert_eq!(hex(md5(b"The quick brown fox jumps over the lazy dog")), "9e107d9d372bb6826bd81d3542a419d6");
+
+        let params = [
+            ("method".to_string(), "track.scrobble".to_string()),
+            ("api_key".to_string(), "k".to_string()),
+            ("format".to_string(), "json".to_string()),
+        ];
+        assert_eq!(lastfm_signature(&params, "s"), hex(md5(b"api_keykmethodtrack.scrobbles")));
+        assert_eq!(config_line("header", "a \"b\" \\ c\n"), "header = \"a \\\"b\\\" \\\\ c\\n\"\n");
+    }
+}
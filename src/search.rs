@@ -0,0 +1,234 @@
+use crossterm::event::KeyCode;
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+use std::{fs, io};
+
+use crate::{
+    cli::SearchOptions,
+    events::{AppEvent, EventBus},
+    formats,
+    providers::{self, acoustid, LyricsProvider, SearchResult},
+    wav,
+};
+
+struct SearchScreen {
+    providers: Vec<Box<dyn LyricsProvider>>,
+    results: Vec<SearchResult>,
+    selected: usize,
+    preview: Vec<String>,
+    status: String,
+}
+
+impl SearchScreen {
+    fn provider(&self, result: &SearchResult) -> &dyn LyricsProvider {
+        self.providers
+            .iter()
+            .find(|provider| provider.name() == result.provider)
+            .map(|provider| provider.as_ref())
+            .expect("results only come from configured providers")
+    }
+
+    fn selected_lyrics(&mut self) -> io::Result<String> {
+        let result = &self.results[self.selected];
+        let lyrics = match &result.lyrics {
+            Some(lyrics) => lyrics.clone(),
+            None => self.provider(result).fetch(result)?,
+        };
+        self.results[self.selected].lyrics = Some(lyrics.clone());
+        Ok(lyrics)
+    }
+
+    fn show_preview(&mut self) {
+        let lyrics = match self.selected_lyrics() {
+            Ok(lyrics) => lyrics,
+            Err(e) => {
+                self.status = format!("Preview failed: {}", e);
+                return;
+            }
+        };
+        match formats::parse_text("lrc", &lyrics) {
+            Ok(lines) => {
+                self.preview = lines
+                    .iter()
+                    .map(|line| format!("{}  {}", crate::format_time(line.start_time), line.text))
+                    .collect();
+                self.status = format!("{} lines", lines.len());
+            }
+            Err(e) => self.status = format!("Lyrics can't be used: {}", e),
+        }
+    }
+
+    fn save(&mut self, options: &SearchOptions) {
+        let saved = self.selected_lyrics().and_then(|lyrics| {
+            fs::create_dir_all(&options.library)?;
+            let path = options.library.join(providers::file_name(&self.results[self.selected]));
+            fs::write(&path, lyrics)?;
+            Ok(path)
+        });
+        self.status = match saved {
+            Ok(path) => format!("Saved {}", path.display()),
+            Err(e) => format!("Save failed: {}", e),
+        };
+    }
+
+    fn select(&mut self, delta: i64) {
+        if self.results.is_empty() {
+            return;
+        }
+        let last = self.results.len() as i64 - 1;
+        self.selected = (self.selected as i64 + delta).clamp(0, last) as usize;
+        self.preview.clear();
+    }
+}
+
+fn ui(f: &mut Frame, screen: &SearchScreen, query: &str) {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(5), Constraint::Length(1), Constraint::Length(1)])
+        .split(f.size());
+    let panes = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(45), Constraint::Percentage(55)])
+        .split(rows[0]);
+
+    let results: Vec<Line> = screen
+        .results
+        .iter()
+        .enumerate()
+        .map(|(i, result)| {
+            let duration = result.duration.map(crate::format_time).unwrap_or_default();
+            let style = if i == screen.selected {
+                Style::default().fg(Color::Black).bg(Color::Rgb(0, 255, 0)).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::White)
+            };
+            Line::from(vec![
+                Span::styled(format!("[{}] ", result.provider), Style::default().fg(Color::Magenta)),
+                Span::styled(format!("{}  {}", result.display_name(), duration), style),
+            ])
+        })
+        .collect();
+    let scroll = screen.selected.saturating_sub(panes[0].height.saturating_sub(3) as usize) as u16;
+    f.render_widget(
+        Paragraph::new(results)
+            .scroll((scroll, 0))
+            .block(Block::default().borders(Borders::ALL).title(format!(" Results for \"{}\" ", query))),
+        panes[0],
+    );
+
+    let preview: Vec<Line> = screen.preview.iter().map(|line| Line::from(line.clone())).collect();
+    f.render_widget(
+        Paragraph::new(preview).block(Block::default().borders(Borders::ALL).title(" Preview ")),
+        panes[1],
+    );
+
+    f.render_widget(
+        Paragraph::new(screen.status.clone()).style(Style::default().fg(Color::Yellow)),
+        rows[1],
+    );
+    f.render_widget(
+        Paragraph::new("↑/↓ Select  Enter Preview  S Save to library  Q Quit").style(Style::default().fg(Color::White)),
+        rows[2],
+    );
+}
+
+// What a recording is called: its tags if it has them, otherwise what AcoustID recognizes it as,
+// and failing that its file name
+fn query_for(audio: &std::path::Path) -> io::Result<String> {
+    let tags = wav::info_tags(&fs::read(audio)?);
+    if let Some(title) = tags.title {
+        return Ok(format!("{} {}", tags.artist.unwrap_or_default(), title).trim().to_string());
+    }
+    eprintln!("Identifying {}...", audio.display());
+    let file_name = || audio.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+    match acoustid::identify(audio) {
+        Ok(Some(identity)) => {
+            eprintln!("Identified as {} - {}", identity.artist, identity.title);
+            Ok(format!("{} {}", identity.artist, identity.title).trim().to_string())
+        }
+        Ok(None) => {
+            eprintln!("AcoustID doesn't know this recording; searching by its file name");
+            Ok(file_name())
+        }
+        Err(e) => {
+            eprintln!("Couldn't identify it ({}); searching by its file name", e);
+            Ok(file_name())
+        }
+    }
+}
+
+pub fn run(options: &SearchOptions) -> Result<(), io::Error> {
+    let mut options = options.clone();
+    if let Some(audio) = &options.audio {
+        options.query = query_for(audio)?;
+    }
+    let options = &options;
+    let providers = providers::by_priority(&options.providers);
+    let mut results = Vec::new();
+    let mut failures = Vec::new();
+
+    for provider in &providers {
+        eprintln!("Searching {}...", provider.name());
+        match provider.search(&options.query) {
+            Ok(found) => results.extend(found),
+            Err(e) => failures.push(format!("{}: {}", provider.name(), e)),
+        }
+    }
+
+    if results.is_empty() {
+        for failure in &failures {
+            eprintln!("{}", failure);
+        }
+        println!("No synced lyrics found for \"{}\"", options.query);
+        return Ok(());
+    }
+
+    let status = match failures.is_empty() {
+        true => format!("{} results", results.len()),
+        false => format!("{} results ({})", results.len(), failures.join("; ")),
+    };
+    let mut screen = SearchScreen {
+        providers,
+        results,
+        selected: 0,
+        preview: Vec::new(),
+        status,
+    };
+
+    let events = EventBus::new();
+    let mut terminal = crate::setup_terminal()?;
+    events.spawn_terminal_sources(crate::TICK_RATE);
+
+    let result = (|| -> Result<(), io::Error> {
+        loop {
+            terminal.draw(|f| {
+                ui(f, &screen, &options.query);
+                crate::compat::adapt(f.buffer_mut());
+            })?;
+            let Ok(event) = events.receiver().recv() else {
+                return Ok(());
+            };
+            if let AppEvent::Terminate = event {
+                return Ok(());
+            }
+            if let AppEvent::Input(key) = event {
+                match key.code {
+                    KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                    KeyCode::Up => screen.select(-1),
+                    KeyCode::Down => screen.select(1),
+                    KeyCode::Enter => screen.show_preview(),
+                    KeyCode::Char('s') => screen.save(options),
+                    _ => {}
+                }
+            }
+        }
+    })();
+
+    crate::restore_terminal()?;
+    result
+}
@@ -0,0 +1,295 @@
+use serde::Serialize;
+use std::{
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream, UdpSocket},
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{cast, plugin::Renderer, remote::RemoteControl, timeline, KaraokeApp, LyricLine};
+
+const PAGE: &str = include_str!("server_page.html");
+// Just the current and next lines, big, for a TV across the room
+const CAST_PAGE: &str = include_str!("cast_page.html");
+// A client that sends more than this, or takes longer than this to send it, is cut off
+const MAX_REQUEST_HEAD: u64 = 16 * 1024;
+const MAX_HEADERS: usize = 64;
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EventLine {
+    pub text: String,
+    pub start_time: f64,
+    pub end_time: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LyricEvent {
+    Song {
+        title: String,
+        duration: f64,
+        lines: Vec<EventLine>,
+    },
+    LineChanged {
+        index: Option<usize>,
+        text: String,
+    },
+    Progress {
+        time: f64,
+        line_progress: f64,
+    },
+    Playback {
+        paused: bool,
+    },
+}
+
+impl LyricEvent {
+    pub fn song(title: &str, duration: f64, lyrics: &[LyricLine]) -> Self {
+        LyricEvent::Song {
+            title: title.to_string(),
+            duration,
+            lines: lyrics
+                .iter()
+                .map(|line| EventLine {
+                    text: line.text.clone(),
+                    start_time: line.start_time,
+                    end_time: line.end_time,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct State {
+    clients: Vec<TcpStream>,
+    // Replayed to every new client so it can render immediately
+    song: Option<String>,
+    line: Option<String>,
+    playback: Option<String>,
+}
+
+#[derive(Clone)]
+pub struct Broadcaster {
+    state: Arc<Mutex<State>>,
+}
+
+impl Broadcaster {
+    pub fn broadcast(&self, event: &LyricEvent) {
+        let Ok(json) = serde_json::to_string(event) else {
+            return;
+        };
+        let frame = format!("data: {}\n\n", json);
+
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        match event {
+            LyricEvent::Song { .. } => state.song = Some(frame.clone()),
+            LyricEvent::LineChanged { .. } => state.line = Some(frame.clone()),
+            LyricEvent::Playback { .. } => state.playback = Some(frame.clone()),
+            LyricEvent::Progress { .. } => {}
+        }
+        // Clients that can't be written to have disconnected
+        state.clients.retain_mut(|client| client.write_all(frame.as_bytes()).is_ok());
+    }
+}
+
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+// Turns the app's state into events, only sending what changed since the last frame
+pub struct EventTracker {
+    broadcaster: Broadcaster,
+    song_sent: bool,
+    line: Option<Option<usize>>,
+    paused: Option<bool>,
+    last_progress: Option<Instant>,
+}
+
+impl EventTracker {
+    pub fn new(broadcaster: Broadcaster) -> Self {
+        Self {
+            broadcaster,
+            song_sent: false,
+            line: None,
+            paused: None,
+            last_progress: None,
+        }
+    }
+}
+
+impl Renderer for EventTracker {
+    fn render(&mut self, app: &KaraokeApp) -> io::Result<()> {
+        let broadcaster = &self.broadcaster;
+        if !self.song_sent {
+            broadcaster.broadcast(&LyricEvent::song(&app.song_title, app.song_duration, &app.lyrics));
+            self.song_sent = true;
+        }
+
+        let current_time = app.lyric_time();
+        let index = app.active_line(current_time);
+
+        if self.line != Some(index) {
+            let text = index.map(|i| app.lyrics[i].text.clone()).unwrap_or_default();
+            broadcaster.broadcast(&LyricEvent::LineChanged { index, text });
+            self.line = Some(index);
+        }
+
+        let paused_changed = self.paused != Some(app.paused);
+        if paused_changed {
+            broadcaster.broadcast(&LyricEvent::Playback { paused: app.paused });
+            self.paused = Some(app.paused);
+        }
+
+        let progress_due = self.last_progress.is_none_or(|at| at.elapsed() >= PROGRESS_INTERVAL);
+        if paused_changed || progress_due {
+            let line_progress = index.map_or(0.0, |i| timeline::line_progress(&app.lyrics[i], current_time));
+            broadcaster.broadcast(&LyricEvent::Progress {
+                time: current_time,
+                line_progress,
+            });
+            self.last_progress = Some(Instant::now());
+        }
+        Ok(())
+    }
+
+    // Clients get the new song's full lyrics and state
+    fn song_changed(&mut self) {
+        *self = Self::new(self.broadcaster.clone());
+    }
+}
+
+// The address to give phones. A server listening on every interface is reached through this
+// machine's network address, the one the OS would send from; connecting a UDP socket sends
+// nothing.
+pub fn page_url(bind: &str) -> String {
+    let Ok(addr) = bind.parse::<SocketAddr>() else {
+        return format!("http://{}", bind);
+    };
+    if !addr.ip().is_unspecified() {
+        return format!("http://{}", addr);
+    }
+    let local = UdpSocket::bind("0.0.0.0:0").and_then(|socket| {
+        socket.connect("192.0.2.1:80")?;
+        socket.local_addr()
+    });
+    match local {
+        Ok(local) if !local.ip().is_unspecified() => format!("http://{}:{}", local.ip(), addr.port()),
+        _ => format!("http://{}", addr),
+    }
+}
+
+// Remote control endpoints under /api are only served when `remote` is given
+pub fn start(bind: &str, remote: Option<RemoteControl>) -> io::Result<Broadcaster> {
+    let listener = TcpListener::bind(bind)?;
+    let broadcaster = Broadcaster {
+        state: Arc::new(Mutex::new(State::default())),
+    };
+
+    let accept_state = broadcaster.state.clone();
+    let remote = Arc::new(remote);
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let state = accept_state.clone();
+            let remote = remote.clone();
+            thread::spawn(move || {
+                if let Err(e) = handle_connection(stream, &state, remote.as_ref().as_ref()) {
+                    log::debug!("web client dropped: {}", e);
+                }
+            });
+        }
+    });
+
+    Ok(broadcaster)
+}
+
+// The request line and headers, names as sent and values trimmed
+pub fn read_head(stream: &TcpStream) -> io::Result<(String, Vec<(String, String)>)> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?.take(MAX_REQUEST_HEAD));
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+
+    let mut headers = Vec::new();
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        if headers.len() == MAX_HEADERS {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "too many headers"));
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            headers.push((name.trim().to_string(), value.trim().to_string()));
+        }
+        header.clear();
+    }
+    Ok((request_line, headers))
+}
+
+fn handle_connection(mut stream: TcpStream, state: &Mutex<State>, remote: Option<&RemoteControl>) -> io::Result<()> {
+    let (request_line, headers) = read_head(&stream)?;
+    let bearer = headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("authorization"))
+        .and_then(|(_, value)| value.strip_prefix("Bearer "));
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or("GET");
+    let target = parts.next().unwrap_or("/");
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    match path {
+        _ if path.starts_with("/api/") => {
+            let (status, body) = match remote {
+                Some(remote) => remote.handle(method, path, query, bearer),
+                None => (404, "{\"ok\":false,\"error\":\"remote control is disabled\"}".to_string()),
+            };
+            let reason = match status {
+                200 => "OK",
+                400 => "Bad Request",
+                401 => "Unauthorized",
+                404 => "Not Found",
+                405 => "Method Not Allowed",
+                _ => "Service Unavailable",
+            };
+            write!(
+                stream,
+                "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                reason,
+                body.len(),
+                body
+            )?;
+        }
+        "/" | "/index.html" => {
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                PAGE.len(),
+                PAGE
+            )?;
+        }
+        cast::PAGE_PATH => {
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                CAST_PAGE.len(),
+                CAST_PAGE
+            )?;
+        }
+        "/events" => {
+            // A stalled browser must not be able to freeze the render loop
+            stream.set_write_timeout(Some(Duration::from_millis(200)))?;
+            stream.write_all(
+                b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nAccess-Control-Allow-Origin: *\r\n\r\n",
+            )?;
+            let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+            for frame in [&state.song, &state.playback, &state.line].into_iter().flatten() {
+                stream.write_all(frame.as_bytes())?;
+            }
+            state.clients.push(stream);
+        }
+        _ => {
+            stream.write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")?;
+        }
+    }
+
+    Ok(())
+}
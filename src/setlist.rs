@@ -0,0 +1,502 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    editor::{text_edit_spans, TextEdit},
+    events::{AppEvent, EventBus},
+    formats,
+    party::PartySession,
+    song_config,
+};
+
+// Several singers on one song are written the way the party screens show them
+const TOGETHER: &str = " & ";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Entry {
+    pub song: PathBuf,
+    #[serde(default)]
+    pub singer: String,
+    #[serde(default)]
+    pub notes: String,
+}
+
+impl Entry {
+    fn singers(&self) -> impl Iterator<Item = &str> {
+        self.singer.split('&').map(str::trim).filter(|name| !name.is_empty())
+    }
+}
+
+// A night's songs in the order they're sung, planned ahead by the host
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Setlist {
+    pub name: String,
+    pub entries: Vec<Entry>,
+}
+
+impl Setlist {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let json = fs::read_to_string(path)?;
+        serde_json::from_str(&json).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path.display(), e)))
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        fs::write(path, json)
+    }
+
+    // The setlist as a party: everyone named in it plays, and songs nobody was given go to
+    // whoever's turn it is
+    pub fn to_party(&self) -> io::Result<PartySession> {
+        let mut names: Vec<String> = Vec::new();
+        for name in self.entries.iter().flat_map(Entry::singers) {
+            if !names.iter().any(|known| known == name) {
+                names.push(name.to_string());
+            }
+        }
+        if self.entries.is_empty() || names.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "the setlist needs at least one song with a singer"));
+        }
+        let lineup = self
+            .entries
+            .iter()
+            .map(|entry| entry.singers().filter_map(|name| names.iter().position(|known| known == name)).collect())
+            .collect();
+        let songs = self.entries.iter().map(|entry| entry.song.clone()).collect();
+        let mut session = PartySession::new(names, songs, false);
+        session.lineup = lineup;
+        Ok(session)
+    }
+}
+
+// What the run sheet says about each song, read from the song itself where it can be
+struct Row<'a> {
+    entry: &'a Entry,
+    name: String,
+    length: Option<f64>,
+}
+
+fn rows(setlist: &Setlist) -> Vec<Row<'_>> {
+    setlist
+        .entries
+        .iter()
+        .map(|entry| {
+            let config = formats::load(&entry.song).ok();
+            let stem = entry.song.file_stem().map_or_else(|| entry.song.display().to_string(), |stem| stem.to_string_lossy().into_owned());
+            let name = match &config {
+                Some(config) => crate::library::song_stem(config.artist.as_deref(), &config.title),
+                None => stem,
+            };
+            let length = config.map(|config| config.given_duration().unwrap_or_else(|| config.lyrics_end() + song_config::DEFAULT_OUTRO));
+            Row { entry, name, length }
+        })
+        .collect()
+}
+
+fn clock(seconds: f64) -> String {
+    let seconds = seconds.max(0.0).round() as u64;
+    match seconds >= 3600 {
+        true => format!("{}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60),
+        false => format!("{}:{:02}", seconds / 60, seconds % 60),
+    }
+}
+
+// Each song's length and when it starts into the night, or "?" for songs that couldn't be read
+fn timings(rows: &[Row]) -> (Vec<(String, String)>, f64) {
+    let mut elapsed = 0.0;
+    let times = rows
+        .iter()
+        .map(|row| {
+            let starts = clock(elapsed);
+            elapsed += row.length.unwrap_or(0.0);
+            (starts, row.length.map_or_else(|| "?".to_string(), clock))
+        })
+        .collect();
+    (times, elapsed)
+}
+
+fn markdown_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+fn markdown(setlist: &Setlist) -> String {
+    let rows = rows(setlist);
+    let (times, total) = timings(&rows);
+    let mut out = format!("# {}\n\n", setlist.name);
+    out.push_str("| # | Starts | Song | Singer | Length | Notes |\n");
+    out.push_str("|---|--------|------|--------|--------|-------|\n");
+    for (i, (row, (starts, length))) in rows.iter().zip(times).enumerate() {
+        out.push_str(&format!(
+            "| {} | {} | {} | {} | {} | {} |\n",
+            i + 1,
+            starts,
+            markdown_cell(&row.name),
+            markdown_cell(&row.entry.singer),
+            length,
+            markdown_cell(&row.entry.notes)
+        ));
+    }
+    out.push_str(&format!("\n{} songs, {} in all\n", rows.len(), clock(total)));
+    out
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+// A page that prints as the run sheet on its own, without the browser's colours
+fn html(setlist: &Setlist) -> String {
+    let rows = rows(setlist);
+    let (times, total) = timings(&rows);
+    let name = escape_html(&setlist.name);
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n\
+         body {{ font-family: sans-serif; margin: 2em; }}\n\
+         table {{ border-collapse: collapse; width: 100%; }}\n\
+         th, td {{ border-bottom: 1px solid #999; padding: 0.4em 0.6em; text-align: left; vertical-align: top; }}\n\
+         td.time {{ white-space: nowrap; font-variant-numeric: tabular-nums; }}\n\
+         </style>\n</head>\n<body>\n<h1>{}</h1>\n<table>\n\
+         <tr><th>#</th><th>Starts</th><th>Song</th><th>Singer</th><th>Length</th><th>Notes</th></tr>\n",
+        name, name
+    );
+    for (i, (row, (starts, length))) in rows.iter().zip(times).enumerate() {
+        out.push_str(&format!(
+            "<tr><td>{}</td><td class=\"time\">{}</td><td>{}</td><td>{}</td><td class=\"time\">{}</td><td>{}</td></tr>\n",
+            i + 1,
+            starts,
+            escape_html(&row.name),
+            escape_html(&row.entry.singer),
+            length,
+            escape_html(&row.entry.notes).replace('\n', "<br>")
+        ));
+    }
+    out.push_str(&format!("</table>\n<p>{} songs, {} in all</p>\n</body>\n</html>\n", rows.len(), clock(total)));
+    out
+}
+
+// The run sheet is Markdown or HTML, going by the output's extension
+pub fn export(setlist: &Path, output: &Path) -> io::Result<()> {
+    let setlist = Setlist::load(setlist)?;
+    let extension = output.extension().map(|ext| ext.to_string_lossy().to_ascii_lowercase()).unwrap_or_default();
+    let sheet = match extension.as_str() {
+        "md" | "markdown" => markdown(&setlist),
+        "html" | "htm" => html(&setlist),
+        _ => return Err(io::Error::new(io::ErrorKind::InvalidInput, "run sheets are written as .md or .html")),
+    };
+    fs::write(output, sheet)?;
+    println!("Wrote the run sheet for {} to {}", setlist.name, output.display());
+    Ok(())
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Field {
+    Name,
+    Song,
+    Singer,
+    Notes,
+}
+
+impl Field {
+    fn label(self) -> &'static str {
+        match self {
+            Field::Name => "Setlist name",
+            Field::Song => "Add song",
+            Field::Singer => "Singer",
+            Field::Notes => "Notes",
+        }
+    }
+}
+
+struct SetlistEditor {
+    setlist: Setlist,
+    selected: usize,
+    status: String,
+    dirty: bool,
+    quit_warned: bool,
+    typing: Option<(Field, TextEdit)>,
+}
+
+impl SetlistEditor {
+    fn select(&mut self, delta: i64) {
+        let last = self.setlist.entries.len() as i64 - 1;
+        self.selected = (self.selected as i64 + delta).clamp(0, last.max(0)) as usize;
+    }
+
+    fn changed(&mut self, status: impl Into<String>) {
+        self.dirty = true;
+        self.status = status.into();
+    }
+
+    fn start_typing(&mut self, field: Field) {
+        let entry = self.setlist.entries.get(self.selected);
+        let text = match field {
+            Field::Name => self.setlist.name.as_str(),
+            Field::Song => "",
+            Field::Singer | Field::Notes if entry.is_none() => return,
+            Field::Singer => entry.map_or("", |entry| entry.singer.as_str()),
+            Field::Notes => entry.map_or("", |entry| entry.notes.as_str()),
+        };
+        self.typing = Some((field, TextEdit::new(text)));
+    }
+
+    fn finish_typing(&mut self) {
+        let Some((field, edit)) = self.typing.take() else {
+            return;
+        };
+        let text = edit.text.trim().to_string();
+        match field {
+            Field::Name => {
+                self.setlist.name = text;
+                self.changed("Renamed the setlist");
+            }
+            Field::Song if text.is_empty() => {}
+            Field::Song => {
+                let song = PathBuf::from(&text);
+                if !song.is_file() {
+                    self.status = format!("No song at {}", song.display());
+                    return;
+                }
+                // New songs go after the selected one, so a list can be built up in order
+                let at = (self.selected + 1).min(self.setlist.entries.len());
+                self.setlist.entries.insert(at, Entry { song, ..Entry::default() });
+                self.selected = at;
+                self.changed(format!("Added {}", text));
+            }
+            Field::Singer => {
+                self.setlist.entries[self.selected].singer = text.split('&').map(str::trim).filter(|name| !name.is_empty()).collect::<Vec<_>>().join(TOGETHER);
+                self.changed("Set the singer");
+            }
+            Field::Notes => {
+                self.setlist.entries[self.selected].notes = text;
+                self.changed("Set the notes");
+            }
+        }
+    }
+
+    fn remove(&mut self) {
+        if self.selected >= self.setlist.entries.len() {
+            return;
+        }
+        let entry = self.setlist.entries.remove(self.selected);
+        self.select(0);
+        self.changed(format!("Removed {}", entry.song.display()));
+    }
+
+    // Moves the selected song up or down the running order
+    fn shift(&mut self, delta: i64) {
+        let to = self.selected as i64 + delta;
+        if to < 0 || to as usize >= self.setlist.entries.len() {
+            return;
+        }
+        self.setlist.entries.swap(self.selected, to as usize);
+        self.selected = to as usize;
+        self.changed("Moved the song");
+    }
+
+    fn save(&mut self, path: &Path) {
+        self.status = match self.setlist.save(path) {
+            Ok(()) => {
+                self.dirty = false;
+                format!("Saved {}", path.display())
+            }
+            Err(e) => format!("Save failed: {}", e),
+        };
+    }
+
+    // Returns true when the editor should close
+    fn handle_key(&mut self, key: KeyEvent, path: &Path) -> bool {
+        if let Some((_, edit)) = &mut self.typing {
+            match key.code {
+                KeyCode::Enter => self.finish_typing(),
+                KeyCode::Esc => self.typing = None,
+                _ => {
+                    edit.type_key(key);
+                }
+            }
+            return false;
+        }
+
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let shift = key.modifiers.contains(KeyModifiers::SHIFT);
+        match key.code {
+            KeyCode::Char('s') if ctrl => self.save(path),
+            KeyCode::Char('q') | KeyCode::Esc if self.dirty && !self.quit_warned => {
+                self.status = "Unsaved changes - press Q again to quit without saving".to_string();
+                self.quit_warned = true;
+                return false;
+            }
+            KeyCode::Char('q') | KeyCode::Esc => return true,
+            KeyCode::Up if shift => self.shift(-1),
+            KeyCode::Down if shift => self.shift(1),
+            KeyCode::Up => self.select(-1),
+            KeyCode::Down => self.select(1),
+            KeyCode::Char('a') | KeyCode::Char('A') => self.start_typing(Field::Song),
+            KeyCode::Char('d') | KeyCode::Char('D') | KeyCode::Delete => self.remove(),
+            KeyCode::Char('s') | KeyCode::Char('S') | KeyCode::Enter => self.start_typing(Field::Singer),
+            KeyCode::Char('n') | KeyCode::Char('N') => self.start_typing(Field::Notes),
+            KeyCode::Char('r') | KeyCode::Char('R') => self.start_typing(Field::Name),
+            _ => {}
+        }
+        self.quit_warned = false;
+        false
+    }
+}
+
+fn ui(f: &mut Frame, editor: &SetlistEditor, path: &Path) {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(5), Constraint::Length(1), Constraint::Length(1)])
+        .split(f.size());
+
+    let entries = &editor.setlist.entries;
+    let song_width = entries.iter().map(|entry| entry.song.display().to_string().chars().count()).max().unwrap_or(0).min(40);
+    let mut lines: Vec<Line> = Vec::new();
+    for (i, entry) in entries.iter().enumerate() {
+        let selected = i == editor.selected;
+        let style = match selected {
+            true => Style::default().fg(Color::Black).bg(Color::Rgb(0, 255, 0)).add_modifier(Modifier::BOLD),
+            false => Style::default().fg(Color::White),
+        };
+        let typing = editor.typing.as_ref().filter(|_| selected);
+        let mut spans = vec![
+            Span::styled(format!("{:>3}. ", i + 1), Style::default().fg(Color::Yellow)),
+            Span::styled(format!("{:<width$}", entry.song.display().to_string(), width = song_width), style),
+            Span::raw("  "),
+        ];
+        match typing {
+            Some((Field::Singer, edit)) => spans.extend(text_edit_spans(edit)),
+            _ if entry.singer.is_empty() => spans.push(Span::styled("(next turn)", Style::default().fg(Color::Rgb(80, 80, 80)))),
+            _ => spans.push(Span::styled(entry.singer.clone(), Style::default().fg(Color::Magenta))),
+        }
+        spans.push(Span::raw("  "));
+        match typing {
+            Some((Field::Notes, edit)) => spans.extend(text_edit_spans(edit)),
+            _ => spans.push(Span::styled(entry.notes.clone(), Style::default().fg(Color::Rgb(0, 200, 255)))),
+        }
+        lines.push(Line::from(spans));
+    }
+    if entries.is_empty() {
+        lines.push(Line::styled("No songs yet - press A to add one", Style::default().fg(Color::Rgb(80, 80, 80))));
+    }
+    let scroll = editor.selected.saturating_sub(rows[0].height.saturating_sub(3) as usize / 2) as u16;
+    let name = match editor.setlist.name.is_empty() {
+        true => path.display().to_string(),
+        false => editor.setlist.name.clone(),
+    };
+    let title = format!(" Setlist: {}{} ", name, if editor.dirty { " *" } else { "" });
+    f.render_widget(
+        Paragraph::new(lines).scroll((scroll, 0)).block(Block::default().borders(Borders::ALL).title(title)),
+        rows[0],
+    );
+
+    let status = match &editor.typing {
+        Some((field @ (Field::Name | Field::Song), edit)) => {
+            let mut spans = vec![Span::styled(format!("{}: ", field.label()), Style::default().fg(Color::Yellow))];
+            spans.extend(text_edit_spans(edit));
+            Line::from(spans)
+        }
+        _ => Line::styled(editor.status.clone(), Style::default().fg(Color::Yellow)),
+    };
+    f.render_widget(Paragraph::new(status), rows[1]);
+    let help = match &editor.typing {
+        Some((Field::Singer, _)) => "Type a name, or names joined with &  Enter Done  Esc Cancel",
+        Some(_) => "Type to edit  Enter Done  Esc Cancel",
+        None => "↑/↓ Select  Shift+↑/↓ Move  A Add song  D Remove  S Singer  N Notes  R Rename  Ctrl+S Save  Q Quit",
+    };
+    f.render_widget(Paragraph::new(help).style(Style::default().fg(Color::White)), rows[2]);
+}
+
+// Opens the setlist, or starts a new one, with `songs` added to the end
+pub fn edit(path: &Path, songs: &[PathBuf]) -> io::Result<()> {
+    let mut setlist = match path.exists() {
+        true => Setlist::load(path)?,
+        false => Setlist {
+            name: path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default(),
+            entries: Vec::new(),
+        },
+    };
+    setlist.entries.extend(songs.iter().map(|song| Entry { song: song.clone(), ..Entry::default() }));
+    let mut editor = SetlistEditor {
+        status: format!("{} songs", setlist.entries.len()),
+        dirty: !songs.is_empty() || !path.exists(),
+        setlist,
+        selected: 0,
+        quit_warned: false,
+        typing: None,
+    };
+
+    let events = EventBus::new();
+    let mut terminal = crate::setup_terminal()?;
+    events.spawn_terminal_sources(crate::TICK_RATE);
+
+    let result = (|| -> Result<(), io::Error> {
+        loop {
+            terminal.draw(|f| {
+                ui(f, &editor, path);
+                crate::compat::adapt(f.buffer_mut());
+            })?;
+            let Ok(event) = events.receiver().recv() else {
+                return Ok(());
+            };
+            if let AppEvent::Terminate = event {
+                return Ok(());
+            }
+            if let AppEvent::Input(key) = event {
+                if editor.handle_key(key, path) {
+                    return Ok(());
+                }
+            }
+        }
+    })();
+
+    crate::restore_terminal()?;
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(song: &str, singer: &str, notes: &str) -> Entry {
+        Entry {
+            song: PathBuf::from(song),
+            singer: singer.to_string(),
+            notes: notes.to_string(),
+        }
+    }
+
+    #[test]
+    fn setlists_run_as_parties_and_print_as_run_sheets() {
+        let setlist = Setlist {
+            name: "Friday".to_string(),
+            entries: vec![
+                entry("missing/opener.lrc", "Ann", "Warm up | slow"),
+                entry("missing/duet.lrc", "Bob & Ann", ""),
+                entry("missing/anyone.lrc", "", ""),
+            ],
+        };
+        let party = setlist.to_party().unwrap();
+        let names: Vec<&str> = party.players.iter().map(|player| player.name.as_str()).collect();
+        assert_eq!(names, ["Ann", "Bob"]);
+        assert_eq!(party.lineup, [vec![0], vec![1, 0], vec![]]);
+        assert!(Setlist { entries: vec![entry("a.lrc", "", "")], ..setlist.clone() }.to_party().is_err());
+
+        let sheet = markdown(&setlist);
+        assert!(sheet.starts_with("# Friday\n"));
+        assert!(sheet.contains("| 1 | 0:00 | opener | Ann | ? | Warm up \\| slow |\n"));
+        assert!(sheet.contains("| 2 | 0:00 | duet | Bob & Ann | ? |  |\n"));
+        assert!(html(&setlist).contains("<td>Bob &amp; Ann</td>"));
+        assert_eq!(clock(3725.0), "1:02:05");
+    }
+}
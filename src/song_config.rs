@@ -1,10 +1,72 @@
-use crate::LyricLine;
+use serde::{Deserialize, Serialize};
+use std::{fs, io, path::Path};
 
+use crate::{
+    layout::LyricWindow,
+    style::{LineStyle, SongStyle},
+    timeline, LyricLine,
+};
+
+// A named part of the song (Verse 1, Chorus, ...) that starts at `start_time` and runs until
+// the next one
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Section {
+    pub name: String,
+    pub start_time: f64,
+}
+
+// The beat for the metronome; `offset` is when the first beat falls
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Tempo {
+    pub bpm: f64,
+    #[serde(default = "default_time_signature")]
+    pub time_signature: String,
+    #[serde(default)]
+    pub offset: f64,
+}
+
+fn default_time_signature() -> String {
+    "4/4".to_string()
+}
+
+impl Tempo {
+    // The top number of the time signature, e.g. 3 for 3/4
+    pub fn beats_per_bar(&self) -> u32 {
+        self.time_signature
+            .split_once('/')
+            .and_then(|(beats, _)| beats.trim().parse().ok())
+            .filter(|&beats| beats > 0)
+            .unwrap_or(4)
+    }
+}
+
+// Seconds a song runs on after its last line when there's no recording or length to go by
+pub const DEFAULT_OUTRO: f64 = 3.0;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SongConfig {
     pub title: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub artist: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub album: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    // 0 when the song file doesn't say, to be worked out from its recording or lyrics
+    #[serde(default)]
     pub duration: f64,
+    #[serde(default)]
     pub start_position: f64,
     pub lyrics: Vec<LyricLine>,
+    #[serde(default)]
+    pub sections: Vec<Section>,
+    #[serde(default, skip_serializing_if = "SongStyle::is_plain")]
+    pub style: SongStyle,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tempo: Option<Tempo>,
+    // How many lyric lines are shown and where the current one sits
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub view: Option<LyricWindow>,
 }
 
 impl SongConfig {
@@ -16,51 +78,118 @@ impl SongConfig {
 
         // Lyrics with timing - modify or add lines as needed
         // Format: text, start_time (seconds), end_time (seconds)
+        // For per-word highlighting fill `segments` with Segment { text, start_time, end_time }
+        // entries whose texts add up to the line's text
         let lyrics = vec![
             LyricLine {
                 text: "Example line 1".to_string(),
                 start_time: 0.0, // Start time
                 end_time: 3.0, // End time 
+                segments: Vec::new(),
+                singer: 0,
+                style: LineStyle::default(),
+                chords: Vec::new(),
             },
             LyricLine {
                 text: "Example line 2".to_string(),
                 start_time: 3.0,
                 end_time: 6.0,
+                segments: Vec::new(),
+                singer: 0,
+                style: LineStyle::default(),
+                chords: Vec::new(),
             },
             LyricLine {
                 text: "Example line 3".to_string(),
                 start_time: 6.0,
                 end_time: 9.0,
+                segments: Vec::new(),
+                singer: 0,
+                style: LineStyle::default(),
+                chords: Vec::new(),
             },
             LyricLine {
                 text: "Example line 4".to_string(),
                 start_time: 9.0,
                 end_time: 12.0,
+                segments: Vec::new(),
+                singer: 0,
+                style: LineStyle::default(),
+                chords: Vec::new(),
             },
             LyricLine {
                 text: "Example line 5".to_string(),
                 start_time: 12.0,
                 end_time: 15.0,
+                segments: Vec::new(),
+                singer: 0,
+                style: LineStyle::default(),
+                chords: Vec::new(),
             },
             LyricLine {
                 text: "Example line 6".to_string(),
                 start_time: 15.0,
                 end_time: 18.0,
+                segments: Vec::new(),
+                singer: 0,
+                style: LineStyle::default(),
+                chords: Vec::new(),
             },
             LyricLine {
                 text: "Example line 7".to_string(),
                 start_time: 18.0,
                 end_time: 21.0,
+                segments: Vec::new(),
+                singer: 0,
+                style: LineStyle::default(),
+                chords: Vec::new(),
             },
 
         ];
 
+        // Optional song sections shown in the header; n/p jump between them
+        let sections = vec![
+            Section {
+                name: "Verse".to_string(),
+                start_time: 0.0,
+            },
+            Section {
+                name: "Chorus".to_string(),
+                start_time: 12.0,
+            },
+        ];
+
         Self {
             title,
+            artist: None,
+            album: None,
+            key: None,
             duration,
             start_position,
             lyrics,
+            sections,
+            style: SongStyle::default(),
+            tempo: None,
+            view: None,
         }
     }
-}
 
+    pub fn given_duration(&self) -> Option<f64> {
+        (self.duration > 0.0).then_some(self.duration)
+    }
+
+    pub fn lyrics_end(&self) -> f64 {
+        self.lyrics.iter().map(|line| line.end_time).fold(0.0, f64::max)
+    }
+
+    // Song files use the same fields as the built-in config, e.g.
+    // { "title": "...", "duration": 21.0, "lyrics": [{ "text": "...", "start_time": 0.0, "end_time": 3.0 }] }
+    pub fn from_file(path: &Path) -> io::Result<Self> {
+        let json = fs::read_to_string(path)?;
+        let mut config: Self = serde_json::from_str(&json).map_err(|e| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path.display(), e))
+        })?;
+        timeline::sort_lines(&mut config.lyrics);
+        Ok(config)
+    }
+}
@@ -0,0 +1,180 @@
+use serde::Serialize;
+use std::{
+    fs::OpenOptions,
+    io::{self, Write},
+    path::PathBuf,
+    sync::mpsc::{self, SyncSender, TrySendError},
+    thread,
+};
+
+use crate::{plugin::Renderer, KaraokeApp};
+
+// Lines waiting for a reader that's fallen behind are dropped past this
+const QUEUED_EVENTS: usize = 64;
+
+// One JSON object per line, for status bar widgets and streaming overlays
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum StatusEvent {
+    SongLoaded {
+        title: String,
+        artist: Option<String>,
+        path: Option<PathBuf>,
+        duration: f64,
+    },
+    // Between lines `index` and `text` are null, so a widget can clear itself
+    LineChanged {
+        index: Option<usize>,
+        text: Option<String>,
+        time: f64,
+    },
+    Paused { time: f64 },
+    Playing { time: f64 },
+    Ended { title: String },
+}
+
+// A number is a file descriptor the caller opened, e.g. `--status-fd 3 3>status.jsonl`
+fn target_path(target: &str) -> PathBuf {
+    match target.parse::<u32>() {
+        Ok(fd) => PathBuf::from(format!("/dev/fd/{}", fd)),
+        Err(_) => PathBuf::from(target),
+    }
+}
+
+// Status lines written to `--status-fd` as they happen
+pub struct StatusStream {
+    out: Option<SyncSender<String>>,
+    song_sent: bool,
+    line: Option<Option<usize>>,
+    paused: Option<bool>,
+    ended: bool,
+}
+
+impl StatusStream {
+    pub fn open(target: &str) -> io::Result<Self> {
+        let path = target_path(target);
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| io::Error::new(e.kind(), format!("couldn't open {} for status lines: {}", path.display(), e)))?;
+        let (out, queue) = mpsc::sync_channel::<String>(QUEUED_EVENTS);
+        thread::spawn(move || {
+            for json in queue {
+                if let Err(e) = writeln!(file, "{}", json).and_then(|_| file.flush()) {
+                    log::warn!("stopped writing status lines: {}", e);
+                    break;
+                }
+            }
+        });
+        Ok(Self {
+            out: Some(out),
+            song_sent: false,
+            line: None,
+            paused: None,
+            ended: false,
+        })
+    }
+
+    // Nobody reading anymore shouldn't stop the song, it just stops the lines, and a reader
+    // that falls behind misses some
+    fn send(&mut self, event: &StatusEvent) {
+        let Some(out) = &self.out else {
+            return;
+        };
+        let Ok(json) = serde_json::to_string(event) else {
+            return;
+        };
+        match out.try_send(json) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => log::warn!("status lines are behind; dropped one"),
+            Err(TrySendError::Disconnected(_)) => self.out = None,
+        }
+    }
+
+    fn changes(&mut self, app: &KaraokeApp) -> Vec<StatusEvent> {
+        let mut events = Vec::new();
+        if !self.song_sent {
+            events.push(StatusEvent::SongLoaded {
+                title: app.song_title.clone(),
+                artist: app.song_artist.clone(),
+                path: app.song_path.clone(),
+                duration: app.song_duration,
+            });
+            self.song_sent = true;
+        }
+
+        let time = app.lyric_time();
+        let index = app.active_line(time);
+        if self.line != Some(index) {
+            events.push(StatusEvent::LineChanged {
+                index,
+                text: index.map(|i| app.lyrics[i].text.clone()),
+                time,
+            });
+            self.line = Some(index);
+        }
+
+        // The song stopping at its end is reported as the end, not as a pause
+        let ended = app.is_song_ended();
+        let paused = app.is_paused();
+        if !ended && self.paused != Some(paused) {
+            events.push(if paused { StatusEvent::Paused { time } } else { StatusEvent::Playing { time } });
+        }
+        self.paused = Some(paused);
+        if ended && !self.ended {
+            events.push(StatusEvent::Ended {
+                title: app.song_title.clone(),
+            });
+        }
+        self.ended = ended;
+        events
+    }
+}
+
+impl Renderer for StatusStream {
+    fn render(&mut self, app: &KaraokeApp) -> io::Result<()> {
+        for event in self.changes(app) {
+            self.send(&event);
+        }
+        Ok(())
+    }
+
+    fn song_changed(&mut self) {
+        self.song_sent = false;
+        self.line = None;
+        self.paused = None;
+        self.ended = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn each_change_is_reported_once() {
+        let mut app = KaraokeApp::from_config(crate::song_config::SongConfig::load(), None);
+        app.paused = true;
+        app.current_position = app.lyrics[1].start_time + 0.1;
+        let mut stream = StatusStream {
+            out: None,
+            song_sent: false,
+            line: None,
+            paused: None,
+            ended: false,
+        };
+
+        let events = stream.changes(&app);
+        assert!(matches!(
+            events[..],
+            [StatusEvent::SongLoaded { .. }, StatusEvent::LineChanged { index: Some(1), .. }, StatusEvent::Paused { .. }]
+        ));
+        assert!(stream.changes(&app).is_empty());
+
+        app.current_position = app.song_duration;
+        assert!(matches!(stream.changes(&app)[..], [StatusEvent::LineChanged { index: None, .. }, StatusEvent::Ended { .. }]));
+        assert_eq!(target_path("3"), PathBuf::from("/dev/fd/3"));
+        assert_eq!(target_path("status.jsonl"), PathBuf::from("status.jsonl"));
+    }
+}
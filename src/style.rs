@@ -0,0 +1,205 @@
+use ratatui::style::Color;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::LyricLine;
+
+// Style hints for a line. Unset fields fall through to the section, singer and song styles,
+// so a single line can also switch a song-wide setting back off.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LineStyle {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bold: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub italic: Option<bool>,
+    // Makes a line stand out, e.g. a chorus
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub emphasis: Option<bool>,
+}
+
+impl LineStyle {
+    pub fn is_plain(&self) -> bool {
+        *self == LineStyle::default()
+    }
+
+    // `over` wins wherever it says something
+    fn merge(self, over: &LineStyle) -> LineStyle {
+        LineStyle {
+            color: over.color.clone().or(self.color),
+            bold: over.bold.or(self.bold),
+            italic: over.italic.or(self.italic),
+            emphasis: over.emphasis.or(self.emphasis),
+        }
+    }
+
+    pub fn is_bold(&self) -> bool {
+        self.bold.unwrap_or(false) || self.is_emphasized()
+    }
+
+    pub fn is_italic(&self) -> bool {
+        self.italic.unwrap_or(false)
+    }
+
+    pub fn is_emphasized(&self) -> bool {
+        self.emphasis.unwrap_or(false)
+    }
+
+    pub fn color(&self) -> Option<Color> {
+        self.color.as_deref().and_then(parse_color)
+    }
+}
+
+// Song-wide styles from the song config
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SongStyle {
+    // Applies to every line
+    pub lines: LineStyle,
+    // Highlight colors for the duet parts in order, replacing the built-in ones
+    pub singers: Vec<String>,
+    // Styles for lines in sections whose names start with the key, ignoring case, so
+    // "Chorus" also covers "Chorus 2"
+    pub sections: BTreeMap<String, LineStyle>,
+}
+
+impl SongStyle {
+    pub fn is_plain(&self) -> bool {
+        *self == SongStyle::default()
+    }
+}
+
+// From the least to the most specific: song, singer, section, then the line's own hints
+pub fn resolve(song: &SongStyle, section: Option<&str>, line: &LyricLine) -> LineStyle {
+    let mut style = song.lines.clone();
+    if let Some(color) = song.singers.get(line.singer) {
+        style.color = Some(color.clone());
+    }
+    if let Some(name) = section.map(str::to_lowercase) {
+        for (key, section_style) in &song.sections {
+            if name.starts_with(&key.to_lowercase()) {
+                style = style.merge(section_style);
+            }
+        }
+    }
+    style.merge(&line.style)
+}
+
+// Color names or #rrggbb
+pub fn parse_color(name: &str) -> Option<Color> {
+    let name = name.trim().to_ascii_lowercase();
+    if let Some(hex) = name.strip_prefix('#') {
+        let value = u32::from_str_radix(hex, 16).ok().filter(|_| hex.len() == 6)?;
+        return Some(Color::Rgb((value >> 16) as u8, (value >> 8) as u8, value as u8));
+    }
+    let (r, g, b) = match name.as_str() {
+        "red" => (255, 60, 60),
+        "orange" => (255, 150, 0),
+        "yellow" => (255, 220, 0),
+        "green" => (0, 255, 0),
+        "cyan" => (0, 220, 220),
+        "blue" => (80, 140, 255),
+        "purple" => (180, 100, 255),
+        "pink" => (255, 100, 200),
+        "white" => (255, 255, 255),
+        _ => return None,
+    };
+    Some(Color::Rgb(r, g, b))
+}
+
+// Lyric files tag a line with hints in braces before its text, e.g. `{chorus}`,
+// `{bold italic}` or `{color=#ff8800}`. Braces holding anything else are left as text.
+pub fn split_hints(body: &str) -> (LineStyle, &str) {
+    let trimmed = body.trim_start();
+    let Some((inside, rest)) = trimmed.strip_prefix('{').and_then(|s| s.split_once('}')) else {
+        return (LineStyle::default(), body);
+    };
+
+    let mut style = LineStyle::default();
+    for word in inside.split_whitespace() {
+        match word.to_ascii_lowercase().as_str() {
+            "bold" => style.bold = Some(true),
+            "italic" => style.italic = Some(true),
+            "chorus" | "emphasis" => style.emphasis = Some(true),
+            color => {
+                let color = color.strip_prefix("color=").unwrap_or(color);
+                if parse_color(color).is_none() {
+                    return (LineStyle::default(), body);
+                }
+                style.color = Some(color.to_string());
+            }
+        }
+    }
+    if style.is_plain() {
+        return (LineStyle::default(), body);
+    }
+    (style, rest)
+}
+
+// The brace prefix `split_hints` reads back, empty for a plain line
+pub fn hints(style: &LineStyle) -> String {
+    let mut words = Vec::new();
+    if style.bold == Some(true) {
+        words.push("bold".to_string());
+    }
+    if style.italic == Some(true) {
+        words.push("italic".to_string());
+    }
+    if style.emphasis == Some(true) {
+        words.push("chorus".to_string());
+    }
+    if let Some(color) = &style.color {
+        words.push(format!("color={}", color));
+    }
+    match words.is_empty() {
+        true => String::new(),
+        false => format!("{{{}}}", words.join(" ")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(singer: usize, style: LineStyle) -> LyricLine {
+        LyricLine {
+            text: "la".to_string(),
+            start_time: 0.0,
+            end_time: 1.0,
+            segments: Vec::new(),
+            singer,
+            style,
+            chords: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn hints_read_back_what_they_write() {
+        let (style, text) = split_hints("{Chorus color=#FF8800} We will");
+        assert_eq!((style.emphasis, style.color.as_deref(), text), (Some(true), Some("#ff8800"), " We will"));
+        assert_eq!(split_hints(&format!("{}x", hints(&style))), (style, "x"));
+
+        // Not a style, so it's part of the lyrics
+        assert_eq!(split_hints("{sic} x").1, "{sic} x");
+        assert_eq!(split_hints("x").0, LineStyle::default());
+    }
+
+    #[test]
+    fn specific_styles_win() {
+        let mut song = SongStyle {
+            lines: LineStyle { italic: Some(true), ..LineStyle::default() },
+            singers: vec!["white".to_string(), "pink".to_string()],
+            ..SongStyle::default()
+        };
+        song.sections.insert("chorus".to_string(), LineStyle { emphasis: Some(true), ..LineStyle::default() });
+
+        let style = resolve(&song, Some("Chorus 2"), &line(1, LineStyle::default()));
+        assert_eq!((style.color(), style.is_italic(), style.is_bold()), (parse_color("pink"), true, true));
+
+        let own = LineStyle { italic: Some(false), color: Some("red".to_string()), ..LineStyle::default() };
+        let style = resolve(&song, Some("Verse"), &line(0, own));
+        assert_eq!((style.color.as_deref(), style.is_italic(), style.is_emphasized()), (Some("red"), false, false));
+    }
+}
@@ -0,0 +1,290 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::VecDeque,
+    io::{self, BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    sync::{mpsc::Sender, Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{events::AppEvent, plugin::Renderer, song_config::SongConfig, KaraokeApp};
+
+const STATE_INTERVAL: Duration = Duration::from_millis(500);
+const PING_INTERVAL: Duration = Duration::from_secs(1);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(5);
+// Offset estimates come from the fastest of this many recent round trips
+const OFFSET_SAMPLES: usize = 8;
+
+// One JSON object per line in both directions. Times are seconds on the sender's own clock.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum SyncMessage {
+    Song { song: Box<SongConfig> },
+    State { host_time: f64, position: f64, paused: bool },
+    Ping { client_time: f64 },
+    Pong { client_time: f64, host_time: f64 },
+}
+
+// What a client tells its main loop
+#[derive(Debug, Clone)]
+pub enum SyncUpdate {
+    Connected(bool),
+    Song(Box<SongConfig>),
+    // Already corrected for the network delay
+    Position { position: f64, paused: bool },
+}
+
+fn encode(message: &SyncMessage) -> Vec<u8> {
+    let mut line = serde_json::to_vec(message).unwrap_or_default();
+    line.push(b'\n');
+    line
+}
+
+fn seconds_since(origin: Instant) -> f64 {
+    origin.elapsed().as_secs_f64()
+}
+
+#[derive(Default)]
+struct HostState {
+    clients: Vec<TcpStream>,
+    // Replayed to every new client
+    song: Option<Vec<u8>>,
+}
+
+impl HostState {
+    fn broadcast(&mut self, line: &[u8]) {
+        self.clients.retain_mut(|client| client.write_all(line).is_ok());
+    }
+}
+
+// Shares the song and playback position with every `--sync-join` client
+pub struct SyncHost {
+    state: Arc<Mutex<HostState>>,
+    origin: Instant,
+    song_sent: bool,
+    last_state: Option<(Instant, f64, bool)>,
+}
+
+impl SyncHost {
+    pub fn start(bind: &str) -> io::Result<Self> {
+        let listener = TcpListener::bind(bind)?;
+        let state = Arc::new(Mutex::new(HostState::default()));
+        let origin = Instant::now();
+
+        let accept_state = state.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let state = accept_state.clone();
+                thread::spawn(move || {
+                    if let Err(e) = serve_client(stream, &state, origin) {
+                        log::debug!("sync client dropped: {}", e);
+                    }
+                });
+            }
+        });
+
+        Ok(Self {
+            state,
+            origin,
+            song_sent: false,
+            last_state: None,
+        })
+    }
+
+    fn send(&self, message: &SyncMessage) {
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).broadcast(&encode(message));
+    }
+}
+
+fn serve_client(stream: TcpStream, state: &Mutex<HostState>, origin: Instant) -> io::Result<()> {
+    stream.set_nodelay(true)?;
+    // A stalled client must not be able to freeze the host's render loop; the clones share it
+    stream.set_write_timeout(Some(Duration::from_millis(200)))?;
+    let mut writer = stream.try_clone()?;
+    {
+        let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(song) = &state.song {
+            writer.write_all(song)?;
+        }
+        state.clients.push(stream.try_clone()?);
+    }
+
+    for line in BufReader::new(stream).lines() {
+        if let Ok(SyncMessage::Ping { client_time }) = serde_json::from_str(&line?) {
+            let pong = SyncMessage::Pong {
+                client_time,
+                host_time: seconds_since(origin),
+            };
+            // Holding the lock keeps the reply from interleaving with a broadcast
+            let _state = state.lock().unwrap_or_else(|e| e.into_inner());
+            writer.write_all(&encode(&pong))?;
+        }
+    }
+    Ok(())
+}
+
+impl Renderer for SyncHost {
+    fn render(&mut self, app: &KaraokeApp) -> io::Result<()> {
+        if !self.song_sent {
+            let song = encode(&SyncMessage::Song { song: Box::new(app.song_config()) });
+            let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+            state.broadcast(&song);
+            state.song = Some(song);
+            self.song_sent = true;
+            self.last_state = None;
+        }
+
+        // Besides the regular heartbeat, pauses and seeks go out straight away
+        let position = app.get_current_time();
+        let due = match self.last_state {
+            None => true,
+            Some((at, sent_position, paused)) => {
+                let expected = if paused { sent_position } else { sent_position + at.elapsed().as_secs_f64() * app.speed() };
+                at.elapsed() >= STATE_INTERVAL || paused != app.paused || (position - expected).abs() > 0.25
+            }
+        };
+        if due {
+            self.send(&SyncMessage::State {
+                host_time: seconds_since(self.origin),
+                position,
+                paused: app.paused,
+            });
+            self.last_state = Some((Instant::now(), position, app.paused));
+        }
+        Ok(())
+    }
+
+    fn song_changed(&mut self) {
+        self.song_sent = false;
+    }
+}
+
+// NTP-style estimate of how far the host's clock is ahead of ours
+#[derive(Default)]
+struct OffsetEstimator {
+    samples: VecDeque<(f64, f64)>,
+}
+
+impl OffsetEstimator {
+    fn add(&mut self, sent: f64, host_time: f64, received: f64) {
+        let round_trip = received - sent;
+        // The host stamped its reply about halfway through the round trip
+        let offset = host_time - (sent + received) / 2.0;
+        self.samples.push_back((round_trip, offset));
+        if self.samples.len() > OFFSET_SAMPLES {
+            self.samples.pop_front();
+        }
+    }
+
+    fn offset(&self) -> Option<f64> {
+        self.samples
+            .iter()
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+            .map(|&(_, offset)| offset)
+    }
+}
+
+// Follows a host from a background thread, reconnecting with a growing delay whenever the
+// connection drops
+pub fn join(address: String, events: Sender<AppEvent>) {
+    thread::spawn(move || {
+        let origin = Instant::now();
+        let mut delay = Duration::from_millis(500);
+        loop {
+            if let Ok(stream) = TcpStream::connect(&address) {
+                delay = Duration::from_millis(500);
+                if events.send(AppEvent::Sync(SyncUpdate::Connected(true))).is_err() {
+                    return;
+                }
+                if let Err(e) = follow(stream, &events, origin) {
+                    log::debug!("lost the sync host: {}", e);
+                }
+                if events.send(AppEvent::Sync(SyncUpdate::Connected(false))).is_err() {
+                    return;
+                }
+            }
+            thread::sleep(delay);
+            delay = (delay * 2).min(MAX_RETRY_DELAY);
+        }
+    });
+}
+
+fn follow(stream: TcpStream, events: &Sender<AppEvent>, origin: Instant) -> io::Result<()> {
+    stream.set_nodelay(true)?;
+    stream.set_read_timeout(Some(PING_INTERVAL / 4))?;
+    let mut writer = stream.try_clone()?;
+    let mut reader = BufReader::new(stream);
+    let mut estimator = OffsetEstimator::default();
+    let mut last_ping: Option<Instant> = None;
+    // A state that arrived before the first pong can't be placed on our clock yet
+    let mut waiting_state = None;
+    let mut line = Vec::new();
+
+    loop {
+        if last_ping.is_none_or(|at| at.elapsed() >= PING_INTERVAL) {
+            writer.write_all(&encode(&SyncMessage::Ping {
+                client_time: seconds_since(origin),
+            }))?;
+            last_ping = Some(Instant::now());
+        }
+
+        match reader.read_until(b'\n', &mut line) {
+            Ok(0) => return Ok(()),
+            Ok(_) if line.ends_with(b"\n") => {}
+            Ok(_) => continue,
+            Err(e) if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => continue,
+            Err(e) => return Err(e),
+        }
+
+        let message = serde_json::from_slice(&line);
+        line.clear();
+        let mut updates = Vec::new();
+        match message {
+            Ok(SyncMessage::Song { song }) => updates.push(SyncUpdate::Song(song)),
+            Ok(SyncMessage::Pong { client_time, host_time }) => {
+                estimator.add(client_time, host_time, seconds_since(origin));
+            }
+            Ok(SyncMessage::State { host_time, position, paused }) => waiting_state = Some((host_time, position, paused)),
+            _ => {}
+        }
+
+        if let Some(offset) = estimator.offset() {
+            if let Some((host_time, position, paused)) = waiting_state.take() {
+                let in_flight = seconds_since(origin) + offset - host_time;
+                updates.push(SyncUpdate::Position {
+                    position: if paused { position } else { position + in_flight.max(0.0) },
+                    paused,
+                });
+            }
+        }
+
+        for update in updates {
+            if events.send(AppEvent::Sync(update)).is_err() {
+                return Ok(());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offset_comes_from_the_fastest_round_trip() {
+        let mut estimator = OffsetEstimator::default();
+        assert_eq!(estimator.offset(), None);
+        // Host is 100s ahead; the slow sample's reply sat in a queue on the way back
+        estimator.add(1.0, 101.05, 1.1);
+        estimator.add(2.0, 102.01, 2.02);
+        estimator.add(3.0, 103.05, 3.5);
+        assert!((estimator.offset().unwrap() - 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn messages_are_tagged_json_lines() {
+        let line = encode(&SyncMessage::Ping { client_time: 1.5 });
+        assert_eq!(line, b"{\"type\":\"ping\",\"client_time\":1.5}\n");
+    }
+}
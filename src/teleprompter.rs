@@ -0,0 +1,81 @@
+use crate::KaraokeApp;
+
+// How early the line after the current one shows up
+pub const DEFAULT_LEAD: f64 = 2.0;
+
+// What a performer sees: the line to sing now, large, and the one after it once it's close
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Prompt {
+    pub current: Option<usize>,
+    pub next: Option<usize>,
+    // Seconds until `current` starts, when it's a line shown early during a gap
+    pub starts_in: Option<f64>,
+}
+
+// Lines come onto the prompt `lead` seconds before they start: into the next slot while a line
+// is being sung, or straight into the current one between lines
+pub fn prompt(app: &KaraokeApp, lyric_time: f64, lead: f64) -> Prompt {
+    let upcoming = app.lyrics.partition_point(|line| line.start_time <= lyric_time);
+    let due = |i: usize| app.lyrics.get(i).is_some_and(|line| line.start_time - lead <= lyric_time);
+
+    let (current, starts_in) = match app.active_line(lyric_time) {
+        Some(i) => (Some(i), None),
+        None if due(upcoming) => (Some(upcoming), Some(app.lyrics[upcoming].start_time - lyric_time)),
+        None => (None, None),
+    };
+    let next = current.map_or(upcoming, |i| i + 1);
+    Prompt {
+        current,
+        next: due(next).then_some(next),
+        starts_in,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        song_config::SongConfig,
+        style::{LineStyle, SongStyle},
+        LyricLine,
+    };
+
+    #[test]
+    fn lines_come_onto_the_prompt_early() {
+        let lyrics = [("one", 1.0, 3.0), ("two", 4.0, 6.0), ("three", 12.0, 14.0)]
+            .iter()
+            .map(|&(text, start_time, end_time)| LyricLine {
+                text: text.to_string(),
+                start_time,
+                end_time,
+                segments: Vec::new(),
+                singer: 0,
+                style: LineStyle::default(),
+                chords: Vec::new(),
+            })
+            .collect();
+        let app = KaraokeApp::from_config(
+            SongConfig {
+                title: "Test".to_string(),
+                artist: None,
+                album: None,
+                key: None,
+                duration: 15.0,
+                start_position: 0.0,
+                lyrics,
+                sections: Vec::new(),
+                style: SongStyle::default(),
+                tempo: None,
+                view: None,
+            },
+            None,
+        );
+        let at = |time| prompt(&app, time, 2.0);
+
+        assert_eq!(at(0.5), Prompt { current: Some(0), next: None, starts_in: Some(0.5) });
+        assert_eq!(at(1.5), Prompt { current: Some(0), next: None, starts_in: None });
+        assert_eq!(at(2.5), Prompt { current: Some(0), next: Some(1), starts_in: None });
+        assert_eq!(at(8.0), Prompt { current: None, next: None, starts_in: None });
+        assert_eq!(at(10.5), Prompt { current: Some(2), next: None, starts_in: Some(1.5) });
+    }
+}
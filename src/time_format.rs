@@ -0,0 +1,64 @@
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TimeFormat {
+    // m:ss, switching to h:mm:ss for songs an hour or longer
+    #[default]
+    Auto,
+    // Always h:mm:ss
+    Hours,
+    // Like auto with tenths of a second, for timing work
+    Tenths,
+}
+
+impl std::str::FromStr for TimeFormat {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(TimeFormat::Auto),
+            "hours" => Ok(TimeFormat::Hours),
+            "tenths" => Ok(TimeFormat::Tenths),
+            _ => Err(()),
+        }
+    }
+}
+
+// `longest` is the largest time shown alongside this one, usually the song length, so that
+// every time on screen uses the same layout
+pub fn format(seconds: f64, format: TimeFormat, longest: f64) -> String {
+    let tenths = (seconds.max(0.0) * 10.0).floor() as u64;
+    let whole = tenths / 10;
+    let text = if format == TimeFormat::Hours || longest >= 3600.0 {
+        format!("{}:{:02}:{:02}", whole / 3600, whole / 60 % 60, whole % 60)
+    } else {
+        format!("{}:{:02}", whole / 60, whole % 60)
+    };
+    match format {
+        TimeFormat::Tenths => format!("{}.{}", text, tenths % 10),
+        _ => text,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn minutes_by_default() {
+        assert_eq!(format(75.9, TimeFormat::Auto, 200.0), "1:15");
+        assert_eq!(format(-3.0, TimeFormat::Auto, 200.0), "0:00");
+    }
+
+    #[test]
+    fn hours_for_long_mixes() {
+        assert_eq!(format(3725.0, TimeFormat::Auto, 3725.0), "1:02:05");
+        // Short times next to a long duration keep the same layout
+        assert_eq!(format(65.0, TimeFormat::Auto, 3725.0), "0:01:05");
+        assert_eq!(format(65.0, TimeFormat::Hours, 100.0), "0:01:05");
+    }
+
+    #[test]
+    fn tenths_of_a_second() {
+        assert_eq!(format(12.34, TimeFormat::Tenths, 200.0), "0:12.3");
+        assert_eq!(format(3600.05, TimeFormat::Tenths, 3600.05), "1:00:00.0");
+    }
+}
@@ -0,0 +1,338 @@
+use std::cell::Cell;
+
+use crate::{song_config::Section, LyricLine};
+
+// Where a single line stands at a given moment
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineState {
+    Upcoming,
+    Active(f64),
+    Done,
+}
+
+impl LineState {
+    pub fn is_done(self) -> bool {
+        self == LineState::Done
+    }
+}
+
+// A line is active on [start_time, end_time); lines with no length are never active
+pub fn line_state(line: &LyricLine, time: f64) -> LineState {
+    if time < line.start_time {
+        LineState::Upcoming
+    } else if time >= line.end_time {
+        LineState::Done
+    } else {
+        LineState::Active((time - line.start_time) / (line.end_time - line.start_time))
+    }
+}
+
+pub fn line_progress(line: &LyricLine, time: f64) -> f64 {
+    match line_state(line, time) {
+        LineState::Upcoming => 0.0,
+        LineState::Active(progress) => progress,
+        LineState::Done => 1.0,
+    }
+}
+
+// How many characters of the line have been sung. Timed segments are filled one after another;
+// lines without segments sweep evenly across the whole line.
+pub fn sung_chars(line: &LyricLine, time: f64) -> usize {
+    sung_position(line, time) as usize
+}
+
+// The same, with the part of the character being sung, for fills that change within one
+pub fn sung_position(line: &LyricLine, time: f64) -> f64 {
+    if line.segments.is_empty() {
+        return line.text.chars().count() as f64 * line_progress(line, time);
+    }
+
+    line.segments
+        .iter()
+        .map(|segment| {
+            let chars = segment.text.chars().count() as f64;
+            if time >= segment.end_time {
+                chars
+            } else if time <= segment.start_time {
+                0.0
+            } else {
+                let progress = (time - segment.start_time) / (segment.end_time - segment.start_time);
+                chars * progress
+            }
+        })
+        .sum()
+}
+
+// Where each word starts, as a character index and a time. Timed segments are the words when
+// a line has them; otherwise words start as the even sweep reaches them.
+fn word_starts(line: &LyricLine) -> Vec<(usize, f64)> {
+    if !line.segments.is_empty() {
+        let mut position = 0;
+        return line
+            .segments
+            .iter()
+            .map(|segment| {
+                let at = position + segment.text.chars().take_while(|c| c.is_whitespace()).count();
+                position += segment.text.chars().count();
+                (at, segment.start_time)
+            })
+            .collect();
+    }
+    let chars: Vec<char> = line.text.chars().collect();
+    let length = line.end_time - line.start_time;
+    (0..chars.len())
+        .filter(|&i| !chars[i].is_whitespace() && (i == 0 || chars[i - 1].is_whitespace()))
+        .map(|i| (i, line.start_time + length * i as f64 / chars.len() as f64))
+        .collect()
+}
+
+// The bouncing ball over an active line: the character it's above, fractional while it hops
+// between words, and its height from 0 as it lands on a word to 1 at the top of the hop
+pub fn bounce(line: &LyricLine, time: f64) -> Option<(f64, f64)> {
+    if !matches!(line_state(line, time), LineState::Active(_)) {
+        return None;
+    }
+    let words = word_starts(line);
+    let current = words.iter().rposition(|&(_, start)| start <= time).unwrap_or(0);
+    let &(from, start) = words.get(current)?;
+    // The last word gets a hop of its own in place
+    let (to, end) = words.get(current + 1).copied().unwrap_or((from, line.end_time));
+    let phase = match end > start {
+        true => ((time - start) / (end - start)).clamp(0.0, 1.0),
+        false => 0.0,
+    };
+    Some((from as f64 + (to as f64 - from as f64) * phase, (std::f64::consts::PI * phase).sin()))
+}
+
+// The most recent line that has started, or None before the first line.
+// Lines must be sorted by start time.
+pub fn last_started(lines: &[LyricLine], time: f64) -> Option<usize> {
+    lines.partition_point(|line| line.start_time <= time).checked_sub(1)
+}
+
+// Whatever order a file has its lines in, so the lookups here can rely on it
+pub fn sort_lines(lines: &mut [LyricLine]) {
+    lines.sort_by(|a, b| a.start_time.total_cmp(&b.start_time));
+}
+
+// The line being sung right now: None before the first line, in gaps, and after the end.
+// When lines overlap the one that started last wins.
+pub fn active_index(lines: &[LyricLine], time: f64) -> Option<usize> {
+    last_started(lines, time).filter(|&i| time < lines[i].end_time)
+}
+
+// The line the display should center on: the active line, or in gaps and after the end the
+// line that just finished.
+pub fn focus_index(lines: &[LyricLine], time: f64) -> Option<usize> {
+    last_started(lines, time)
+}
+
+// Remembers the last line found, since from one frame to the next playback is almost always still
+// on it or has moved on to the next one. Anything else, like a seek, falls back to the binary search.
+#[derive(Debug, Clone, Default)]
+pub struct LineCursor {
+    last: Cell<Option<usize>>,
+}
+
+impl LineCursor {
+    pub fn last_started(&self, lines: &[LyricLine], time: f64) -> Option<usize> {
+        let is_at = |i: usize| {
+            lines.get(i).is_some_and(|line| line.start_time <= time)
+                && lines.get(i + 1).is_none_or(|next| next.start_time > time)
+        };
+        let found = match self.last.get() {
+            Some(i) if is_at(i) => Some(i),
+            Some(i) if is_at(i + 1) => Some(i + 1),
+            _ => last_started(lines, time),
+        };
+        self.last.set(found);
+        found
+    }
+
+    pub fn active_index(&self, lines: &[LyricLine], time: f64) -> Option<usize> {
+        self.last_started(lines, time).filter(|&i| time < lines[i].end_time)
+    }
+
+    pub fn focus_index(&self, lines: &[LyricLine], time: f64) -> Option<usize> {
+        self.last_started(lines, time)
+    }
+}
+
+// The section playing at `time`; sections must be sorted by start time
+pub fn section_index(sections: &[Section], time: f64) -> Option<usize> {
+    sections.partition_point(|section| section.start_time <= time).checked_sub(1)
+}
+
+pub fn next_section(sections: &[Section], time: f64) -> Option<usize> {
+    let next = section_index(sections, time).map_or(0, |i| i + 1);
+    (next < sections.len()).then_some(next)
+}
+
+// Like a media player's back button: shortly after a section starts this goes to the one
+// before it, otherwise back to the start of the current one
+pub fn previous_section(sections: &[Section], time: f64) -> Option<usize> {
+    const RESTART_WINDOW: f64 = 2.0;
+    let current = section_index(sections, time)?;
+    if time - sections[current].start_time < RESTART_WINDOW {
+        Some(current.saturating_sub(1))
+    } else {
+        Some(current)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{style::LineStyle, Segment};
+
+    fn lines(times: &[(f64, f64)]) -> Vec<LyricLine> {
+        times
+            .iter()
+            .enumerate()
+            .map(|(i, &(start_time, end_time))| LyricLine {
+                text: format!("line {}", i),
+                start_time,
+                end_time,
+                segments: Vec::new(),
+                singer: 0,
+                style: LineStyle::default(),
+                chords: Vec::new(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn nothing_is_active_before_the_first_line() {
+        let lyrics = lines(&[(1.0, 2.0), (2.0, 3.0)]);
+        assert_eq!(active_index(&lyrics, 0.0), None);
+        assert_eq!(focus_index(&lyrics, 0.99), None);
+        assert_eq!(line_state(&lyrics[0], 0.5), LineState::Upcoming);
+    }
+
+    #[test]
+    fn start_is_inclusive_and_end_is_exclusive() {
+        let lyrics = lines(&[(1.0, 2.0), (2.0, 3.0)]);
+        assert_eq!(active_index(&lyrics, 1.0), Some(0));
+        assert_eq!(active_index(&lyrics, 2.0), Some(1));
+        assert_eq!(line_state(&lyrics[0], 2.0), LineState::Done);
+    }
+
+    #[test]
+    fn gaps_have_no_active_line_but_keep_focus() {
+        let lyrics = lines(&[(1.0, 2.0), (4.0, 5.0)]);
+        assert_eq!(active_index(&lyrics, 3.0), None);
+        assert_eq!(focus_index(&lyrics, 3.0), Some(0));
+    }
+
+    #[test]
+    fn completed_last_line_is_not_active() {
+        let lyrics = lines(&[(1.0, 2.0), (2.0, 3.0)]);
+        assert_eq!(active_index(&lyrics, 3.0), None);
+        assert_eq!(active_index(&lyrics, 100.0), None);
+        assert_eq!(focus_index(&lyrics, 100.0), Some(1));
+        assert!(line_state(&lyrics[1], 3.0).is_done());
+    }
+
+    #[test]
+    fn overlapping_lines_prefer_the_later_start() {
+        let lyrics = lines(&[(1.0, 4.0), (2.0, 3.0)]);
+        assert_eq!(active_index(&lyrics, 2.5), Some(1));
+        assert_eq!(active_index(&lyrics, 3.5), None);
+    }
+
+    #[test]
+    fn progress_is_linear_within_a_line() {
+        let lyrics = lines(&[(2.0, 4.0)]);
+        assert_eq!(line_state(&lyrics[0], 3.0), LineState::Active(0.5));
+        assert_eq!(line_progress(&lyrics[0], 1.0), 0.0);
+        assert_eq!(line_progress(&lyrics[0], 5.0), 1.0);
+    }
+
+    #[test]
+    fn lines_listed_out_of_order_are_found_once_sorted() {
+        let mut lyrics = lines(&[(4.0, 5.0), (1.0, 2.0)]);
+        sort_lines(&mut lyrics);
+        assert_eq!(lyrics[0].text, "line 1");
+        assert_eq!(active_index(&lyrics, 4.5), Some(1));
+    }
+
+    #[test]
+    fn segments_fill_one_after_another() {
+        let mut line = lines(&[(0.0, 4.0)]).remove(0);
+        line.text = "Hel lo".to_string();
+        line.segments = vec![
+            Segment { text: "Hel".to_string(), start_time: 0.0, end_time: 1.0 },
+            Segment { text: " lo".to_string(), start_time: 3.0, end_time: 4.0 },
+        ];
+        assert_eq!(sung_chars(&line, 0.5), 1);
+        assert_eq!(sung_chars(&line, 2.0), 3);
+        assert_eq!(sung_chars(&line, 3.5), 4);
+        assert_eq!(sung_chars(&line, 4.0), 6);
+    }
+
+    #[test]
+    fn lines_without_segments_sweep_evenly() {
+        let line = lines(&[(0.0, 4.0)]).remove(0);
+        assert_eq!(sung_chars(&line, 2.0), 3);
+    }
+
+    #[test]
+    fn ball_lands_on_each_word_as_it_starts() {
+        let mut line = lines(&[(0.0, 4.0)]).remove(0);
+        line.text = "ab cd".to_string();
+        assert_eq!(bounce(&line, -1.0), None);
+        assert_eq!(bounce(&line, 0.0), Some((0.0, 0.0)));
+        let (at, height) = bounce(&line, 1.2).unwrap();
+        assert!((at - 1.5).abs() < 1e-9 && (height - 1.0).abs() < 1e-9);
+        assert_eq!(bounce(&line, 2.4).map(|(at, _)| at), Some(3.0));
+
+        line.segments = vec![
+            Segment { text: "ab".to_string(), start_time: 0.0, end_time: 1.0 },
+            Segment { text: " cd".to_string(), start_time: 3.0, end_time: 4.0 },
+        ];
+        assert_eq!(bounce(&line, 1.5).map(|(at, _)| at), Some(1.5));
+        assert_eq!(bounce(&line, 3.0), Some((3.0, 0.0)));
+    }
+
+    #[test]
+    fn empty_lyrics_never_panic() {
+        assert_eq!(active_index(&[], 1.0), None);
+        assert_eq!(focus_index(&[], 1.0), None);
+    }
+
+    #[test]
+    fn cursor_agrees_with_the_search_however_time_moves() {
+        let lyrics = lines(&[(1.0, 2.0), (2.0, 3.0), (4.0, 5.0), (4.0, 6.0), (8.0, 9.0)]);
+        let cursor = LineCursor::default();
+        for time in [0.0, 1.0, 1.5, 2.0, 3.5, 4.0, 7.0, 9.5, 2.5, 0.5, 4.5, 100.0] {
+            assert_eq!(cursor.focus_index(&lyrics, time), focus_index(&lyrics, time), "{}", time);
+            assert_eq!(cursor.active_index(&lyrics, time), active_index(&lyrics, time), "{}", time);
+        }
+        // A song swapped for a shorter one doesn't trip over the old position
+        assert_eq!(cursor.focus_index(&lyrics[..2], 100.0), Some(1));
+        assert_eq!(cursor.focus_index(&[], 1.0), None);
+    }
+
+    fn sections() -> Vec<Section> {
+        [("Intro", 0.0), ("Verse", 10.0), ("Chorus", 30.0)]
+            .iter()
+            .map(|&(name, start_time)| Section { name: name.to_string(), start_time })
+            .collect()
+    }
+
+    #[test]
+    fn sections_run_until_the_next_one() {
+        assert_eq!(section_index(&sections(), 15.0), Some(1));
+        assert_eq!(section_index(&sections()[1..], 5.0), None);
+        assert_eq!(next_section(&sections(), 15.0), Some(2));
+        assert_eq!(next_section(&sections(), 35.0), None);
+        assert_eq!(next_section(&sections()[1..], 5.0), Some(0));
+    }
+
+    #[test]
+    fn previous_restarts_the_section_unless_it_just_began() {
+        assert_eq!(previous_section(&sections(), 15.0), Some(1));
+        assert_eq!(previous_section(&sections(), 31.0), Some(1));
+        assert_eq!(previous_section(&sections(), 1.0), Some(0));
+    }
+}
@@ -0,0 +1,75 @@
+use ratatui::{
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+use std::time::{Duration, Instant};
+
+// Fully visible for this long, then fading out over FADE_TIME
+const SHOW_TIME: Duration = Duration::from_millis(1500);
+const FADE_TIME: Duration = Duration::from_millis(700);
+
+// A short message in the top-right corner, such as "Offset +0.3s". A new one replaces the old.
+#[derive(Debug, Clone)]
+pub struct Toast {
+    message: String,
+    shown_at: Instant,
+}
+
+impl Toast {
+    pub fn new(message: impl Into<String>, now: Instant) -> Self {
+        Self {
+            message: message.into(),
+            shown_at: now,
+        }
+    }
+
+    // How far the toast has faded, from 0.0 to 1.0; None once it's gone
+    pub fn fade(&self, now: Instant) -> Option<f64> {
+        let fading = now.duration_since(self.shown_at).saturating_sub(SHOW_TIME);
+        let fade = fading.as_secs_f64() / FADE_TIME.as_secs_f64();
+        (fade < 1.0).then_some(fade)
+    }
+}
+
+pub fn render(f: &mut Frame, area: Rect, toast: &Toast, now: Instant) {
+    let Some(fade) = toast.fade(now) else {
+        return;
+    };
+    let width = (toast.message.chars().count() as u16 + 4).min(area.width);
+    if width < 5 || area.height < 3 {
+        return;
+    }
+    let corner = Rect::new(area.x + area.width - width, area.y, width, 3);
+
+    let widget = Paragraph::new(toast.message.clone())
+        .style(
+            Style::default()
+                .fg(crate::fade_color(Color::White, fade))
+                .bg(Color::Rgb(40, 44, 64))
+                .add_modifier(Modifier::BOLD),
+        )
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(crate::fade_color(Color::Rgb(0, 200, 255), fade))),
+        );
+    f.render_widget(widget, corner);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stays_then_fades_then_expires() {
+        let start = Instant::now();
+        let toast = Toast::new("Offset +0.3s", start);
+        assert_eq!(toast.fade(start + Duration::from_secs(1)), Some(0.0));
+
+        let halfway = toast.fade(start + Duration::from_millis(1850)).unwrap();
+        assert!((halfway - 0.5).abs() < 1e-9);
+        assert_eq!(toast.fade(start + Duration::from_millis(2200)), None);
+    }
+}
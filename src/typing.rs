@@ -0,0 +1,127 @@
+use crate::{timeline, LyricLine};
+
+// Words per minute count five characters as a word
+const WORD_LENGTH: f64 = 5.0;
+
+// Typing along with the song: each line is typed while it's sung, and the speed is measured
+// against the time the song gives its lines
+#[derive(Debug, Clone, Default)]
+pub struct TypingPractice {
+    typed: Vec<String>,
+    keystrokes: usize,
+    mistakes: usize,
+}
+
+// A different case still counts as the right letter
+pub fn matches(expected: char, typed: char) -> bool {
+    expected == typed || expected.to_lowercase().eq(typed.to_lowercase())
+}
+
+impl TypingPractice {
+    pub fn new(line_count: usize) -> Self {
+        Self {
+            typed: vec![String::new(); line_count],
+            ..Self::default()
+        }
+    }
+
+    // The line being sung, or the next one between lines
+    fn target(lyrics: &[LyricLine], lyric_time: f64) -> Option<usize> {
+        Some(lyrics.partition_point(|line| line.end_time <= lyric_time)).filter(|&i| i < lyrics.len())
+    }
+
+    pub fn typed(&self, line: usize) -> &str {
+        self.typed.get(line).map_or("", String::as_str)
+    }
+
+    pub fn type_char(&mut self, lyrics: &[LyricLine], lyric_time: f64, c: char) {
+        let Some(line) = Self::target(lyrics, lyric_time) else {
+            return;
+        };
+        let typed = &mut self.typed[line];
+        let Some(expected) = lyrics[line].text.chars().nth(typed.chars().count()) else {
+            return;
+        };
+        typed.push(c);
+        self.keystrokes += 1;
+        if !matches(expected, c) {
+            self.mistakes += 1;
+        }
+    }
+
+    pub fn backspace(&mut self, lyrics: &[LyricLine], lyric_time: f64) {
+        if let Some(line) = Self::target(lyrics, lyric_time) {
+            self.typed[line].pop();
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new(self.typed.len());
+    }
+
+    // Keystrokes that were right the first time, corrected mistakes included
+    pub fn accuracy(&self) -> f64 {
+        match self.keystrokes {
+            0 => 1.0,
+            keystrokes => (keystrokes - self.mistakes) as f64 / keystrokes as f64,
+        }
+    }
+
+    // Your speed and the song's, over the time its lines have had so far
+    pub fn wpm(&self, lyrics: &[LyricLine], lyric_time: f64) -> (f64, f64) {
+        let minutes: f64 =
+            lyrics.iter().map(|line| (lyric_time.min(line.end_time) - line.start_time).max(0.0)).sum::<f64>() / 60.0;
+        if minutes <= 0.0 {
+            return (0.0, 0.0);
+        }
+        let correct: usize = lyrics
+            .iter()
+            .zip(&self.typed)
+            .map(|(line, typed)| line.text.chars().zip(typed.chars()).filter(|&(e, t)| matches(e, t)).count())
+            .sum();
+        let sung: usize = lyrics.iter().map(|line| timeline::sung_chars(line, lyric_time)).sum();
+        let per_minute = |chars: usize| chars as f64 / WORD_LENGTH / minutes;
+        (per_minute(correct), per_minute(sung))
+    }
+
+    pub fn describe(&self, lyrics: &[LyricLine], lyric_time: f64) -> String {
+        let (wpm, song) = self.wpm(lyrics, lyric_time);
+        format!("⌨ {:.0} WPM (song {:.0}) · {:.0}% accuracy", wpm, song, self.accuracy() * 100.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn typing_is_scored_against_the_song() {
+        let lyrics: Vec<LyricLine> = [("Hello there", 0.0, 6.0), ("again", 10.0, 12.0)]
+            .iter()
+            .map(|&(text, start_time, end_time)| LyricLine {
+                text: text.to_string(),
+                start_time,
+                end_time,
+                segments: Vec::new(),
+                singer: 0,
+                style: Default::default(),
+                chords: Vec::new(),
+            })
+            .collect();
+        let mut typing = TypingPractice::new(lyrics.len());
+        for c in "hw".chars() {
+            typing.type_char(&lyrics, 3.0, c);
+        }
+        typing.backspace(&lyrics, 3.0);
+        typing.type_char(&lyrics, 3.0, 'e');
+        typing.type_char(&lyrics, 3.0, 'l');
+        assert_eq!((typing.typed(0), typing.accuracy()), ("hel", 0.75));
+
+        // Three right characters in three seconds is 12 WPM; the song got through 5 of 11
+        let (wpm, song) = typing.wpm(&lyrics, 3.0);
+        assert!((wpm - 12.0).abs() < 1e-9 && (song - 20.0).abs() < 1e-9);
+
+        typing.type_char(&lyrics, 8.0, 'a');
+        assert_eq!(typing.typed(1), "a");
+    }
+}
@@ -0,0 +1,151 @@
+use std::fmt;
+
+use crate::song_config::SongConfig;
+
+// Instrumental breaks are normal, but a silence this long usually means a missing line
+const LONG_GAP: f64 = 30.0;
+// How long a line with no usable end time is assumed to last
+const DEFAULT_LINE_LENGTH: f64 = 3.0;
+
+// Line numbers count from 1, as in the file
+#[derive(Debug, Clone, PartialEq)]
+pub enum Issue {
+    OutOfOrder { line: usize },
+    EndsBeforeStart { line: usize },
+    Overlaps { line: usize, seconds: f64 },
+    LongGap { line: usize, seconds: f64 },
+    PastEnd { line: usize },
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Issue::OutOfOrder { line } => write!(f, "line {} starts before the line above it", line),
+            Issue::EndsBeforeStart { line } => write!(f, "line {} ends before it starts", line),
+            Issue::Overlaps { line, seconds } => write!(f, "line {} runs {:.2}s into the next line", line, seconds),
+            Issue::LongGap { line, seconds } => write!(f, "{:.0}s without lyrics before line {}", seconds, line),
+            Issue::PastEnd { line } => write!(f, "line {} runs past the end of the song", line),
+        }
+    }
+}
+
+pub fn check(config: &SongConfig) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    for (i, line) in config.lyrics.iter().enumerate() {
+        let number = i + 1;
+        if line.end_time < line.start_time {
+            issues.push(Issue::EndsBeforeStart { line: number });
+        }
+        if config.given_duration().is_some_and(|duration| line.end_time > duration + 0.005) {
+            issues.push(Issue::PastEnd { line: number });
+        }
+
+        let Some(previous) = i.checked_sub(1).map(|p| &config.lyrics[p]) else {
+            continue;
+        };
+        if line.start_time < previous.start_time {
+            issues.push(Issue::OutOfOrder { line: number });
+            continue;
+        }
+        // Duet parts are allowed to overlap each other
+        let overlap = previous.end_time - line.start_time;
+        if previous.singer == line.singer && overlap > 0.005 && previous.end_time >= previous.start_time {
+            issues.push(Issue::Overlaps { line: i, seconds: overlap });
+        }
+        let gap = line.start_time - previous.end_time.max(previous.start_time);
+        if gap > LONG_GAP {
+            issues.push(Issue::LongGap { line: number, seconds: gap });
+        }
+    }
+    issues
+}
+
+// Sorts the lines, derives missing or backwards end times from the next line, trims overlaps
+// and stretches a song's given length to cover its last line. Long gaps are left alone.
+pub fn fix(config: &mut SongConfig) {
+    config.lyrics.sort_by(|a, b| a.start_time.total_cmp(&b.start_time));
+
+    for i in 0..config.lyrics.len() {
+        let next_start = config.lyrics[i + 1..]
+            .iter()
+            .find(|next| next.singer == config.lyrics[i].singer)
+            .map(|next| next.start_time);
+        let line = &mut config.lyrics[i];
+        if line.end_time < line.start_time {
+            line.end_time = next_start.unwrap_or(line.start_time + DEFAULT_LINE_LENGTH);
+        }
+        if let Some(next_start) = next_start {
+            line.end_time = line.end_time.min(next_start);
+        }
+        let (start, end) = (line.start_time, line.end_time);
+        for segment in &mut line.segments {
+            segment.start_time = segment.start_time.clamp(start, end);
+            segment.end_time = segment.end_time.clamp(segment.start_time, end);
+        }
+    }
+
+    if config.given_duration().is_some() {
+        config.duration = config.duration.max(config.lyrics_end());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        style::{LineStyle, SongStyle},
+        LyricLine,
+    };
+
+    fn song(lines: &[(f64, f64)], duration: f64) -> SongConfig {
+        SongConfig {
+            title: "Test".to_string(),
+            artist: None,
+            album: None,
+            key: None,
+            duration,
+            start_position: 0.0,
+            lyrics: lines
+                .iter()
+                .map(|&(start_time, end_time)| LyricLine {
+                    text: "la".to_string(),
+                    start_time,
+                    end_time,
+                    segments: Vec::new(),
+                    singer: 0,
+                    style: LineStyle::default(),
+                    chords: Vec::new(),
+                })
+                .collect(),
+            sections: Vec::new(),
+            style: SongStyle::default(),
+            tempo: None,
+            view: None,
+        }
+    }
+
+    #[test]
+    fn finds_each_kind_of_problem() {
+        let config = song(&[(1.0, 3.0), (2.5, 2.0), (50.0, 52.0), (40.0, 45.0), (60.0, 70.0)], 65.0);
+        assert_eq!(
+            check(&config),
+            [
+                Issue::EndsBeforeStart { line: 2 },
+                Issue::Overlaps { line: 1, seconds: 0.5 },
+                Issue::LongGap { line: 3, seconds: 47.5 },
+                Issue::OutOfOrder { line: 4 },
+                Issue::PastEnd { line: 5 },
+            ]
+        );
+    }
+
+    #[test]
+    fn fixing_leaves_nothing_but_gaps() {
+        let mut config = song(&[(1.0, 3.0), (2.5, 2.0), (10.0, 12.0), (5.0, 4.0), (60.0, 70.0)], 65.0);
+        fix(&mut config);
+        let times: Vec<(f64, f64)> = config.lyrics.iter().map(|line| (line.start_time, line.end_time)).collect();
+        assert_eq!(times, [(1.0, 2.5), (2.5, 5.0), (5.0, 10.0), (10.0, 12.0), (60.0, 70.0)]);
+        assert_eq!(config.duration, 70.0);
+        assert!(check(&config).iter().all(|issue| matches!(issue, Issue::LongGap { .. })));
+    }
+}
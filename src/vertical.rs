@@ -0,0 +1,185 @@
+use std::borrow::Cow;
+
+use crate::{
+    layout::{LyricFrame, LyricRow, SpanKind, StyledText},
+    timeline::{self, LineState},
+    KaraokeApp, LyricLine,
+};
+
+// Every character takes a full-width cell, with a gap between columns
+const CELL_WIDTH: usize = 2;
+const COLUMN_GAP: &str = "  ";
+const MAX_COLUMNS: usize = 7;
+// Marks the column being sung, above its first character
+const ACTIVE_MARKER: &str = "▼ ";
+const CONTINUED: &str = "︙";
+
+// Punctuation has its own upright forms in vertical writing
+fn vertical_form(c: char) -> char {
+    match c {
+        '、' => '︑',
+        '。' => '︒',
+        '，' | ',' => '︐',
+        '：' | ':' => '︓',
+        '；' | ';' => '︔',
+        '！' | '!' => '︕',
+        '？' | '?' => '︖',
+        'ー' | '—' | '-' => '丨',
+        '…' => '︙',
+        '（' | '(' => '︵',
+        '）' | ')' => '︶',
+        '「' => '﹁',
+        '」' => '﹂',
+        '『' => '﹃',
+        '』' => '﹄',
+        _ => c,
+    }
+}
+
+// A character padded out to a full cell
+fn cell(c: char) -> String {
+    let c = vertical_form(c);
+    let text = c.to_string();
+    let width = ratatui::text::Span::raw(text.as_str()).width();
+    format!("{}{}", text, " ".repeat(CELL_WIDTH.saturating_sub(width)))
+}
+
+// One line as a column of cells. The active line scrolls so the sweep stays in view; the others
+// are cut short with a continuation mark.
+fn column(line: &LyricLine, lyric_time: f64, active: bool, rows: usize) -> Vec<(String, SpanKind)> {
+    let chars: Vec<char> = line.text.chars().collect();
+    let sung = timeline::sung_chars(line, lyric_time);
+    let kind = |i: usize| match (active, timeline::line_state(line, lyric_time)) {
+        (true, _) if i < sung => SpanKind::Sung,
+        (true, _) => SpanKind::Unsung,
+        (false, LineState::Done) => SpanKind::Completed,
+        (false, _) => SpanKind::Upcoming,
+    };
+
+    let start = match active && chars.len() > rows {
+        true => sung.saturating_sub(rows / 2).min(chars.len() - rows),
+        false => 0,
+    };
+    let mut cells: Vec<(String, SpanKind)> =
+        (start..chars.len().min(start + rows)).map(|i| (cell(chars[i]), kind(i))).collect();
+    if chars.len() > start + rows {
+        if let Some(last) = cells.last_mut() {
+            last.0 = cell(CONTINUED.chars().next().unwrap_or(' '));
+        }
+    }
+    cells
+}
+
+// Traditional vertical writing: each line is a column read top to bottom, and the song moves
+// from the rightmost column to the left. The active line sits in the middle column.
+pub fn layout_vertical(app: &KaraokeApp, lyric_time: f64, width: usize, height: usize) -> LyricFrame<'static> {
+    let fit = (width + COLUMN_GAP.len()) / (CELL_WIDTH + COLUMN_GAP.len());
+    let columns = match fit.min(MAX_COLUMNS) {
+        0 => return LyricFrame { rows: vec![LyricRow::default(); height] },
+        n if n % 2 == 0 => n - 1,
+        n => n,
+    };
+    let half = columns / 2;
+    let rows = height.saturating_sub(1);
+    let focus = app.focus_line(lyric_time);
+    let active = app.active_line(lyric_time).or(focus);
+
+    // Right to left: finished lines, the active one, then the ones coming up
+    let center = focus.map_or(-1, |i| i as i64);
+    let lines: Vec<Option<usize>> = (0..columns)
+        .map(|k| {
+            let index = center + half as i64 - k as i64;
+            (index >= 0 && (index as usize) < app.lyrics.len()).then_some(index as usize)
+        })
+        .collect();
+    let cells: Vec<Vec<(String, SpanKind)>> = lines
+        .iter()
+        .map(|index| match index {
+            Some(i) => column(&app.lyrics[*i], lyric_time, Some(*i) == active, rows),
+            None => Vec::new(),
+        })
+        .collect();
+
+    let blank = " ".repeat(CELL_WIDTH);
+    let mut frame_rows = Vec::with_capacity(height);
+    for row in 0..height {
+        let mut spans: Vec<StyledText> = Vec::new();
+        for (k, column) in cells.iter().enumerate() {
+            let (text, kind) = match row {
+                0 if lines[k].is_some() && lines[k] == active => (ACTIVE_MARKER.to_string(), SpanKind::Marker),
+                0 => (blank.clone(), SpanKind::Marker),
+                _ => column.get(row - 1).cloned().unwrap_or((blank.clone(), SpanKind::Upcoming)),
+            };
+            let text = if k + 1 < cells.len() { text + COLUMN_GAP } else { text };
+            match spans.last_mut() {
+                Some(span) if span.kind == kind => span.text.to_mut().push_str(&text),
+                _ => spans.push(StyledText {
+                    text: Cow::Owned(text),
+                    kind,
+                }),
+            }
+        }
+        frame_rows.push(LyricRow {
+            spans,
+            singer: active.map_or(0, |i| app.lyrics[i].singer),
+            ..LyricRow::default()
+        });
+    }
+    LyricFrame { rows: frame_rows }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        song_config::SongConfig,
+        style::{LineStyle, SongStyle},
+    };
+
+    #[test]
+    fn lines_run_down_columns_from_right_to_left() {
+        let lyrics = [("春の風", 0.0, 3.0), ("歌、", 3.0, 4.0), ("夜", 4.0, 5.0)]
+            .iter()
+            .map(|&(text, start_time, end_time)| LyricLine {
+                text: text.to_string(),
+                start_time,
+                end_time,
+                segments: Vec::new(),
+                singer: 0,
+                style: LineStyle::default(),
+                chords: Vec::new(),
+            })
+            .collect();
+        let app = KaraokeApp::from_config(
+            SongConfig {
+                title: "Test".to_string(),
+                artist: None,
+                album: None,
+                key: None,
+                duration: 5.0,
+                start_position: 0.0,
+                lyrics,
+                sections: Vec::new(),
+                style: SongStyle::default(),
+                tempo: None,
+                view: None,
+            },
+            None,
+        );
+
+        let rows: Vec<String> = layout_vertical(&app, 3.5, 14, 4)
+            .rows
+            .iter()
+            .map(|row| row.spans.iter().map(|span| format!("<{:?}:{}>", span.kind, span.text)).collect())
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                "<Marker:    ▼     >",
+                "<Upcoming:夜  ><Sung:歌  ><Completed:春>",
+                "<Upcoming:    ><Unsung:︑  ><Completed:の>",
+                "<Upcoming:        ><Completed:風>",
+            ]
+        );
+    }
+}
@@ -0,0 +1,260 @@
+use ratatui::{
+    layout::{Alignment, Rect},
+    style::{Color, Style},
+    text::Line,
+    widgets::Paragraph,
+    Frame,
+};
+
+use crate::wav::WavData;
+
+pub const PANEL_HEIGHT: u16 = 6;
+const FFT_SIZE: usize = 2048;
+// Spectrum bars span this range on a log scale
+const LOWEST_FREQUENCY: f64 = 40.0;
+const HIGHEST_FREQUENCY: f64 = 16_000.0;
+const FLOOR_DB: f64 = -60.0;
+const WAVEFORM_SECONDS: f64 = 0.05;
+const BLOCKS: [char; 9] = [' ', '▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VisualizerMode {
+    Spectrum,
+    Waveform,
+    Hidden,
+}
+
+// Shows the song's audio around the playback position; the recording is mixed down to mono
+pub struct Visualizer {
+    samples: Vec<f32>,
+    sample_rate: u32,
+    pub mode: VisualizerMode,
+}
+
+impl Visualizer {
+    pub fn new(recording: &WavData) -> Self {
+        let channels = recording.channels.max(1) as usize;
+        let samples = recording
+            .samples
+            .chunks(channels)
+            .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+            .collect();
+        Self {
+            samples,
+            sample_rate: recording.sample_rate,
+            mode: VisualizerMode::Spectrum,
+        }
+    }
+
+    // Spectrum, then waveform, then hidden
+    pub fn cycle(&mut self) {
+        self.mode = match self.mode {
+            VisualizerMode::Spectrum => VisualizerMode::Waveform,
+            VisualizerMode::Waveform => VisualizerMode::Hidden,
+            VisualizerMode::Hidden => VisualizerMode::Spectrum,
+        };
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.mode != VisualizerMode::Hidden
+    }
+
+    // `len` samples centered on `time`, zero padded past either end of the recording
+    fn window(&self, time: f64, len: usize) -> Vec<f32> {
+        let center = (time * self.sample_rate as f64) as i64;
+        (0..len as i64)
+            .map(|i| {
+                let idx = center - len as i64 / 2 + i;
+                if idx < 0 {
+                    0.0
+                } else {
+                    self.samples.get(idx as usize).copied().unwrap_or(0.0)
+                }
+            })
+            .collect()
+    }
+
+    pub fn rows(&self, time: f64, width: usize, height: usize) -> Vec<String> {
+        match self.mode {
+            VisualizerMode::Spectrum => {
+                let levels = spectrum_bars(&self.window(time, FFT_SIZE), self.sample_rate, width);
+                block_rows(&levels, height)
+            }
+            VisualizerMode::Waveform => {
+                let len = (self.sample_rate as f64 * WAVEFORM_SECONDS) as usize;
+                braille_rows(&self.window(time, len), width, height)
+            }
+            VisualizerMode::Hidden => Vec::new(),
+        }
+    }
+}
+
+// In-place iterative radix-2 FFT; `re.len()` must be a power of two
+fn fft(re: &mut [f64], im: &mut [f64]) {
+    let n = re.len();
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+    while len <= n {
+        let angle = -2.0 * std::f64::consts::PI / len as f64;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (sin, cos) = (angle * k as f64).sin_cos();
+                let (a, b) = (start + k, start + k + len / 2);
+                let t_re = re[b] * cos - im[b] * sin;
+                let t_im = re[b] * sin + im[b] * cos;
+                re[b] = re[a] - t_re;
+                im[b] = im[a] - t_im;
+                re[a] += t_re;
+                im[a] += t_im;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+// Loudness of `bars` log-spaced frequency bands, each 0.0..=1.0
+pub fn spectrum_bars(window: &[f32], sample_rate: u32, bars: usize) -> Vec<f64> {
+    let n = window.len();
+    let mut re: Vec<f64> = window
+        .iter()
+        .enumerate()
+        .map(|(i, &sample)| {
+            let hann = 0.5 - 0.5 * (2.0 * std::f64::consts::PI * i as f64 / (n - 1) as f64).cos();
+            sample as f64 * hann
+        })
+        .collect();
+    let mut im = vec![0.0; n];
+    fft(&mut re, &mut im);
+
+    let bin_width = sample_rate as f64 / n as f64;
+    let top = HIGHEST_FREQUENCY.min(sample_rate as f64 / 2.0);
+    let ratio = (top / LOWEST_FREQUENCY).powf(1.0 / bars.max(1) as f64);
+
+    (0..bars)
+        .map(|bar| {
+            let low = LOWEST_FREQUENCY * ratio.powi(bar as i32);
+            let first = (low / bin_width) as usize;
+            let last = ((low * ratio / bin_width) as usize).max(first + 1).min(n / 2);
+            let peak = (first..last)
+                .map(|bin| (re[bin] * re[bin] + im[bin] * im[bin]).sqrt())
+                .fold(0.0, f64::max);
+            // A full-scale sine under a Hann window peaks at n / 4
+            let db = 20.0 * (peak / (n as f64 / 4.0)).max(1e-9).log10();
+            ((db - FLOOR_DB) / -FLOOR_DB).clamp(0.0, 1.0)
+        })
+        .collect()
+}
+
+// Vertical bars built from eighth blocks, top row first
+pub fn block_rows(levels: &[f64], height: usize) -> Vec<String> {
+    (0..height)
+        .map(|row| {
+            let floor = (height - 1 - row) * 8;
+            levels
+                .iter()
+                .map(|level| {
+                    let eighths = (level * (height * 8) as f64).round() as usize;
+                    BLOCKS[eighths.saturating_sub(floor).min(8)]
+                })
+                .collect()
+        })
+        .collect()
+}
+
+// Each braille cell holds 2x4 dots, bit order per the Unicode braille block
+const BRAILLE_DOTS: [[u8; 4]; 2] = [[0x01, 0x02, 0x04, 0x40], [0x08, 0x10, 0x20, 0x80]];
+
+// Traces the samples as a line plot, joining neighbouring points with vertical strokes
+pub fn braille_rows(samples: &[f32], width: usize, height: usize) -> Vec<String> {
+    let (dots_x, dots_y) = (width * 2, height * 4);
+    let mut cells = vec![vec![0u8; width]; height];
+    if samples.is_empty() || dots_y == 0 {
+        return vec![String::new(); height];
+    }
+
+    let y_of = |x: usize| {
+        let sample = samples[x * samples.len() / dots_x].clamp(-1.0, 1.0) as f64;
+        (((1.0 - sample) / 2.0) * (dots_y - 1) as f64).round() as usize
+    };
+
+    let mut previous = None;
+    for x in 0..dots_x {
+        let y = y_of(x);
+        let (from, to) = match previous {
+            Some(p) if p < y => (p + 1, y),
+            Some(p) if p > y => (y, p - 1),
+            _ => (y, y),
+        };
+        for dot_y in from..=to {
+            cells[dot_y / 4][x / 2] |= BRAILLE_DOTS[x % 2][dot_y % 4];
+        }
+        previous = Some(y);
+    }
+
+    cells
+        .into_iter()
+        .map(|row| row.into_iter().map(|bits| char::from_u32(0x2800 + bits as u32).unwrap_or(' ')).collect())
+        .collect()
+}
+
+pub fn render(f: &mut Frame, area: Rect, visualizer: &Visualizer, time: f64) {
+    let lines: Vec<Line> = visualizer
+        .rows(time, area.width as usize, area.height as usize)
+        .into_iter()
+        .map(Line::from)
+        .collect();
+    let widget = Paragraph::new(lines)
+        .alignment(Alignment::Center)
+        .style(Style::default().fg(Color::Rgb(0, 200, 255)).bg(Color::Rgb(20, 24, 40)));
+    f.render_widget(widget, area);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(frequency: f64, sample_rate: u32, len: usize) -> Vec<f32> {
+        (0..len)
+            .map(|i| (2.0 * std::f64::consts::PI * frequency * i as f64 / sample_rate as f64).sin() as f32)
+            .collect()
+    }
+
+    #[test]
+    fn a_sine_lights_up_its_band() {
+        let bars = spectrum_bars(&sine(1000.0, 44_100, FFT_SIZE), 44_100, 16);
+        let loudest = (0..bars.len()).max_by(|&a, &b| bars[a].total_cmp(&bars[b])).unwrap();
+        // 16 bands from 40Hz to 16kHz put 1kHz in band 8
+        assert_eq!(loudest, 8);
+        assert!(bars[loudest] > 0.9);
+        assert!(bars[0] < 0.3);
+    }
+
+    #[test]
+    fn silence_has_an_empty_spectrum() {
+        assert!(spectrum_bars(&[0.0; 256], 8000, 4).iter().all(|&level| level == 0.0));
+    }
+
+    #[test]
+    fn blocks_stack_from_the_bottom() {
+        assert_eq!(block_rows(&[0.0, 0.75, 1.0, 0.0625], 2), [" ▄█ ", " ██▁"]);
+    }
+
+    #[test]
+    fn flat_waveform_is_a_line_through_the_middle() {
+        let rows = braille_rows(&[0.0; 100], 3, 2);
+        assert_eq!(rows, ["⠀⠀⠀", "⠉⠉⠉"]);
+    }
+}
@@ -0,0 +1,84 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+use crate::formats;
+
+// How often the files are looked at; often enough that a save in the editor shows up right away
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+// The song file and the timing file synced to it, checked for changes on every tick
+pub struct FileWatch {
+    song: PathBuf,
+    // Each file and when it was last seen changed
+    files: Vec<(PathBuf, Option<SystemTime>)>,
+    checked: Instant,
+}
+
+impl FileWatch {
+    pub fn new(song: &Path) -> Self {
+        let files = std::iter::once(song.to_path_buf())
+            .chain(formats::timing_file(song))
+            .map(|path| {
+                let seen = modified(&path);
+                (path, seen)
+            })
+            .collect();
+        Self {
+            song: song.to_path_buf(),
+            files,
+            checked: Instant::now(),
+        }
+    }
+
+    pub fn song(&self) -> &Path {
+        &self.song
+    }
+
+    // True once for each change. Editors that save by replacing the file leave it missing for a
+    // moment, which isn't a change; it's picked up when the new file is there.
+    pub fn changed(&mut self, now: Instant) -> bool {
+        if now.duration_since(self.checked) < POLL_INTERVAL {
+            return false;
+        }
+        self.checked = now;
+        let mut changed = false;
+        for (path, seen) in &mut self.files {
+            let current = modified(path);
+            if current.is_some() && current != *seen {
+                *seen = current;
+                changed = true;
+            }
+        }
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_change_is_seen_once() {
+        let path = std::env::temp_dir().join(format!("karaoke-watch-test-{}.lrc", std::process::id()));
+        fs::write(&path, "[00:01.00]one\n").unwrap();
+        let mut watch = FileWatch::new(&path);
+        let start = Instant::now();
+        assert!(!watch.changed(start + POLL_INTERVAL));
+
+        let file = fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000)).unwrap();
+        assert!(!watch.changed(start + POLL_INTERVAL));
+        assert!(watch.changed(start + POLL_INTERVAL * 2));
+        assert!(!watch.changed(start + POLL_INTERVAL * 3));
+
+        fs::remove_file(&path).unwrap();
+        assert!(!watch.changed(start + POLL_INTERVAL * 4));
+    }
+}
@@ -0,0 +1,182 @@
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+// Interleaved samples normalized to -1.0..=1.0
+pub struct WavData {
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub samples: Vec<f32>,
+}
+
+impl WavData {
+    pub fn frames(&self) -> usize {
+        self.samples.len() / self.channels.max(1) as usize
+    }
+
+    pub fn frame(&self, idx: usize) -> &[f32] {
+        let ch = self.channels as usize;
+        &self.samples[idx * ch..idx * ch + ch]
+    }
+
+    // RMS loudness of consecutive windows of `seconds` each, across all channels
+    pub fn rms_levels(&self, seconds: f64) -> Vec<f32> {
+        let frame_len = ((self.sample_rate as f64 * seconds) as usize).max(1);
+        let channels = self.channels.max(1) as usize;
+
+        self.samples
+            .chunks(frame_len * channels)
+            .map(|chunk| {
+                let energy: f32 = chunk.iter().map(|s| s * s).sum();
+                (energy / chunk.len() as f32).sqrt()
+            })
+            .collect()
+    }
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+pub fn read(path: &Path) -> io::Result<WavData> {
+    let mut bytes = Vec::new();
+    BufReader::new(File::open(path)?).read_to_end(&mut bytes)?;
+    parse(&bytes)
+}
+
+pub fn parse(bytes: &[u8]) -> io::Result<WavData> {
+    if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        return Err(invalid("not a RIFF/WAVE file"));
+    }
+
+    let mut format: Option<(u16, u16, u32, u16)> = None;
+    let mut pos = 12;
+
+    while pos + 8 <= bytes.len() {
+        let id = &bytes[pos..pos + 4];
+        let size = u32::from_le_bytes([bytes[pos + 4], bytes[pos + 5], bytes[pos + 6], bytes[pos + 7]]) as usize;
+        let body_start = pos + 8;
+        let body_end = (body_start + size).min(bytes.len());
+        let body = &bytes[body_start..body_end];
+
+        if id == b"fmt " {
+            if body.len() < 16 {
+                return Err(invalid("truncated fmt chunk"));
+            }
+            let tag = u16::from_le_bytes([body[0], body[1]]);
+            let channels = u16::from_le_bytes([body[2], body[3]]);
+            let sample_rate = u32::from_le_bytes([body[4], body[5], body[6], body[7]]);
+            let bits = u16::from_le_bytes([body[14], body[15]]);
+            // WAVE_FORMAT_EXTENSIBLE keeps the real format tag in the sub-format GUID
+            let tag = if tag == 0xFFFE && body.len() >= 26 {
+                u16::from_le_bytes([body[24], body[25]])
+            } else {
+                tag
+            };
+            format = Some((tag, channels, sample_rate, bits));
+        } else if id == b"data" {
+            let (tag, channels, sample_rate, bits) = format.ok_or_else(|| invalid("data chunk before fmt chunk"))?;
+            if channels == 0 || sample_rate == 0 {
+                return Err(invalid("invalid channel count or sample rate"));
+            }
+            let samples = decode_samples(body, tag, bits)?;
+            return Ok(WavData { sample_rate, channels, samples });
+        }
+
+        // Chunks are padded to an even number of bytes
+        pos = body_start + size + (size & 1);
+    }
+
+    Err(invalid("missing data chunk"))
+}
+
+// Title and artist from a LIST/INFO chunk, as taggers and audio editors write them
+#[derive(Debug, Default, PartialEq)]
+pub struct InfoTags {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+}
+
+pub fn info_tags(bytes: &[u8]) -> InfoTags {
+    let mut tags = InfoTags::default();
+    let chunks = |bytes: &[u8], mut pos: usize| {
+        let mut found = Vec::new();
+        while pos + 8 <= bytes.len() {
+            let size = u32::from_le_bytes([bytes[pos + 4], bytes[pos + 5], bytes[pos + 6], bytes[pos + 7]]) as usize;
+            let body_end = (pos + 8 + size).min(bytes.len());
+            found.push((bytes[pos..pos + 4].to_vec(), bytes[pos + 8..body_end].to_vec()));
+            pos = pos + 8 + size + (size & 1);
+        }
+        found
+    };
+    for (id, body) in chunks(bytes, 12) {
+        if id != b"LIST" || !body.starts_with(b"INFO") {
+            continue;
+        }
+        for (id, value) in chunks(&body, 4) {
+            let value = String::from_utf8_lossy(&value).trim_end_matches('\0').trim().to_string();
+            let value = Some(value).filter(|value| !value.is_empty());
+            match &id[..] {
+                b"INAM" => tags.title = value,
+                b"IART" => tags.artist = value,
+                _ => {}
+            }
+        }
+    }
+    tags
+}
+
+fn decode_samples(body: &[u8], tag: u16, bits: u16) -> io::Result<Vec<f32>> {
+    match (tag, bits) {
+        (1, 8) => Ok(body.iter().map(|&b| (b as f32 - 128.0) / 128.0).collect()),
+        (1, 16) => Ok(body
+            .chunks_exact(2)
+            .map(|b| i16::from_le_bytes([b[0], b[1]]) as f32 / 32768.0)
+            .collect()),
+        (1, 24) => Ok(body
+            .chunks_exact(3)
+            .map(|b| (i32::from_le_bytes([0, b[0], b[1], b[2]]) >> 8) as f32 / 8_388_608.0)
+            .collect()),
+        (1, 32) => Ok(body
+            .chunks_exact(4)
+            .map(|b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f32 / 2_147_483_648.0)
+            .collect()),
+        (3, 32) => Ok(body
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect()),
+        _ => Err(invalid("unsupported WAV sample format")),
+    }
+}
+
+// Always writes 16-bit PCM, which every player understands
+pub fn write(path: &Path, wav: &WavData) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    let data_len = (wav.samples.len() * 2) as u32;
+    let block_align = wav.channels * 2;
+    let byte_rate = wav.sample_rate * block_align as u32;
+
+    out.write_all(b"RIFF")?;
+    out.write_all(&(36 + data_len).to_le_bytes())?;
+    out.write_all(b"WAVE")?;
+
+    out.write_all(b"fmt ")?;
+    out.write_all(&16u32.to_le_bytes())?;
+    out.write_all(&1u16.to_le_bytes())?;
+    out.write_all(&wav.channels.to_le_bytes())?;
+    out.write_all(&wav.sample_rate.to_le_bytes())?;
+    out.write_all(&byte_rate.to_le_bytes())?;
+    out.write_all(&block_align.to_le_bytes())?;
+    out.write_all(&16u16.to_le_bytes())?;
+
+    out.write_all(b"data")?;
+    out.write_all(&data_len.to_le_bytes())?;
+    for &sample in &wav.samples {
+        let value = (sample.clamp(-1.0, 1.0) * 32767.0).round() as i16;
+        out.write_all(&value.to_le_bytes())?;
+    }
+
+    out.flush()
+}